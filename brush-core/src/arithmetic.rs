@@ -34,6 +34,14 @@ pub enum EvalError {
     #[error("failed to parse expression: '{0}'")]
     ParseError(String),
 
+    /// Referenced a variable that is not set while `set -u` is in effect.
+    #[error("{0}: unbound variable")]
+    UnboundVariable(String),
+
+    /// Variable values referenced other variables too deeply (e.g., a cycle).
+    #[error("{0}: expression recursion level exceeded")]
+    RecursionLimitExceeded(String),
+
     /// Failed to trace an arithmetic expression.
     #[error("failed tracing expression")]
     TraceError,
@@ -67,6 +75,7 @@ impl ExpandAndEvaluate for ast::UnexpandedArithmeticExpr {
         if trace_if_needed && shell.options.print_commands_and_arguments {
             shell
                 .trace_command(std::format!("(( {expr} ))"))
+                .await
                 .map_err(|_err| EvalError::TraceError)?;
         }
 
@@ -131,26 +140,104 @@ impl Evaluatable for ast::ArithmeticExpr {
     }
 }
 
+/// Evaluates the given value(s) being assigned to a variable with the integer attribute.
+/// Each value is evaluated as an arithmetic expression; any value that fails to parse or
+/// evaluate is treated as 0.
+///
+/// # Arguments
+///
+/// * `shell` - The shell to use for evaluation.
+/// * `value` - The (already expanded) value being assigned.
+pub(crate) async fn eval_integer_assignment(
+    shell: &mut Shell,
+    value: variables::ShellValueLiteral,
+) -> variables::ShellValueLiteral {
+    match value {
+        variables::ShellValueLiteral::Scalar(s) => {
+            variables::ShellValueLiteral::Scalar(eval_integer_value(shell, s.as_str()).await)
+        }
+        variables::ShellValueLiteral::Array(variables::ArrayLiteral(elements)) => {
+            let mut evaluated = vec![];
+            for (key, value) in elements {
+                evaluated.push((key, eval_integer_value(shell, value.as_str()).await));
+            }
+            variables::ShellValueLiteral::Array(variables::ArrayLiteral(evaluated))
+        }
+    }
+}
+
+async fn eval_integer_value(shell: &mut Shell, value: &str) -> String {
+    let result = match brush_parser::arithmetic::parse(value) {
+        Ok(expr) => expr.eval(shell).await.unwrap_or(0),
+        Err(_) => 0,
+    };
+
+    result.to_string()
+}
+
+/// Maximum depth to which variable values may be recursively evaluated as expressions.
+const MAX_RECURSION_DEPTH: usize = 1024;
+
 async fn deref_lvalue(shell: &mut Shell, lvalue: &ast::ArithmeticTarget) -> Result<i64, EvalError> {
-    let value_str: Cow<'_, str> = match lvalue {
-        ast::ArithmeticTarget::Variable(name) => shell
-            .env
-            .get(name)
-            .map_or_else(|| Cow::Borrowed(""), |(_, v)| v.value().to_cow_string()),
+    let (name, value_str): (&str, Cow<'_, str>) = match lvalue {
+        ast::ArithmeticTarget::Variable(name) => match shell.env.get(name) {
+            Some((_, v)) => {
+                let value_str = match v.resolve_value(shell) {
+                    Cow::Borrowed(value) => value.to_cow_string(),
+                    Cow::Owned(value) => Cow::Owned(value.to_cow_string().into_owned()),
+                };
+                (name.as_str(), value_str)
+            }
+            None if shell.options.treat_unset_variables_as_error => {
+                return Err(EvalError::UnboundVariable(name.clone()));
+            }
+            None => (name.as_str(), Cow::Borrowed("")),
+        },
         ast::ArithmeticTarget::ArrayElement(name, index_expr) => {
             let index_str = index_expr.eval(shell).await?.to_string();
 
-            shell
+            let value_str = shell
                 .env
                 .get(name)
-                .map_or_else(|| Ok(None), |(_, v)| v.value().get_at(index_str.as_str()))
+                .map_or_else(
+                    || Ok(None),
+                    |(_, v)| match v.resolve_value(shell) {
+                        Cow::Borrowed(value) => value.get_at(index_str.as_str()),
+                        Cow::Owned(value) => Ok(value
+                            .get_at(index_str.as_str())?
+                            .map(|s| Cow::Owned(s.into_owned()))),
+                    },
+                )
                 .map_err(|_err| EvalError::FailedToAccessArray)?
-                .unwrap_or(Cow::Borrowed(""))
+                .unwrap_or(Cow::Borrowed(""));
+
+            (name.as_str(), value_str)
         }
     };
 
-    let value: i64 = value_str.parse().unwrap_or(0);
-    Ok(value)
+    let value_str = value_str.trim();
+    if value_str.is_empty() {
+        return Ok(0);
+    }
+
+    if let Ok(value) = value_str.parse() {
+        return Ok(value);
+    }
+
+    // The value isn't a plain number, so evaluate it as an expression in its own right;
+    // this also takes care of values that name other variables.
+    let expr = brush_parser::arithmetic::parse(value_str)
+        .map_err(|_err| EvalError::ParseError(value_str.to_owned()))?;
+
+    if shell.arithmetic_recursion_depth >= MAX_RECURSION_DEPTH {
+        return Err(EvalError::RecursionLimitExceeded(name.to_owned()));
+    }
+
+    shell.arithmetic_recursion_depth += 1;
+    let result = expr.eval(shell).await;
+    shell.arithmetic_recursion_depth -= 1;
+
+    result
 }
 
 #[allow(clippy::unnecessary_wraps)]
@@ -227,7 +314,7 @@ async fn apply_binary_op(
             if right == 0 {
                 Err(EvalError::DivideByZero)
             } else {
-                Ok(left % right)
+                Ok(left.wrapping_rem(right))
             }
         }
         ast::BinaryOperator::Comma => Ok(right),
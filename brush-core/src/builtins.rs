@@ -13,6 +13,7 @@ mod bg;
 mod break_;
 mod brushinfo;
 mod builtin_;
+mod caller;
 mod cd;
 mod colon;
 mod command;
@@ -20,6 +21,7 @@ mod complete;
 mod continue_;
 mod declare;
 mod dirs;
+mod disown;
 mod dot;
 mod echo;
 mod enable;
@@ -30,13 +32,18 @@ mod exit;
 mod export;
 mod factory;
 mod false_;
+mod fc;
 mod fg;
 mod getopts;
+mod hash;
 mod help;
+mod history;
 mod jobs;
 #[cfg(unix)]
 mod kill;
 mod let_;
+pub(crate) mod line_reader;
+mod mapfile;
 mod popd;
 mod printf;
 mod pushd;
@@ -46,17 +53,23 @@ mod return_;
 mod set;
 mod shift;
 mod shopt;
+mod suspend;
 mod test;
+#[cfg(unix)]
+mod times;
 mod trap;
 mod true_;
 mod type_;
 #[cfg(unix)]
+mod ulimit;
+#[cfg(unix)]
 mod umask;
 mod unalias;
 mod unimp;
 mod unset;
 mod wait;
 
+pub(crate) use dirs::{get_stack as get_directory_stack, StackOffset};
 pub(crate) use factory::get_default_builtins;
 pub use factory::{builtin, simple_builtin, SimpleCommand};
 
@@ -1,4 +1,5 @@
 use clap::Parser;
+use itertools::Itertools;
 use std::io::Write;
 
 use crate::{builtins, commands};
@@ -24,7 +25,7 @@ impl builtins::Command for AliasCommand {
         let mut exit_code = builtins::ExitCode::Success;
 
         if self.print || self.aliases.is_empty() {
-            for (name, value) in &context.shell.aliases {
+            for (name, value) in context.shell.aliases.iter().sorted() {
                 writeln!(context.stdout(), "alias {name}='{value}'")?;
             }
         } else {
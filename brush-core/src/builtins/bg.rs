@@ -1,7 +1,7 @@
 use clap::Parser;
 use std::io::Write;
 
-use crate::{builtins, commands};
+use crate::{builtins, commands, jobs};
 
 /// Moves a job to run in the background.
 #[derive(Parser)]
@@ -18,27 +18,48 @@ impl builtins::Command for BgCommand {
     ) -> Result<crate::builtins::ExitCode, crate::error::Error> {
         let mut exit_code = builtins::ExitCode::Success;
 
-        if !self.job_specs.is_empty() {
-            for job_spec in &self.job_specs {
-                if let Some(job) = context.shell.jobs.resolve_job_spec(job_spec) {
-                    job.move_to_background()?;
-                } else {
-                    writeln!(
-                        context.stderr(),
-                        "{}: {}: no such job",
-                        context.command_name,
-                        job_spec
-                    )?;
+        if !context.shell.options.enable_job_control {
+            writeln!(context.stderr(), "{}: no job control", context.command_name)?;
+            return Ok(builtins::ExitCode::Custom(1));
+        }
+
+        let job_specs: Vec<_> = if self.job_specs.is_empty() {
+            vec![None]
+        } else {
+            self.job_specs.iter().map(|s| Some(s.as_str())).collect()
+        };
+
+        for job_spec in job_specs {
+            let mut stdout = context.stdout();
+            let mut stderr = context.stderr();
+
+            match context.shell.jobs.resolve_job_spec_or_current(job_spec) {
+                Ok(id) => {
+                    let Some(job) = context.shell.jobs.get_mut(id) else {
+                        continue;
+                    };
+
+                    if matches!(job.state, jobs::JobState::Running) {
+                        writeln!(
+                            stderr,
+                            "{}: job {id} already in background",
+                            context.command_name
+                        )?;
+                    } else {
+                        job.move_to_background()?;
+                        writeln!(
+                            stdout,
+                            "[{id}]{} {}",
+                            job.get_annotation(),
+                            job.get_display_command_line()
+                        )?;
+                    }
+                }
+                Err(e) => {
+                    writeln!(stderr, "{}: {e}", context.command_name)?;
                     exit_code = builtins::ExitCode::Custom(1);
                 }
             }
-        } else {
-            if let Some(job) = context.shell.jobs.current_job_mut() {
-                job.move_to_background()?;
-            } else {
-                writeln!(context.stderr(), "{}: no current job", context.command_name)?;
-                exit_code = builtins::ExitCode::Custom(1);
-            }
         }
 
         Ok(exit_code)
@@ -21,7 +21,12 @@ impl builtins::Command for BuiltinCommand {
         mut context: commands::ExecutionContext<'_>,
     ) -> Result<crate::builtins::ExitCode, crate::error::Error> {
         if let Some(builtin_name) = &self.builtin_name {
-            if let Some(builtin) = context.shell.builtins.get(builtin_name) {
+            if let Some(builtin) = context
+                .shell
+                .builtins
+                .get(builtin_name)
+                .filter(|b| !b.disabled)
+            {
                 context.command_name.clone_from(builtin_name);
 
                 let args: Vec<commands::CommandArg> = std::iter::once(builtin_name.into())
@@ -32,7 +37,11 @@ impl builtins::Command for BuiltinCommand {
                     .await
                     .map(|res: builtins::BuiltinResult| res.exit_code)
             } else {
-                writeln!(context.stderr(), "{builtin_name}: command not found")?;
+                writeln!(
+                    context.stderr(),
+                    "{}: {builtin_name}: not a shell builtin",
+                    context.command_name
+                )?;
                 Ok(builtins::ExitCode::Custom(1))
             }
         } else {
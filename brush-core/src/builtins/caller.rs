@@ -0,0 +1,61 @@
+use clap::Parser;
+use std::io::Write;
+
+use crate::{builtins, commands};
+
+/// Return the context of the current subroutine call.
+#[derive(Parser)]
+pub(crate) struct CallerCommand {
+    /// Number of frames to go back up the call stack; if given, the name of the calling
+    /// subroutine is also displayed.
+    expr: Option<String>,
+}
+
+#[async_trait::async_trait]
+impl builtins::Command for CallerCommand {
+    async fn execute(
+        &self,
+        context: commands::ExecutionContext<'_>,
+    ) -> Result<crate::builtins::ExitCode, crate::error::Error> {
+        let call_stack = &context.shell.call_stack;
+
+        let Some(expr) = &self.expr else {
+            // Display the line and file from which the current subroutine was called.
+            let Some(frame) = call_stack.front() else {
+                return Ok(builtins::ExitCode::Custom(1));
+            };
+
+            let source = call_stack
+                .get(1)
+                .map_or("NULL", |caller| caller.source.as_str());
+
+            writeln!(context.stdout(), "{} {source}", frame.call_line)?;
+            return Ok(builtins::ExitCode::Success);
+        };
+
+        let Ok(depth) = expr.parse::<usize>() else {
+            writeln!(
+                context.stderr(),
+                "{}: {expr}: invalid number",
+                context.command_name
+            )?;
+            return Ok(builtins::ExitCode::InvalidUsage);
+        };
+
+        let frame = call_stack.get(depth);
+        let caller = call_stack.get(depth + 1);
+        let (Some(frame), Some(caller)) = (frame, caller) else {
+            return Ok(builtins::ExitCode::Custom(1));
+        };
+
+        writeln!(
+            context.stdout(),
+            "{} {} {}",
+            frame.call_line,
+            caller.name(),
+            caller.source
+        )?;
+
+        Ok(builtins::ExitCode::Success)
+    }
+}
@@ -1,9 +1,9 @@
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use clap::Parser;
 
-use crate::{builtins, commands};
+use crate::{builtins, commands, Shell};
 
 /// Change the current working directory.
 #[derive(Parser)]
@@ -35,16 +35,28 @@ impl builtins::Command for CdCommand {
         context: commands::ExecutionContext<'_>,
     ) -> Result<crate::builtins::ExitCode, crate::error::Error> {
         // TODO: implement options
-        if self.force_follow_symlinks
-            || self.use_physical_dir
-            || self.exit_on_failed_cwd_resolution
-            || self.file_with_xattr_as_dir
-        {
+        if self.exit_on_failed_cwd_resolution || self.file_with_xattr_as_dir {
             return crate::error::unimp("options to cd");
         }
 
-        let target_dir = if let Some(target_dir) = &self.target_dir {
-            target_dir.clone()
+        // N.B. We print the new working directory if it wasn't named explicitly.
+        let mut print_new_dir = false;
+
+        let mut target_dir = if let Some(target_dir) = &self.target_dir {
+            if target_dir.as_os_str() == "-" {
+                if let Some(oldpwd) = context.shell.env.get_str("OLDPWD") {
+                    print_new_dir = true;
+                    PathBuf::from(oldpwd.to_string())
+                } else {
+                    writeln!(context.stderr(), "cd: OLDPWD not set")?;
+                    return Ok(builtins::ExitCode::Custom(1));
+                }
+            } else if let Some(found_dir) = search_cdpath(context.shell, target_dir) {
+                print_new_dir = true;
+                found_dir
+            } else {
+                target_dir.clone()
+            }
         } else {
             if let Some(home_var) = context.shell.env.get_str("HOME") {
                 PathBuf::from(home_var.to_string())
@@ -54,6 +66,14 @@ impl builtins::Command for CdCommand {
             }
         };
 
+        // With -P, symlinks are resolved before changing directory; otherwise, the
+        // target is interpreted logically.
+        if self.use_physical_dir && !self.force_follow_symlinks {
+            if let Ok(resolved) = context.shell.get_absolute_path(&target_dir).canonicalize() {
+                target_dir = resolved;
+            }
+        }
+
         match context.shell.set_working_dir(&target_dir) {
             Ok(()) => {}
             Err(e) => {
@@ -62,6 +82,45 @@ impl builtins::Command for CdCommand {
             }
         }
 
+        if print_new_dir {
+            writeln!(
+                context.stdout(),
+                "{}",
+                context.shell.working_dir.to_string_lossy()
+            )?;
+        }
+
         Ok(builtins::ExitCode::Success)
     }
 }
+
+/// Searches the directories listed in `CDPATH` for the given relative target directory,
+/// returning the first match found.
+fn search_cdpath(shell: &Shell, target_dir: &Path) -> Option<PathBuf> {
+    // Only relative paths that don't start with `.` or `..` are searched for.
+    if !matches!(
+        target_dir.components().next(),
+        Some(std::path::Component::Normal(_))
+    ) {
+        return None;
+    }
+
+    let cdpath = shell.env.get_str("CDPATH")?;
+
+    for cdpath_entry in cdpath.split(':') {
+        // N.B. An empty entry refers to the current directory, which is checked anyway.
+        if cdpath_entry.is_empty() {
+            if shell.get_absolute_path(target_dir).is_dir() {
+                return None;
+            }
+            continue;
+        }
+
+        let candidate = shell.get_absolute_path(&Path::new(cdpath_entry).join(target_dir));
+        if candidate.is_dir() {
+            return Some(candidate);
+        }
+    }
+
+    None
+}
@@ -1,7 +1,7 @@
 use clap::Parser;
 use std::{fmt::Display, io::Write, path::Path};
 
-use crate::{builtins, commands, error, shell, sys::fs::PathExt, ExecutionResult};
+use crate::{builtins, commands, error, keywords, shell, sys::fs::PathExt, ExecutionResult};
 
 /// Directly invokes an external command, without going through typical search order.
 #[derive(Parser)]
@@ -37,28 +37,45 @@ impl builtins::Command for CommandCommand {
         }
 
         if self.print_description || self.print_verbose_description {
-            if let Some(found_cmd) = self.try_find_command(context.shell) {
-                if self.print_description {
-                    writeln!(context.stdout(), "{found_cmd}")?;
-                } else {
-                    match found_cmd {
-                        FoundCommand::Builtin(_name) => {
-                            writeln!(context.stdout(), "{} is a shell builtin", self.command_name)?;
-                        }
-                        FoundCommand::External(path) => {
-                            writeln!(context.stdout(), "{} is {path}", self.command_name)?;
+            let mut found_any = false;
+
+            for name in std::iter::once(&self.command_name).chain(self.args.iter()) {
+                if let Some(found_cmd) = Self::try_find_command(context.shell, name) {
+                    found_any = true;
+
+                    if self.print_description {
+                        writeln!(context.stdout(), "{found_cmd}")?;
+                    } else {
+                        match found_cmd {
+                            FoundCommand::Alias(name, value) => {
+                                writeln!(context.stdout(), "{name} is aliased to `{value}'")?;
+                            }
+                            FoundCommand::Keyword(name) => {
+                                writeln!(context.stdout(), "{name} is a shell keyword")?;
+                            }
+                            FoundCommand::Function(name, definition) => {
+                                writeln!(context.stdout(), "{name} is a function")?;
+                                writeln!(context.stdout(), "{definition}")?;
+                            }
+                            FoundCommand::Builtin(name) => {
+                                writeln!(context.stdout(), "{name} is a shell builtin")?;
+                            }
+                            FoundCommand::Hashed(path) => {
+                                writeln!(context.stdout(), "{name} is hashed ({path})")?;
+                            }
+                            FoundCommand::External(path) => {
+                                writeln!(context.stdout(), "{name} is {path}")?;
+                            }
                         }
                     }
+                } else if self.print_verbose_description {
+                    writeln!(context.stderr(), "command: {name}: not found")?;
                 }
+            }
+
+            if found_any {
                 Ok(builtins::ExitCode::Success)
             } else {
-                if self.print_verbose_description {
-                    writeln!(
-                        context.stderr(),
-                        "command: {}: not found",
-                        self.command_name
-                    )?;
-                }
                 Ok(builtins::ExitCode::Custom(1))
             }
         } else {
@@ -68,25 +85,32 @@ impl builtins::Command for CommandCommand {
 }
 
 enum FoundCommand {
+    Alias(String, String),
+    Keyword(String),
+    Function(String, String),
     Builtin(String),
+    Hashed(String),
     External(String),
 }
 
 impl Display for FoundCommand {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            FoundCommand::Builtin(name) => write!(f, "{name}"),
-            FoundCommand::External(path) => write!(f, "{path}"),
+            FoundCommand::Alias(name, value) => write!(f, "alias {name}='{value}'"),
+            FoundCommand::Keyword(name)
+            | FoundCommand::Function(name, _)
+            | FoundCommand::Builtin(name) => write!(f, "{name}"),
+            FoundCommand::Hashed(path) | FoundCommand::External(path) => write!(f, "{path}"),
         }
     }
 }
 
 impl CommandCommand {
     #[allow(clippy::unwrap_in_result)]
-    fn try_find_command(&self, shell: &shell::Shell) -> Option<FoundCommand> {
+    fn try_find_command(shell: &shell::Shell, name: &str) -> Option<FoundCommand> {
         // Look in path.
-        if self.command_name.contains(std::path::MAIN_SEPARATOR) {
-            let candidate_path = shell.get_absolute_path(Path::new(&self.command_name));
+        if name.contains(std::path::MAIN_SEPARATOR) {
+            let candidate_path = shell.get_absolute_path(Path::new(name));
             if candidate_path.executable() {
                 Some(FoundCommand::External(
                     candidate_path
@@ -99,14 +123,31 @@ impl CommandCommand {
                 None
             }
         } else {
-            if let Some(builtin_cmd) = shell.builtins.get(self.command_name.as_str()) {
-                if !builtin_cmd.disabled {
-                    return Some(FoundCommand::Builtin(self.command_name.clone()));
-                }
+            if let Some(value) = shell.aliases.get(name) {
+                return Some(FoundCommand::Alias(name.to_owned(), value.clone()));
+            }
+
+            if keywords::is_keyword(shell, name) {
+                return Some(FoundCommand::Keyword(name.to_owned()));
+            }
+
+            if let Some(registration) = shell.funcs.get(name) {
+                return Some(FoundCommand::Function(
+                    name.to_owned(),
+                    registration.definition.to_string(),
+                ));
+            }
+
+            if shell.builtins.get(name).is_some_and(|b| !b.disabled) {
+                return Some(FoundCommand::Builtin(name.to_owned()));
+            }
+
+            if let Some(path) = shell.get_cached_program_location(name) {
+                return Some(FoundCommand::Hashed(path.to_string_lossy().to_string()));
             }
 
             shell
-                .find_executables_in_path(self.command_name.as_str())
+                .find_executables_in_path(name)
                 .first()
                 .map(|path| FoundCommand::External(path.to_string_lossy().to_string()))
         }
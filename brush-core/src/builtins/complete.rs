@@ -1,4 +1,5 @@
 use clap::{arg, Parser};
+use itertools::Itertools;
 use std::collections::HashMap;
 use std::fmt::Write as _;
 use std::io::Write;
@@ -7,6 +8,7 @@ use crate::builtins;
 use crate::commands;
 use crate::completion::{self, CompleteAction, CompleteOption, Spec};
 use crate::error;
+use crate::escape;
 
 #[derive(Parser)]
 pub(crate) struct CommonCompleteCommandArgs {
@@ -267,24 +269,35 @@ impl CompleteCommand {
                     return error::unimp("special spec not found");
                 }
             } else {
-                for (command_name, spec) in context.shell.completion_config.iter() {
-                    Self::display_spec(context, None, Some(command_name.as_str()), spec)?;
-                }
+                Self::display_all_specs(context)?;
             }
         } else if self.remove {
             if let Some(target_spec) = target_spec {
                 let mut new_spec = None;
                 std::mem::swap(&mut new_spec, target_spec);
             } else {
-                return error::unimp("remove all specs");
+                context.shell.completion_config.clear();
             }
+        } else if let Some(target_spec) = target_spec {
+            let mut new_spec = Some(self.common_args.create_spec());
+            std::mem::swap(&mut new_spec, target_spec);
         } else {
-            if let Some(target_spec) = target_spec {
-                let mut new_spec = Some(self.common_args.create_spec());
-                std::mem::swap(&mut new_spec, target_spec);
-            } else {
-                return error::unimp("set unspecified spec");
-            }
+            // With no names (and no special scenario), we display all specs.
+            Self::display_all_specs(context)?;
+        }
+
+        Ok(())
+    }
+
+    fn display_all_specs(context: &commands::ExecutionContext<'_>) -> Result<(), error::Error> {
+        let specs = context
+            .shell
+            .completion_config
+            .iter()
+            .sorted_by_key(|(name, _)| *name);
+
+        for (command_name, spec) in specs {
+            Self::display_spec(context, None, Some(command_name.as_str()), spec)?;
         }
 
         Ok(())
@@ -298,7 +311,11 @@ impl CompleteCommand {
             Self::display_spec(context, None, Some(name), spec)?;
             Ok(true)
         } else {
-            writeln!(context.stderr(), "no completion found for command")?;
+            writeln!(
+                context.stderr(),
+                "{}: {name}: no completion specification",
+                context.command_name
+            )?;
             Ok(false)
         }
     }
@@ -311,88 +328,68 @@ impl CompleteCommand {
     ) -> Result<(), error::Error> {
         let mut s = String::from("complete");
 
-        if let Some(special_name) = special_name {
-            s.push(' ');
-            s.push_str(special_name);
-        }
-
-        for action in &spec.actions {
-            let action_str = match action {
-                CompleteAction::Alias => "alias",
-                CompleteAction::ArrayVar => "arrayvar",
-                CompleteAction::Binding => "binding",
-                CompleteAction::Builtin => "builtin",
-                CompleteAction::Command => "command",
-                CompleteAction::Directory => "directory",
-                CompleteAction::Disabled => "disabled",
-                CompleteAction::Enabled => "enabled",
-                CompleteAction::Export => "export",
-                CompleteAction::File => "file",
-                CompleteAction::Function => "function",
-                CompleteAction::Group => "group",
-                CompleteAction::HelpTopic => "helptopic",
-                CompleteAction::HostName => "hostname",
-                CompleteAction::Job => "job",
-                CompleteAction::Keyword => "keyword",
-                CompleteAction::Running => "running",
-                CompleteAction::Service => "service",
-                CompleteAction::SetOpt => "setopt",
-                CompleteAction::ShOpt => "shopt",
-                CompleteAction::Signal => "signal",
-                CompleteAction::Stopped => "stopped",
-                CompleteAction::User => "user",
-                CompleteAction::Variable => "variable",
-            };
-
-            let piece = std::format!(" -A {action_str}");
-            s.push_str(&piece);
-        }
-
-        if spec.options.bash_default {
-            s.push_str(" -o bashdefault");
-        }
-        if spec.options.default {
-            s.push_str(" -o default");
-        }
-        if spec.options.dir_names {
-            s.push_str(" -o dirnames");
-        }
-        if spec.options.file_names {
-            s.push_str(" -o filenames");
-        }
-        if spec.options.no_quote {
-            s.push_str(" -o noquote");
-        }
-        if spec.options.no_sort {
-            s.push_str(" -o nosort");
-        }
-        if spec.options.no_space {
-            s.push_str(" -o nospace");
-        }
-        if spec.options.plus_dirs {
-            s.push_str(" -o plusdirs");
+        // N.B. Everything is displayed in the same order as bash, regardless of the
+        // order in which it was specified.
+        let options = [
+            (spec.options.bash_default, "bashdefault"),
+            (spec.options.default, "default"),
+            (spec.options.dir_names, "dirnames"),
+            (spec.options.file_names, "filenames"),
+            (spec.options.no_quote, "noquote"),
+            (spec.options.no_sort, "nosort"),
+            (spec.options.no_space, "nospace"),
+            (spec.options.plus_dirs, "plusdirs"),
+        ];
+        for (_, option_name) in options.iter().filter(|(enabled, _)| *enabled) {
+            write!(s, " -o {option_name}")?;
+        }
+
+        let action_flags = spec.actions.iter().filter_map(action_flag).sorted().dedup();
+        for flag in action_flags {
+            write!(s, " -{flag}")?;
+        }
+
+        let actions_without_flags = spec
+            .actions
+            .iter()
+            .filter(|action| action_flag(action).is_none())
+            .map(action_name)
+            .sorted()
+            .dedup();
+        for action_name in actions_without_flags {
+            write!(s, " -A {action_name}")?;
+        }
+
+        let quoted_args = [
+            ("G", spec.glob_pattern.clone()),
+            ("W", spec.word_list.clone()),
+            ("P", spec.prefix.clone()),
+            ("S", spec.suffix.clone()),
+            (
+                "X",
+                spec.filter_pattern.as_ref().map(|filter_pattern| {
+                    if spec.filter_pattern_excludes {
+                        filter_pattern.clone()
+                    } else {
+                        std::format!("!{filter_pattern}")
+                    }
+                }),
+            ),
+            ("C", spec.command.clone()),
+        ];
+        for (flag, value) in quoted_args {
+            if let Some(value) = value {
+                write!(s, " -{flag} {}", escape::single_quote(value.as_str()))?;
+            }
         }
 
-        if let Some(glob_pattern) = &spec.glob_pattern {
-            write!(s, " -G {glob_pattern}")?;
-        }
-        if let Some(word_list) = &spec.word_list {
-            write!(s, " -W {word_list}")?;
-        }
         if let Some(function_name) = &spec.function_name {
             write!(s, " -F {function_name}")?;
         }
-        if let Some(command) = &spec.command {
-            write!(s, " -C {command}")?;
-        }
-        if let Some(filter_pattern) = &spec.filter_pattern {
-            write!(s, " -X {filter_pattern}")?;
-        }
-        if let Some(prefix) = &spec.prefix {
-            write!(s, " -P {prefix}")?;
-        }
-        if let Some(suffix) = &spec.suffix {
-            write!(s, " -S {suffix}")?;
+
+        if let Some(special_name) = special_name {
+            s.push(' ');
+            s.push_str(special_name);
         }
 
         if let Some(command_name) = command_name {
@@ -413,6 +410,15 @@ impl CompleteCommand {
         if self.print {
             return Self::try_display_spec_for_command(context, name);
         } else if self.remove {
+            if context.shell.completion_config.get(name).is_none() {
+                writeln!(
+                    context.stderr(),
+                    "{}: {name}: no completion specification",
+                    context.command_name
+                )?;
+                return Ok(false);
+            }
+
             context.shell.completion_config.remove(name);
             return Ok(true);
         }
@@ -471,6 +477,11 @@ impl builtins::Command for CompGenCommand {
 
         match result {
             completion::Answer::Candidates(candidates, _options) => {
+                // Like bash, we fail if there were no candidates.
+                if candidates.is_empty() {
+                    return Ok(builtins::ExitCode::Custom(1));
+                }
+
                 for candidate in candidates {
                     writeln!(context.stdout(), "{candidate}")?;
                 }
@@ -608,3 +619,52 @@ impl CompOptCommand {
         }
     }
 }
+
+/// Returns the single-character option flag that may be used to select the given action,
+/// if it has one.
+fn action_flag(action: &CompleteAction) -> Option<char> {
+    match action {
+        CompleteAction::Alias => Some('a'),
+        CompleteAction::Builtin => Some('b'),
+        CompleteAction::Command => Some('c'),
+        CompleteAction::Directory => Some('d'),
+        CompleteAction::Export => Some('e'),
+        CompleteAction::File => Some('f'),
+        CompleteAction::Group => Some('g'),
+        CompleteAction::Job => Some('j'),
+        CompleteAction::Keyword => Some('k'),
+        CompleteAction::Service => Some('s'),
+        CompleteAction::User => Some('u'),
+        CompleteAction::Variable => Some('v'),
+        _ => None,
+    }
+}
+
+fn action_name(action: &CompleteAction) -> &'static str {
+    match action {
+        CompleteAction::Alias => "alias",
+        CompleteAction::ArrayVar => "arrayvar",
+        CompleteAction::Binding => "binding",
+        CompleteAction::Builtin => "builtin",
+        CompleteAction::Command => "command",
+        CompleteAction::Directory => "directory",
+        CompleteAction::Disabled => "disabled",
+        CompleteAction::Enabled => "enabled",
+        CompleteAction::Export => "export",
+        CompleteAction::File => "file",
+        CompleteAction::Function => "function",
+        CompleteAction::Group => "group",
+        CompleteAction::HelpTopic => "helptopic",
+        CompleteAction::HostName => "hostname",
+        CompleteAction::Job => "job",
+        CompleteAction::Keyword => "keyword",
+        CompleteAction::Running => "running",
+        CompleteAction::Service => "service",
+        CompleteAction::SetOpt => "setopt",
+        CompleteAction::ShOpt => "shopt",
+        CompleteAction::Signal => "signal",
+        CompleteAction::Stopped => "stopped",
+        CompleteAction::User => "user",
+        CompleteAction::Variable => "variable",
+    }
+}
@@ -3,7 +3,7 @@ use itertools::Itertools;
 use std::io::Write;
 
 use crate::{
-    builtins, commands,
+    arithmetic, builtins, commands,
     env::{EnvironmentLookup, EnvironmentScope},
     error,
     variables::{
@@ -126,6 +126,15 @@ impl builtins::Command for DeclareCommand {
             _ => DeclareVerb::Declare,
         };
 
+        if matches!(verb, DeclareVerb::Local) && !context.shell.in_function() {
+            writeln!(
+                context.stderr(),
+                "{}: can only be used in a function",
+                context.command_name
+            )?;
+            return Ok(builtins::ExitCode::Custom(1));
+        }
+
         // TODO: implement declare -I
         if self.locals_inherit_from_prev_scope {
             writeln!(
@@ -143,7 +152,10 @@ impl builtins::Command for DeclareCommand {
                         result = builtins::ExitCode::Custom(1);
                     }
                 } else {
-                    if !self.process_declaration(&mut context, declaration, verb)? {
+                    if !self
+                        .process_declaration(&mut context, declaration, verb)
+                        .await?
+                    {
                         result = builtins::ExitCode::Custom(1);
                     }
                 }
@@ -192,6 +204,13 @@ impl DeclareCommand {
                 if self.function_names_only {
                     if self.print {
                         writeln!(context.stdout(), "declare -f {name}")?;
+                    } else if context.shell.options.enable_debugger {
+                        writeln!(
+                            context.stdout(),
+                            "{name} {} {}",
+                            func_registration.line,
+                            func_registration.definition.source
+                        )?;
                     } else {
                         writeln!(context.stdout(), "{name}")?;
                     }
@@ -203,7 +222,7 @@ impl DeclareCommand {
                 writeln!(context.stderr(), "declare: {name}: not found")?;
                 Ok(false)
             }
-        } else if let Some(variable) = context.shell.env.get_using_policy(name, lookup) {
+        } else if let Some(variable) = context.shell.env.get_unresolved_using_policy(name, lookup) {
             let mut cs = variable.get_attribute_flags();
             if cs.is_empty() {
                 cs.push('-');
@@ -219,7 +238,7 @@ impl DeclareCommand {
                 context.stdout(),
                 "declare -{cs} {name}{separator_str}{}",
                 variable
-                    .value()
+                    .resolve_value(context.shell)
                     .format(variables::FormatStyle::DeclarePrint)?
             )?;
 
@@ -230,7 +249,7 @@ impl DeclareCommand {
         }
     }
 
-    fn process_declaration(
+    async fn process_declaration(
         &self,
         context: &mut crate::commands::ExecutionContext<'_>,
         declaration: &commands::CommandArg,
@@ -244,7 +263,7 @@ impl DeclareCommand {
         }
 
         // Extract the variable name and the initial value being assigned (if any).
-        let (name, assigned_index, initial_value, name_is_array) =
+        let (name, assigned_index, mut initial_value, name_is_array) =
             Self::declaration_to_name_and_value(declaration)?;
 
         // Figure out where we should look.
@@ -254,11 +273,81 @@ impl DeclareCommand {
             EnvironmentLookup::Anywhere
         };
 
+        // Unless we're changing the nameref attribute, declarations of a name reference apply
+        // to the variable it refers to.
+        let name = if self.make_nameref.is_some() {
+            let self_reference = matches!(
+                &initial_value,
+                Some(ShellValueLiteral::Scalar(target)) if *target == name
+            );
+            if self.make_nameref.to_bool() == Some(true) && self_reference {
+                writeln!(
+                    context.stderr(),
+                    "{}: {name}: nameref variable self references not allowed",
+                    context.command_name
+                )?;
+                return Ok(false);
+            }
+
+            name
+        } else {
+            context
+                .shell
+                .env
+                .resolve_nameref(name.as_str(), lookup)?
+                .into_owned()
+        };
+
+        // Values assigned to variables with the integer attribute are evaluated arithmetically.
+        let treat_as_integer = self.make_integer.to_bool().unwrap_or_else(|| {
+            context
+                .shell
+                .env
+                .get_unresolved_using_policy(name.as_str(), lookup)
+                .is_some_and(|var| var.is_treated_as_integer())
+        });
+        if treat_as_integer {
+            if let Some(value) = initial_value {
+                initial_value =
+                    Some(arithmetic::eval_integer_assignment(context.shell, value).await);
+            }
+        }
+
+        // Values may not be assigned to readonly variables, and readonly globals may not be
+        // shadowed by new locals. As in bash, readonly locals of calling functions may be.
+        let existing_var = context
+            .shell
+            .env
+            .get_unresolved_using_policy(name.as_str(), lookup);
+        let assigns_readonly =
+            initial_value.is_some() && existing_var.is_some_and(|var| var.is_readonly());
+        let shadows_readonly = create_var_local
+            && existing_var.is_none()
+            && context
+                .shell
+                .env
+                .get_unresolved_using_policy(name.as_str(), EnvironmentLookup::OnlyInGlobal)
+                .is_some_and(|var| var.is_readonly());
+        if assigns_readonly || shadows_readonly {
+            if matches!(verb, DeclareVerb::Readonly) {
+                writeln!(context.stderr(), "{name}: readonly variable")?;
+            } else {
+                writeln!(
+                    context.stderr(),
+                    "{}: {name}: readonly variable",
+                    context.command_name
+                )?;
+            }
+            return Ok(false);
+        }
+
+        context.shell.on_variable_assigned(name.as_str());
+
         // Look up the variable.
         if let Some(var) = context
             .shell
             .env
-            .get_mut_using_policy(name.as_str(), lookup)
+            .get_mut_unresolved_using_policy(name.as_str(), lookup)
         {
             if self.make_associative_array.is_some() {
                 var.convert_to_associative_array()?;
@@ -479,14 +568,16 @@ impl DeclareCommand {
                     context.stdout(),
                     "declare -{cs} {name}{separator_str}{}",
                     variable
-                        .value()
+                        .resolve_value(context.shell)
                         .format(variables::FormatStyle::DeclarePrint)?
                 )?;
             } else {
                 writeln!(
                     context.stdout(),
                     "{name}={}",
-                    variable.value().format(variables::FormatStyle::Basic)?
+                    variable
+                        .resolve_value(context.shell)
+                        .format(variables::FormatStyle::Basic)?
                 )?;
             }
         }
@@ -1,7 +1,8 @@
 use clap::Parser;
 use std::io::Write;
+use std::path::PathBuf;
 
-use crate::{builtins, commands};
+use crate::{builtins, commands, Shell};
 
 /// Manage the current directory stack.
 #[derive(Parser, Debug, Default)]
@@ -21,8 +22,10 @@ pub(crate) struct DirsCommand {
     /// Print one directory per line with its index.
     #[arg(short = 'v')]
     print_one_per_line_with_index: bool,
-    //
-    // TODO: implement +N and -N
+
+    /// Display only the entry at the given offset (+N or -N) in the stack.
+    #[arg(allow_negative_numbers = true)]
+    offset: Option<String>,
 }
 
 #[async_trait::async_trait]
@@ -34,10 +37,24 @@ impl builtins::Command for DirsCommand {
         if self.clear {
             context.shell.directory_stack.clear();
         } else {
-            let dirs = vec![&context.shell.working_dir]
-                .into_iter()
-                .chain(context.shell.directory_stack.iter().rev())
-                .collect::<Vec<_>>();
+            let mut dirs = get_stack(context.shell);
+
+            if let Some(offset) = &self.offset {
+                let Some(offset) = StackOffset::parse(offset) else {
+                    writeln!(context.stderr(), "dirs: {offset}: invalid argument")?;
+                    return Ok(builtins::ExitCode::InvalidUsage);
+                };
+
+                let Some(index) = offset.to_index(dirs.len()) else {
+                    writeln!(
+                        context.stderr(),
+                        "dirs: {offset}: directory stack index out of range"
+                    )?;
+                    return Ok(builtins::ExitCode::Custom(1));
+                };
+
+                dirs = vec![dirs.swap_remove(index)];
+            }
 
             let one_per_line = self.print_one_per_line || self.print_one_per_line_with_index;
 
@@ -69,3 +86,70 @@ impl builtins::Command for DirsCommand {
         Ok(builtins::ExitCode::Success)
     }
 }
+
+/// Identifies an entry in the directory stack, counting from the left (`+N`) or the
+/// right (`-N`) of the listing displayed by `dirs`, starting with zero.
+#[derive(Clone, Copy)]
+pub(crate) enum StackOffset {
+    FromLeft(usize),
+    FromRight(usize),
+}
+
+impl std::fmt::Display for StackOffset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StackOffset::FromLeft(n) => write!(f, "+{n}"),
+            StackOffset::FromRight(n) => write!(f, "-{n}"),
+        }
+    }
+}
+
+impl StackOffset {
+    /// Parses an offset of the form `+N` or `-N`; returns `None` if the string isn't one.
+    pub fn parse(s: &str) -> Option<Self> {
+        if let Some(n) = s.strip_prefix('+') {
+            n.parse().ok().map(StackOffset::FromLeft)
+        } else if let Some(n) = s.strip_prefix('-') {
+            n.parse().ok().map(StackOffset::FromRight)
+        } else {
+            None
+        }
+    }
+
+    /// Resolves the offset to an index into a stack listing with the given number of entries.
+    pub fn to_index(self, len: usize) -> Option<usize> {
+        match self {
+            StackOffset::FromLeft(n) => (n < len).then_some(n),
+            StackOffset::FromRight(n) => len.checked_sub(n + 1),
+        }
+    }
+}
+
+/// Returns the full directory stack listing, starting with the current working directory.
+pub(crate) fn get_stack(shell: &Shell) -> Vec<PathBuf> {
+    std::iter::once(shell.working_dir.clone())
+        .chain(shell.directory_stack.iter().rev().cloned())
+        .collect()
+}
+
+/// Replaces the directory stack with the given listing, changing the working directory
+/// to its first entry unless otherwise requested.
+pub(crate) fn set_stack(
+    shell: &mut Shell,
+    mut dirs: Vec<PathBuf>,
+    change_dir: bool,
+) -> Result<(), crate::error::Error> {
+    if dirs.is_empty() {
+        return Ok(());
+    }
+
+    let first = dirs.remove(0);
+    if change_dir {
+        shell.set_working_dir(&first)?;
+    }
+
+    dirs.reverse();
+    shell.directory_stack = dirs;
+
+    Ok(())
+}
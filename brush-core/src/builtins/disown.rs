@@ -0,0 +1,76 @@
+use clap::Parser;
+use std::io::Write;
+
+use crate::{builtins, commands, jobs};
+
+/// Remove jobs from the current shell.
+#[derive(Parser)]
+pub(crate) struct DisownCommand {
+    /// Mark jobs to not receive SIGHUP when the shell exits, instead of removing them.
+    #[arg(short = 'h')]
+    mark_for_no_sighup: bool,
+
+    /// Operate on all jobs.
+    #[arg(short = 'a')]
+    all_jobs: bool,
+
+    /// Operate only on running jobs.
+    #[arg(short = 'r')]
+    running_jobs_only: bool,
+
+    /// Job specs to operate on.
+    job_specs: Vec<String>,
+}
+
+#[async_trait::async_trait]
+impl builtins::Command for DisownCommand {
+    async fn execute(
+        &self,
+        context: commands::ExecutionContext<'_>,
+    ) -> Result<crate::builtins::ExitCode, crate::error::Error> {
+        let mut exit_code = builtins::ExitCode::Success;
+
+        let ids: Vec<_> = if self.all_jobs || (self.running_jobs_only && self.job_specs.is_empty())
+        {
+            context.shell.jobs.jobs.iter().map(|j| j.id).collect()
+        } else if self.job_specs.is_empty() {
+            match context.shell.jobs.resolve_job_spec_or_current(None) {
+                Ok(id) => vec![id],
+                Err(e) => {
+                    writeln!(context.stderr(), "{}: {e}", context.command_name)?;
+                    return Ok(builtins::ExitCode::Custom(1));
+                }
+            }
+        } else {
+            let mut ids = vec![];
+            for job_spec in &self.job_specs {
+                match context.shell.jobs.resolve_job_spec_to_id(job_spec) {
+                    Ok(id) => ids.push(id),
+                    Err(e) => {
+                        writeln!(context.stderr(), "{}: {e}", context.command_name)?;
+                        exit_code = builtins::ExitCode::Custom(1);
+                    }
+                }
+            }
+            ids
+        };
+
+        for id in ids {
+            let Some(job) = context.shell.jobs.get_mut(id) else {
+                continue;
+            };
+
+            if self.running_jobs_only && !matches!(job.state, jobs::JobState::Running) {
+                continue;
+            }
+
+            // N.B. We don't send SIGHUP to jobs when exiting, so there's nothing to do
+            // for jobs that are only being marked.
+            if !self.mark_for_no_sighup {
+                context.shell.jobs.remove(id);
+            }
+        }
+
+        Ok(exit_code)
+    }
+}
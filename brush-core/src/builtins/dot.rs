@@ -1,8 +1,8 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use clap::Parser;
 
-use crate::{builtins, commands};
+use crate::{builtins, commands, Shell};
 
 /// Evalute the provided script in the current shell environment.
 #[derive(Debug, Parser)]
@@ -23,21 +23,36 @@ impl builtins::Command for DotCommand {
     ) -> Result<crate::builtins::ExitCode, crate::error::Error> {
         // TODO: Handle trap inheritance.
         let script_args: Vec<_> = self.script_args.iter().map(|a| a.as_str()).collect();
+        let script_path = resolve_script_path(context.shell, self.script_path.as_str());
 
         let params = context.params.clone();
         let result = context
             .shell
-            .source(
-                Path::new(&self.script_path),
-                script_args.as_slice(),
-                &params,
-            )
+            .source(script_path.as_path(), script_args.as_slice(), &params)
             .await?;
 
-        if result.exit_code != 0 {
+        // A `return` in the script only stops the sourcing of the script; anything else
+        // (e.g., an `exit`) is propagated.
+        if result.return_from_function_or_script {
             return Ok(builtins::ExitCode::Custom(result.exit_code));
         }
 
-        Ok(builtins::ExitCode::Success)
+        Ok(result.into())
     }
 }
+
+/// Resolves the path of the script to source. Names without a slash are searched
+/// for in PATH (when the `sourcepath` option is enabled), falling back to the
+/// current directory.
+fn resolve_script_path(shell: &Shell, name: &str) -> PathBuf {
+    if !name.contains(std::path::MAIN_SEPARATOR) && shell.options.source_builtin_searches_path {
+        for dir_str in shell.env.get_str("PATH").unwrap_or_default().split(':') {
+            let candidate = shell.get_absolute_path(Path::new(dir_str)).join(name);
+            if candidate.is_file() {
+                return candidate;
+            }
+        }
+    }
+
+    PathBuf::from(name)
+}
@@ -12,11 +12,11 @@ pub(crate) struct EchoCommand {
     no_trailing_newline: bool,
 
     /// Interpret backslash escapes in the provided text.
-    #[arg(short = 'e')]
+    #[arg(short = 'e', overrides_with = "no_interpret_backslash_escapes")]
     interpret_backslash_escapes: bool,
 
-    /// Do not interpret backslash escapes in the provided text.
-    #[arg(short = 'E')]
+    /// Do not interpret backslash escapes in the provided text (the default).
+    #[arg(short = 'E', overrides_with = "interpret_backslash_escapes")]
     no_interpret_backslash_escapes: bool,
 
     /// Tokens to echo to standard output.
@@ -45,34 +45,37 @@ impl builtins::Command for EchoCommand {
         context: commands::ExecutionContext<'_>,
     ) -> Result<crate::builtins::ExitCode, crate::error::Error> {
         let mut trailing_newline = !self.no_trailing_newline;
-        let mut s;
+        let mut output: Vec<u8>;
         if self.interpret_backslash_escapes {
-            s = String::new();
+            output = vec![];
             for (i, arg) in self.args.iter().enumerate() {
                 if i > 0 {
-                    s.push(' ');
+                    output.push(b' ');
                 }
 
-                let (expanded_arg, keep_going) = escape::expand_backslash_escapes(
+                // Expanded escapes may yield arbitrary bytes, so we don't go through
+                // a string here.
+                let (mut expanded_arg, keep_going) = escape::expand_backslash_escapes(
                     arg.as_str(),
                     escape::EscapeMode::EchoBuiltin,
                 )?;
-                s.push_str(&String::from_utf8_lossy(expanded_arg.as_slice()));
+                output.append(&mut expanded_arg);
 
+                // \c stops all further output, including the trailing newline.
                 if !keep_going {
                     trailing_newline = false;
                     break;
                 }
             }
         } else {
-            s = self.args.join(" ");
+            output = self.args.join(" ").into_bytes();
         }
 
         if trailing_newline {
-            s.push('\n');
+            output.push(b'\n');
         }
 
-        write!(context.stdout(), "{s}")?;
+        context.stdout().write_all(output.as_slice())?;
         context.stdout().flush()?;
 
         return Ok(builtins::ExitCode::Success);
@@ -20,10 +20,23 @@ impl builtins::Command for EvalCommand {
 
             tracing::debug!("Applying eval to: {:?}", args_concatenated);
 
+            // N.B. We run the parsed program directly in the current shell (rather than
+            // going through `run_string`) so that evaluation doesn't advance the current
+            // line number, and so that syntax errors are attributed to `eval`.
+            let parse_result = context.shell.parse_string(args_concatenated);
+            let source_info = brush_parser::SourceInfo {
+                source: context.command_name.clone(),
+            };
+
             let params = context.params.clone();
-            let exec_result = context.shell.run_string(args_concatenated, &params).await?;
+            let exec_result = context
+                .shell
+                .run_parsed_result(parse_result, &source_info, &params)
+                .await?;
 
-            Ok(builtins::ExitCode::Custom(exec_result.exit_code))
+            // Propagate any request to exit the shell, return from a function, or
+            // break/continue a loop made by the evaluated script.
+            Ok(exec_result.into())
         } else {
             Ok(builtins::ExitCode::Success)
         }
@@ -1,5 +1,5 @@
 use clap::Parser;
-use std::{borrow::Cow, os::unix::process::CommandExt};
+use std::{borrow::Cow, io::Write, os::unix::process::CommandExt, path::PathBuf};
 
 use crate::{builtins, commands, error};
 
@@ -30,6 +30,21 @@ impl builtins::Command for ExecCommand {
         context: commands::ExecutionContext<'_>,
     ) -> Result<builtins::ExitCode, crate::error::Error> {
         if self.args.is_empty() {
+            // With no command to execute, the redirections written on this command are made
+            // permanent in the shell; those inherited from enclosing commands are not.
+            let mut open_files = context.shell.open_files.try_clone()?;
+            for fd in &context.params.redirected_fds {
+                match context.params.open_files.files.get(fd) {
+                    Some(file) => {
+                        open_files.files.insert(*fd, file.try_dup()?);
+                    }
+                    None => {
+                        open_files.files.remove(fd);
+                    }
+                }
+            }
+
+            context.shell.replace_open_files(open_files);
             return Ok(builtins::ExitCode::Success);
         }
 
@@ -39,21 +54,64 @@ impl builtins::Command for ExecCommand {
             argv0 = Cow::Owned(std::format!("-{argv0}"));
         }
 
-        let mut cmd = commands::compose_std_command(
-            context.shell,
-            &self.args[0],
-            argv0.as_str(),
-            &self.args[1..],
-            context.params.open_files.clone(),
-            self.empty_environment,
-        )?;
+        // Resolve the program ourselves, since the environment given to it may not
+        // include the shell's search path.
+        let program = if self.args[0].contains(std::path::MAIN_SEPARATOR) {
+            Some(PathBuf::from(&self.args[0]))
+        } else {
+            context
+                .shell
+                .find_first_executable_in_path_using_cache(self.args[0].as_str())
+        };
+
+        let exit_code = if let Some(program) = program {
+            let mut cmd = commands::compose_std_command(
+                context.shell,
+                program.to_string_lossy().as_ref(),
+                argv0.as_str(),
+                &self.args[1..],
+                context.params.open_files.clone(),
+                self.empty_environment,
+            )?;
 
-        let exec_error = cmd.exec();
+            // On success, this never returns.
+            let exec_error = cmd.exec();
+
+            match exec_error.kind() {
+                std::io::ErrorKind::NotFound => {
+                    writeln!(
+                        context.stderr(),
+                        "{}: No such file or directory",
+                        self.args[0]
+                    )?;
+                    127
+                }
+                std::io::ErrorKind::PermissionDenied => {
+                    writeln!(
+                        context.stderr(),
+                        "{}: {}: cannot execute: Permission denied",
+                        context.command_name,
+                        self.args[0]
+                    )?;
+                    126
+                }
+                _ => return Err(error::Error::from(exec_error)),
+            }
+        } else {
+            writeln!(
+                context.stderr(),
+                "{}: {}: not found",
+                context.command_name,
+                self.args[0]
+            )?;
+            127
+        };
 
-        if exec_error.kind() == std::io::ErrorKind::NotFound {
-            Ok(builtins::ExitCode::Custom(127))
+        // A non-interactive shell exits if it fails to execute the command.
+        if context.shell.options.interactive {
+            Ok(builtins::ExitCode::Custom(exit_code))
         } else {
-            Err(error::Error::from(exec_error))
+            Ok(builtins::ExitCode::ExitShell(exit_code))
         }
     }
 }
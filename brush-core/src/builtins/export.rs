@@ -5,7 +5,7 @@ use std::io::Write;
 use crate::{
     builtins, commands,
     env::{EnvironmentLookup, EnvironmentScope},
-    variables,
+    error, variables,
 };
 
 /// Add or update exported shell variables.
@@ -43,68 +43,115 @@ impl builtins::Command for ExportCommand {
         &self,
         context: commands::ExecutionContext<'_>,
     ) -> Result<crate::builtins::ExitCode, crate::error::Error> {
-        if !self.declarations.is_empty() {
-            for decl in &self.declarations {
-                match decl {
-                    commands::CommandArg::String(s) => {
-                        // Try to find the variable already present; if we find it, then mark it
-                        // exported.
-                        if let Some((_, variable)) = context.shell.env.get_mut(s) {
+        if self.declarations.is_empty() {
+            self.display_exported_variables(&context)?;
+            return Ok(builtins::ExitCode::Success);
+        }
+
+        let mut result = builtins::ExitCode::Success;
+
+        for decl in &self.declarations {
+            match decl {
+                commands::CommandArg::String(s) => {
+                    // Try to find the variable already present; if we find it, then update its
+                    // export attribute. Otherwise, we declare it (without a value).
+                    if let Some((_, variable)) = context.shell.env.get_mut(s) {
+                        if self.unexport {
+                            variable.unexport();
+                        } else {
                             variable.export();
                         }
+                    } else if !self.unexport {
+                        let mut variable = variables::ShellVariable::new(
+                            variables::ShellValue::Unset(variables::ShellValueUnsetType::Untyped),
+                        );
+                        variable.export();
+                        context
+                            .shell
+                            .env
+                            .add(s, variable, EnvironmentScope::Global)?;
                     }
-                    commands::CommandArg::Assignment(assignment) => {
-                        let name = match &assignment.name {
-                            brush_parser::ast::AssignmentName::VariableName(name) => name,
-                            brush_parser::ast::AssignmentName::ArrayElementName(_, _) => {
-                                writeln!(context.stderr(), "not a valid variable name")?;
-                                return Ok(builtins::ExitCode::InvalidUsage);
-                            }
-                        };
+                }
+                commands::CommandArg::Assignment(assignment) => {
+                    let name = match &assignment.name {
+                        brush_parser::ast::AssignmentName::VariableName(name) => name,
+                        brush_parser::ast::AssignmentName::ArrayElementName(_, _) => {
+                            writeln!(context.stderr(), "not a valid variable name")?;
+                            return Ok(builtins::ExitCode::InvalidUsage);
+                        }
+                    };
 
-                        let value = match &assignment.value {
-                            brush_parser::ast::AssignmentValue::Scalar(s) => {
-                                variables::ShellValueLiteral::Scalar(s.flatten())
-                            }
-                            brush_parser::ast::AssignmentValue::Array(a) => {
-                                variables::ShellValueLiteral::Array(variables::ArrayLiteral(
-                                    a.iter()
-                                        .map(|(k, v)| {
-                                            (k.as_ref().map(|k| k.flatten()), v.flatten())
-                                        })
-                                        .collect(),
-                                ))
-                            }
-                        };
+                    let value = match &assignment.value {
+                        brush_parser::ast::AssignmentValue::Scalar(s) => {
+                            variables::ShellValueLiteral::Scalar(s.flatten())
+                        }
+                        brush_parser::ast::AssignmentValue::Array(a) => {
+                            variables::ShellValueLiteral::Array(variables::ArrayLiteral(
+                                a.iter()
+                                    .map(|(k, v)| (k.as_ref().map(|k| k.flatten()), v.flatten()))
+                                    .collect(),
+                            ))
+                        }
+                    };
 
-                        // Update the variable with the provided value and then mark it exported.
-                        context.shell.env.update_or_add(
-                            name,
-                            value,
-                            |var| {
+                    // Update the variable with the provided value and then update its export
+                    // attribute.
+                    let update_result = context.shell.env.update_or_add(
+                        name,
+                        value,
+                        |var| {
+                            if self.unexport {
+                                var.unexport();
+                            } else {
                                 var.export();
-                                Ok(())
-                            },
-                            EnvironmentLookup::Anywhere,
-                            EnvironmentScope::Global,
-                        )?;
+                            }
+                            Ok(())
+                        },
+                        EnvironmentLookup::Anywhere,
+                        EnvironmentScope::Global,
+                    );
+
+                    match update_result {
+                        Ok(()) => (),
+                        Err(error::Error::ReadonlyVariable) => {
+                            writeln!(context.stderr(), "{name}: readonly variable")?;
+                            result = builtins::ExitCode::Custom(1);
+                        }
+                        Err(e) => return Err(e),
                     }
                 }
             }
-        } else {
-            // Enumerate variables, sorted by key.
-            for (name, variable) in context.shell.env.iter().sorted_by_key(|v| v.0) {
-                if variable.is_exported() {
-                    writeln!(
-                        context.stdout(),
-                        "declare -x {}=\"{}\"",
-                        name,
-                        variable.value().to_cow_string()
-                    )?;
-                }
+        }
+
+        Ok(result)
+    }
+}
+
+impl ExportCommand {
+    fn display_exported_variables(
+        &self,
+        context: &commands::ExecutionContext<'_>,
+    ) -> Result<(), error::Error> {
+        // Enumerate variables, sorted by key.
+        for (name, variable) in context.shell.env.iter().sorted_by_key(|v| v.0) {
+            if !variable.is_exported() {
+                continue;
             }
+
+            let value = variable.resolve_value(context.shell);
+            let assigned_value = if matches!(value.as_ref(), variables::ShellValue::Unset(_)) {
+                String::new()
+            } else {
+                std::format!("={}", value.format(variables::FormatStyle::DeclarePrint)?)
+            };
+
+            writeln!(
+                context.stdout(),
+                "declare -{} {name}{assigned_value}",
+                variable.get_attribute_flags()
+            )?;
         }
 
-        Ok(builtins::ExitCode::Success)
+        Ok(())
     }
 }
@@ -219,10 +219,8 @@ pub(crate) fn get_default_builtins(
         "readonly".into(),
         special_decl_builtin::<declare::DeclareCommand>(),
     );
-    m.insert(
-        "times".into(),
-        special_builtin::<unimp::UnimplementedCommand>(),
-    );
+    #[cfg(unix)]
+    m.insert("times".into(), special_builtin::<times::TimesCommand>());
 
     //
     // Non-special builtins
@@ -233,8 +231,10 @@ pub(crate) fn get_default_builtins(
     m.insert("cd".into(), builtin::<cd::CdCommand>());
     m.insert("command".into(), builtin::<command::CommandCommand>());
     m.insert("false".into(), builtin::<false_::FalseCommand>());
+    m.insert("fc".into(), builtin::<fc::FcCommand>());
     m.insert("fg".into(), builtin::<fg::FgCommand>());
     m.insert("getopts".into(), builtin::<getopts::GetOptsCommand>());
+    m.insert("hash".into(), builtin::<hash::HashCommand>());
     m.insert("help".into(), builtin::<help::HelpCommand>());
     m.insert("jobs".into(), builtin::<jobs::JobsCommand>());
     #[cfg(unix)]
@@ -246,21 +246,21 @@ pub(crate) fn get_default_builtins(
     m.insert("type".into(), builtin::<type_::TypeCommand>());
     #[cfg(unix)]
     m.insert("umask".into(), builtin::<umask::UmaskCommand>());
+    #[cfg(unix)]
+    m.insert("ulimit".into(), builtin::<ulimit::UlimitCommand>());
     m.insert("unalias".into(), builtin::<unalias::UnaliasCommand>());
     m.insert("wait".into(), builtin::<wait::WaitCommand>());
 
-    // TODO: Unimplemented non-special builtins
-    m.insert("fc".into(), builtin::<unimp::UnimplementedCommand>());
-    m.insert("hash".into(), builtin::<unimp::UnimplementedCommand>());
-    m.insert("ulimit".into(), builtin::<unimp::UnimplementedCommand>());
-
     if !options.sh_mode {
         m.insert("builtin".into(), builtin::<builtin_::BuiltinCommand>());
+        m.insert("caller".into(), builtin::<caller::CallerCommand>());
         m.insert("declare".into(), decl_builtin::<declare::DeclareCommand>());
         m.insert("echo".into(), builtin::<echo::EchoCommand>());
         m.insert("enable".into(), builtin::<enable::EnableCommand>());
         m.insert("let".into(), builtin::<let_::LetCommand>());
+        m.insert("mapfile".into(), builtin::<mapfile::MapfileCommand>());
         m.insert("printf".into(), builtin::<printf::PrintfCommand>());
+        m.insert("readarray".into(), builtin::<mapfile::MapfileCommand>());
         m.insert("shopt".into(), builtin::<shopt::ShoptCommand>());
         m.insert("source".into(), special_builtin::<dot::DotCommand>());
         m.insert("test".into(), builtin::<test::TestCommand>());
@@ -277,15 +277,16 @@ pub(crate) fn get_default_builtins(
         m.insert("popd".into(), builtin::<popd::PopdCommand>());
         m.insert("pushd".into(), builtin::<pushd::PushdCommand>());
 
+        // History builtins
+        m.insert("history".into(), builtin::<history::HistoryCommand>());
+
+        // Job control builtins
+        m.insert("disown".into(), builtin::<disown::DisownCommand>());
+        m.insert("suspend".into(), builtin::<suspend::SuspendCommand>());
+
         // TODO: Unimplemented builtins
         m.insert("bind".into(), builtin::<unimp::UnimplementedCommand>());
-        m.insert("caller".into(), builtin::<unimp::UnimplementedCommand>());
-        m.insert("disown".into(), builtin::<unimp::UnimplementedCommand>());
-        m.insert("history".into(), builtin::<unimp::UnimplementedCommand>());
         m.insert("logout".into(), builtin::<unimp::UnimplementedCommand>());
-        m.insert("mapfile".into(), builtin::<unimp::UnimplementedCommand>());
-        m.insert("readarray".into(), builtin::<unimp::UnimplementedCommand>());
-        m.insert("suspend".into(), builtin::<unimp::UnimplementedCommand>());
     }
 
     //
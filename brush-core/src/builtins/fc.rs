@@ -0,0 +1,385 @@
+use clap::Parser;
+use std::io::Write;
+
+use crate::{builtins, commands, error, escape, history, ExecutionResult, Shell};
+
+/// The number of commands listed by `fc -l` when no range is given.
+const DEFAULT_LIST_COUNT: usize = 16;
+
+/// Display, edit, or re-execute commands from the history list.
+#[derive(Parser)]
+pub(crate) struct FcCommand {
+    /// Editor to use for editing the commands; `-` re-executes them without editing.
+    #[arg(short = 'e', value_name = "ENAME")]
+    editor: Option<String>,
+
+    /// List the commands instead of editing them.
+    #[arg(short = 'l')]
+    list: bool,
+
+    /// Omit history numbers when listing.
+    #[arg(short = 'n')]
+    omit_numbers: bool,
+
+    /// Reverse the order in which commands are listed.
+    #[arg(short = 'r')]
+    reverse: bool,
+
+    /// Re-execute a command after applying any `old=new` substitutions.
+    #[arg(short = 's')]
+    reexecute: bool,
+
+    /// Range of commands to operate on; with `-s`, substitutions followed by a command.
+    #[arg(allow_negative_numbers = true)]
+    args: Vec<String>,
+}
+
+#[async_trait::async_trait]
+impl builtins::Command for FcCommand {
+    async fn execute(
+        &self,
+        mut context: commands::ExecutionContext<'_>,
+    ) -> Result<crate::builtins::ExitCode, crate::error::Error> {
+        let selection = Selection::new(context.shell);
+
+        if self.list {
+            self.display_commands(&context, &selection)
+        } else if self.reexecute || self.editor.as_deref() == Some("-") {
+            self.reexecute_command(&mut context, &selection).await
+        } else {
+            self.edit_and_execute_commands(&mut context, &selection)
+                .await
+        }
+    }
+}
+
+impl FcCommand {
+    fn display_commands(
+        &self,
+        context: &commands::ExecutionContext<'_>,
+        selection: &Selection,
+    ) -> Result<builtins::ExitCode, error::Error> {
+        let history = &context.shell.history;
+
+        let (first, last) = match self.args.as_slice() {
+            [] => {
+                let Some(last) = selection.last else {
+                    return Ok(builtins::ExitCode::Success);
+                };
+                let first = last
+                    .saturating_sub(DEFAULT_LIST_COUNT - 1)
+                    .max(selection.first);
+                (Some(first), Some(last))
+            }
+            [first, rest @ ..] => {
+                let first = selection.resolve(history, first, selection.first);
+                let last = match rest.first() {
+                    Some(last) => selection.resolve(history, last, selection.default_last()),
+                    None if first == Some(selection.current) => first,
+                    None => Some(selection.default_last()),
+                };
+                (first, last)
+            }
+        };
+
+        let (Some(first), Some(last)) = (first, last) else {
+            return report_no_command_found(context);
+        };
+
+        for (number, command) in select_commands(history, first, last, self.reverse) {
+            if self.omit_numbers {
+                writeln!(context.stdout(), "\t {command}")?;
+            } else {
+                writeln!(context.stdout(), "{number}\t {command}")?;
+            }
+        }
+
+        Ok(builtins::ExitCode::Success)
+    }
+
+    async fn reexecute_command(
+        &self,
+        context: &mut commands::ExecutionContext<'_>,
+        selection: &Selection,
+    ) -> Result<builtins::ExitCode, error::Error> {
+        // Leading arguments of the form `old=new` are substitutions; anything after them
+        // identifies the command to re-execute.
+        let substitution_count = self.args.iter().take_while(|arg| arg.contains('=')).count();
+        let (substitutions, spec) = self.args.split_at(substitution_count);
+
+        let number = match spec.first() {
+            Some(spec) => selection.resolve_executable(&context.shell.history, spec),
+            None => selection.last,
+        };
+
+        let Some(mut command) = number
+            .and_then(|number| context.shell.history.get(number))
+            .map(ToOwned::to_owned)
+        else {
+            return report_no_command_found(context);
+        };
+
+        for substitution in substitutions {
+            if let Some((old, new)) = substitution.split_once('=') {
+                if !old.is_empty() {
+                    command = command.replace(old, new);
+                }
+            }
+        }
+
+        // The re-executed command takes the place of the `fc` command in the history list.
+        if selection.recorded {
+            context.shell.history.replace_last(command.clone());
+        }
+
+        writeln!(context.stderr(), "{command}")?;
+
+        Ok(run_commands(context, command).await?.into())
+    }
+
+    async fn edit_and_execute_commands(
+        &self,
+        context: &mut commands::ExecutionContext<'_>,
+        selection: &Selection,
+    ) -> Result<builtins::ExitCode, error::Error> {
+        let history = &context.shell.history;
+        let first = match self.args.first() {
+            Some(first) => selection.resolve_executable(history, first),
+            None => selection.last,
+        };
+
+        let last = match self.args.get(1) {
+            Some(last) => selection.resolve_executable(history, last),
+            None => first,
+        };
+
+        let (Some(first), Some(last)) = (first, last) else {
+            return report_no_command_found(context);
+        };
+
+        let mut contents = String::new();
+        for (_, command) in select_commands(history, first, last, self.reverse) {
+            contents.push_str(command);
+            contents.push('\n');
+        }
+
+        // The `fc` command is replaced in the history list by the commands it executes.
+        if selection.recorded {
+            context.shell.history.remove_last();
+        }
+
+        let editor = self.editor.clone().unwrap_or_else(|| {
+            ["FCEDIT", "EDITOR"]
+                .into_iter()
+                .find_map(|name| {
+                    context
+                        .shell
+                        .env
+                        .get_str(name)
+                        .filter(|value| !value.is_empty())
+                        .map(|value| value.into_owned())
+                })
+                .unwrap_or_else(|| String::from("vi"))
+        });
+
+        let (file_path, mut file) = create_editor_file()?;
+        file.write_all(contents.as_bytes())?;
+        drop(file);
+
+        // Invoke the editor on the file, then read back whatever it left there.
+        let editor_command = std::format!(
+            "{editor} {}",
+            escape::single_quote(file_path.to_string_lossy().as_ref())
+        );
+        let edited = run_commands(context, editor_command)
+            .await
+            .and_then(|result| {
+                if result.exit_code == 0 {
+                    Ok(Some(std::fs::read_to_string(&file_path)?))
+                } else {
+                    Ok(None)
+                }
+            });
+
+        let _ = std::fs::remove_file(&file_path);
+
+        let Some(edited) = edited? else {
+            return Ok(builtins::ExitCode::Custom(1));
+        };
+
+        write!(context.stderr(), "{edited}")?;
+
+        for line in edited.lines() {
+            context.shell.add_to_history(line);
+        }
+
+        Ok(run_commands(context, edited).await?.into())
+    }
+}
+
+/// Creates a new, empty file in the temporary directory for the editor to operate on. The
+/// file is created exclusively, so an existing file (or symlink) at the chosen path is never
+/// opened in its place.
+fn create_editor_file() -> Result<(std::path::PathBuf, std::fs::File), error::Error> {
+    const MAX_ATTEMPTS: usize = 16;
+
+    let mut options = std::fs::File::options();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    let mut attempts = 0;
+    loop {
+        let file_path = std::env::temp_dir().join(std::format!(
+            "brush-fc-{}-{}",
+            std::process::id(),
+            rand::random::<u32>()
+        ));
+
+        match options.open(&file_path) {
+            Ok(file) => return Ok((file_path, file)),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists && attempts < MAX_ATTEMPTS => {
+                attempts += 1;
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+/// Describes the portion of the history list that `fc` may select commands from.
+struct Selection {
+    /// History number of the oldest command in the list.
+    first: usize,
+    /// History number of the most recent command preceding the `fc` command, if any.
+    last: Option<usize>,
+    /// History number of the `fc` command itself.
+    current: usize,
+    /// Whether the `fc` command was itself recorded in the history list.
+    recorded: bool,
+}
+
+impl Selection {
+    fn new(shell: &Shell) -> Self {
+        let first = shell.history.first_number();
+        let recorded = shell.options.enable_command_history && !shell.history.is_empty();
+
+        let current = match shell.history.last_number() {
+            Some(last) if recorded => last,
+            Some(last) => last + 1,
+            None => first,
+        };
+
+        let last = current.checked_sub(1).filter(|last| *last >= first);
+
+        Self {
+            first,
+            last,
+            current,
+            recorded,
+        }
+    }
+
+    /// Returns the number used in place of a missing or out-of-range range end.
+    fn default_last(&self) -> usize {
+        self.last.unwrap_or(self.current)
+    }
+
+    /// Resolves a command specification to a history number. Negative numbers are
+    /// relative to the `fc` command, positive numbers are absolute, and strings select
+    /// the most recent command starting with them.
+    ///
+    /// # Arguments
+    ///
+    /// * `history` - The history list.
+    /// * `spec` - The specification to resolve.
+    /// * `out_of_range` - The number to use if `spec` is a number beyond the list.
+    fn resolve(
+        &self,
+        history: &history::History,
+        spec: &str,
+        out_of_range: usize,
+    ) -> Option<usize> {
+        if let Ok(n) = spec.parse::<i64>() {
+            let offset = usize::try_from(n.unsigned_abs()).unwrap_or(usize::MAX);
+            let number = match n {
+                n if n < 0 => self.current.saturating_sub(offset).max(self.first),
+                0 => self.default_last(),
+                _ if (self.first..=self.current).contains(&offset) => offset,
+                _ => out_of_range,
+            };
+
+            Some(number)
+        } else {
+            let last = self.last?;
+            history
+                .iter()
+                .rev()
+                .find(|(number, command)| *number <= last && command.starts_with(spec))
+                .map(|(number, _)| number)
+        }
+    }
+
+    /// Resolves a command specification to the history number of a command that may be
+    /// re-executed; unlike when listing, the `fc` command itself can't be selected.
+    ///
+    /// # Arguments
+    ///
+    /// * `history` - The history list.
+    /// * `spec` - The specification to resolve.
+    fn resolve_executable(&self, history: &history::History, spec: &str) -> Option<usize> {
+        let last = self.last?;
+        self.resolve(history, spec, last)
+            .map(|number| number.min(last))
+    }
+}
+
+fn select_commands(
+    history: &history::History,
+    first: usize,
+    last: usize,
+    reverse: bool,
+) -> Vec<(usize, &str)> {
+    let (low, high) = if first <= last {
+        (first, last)
+    } else {
+        (last, first)
+    };
+
+    let mut commands: Vec<_> = history
+        .iter()
+        .filter(|(number, _)| (low..=high).contains(number))
+        .collect();
+
+    if (first > last) != reverse {
+        commands.reverse();
+    }
+
+    commands
+}
+
+async fn run_commands(
+    context: &mut commands::ExecutionContext<'_>,
+    command_string: String,
+) -> Result<ExecutionResult, error::Error> {
+    let parse_result = context.shell.parse_string(command_string);
+    let source_info = brush_parser::SourceInfo {
+        source: context.command_name.clone(),
+    };
+
+    let params = context.params.clone();
+    context
+        .shell
+        .run_parsed_result(parse_result, &source_info, &params)
+        .await
+}
+
+fn report_no_command_found(
+    context: &commands::ExecutionContext<'_>,
+) -> Result<builtins::ExitCode, error::Error> {
+    writeln!(
+        context.stderr(),
+        "{}: no command found",
+        context.command_name
+    )?;
+    Ok(builtins::ExitCode::Custom(1))
+}
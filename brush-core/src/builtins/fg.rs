@@ -16,54 +16,46 @@ impl builtins::Command for FgCommand {
         &self,
         context: commands::ExecutionContext<'_>,
     ) -> Result<crate::builtins::ExitCode, crate::error::Error> {
-        let mut stderr = context.stdout();
+        let mut stdout = context.stdout();
+        let mut stderr = context.stderr();
 
-        if let Some(job_spec) = &self.job_spec {
-            if let Some(job) = context.shell.jobs.resolve_job_spec(job_spec) {
-                job.move_to_foreground()?;
-                writeln!(stderr, "{}", job.command_line)?;
-
-                let result = job.wait().await?;
-                if context.shell.options.interactive {
-                    sys::terminal::move_self_to_foreground()?;
-                }
-
-                if matches!(job.state, jobs::JobState::Stopped) {
-                    // N.B. We use the '\r' to overwrite any ^Z output.
-                    let formatted = job.to_string();
-                    writeln!(context.stderr(), "\r{formatted}")?;
-                }
+        if !context.shell.options.enable_job_control {
+            writeln!(stderr, "{}: no job control", context.command_name)?;
+            return Ok(builtins::ExitCode::Custom(1));
+        }
 
-                Ok(builtins::ExitCode::from(result))
-            } else {
-                writeln!(
-                    stderr,
-                    "{}: {}: no such job",
-                    job_spec, context.command_name
-                )?;
-                Ok(builtins::ExitCode::Custom(1))
+        let id = match context
+            .shell
+            .jobs
+            .resolve_job_spec_or_current(self.job_spec.as_deref())
+        {
+            Ok(id) => id,
+            Err(e) => {
+                writeln!(stderr, "{}: {e}", context.command_name)?;
+                return Ok(builtins::ExitCode::Custom(1));
             }
-        } else {
-            if let Some(job) = context.shell.jobs.current_job_mut() {
-                job.move_to_foreground()?;
-                writeln!(stderr, "{}", job.command_line)?;
+        };
 
-                let result = job.wait().await?;
-                if context.shell.options.interactive {
-                    sys::terminal::move_self_to_foreground()?;
-                }
+        let Some(job) = context.shell.jobs.get_mut(id) else {
+            return Ok(builtins::ExitCode::Custom(1));
+        };
 
-                if matches!(job.state, jobs::JobState::Stopped) {
-                    // N.B. We use the '\r' to overwrite any ^Z output.
-                    let formatted = job.to_string();
-                    writeln!(context.stderr(), "\r{formatted}")?;
-                }
+        job.move_to_foreground()?;
+        writeln!(stdout, "{}", job.command_line)?;
 
-                Ok(builtins::ExitCode::from(result))
-            } else {
-                writeln!(stderr, "{}: no current job", context.command_name)?;
-                Ok(builtins::ExitCode::Custom(1))
-            }
+        let result = job.wait().await?;
+        if context.shell.options.interactive {
+            sys::terminal::move_self_to_foreground()?;
         }
+
+        if matches!(job.state, jobs::JobState::Stopped) {
+            // N.B. We use the '\r' to overwrite any ^Z output.
+            writeln!(stderr, "\r{job}")?;
+        } else {
+            // The job has completed; it no longer needs to be tracked.
+            context.shell.jobs.remove(id);
+        }
+
+        Ok(builtins::ExitCode::from(result))
     }
 }
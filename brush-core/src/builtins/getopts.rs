@@ -1,8 +1,9 @@
-use std::{borrow::Cow, collections::HashMap};
+use std::collections::HashMap;
+use std::io::Write;
 
 use clap::Parser;
 
-use crate::{builtins, commands, variables};
+use crate::{builtins, commands, env, error, variables};
 
 /// Parse command options.
 #[derive(Parser)]
@@ -13,7 +14,8 @@ pub(crate) struct GetOptsCommand {
     /// Name of variable to receive next option
     variable_name: String,
 
-    /// Arguments to parse
+    /// Arguments to parse; if none are provided, the positional parameters
+    /// are parsed instead.
     #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
     args: Vec<String>,
 }
@@ -24,87 +26,184 @@ impl builtins::Command for GetOptsCommand {
         &self,
         context: commands::ExecutionContext<'_>,
     ) -> Result<crate::builtins::ExitCode, crate::error::Error> {
-        let mut args = HashMap::<char, bool>::new();
-
-        // Build the args map
+        // A leading colon selects silent error reporting.
+        let (silent, options_string) = match self.options_string.strip_prefix(':') {
+            Some(options_string) => (true, options_string),
+            None => (false, self.options_string.as_str()),
+        };
+
+        // Setting OPTERR to 0 suppresses error messages without otherwise changing how
+        // errors are reported.
+        let print_errors = !silent && opterr_enabled(context.shell);
+
+        // Build the map of option characters to whether or not they take an argument.
+        let mut option_specs = HashMap::<char, bool>::new();
         let mut last_char = None;
-        for c in self.options_string.chars() {
+        for c in options_string.chars() {
             if c == ':' {
                 if let Some(last_char) = last_char {
-                    args.insert(last_char, true);
+                    option_specs.insert(last_char, true);
                     continue;
                 } else {
                     return Ok(builtins::ExitCode::InvalidUsage);
                 }
             }
 
-            args.insert(c, false);
+            option_specs.insert(c, false);
             last_char = Some(c);
         }
 
-        // TODO: OPTIND is supposed to be initialized to 1 in each shell/script.
+        let args = if self.args.is_empty() {
+            context.shell.positional_parameters.clone()
+        } else {
+            self.args.clone()
+        };
+
         let mut next_index: usize = context
             .shell
             .env
             .get_str("OPTIND")
-            .unwrap_or(Cow::Borrowed("1"))
-            .parse()?;
-
+            .map_or(Ok(1), |s| s.parse())?;
         if next_index < 1 {
-            return Ok(builtins::ExitCode::InvalidUsage);
+            next_index = 1;
         }
 
-        let mut next_index_zero_based = next_index - 1;
-        if next_index_zero_based >= self.args.len() {
-            return Ok(builtins::ExitCode::Custom(1));
-        }
+        // Resume within a group of options (e.g., `-abc`), unless OPTIND has since
+        // been changed out from under us.
+        let mut char_index = context
+            .shell
+            .getopts_char_position
+            .filter(|(saved_index, _)| *saved_index == next_index)
+            .map_or(1, |(_, char_index)| char_index);
 
-        let arg = self.args[next_index_zero_based].as_str();
-        if !arg.starts_with('-') || arg.len() != 2 {
-            return Ok(builtins::ExitCode::Custom(1));
-        }
+        let arg = args.get(next_index - 1).map_or("", |arg| arg.as_str());
 
-        // Single character option
-        let c = arg.chars().nth(1).unwrap();
-        if let Some(takes_arg) = args.get(&c) {
-            if *takes_arg {
+        // Check for the end of options.
+        if char_index == 1 {
+            if arg == "--" {
                 next_index += 1;
-                next_index_zero_based += 1;
+            }
 
-                if next_index_zero_based >= self.args.len() {
-                    return Ok(builtins::ExitCode::Custom(1));
+            if !arg.starts_with('-') || arg.len() < 2 || arg == "--" {
+                return finish_options(context.shell, self.variable_name.as_str(), next_index);
+            }
+        }
+
+        let arg_chars: Vec<char> = arg.chars().collect();
+        let Some(c) = arg_chars.get(char_index).copied() else {
+            return finish_options(context.shell, self.variable_name.as_str(), next_index);
+        };
+
+        char_index += 1;
+        let mut at_end_of_arg = char_index >= arg_chars.len();
+
+        let mut option_arg = None;
+        let variable_value = match option_specs.get(&c) {
+            Some(false) => c,
+            Some(true) => {
+                if !at_end_of_arg {
+                    // The rest of this argument is the option's argument.
+                    option_arg = Some(arg_chars[char_index..].iter().collect());
+                    at_end_of_arg = true;
+                    c
+                } else if let Some(next_arg) = args.get(next_index) {
+                    option_arg = Some(next_arg.clone());
+                    next_index += 1;
+                    c
+                } else if silent {
+                    option_arg = Some(c.to_string());
+                    ':'
+                } else {
+                    if print_errors {
+                        writeln!(
+                            context.stderr(),
+                            "{}: option requires an argument -- {c}",
+                            get_program_name(context.shell)
+                        )?;
+                    }
+                    '?'
                 }
+            }
+            None => {
+                if silent {
+                    option_arg = Some(c.to_string());
+                } else if print_errors {
+                    writeln!(
+                        context.stderr(),
+                        "{}: illegal option -- {c}",
+                        get_program_name(context.shell)
+                    )?;
+                }
+                '?'
+            }
+        };
 
-                let opt_arg = self.args[next_index_zero_based].as_str();
+        if at_end_of_arg {
+            next_index += 1;
+            char_index = 1;
+        }
 
-                context.shell.env.update_or_add(
-                    "OPTARG",
-                    variables::ShellValueLiteral::Scalar(opt_arg.to_owned()),
-                    |_| Ok(()),
-                    crate::env::EnvironmentLookup::Anywhere,
-                    crate::env::EnvironmentScope::Global,
-                )?;
-            }
+        context.shell.getopts_char_position = if char_index > 1 {
+            Some((next_index, char_index))
+        } else {
+            None
+        };
 
-            context.shell.env.update_or_add(
-                "OPTIND",
-                variables::ShellValueLiteral::Scalar((next_index + 1).to_string()),
-                |_| Ok(()),
-                crate::env::EnvironmentLookup::Anywhere,
-                crate::env::EnvironmentScope::Global,
-            )?;
-
-            context.shell.env.update_or_add(
-                self.variable_name.as_str(),
-                variables::ShellValueLiteral::Scalar(c.to_string()),
-                |_| Ok(()),
-                crate::env::EnvironmentLookup::Anywhere,
-                crate::env::EnvironmentScope::Global,
-            )?;
+        if let Some(option_arg) = option_arg {
+            set_var(context.shell, "OPTARG", option_arg)?;
         } else {
-            return Ok(builtins::ExitCode::Custom(1));
+            context.shell.env.unset("OPTARG")?;
         }
 
+        set_var(context.shell, "OPTIND", next_index.to_string())?;
+        set_var(
+            context.shell,
+            self.variable_name.as_str(),
+            variable_value.to_string(),
+        )?;
+
         Ok(builtins::ExitCode::Success)
     }
 }
+
+/// Updates state to reflect that there are no more options to process.
+fn finish_options(
+    shell: &mut crate::Shell,
+    variable_name: &str,
+    next_index: usize,
+) -> Result<builtins::ExitCode, error::Error> {
+    shell.getopts_char_position = None;
+    shell.env.unset("OPTARG")?;
+
+    set_var(shell, "OPTIND", next_index.to_string())?;
+    set_var(shell, variable_name, String::from("?"))?;
+
+    Ok(builtins::ExitCode::Custom(1))
+}
+
+fn set_var(shell: &mut crate::Shell, name: &str, value: String) -> Result<(), error::Error> {
+    shell.env.update_or_add(
+        name,
+        variables::ShellValueLiteral::Scalar(value),
+        |_| Ok(()),
+        env::EnvironmentLookup::Anywhere,
+        env::EnvironmentScope::Global,
+    )
+}
+
+/// Returns whether `OPTERR` allows error messages; like bash, any value that doesn't
+/// start with a nonzero number disables them.
+fn opterr_enabled(shell: &crate::Shell) -> bool {
+    shell.env.get_str("OPTERR").map_or(true, |value| {
+        let digits: String = value
+            .trim_start()
+            .chars()
+            .take_while(char::is_ascii_digit)
+            .collect();
+        value.is_empty() || digits.parse::<u64>().is_ok_and(|n| n != 0)
+    })
+}
+
+fn get_program_name(shell: &crate::Shell) -> &str {
+    shell.shell_name.as_deref().unwrap_or_default()
+}
@@ -0,0 +1,166 @@
+use clap::Parser;
+use itertools::Itertools;
+use std::{io::Write, path::PathBuf};
+
+use crate::{builtins, commands};
+
+/// Manage the shell's cache of program locations.
+#[derive(Parser)]
+pub(crate) struct HashCommand {
+    /// Forget the cached locations of the named programs.
+    #[arg(short = 'd')]
+    remove: bool,
+
+    /// Display the cache in a reusable format.
+    #[arg(short = 'l')]
+    display_as_usable_input: bool,
+
+    /// Use the given path as the location of the named programs.
+    #[arg(short = 'p')]
+    path_to_use: Option<PathBuf>,
+
+    /// Forget all cached locations.
+    #[arg(short = 'r')]
+    remove_all: bool,
+
+    /// Display the cached locations of the named programs.
+    #[arg(short = 't')]
+    display_paths: bool,
+
+    /// Names of programs to operate on.
+    names: Vec<String>,
+}
+
+#[async_trait::async_trait]
+impl builtins::Command for HashCommand {
+    async fn execute(
+        &self,
+        context: commands::ExecutionContext<'_>,
+    ) -> Result<crate::builtins::ExitCode, crate::error::Error> {
+        let mut result = builtins::ExitCode::Success;
+
+        // Make sure we don't report entries resolved against a stale PATH.
+        let search_path = context
+            .shell
+            .env
+            .get_str("PATH")
+            .unwrap_or_default()
+            .into_owned();
+        context
+            .shell
+            .program_location_cache
+            .sync_with_search_path(search_path.as_str());
+
+        if self.remove_all {
+            context.shell.program_location_cache.reset();
+        }
+
+        if self.names.is_empty() && (self.remove || self.display_paths) {
+            let option = if self.remove { 'd' } else { 't' };
+            writeln!(
+                context.stderr(),
+                "{}: -{option}: option requires an argument",
+                context.command_name
+            )?;
+            return Ok(builtins::ExitCode::Custom(1));
+        }
+
+        if self.names.is_empty() {
+            if !self.remove_all {
+                self.display_cache(&context)?;
+            }
+
+            return Ok(result);
+        }
+
+        for name in &self.names {
+            if self.remove {
+                if !context.shell.program_location_cache.remove(name) {
+                    writeln!(
+                        context.stderr(),
+                        "{}: {name}: not found",
+                        context.command_name
+                    )?;
+                    result = builtins::ExitCode::Custom(1);
+                }
+            } else if self.display_paths {
+                if let Some(entry) = context.shell.program_location_cache.get(name) {
+                    if self.names.len() > 1 {
+                        writeln!(context.stdout(), "{name}\t{}", entry.path.to_string_lossy())?;
+                    } else {
+                        writeln!(context.stdout(), "{}", entry.path.to_string_lossy())?;
+                    }
+                } else {
+                    writeln!(
+                        context.stderr(),
+                        "{}: {name}: not found",
+                        context.command_name
+                    )?;
+                    result = builtins::ExitCode::Custom(1);
+                }
+            } else if let Some(path) = &self.path_to_use {
+                context
+                    .shell
+                    .program_location_cache
+                    .set(name.clone(), path.clone());
+            } else if name.contains(std::path::MAIN_SEPARATOR)
+                || context.shell.funcs.get(name).is_some()
+                || context
+                    .shell
+                    .builtins
+                    .get(name)
+                    .is_some_and(|b| !b.disabled)
+            {
+                // Nothing to cache.
+            } else if let Some(path) = context.shell.find_first_executable_in_path(name) {
+                context.shell.program_location_cache.set(name.clone(), path);
+            } else {
+                writeln!(
+                    context.stderr(),
+                    "{}: {name}: not found",
+                    context.command_name
+                )?;
+                result = builtins::ExitCode::Custom(1);
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+impl HashCommand {
+    fn display_cache(
+        &self,
+        context: &commands::ExecutionContext<'_>,
+    ) -> Result<(), crate::error::Error> {
+        let cache = &context.shell.program_location_cache;
+
+        if self.display_as_usable_input {
+            for (name, entry) in cache.iter().sorted_by_key(|(name, _)| *name) {
+                writeln!(
+                    context.stdout(),
+                    "builtin hash -p {} {name}",
+                    entry.path.to_string_lossy()
+                )?;
+            }
+        } else if cache.is_empty() {
+            writeln!(
+                context.stdout(),
+                "{}: hash table empty",
+                context.command_name
+            )?;
+        } else {
+            writeln!(context.stdout(), "hits\tcommand")?;
+            for (_, entry) in cache.iter().sorted_by_key(|(name, _)| *name) {
+                writeln!(
+                    context.stdout(),
+                    "{:4}\t{}",
+                    entry.hits,
+                    entry.path.to_string_lossy()
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,173 @@
+use clap::Parser;
+use std::{io::Write, path::PathBuf};
+
+use crate::{builtins, commands, error, history};
+
+/// Display or manipulate the history list.
+#[derive(Parser)]
+pub(crate) struct HistoryCommand {
+    /// Clear the history list.
+    #[arg(short = 'c')]
+    clear: bool,
+
+    /// Delete the entry at the given position; negative positions count back from the
+    /// end of the list.
+    #[arg(short = 'd', value_name = "OFFSET", allow_hyphen_values = true)]
+    delete_offset: Option<String>,
+
+    /// Append the entries added during this session to the history file.
+    #[arg(short = 'a')]
+    append_to_file: bool,
+
+    /// Read the history file, appending its contents to the list.
+    #[arg(short = 'r')]
+    read_from_file: bool,
+
+    /// Write the whole list to the history file.
+    #[arg(short = 'w')]
+    write_to_file: bool,
+
+    /// Store the arguments in the list as a single entry, in place of this command.
+    #[arg(short = 's')]
+    store: bool,
+
+    /// Number of entries to display; a history file path with `-a`, `-r`, or `-w`; or
+    /// the entry to store with `-s`.
+    #[arg(allow_hyphen_values = true)]
+    args: Vec<String>,
+}
+
+#[async_trait::async_trait]
+impl builtins::Command for HistoryCommand {
+    async fn execute(
+        &self,
+        mut context: commands::ExecutionContext<'_>,
+    ) -> Result<crate::builtins::ExitCode, crate::error::Error> {
+        if self.clear {
+            context.shell.history.clear();
+        }
+
+        if let Some(offset) = &self.delete_offset {
+            if !delete_entry(&mut context.shell.history, offset) {
+                writeln!(
+                    context.stderr(),
+                    "{}: {offset}: history position out of range",
+                    context.command_name
+                )?;
+                return Ok(builtins::ExitCode::Custom(1));
+            }
+        }
+
+        if self.append_to_file || self.read_from_file || self.write_to_file {
+            return self.update_file(&mut context);
+        }
+
+        if self.store {
+            if !self.args.is_empty() {
+                if context.shell.options.enable_command_history {
+                    context.shell.history.remove_last();
+                }
+
+                let max_entries = context.shell.get_history_limit("HISTSIZE");
+                context.shell.history.add(self.args.join(" "), max_entries);
+            }
+
+            return Ok(builtins::ExitCode::Success);
+        }
+
+        if self.clear || self.delete_offset.is_some() {
+            return Ok(builtins::ExitCode::Success);
+        }
+
+        self.display_entries(&context)
+    }
+}
+
+impl HistoryCommand {
+    fn display_entries(
+        &self,
+        context: &commands::ExecutionContext<'_>,
+    ) -> Result<builtins::ExitCode, error::Error> {
+        let count = match self.args.as_slice() {
+            [] => None,
+            [count] => {
+                if let Ok(count) = count.parse::<usize>() {
+                    Some(count)
+                } else {
+                    writeln!(
+                        context.stderr(),
+                        "{}: {count}: numeric argument required",
+                        context.command_name
+                    )?;
+                    return Ok(builtins::ExitCode::Custom(1));
+                }
+            }
+            _ => {
+                writeln!(
+                    context.stderr(),
+                    "{}: too many arguments",
+                    context.command_name
+                )?;
+                return Ok(builtins::ExitCode::Custom(1));
+            }
+        };
+
+        let history = &context.shell.history;
+        let skip_count = count.map_or(0, |count| history.len().saturating_sub(count));
+
+        for (number, command) in history.iter().skip(skip_count) {
+            writeln!(context.stdout(), "{number:5}  {command}")?;
+        }
+
+        Ok(builtins::ExitCode::Success)
+    }
+
+    fn update_file(
+        &self,
+        context: &mut commands::ExecutionContext<'_>,
+    ) -> Result<builtins::ExitCode, error::Error> {
+        let Some(path) = self
+            .args
+            .first()
+            .map(PathBuf::from)
+            .or_else(|| context.shell.get_history_file_path())
+        else {
+            return Ok(builtins::ExitCode::Success);
+        };
+
+        let result = if self.append_to_file {
+            context.shell.history.append_file(&path)
+        } else if self.read_from_file {
+            let max_entries = context.shell.get_history_limit("HISTSIZE");
+            context.shell.history.read_file(&path, None, max_entries)
+        } else {
+            context.shell.history.write_file(&path)
+        };
+
+        if let Err(e) = result {
+            writeln!(
+                context.stderr(),
+                "{}: {}: {e}",
+                context.command_name,
+                path.to_string_lossy()
+            )?;
+            return Ok(builtins::ExitCode::Custom(1));
+        }
+
+        Ok(builtins::ExitCode::Success)
+    }
+}
+
+/// Deletes the history entry at the given offset, returning whether the offset
+/// identified an entry.
+fn delete_entry(history: &mut history::History, offset: &str) -> bool {
+    let number = match offset.parse::<i64>() {
+        Ok(offset) if offset < 0 => usize::try_from(offset.unsigned_abs())
+            .ok()
+            .and_then(|from_end| history.next_number().checked_sub(from_end)),
+        Ok(offset) => usize::try_from(offset).ok(),
+        Err(_) => None,
+    };
+
+    number.is_some_and(|number| history.remove(number))
+}
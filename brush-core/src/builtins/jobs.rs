@@ -14,7 +14,7 @@ pub(crate) struct JobsCommand {
     #[arg(short = 'n')]
     list_changed_only: bool,
 
-    /// Show only process IDs.
+    /// Show only process group IDs.
     #[arg(short = 'p')]
     show_pids_only: bool,
 
@@ -37,9 +37,6 @@ impl builtins::Command for JobsCommand {
         &self,
         context: commands::ExecutionContext<'_>,
     ) -> Result<crate::builtins::ExitCode, crate::error::Error> {
-        if self.also_show_pids {
-            return error::unimp("jobs -l");
-        }
         if self.list_changed_only {
             return error::unimp("jobs -n");
         }
@@ -49,7 +46,23 @@ impl builtins::Command for JobsCommand {
                 self.display_job(&context, job)?;
             }
         } else {
-            return error::unimp("jobs with job specs");
+            let mut exit_code = builtins::ExitCode::Success;
+
+            for job_spec in &self.job_specs {
+                match context.shell.jobs.resolve_job_spec_to_id(job_spec) {
+                    Ok(id) => {
+                        if let Some(job) = context.shell.jobs.jobs.iter().find(|j| j.id == id) {
+                            self.display_job(&context, job)?;
+                        }
+                    }
+                    Err(e) => {
+                        writeln!(context.stderr(), "{}: {e}", context.command_name)?;
+                        exit_code = builtins::ExitCode::Custom(1);
+                    }
+                }
+            }
+
+            return Ok(exit_code);
         }
 
         Ok(builtins::ExitCode::Success)
@@ -70,9 +83,14 @@ impl JobsCommand {
         }
 
         if self.show_pids_only {
-            if let Some(pid) = job.get_representative_pid() {
-                writeln!(context.stdout(), "{pid}")?;
+            if let Some(pgid) = job
+                .get_process_group_id()
+                .or_else(|| job.get_synthetic_pid())
+            {
+                writeln!(context.stdout(), "{pgid}")?;
             }
+        } else if self.also_show_pids {
+            writeln!(context.stdout(), "{}", job.to_long_string())?;
         } else {
             writeln!(context.stdout(), "{job}")?;
         }
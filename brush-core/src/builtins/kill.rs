@@ -1,7 +1,10 @@
 use clap::Parser;
 use std::io::Write;
 
-use crate::{builtins, commands, error};
+use crate::{builtins, commands, error, sys, traps};
+
+const USAGE: &str =
+    "kill [-s sigspec | -n signum | -sigspec] pid | jobspec ... or kill -l [sigspec]";
 
 /// Signal a job or process.
 #[derive(Parser)]
@@ -12,65 +15,227 @@ pub(crate) struct KillCommand {
 
     /// Number of the signal to send.
     #[arg(short = 'n')]
-    signal_number: Option<usize>,
+    signal_number: Option<String>,
 
-    //
-    // TODO: implement -sigspec syntax
-    //
     /// List known signal names.
     #[arg(short = 'l', short_alias = 'L')]
     list_signals: bool,
 
     // Interpretation of these depends on whether -l is present.
+    #[arg(allow_hyphen_values = true)]
     args: Vec<String>,
 }
 
 #[async_trait::async_trait]
 impl builtins::Command for KillCommand {
+    /// Override the default [builtins::Command::new] function to handle the `-sigspec`
+    /// syntax (e.g., `kill -9 ...` or `kill -TERM ...`), which clap can't express; we
+    /// rewrite it to the equivalent `-s sigspec`.
+    fn new<I>(args: I) -> Result<Self, clap::Error>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut args: Vec<_> = args.into_iter().collect();
+
+        if let Some(first_arg) = args.get(1) {
+            if let Some(sigspec) = first_arg.strip_prefix('-') {
+                if !sigspec.is_empty() && !matches!(sigspec, "s" | "n" | "l" | "L" | "-") {
+                    let sigspec = sigspec.to_owned();
+                    args[1] = String::from("-s");
+                    args.insert(2, sigspec);
+                }
+            }
+        }
+
+        Self::try_parse_from(args)
+    }
+
     async fn execute(
         &self,
         context: commands::ExecutionContext<'_>,
     ) -> Result<crate::builtins::ExitCode, crate::error::Error> {
-        if self.signal_name.is_some() {
-            return error::unimp("kill -s");
-        }
-        if self.signal_number.is_some() {
-            return error::unimp("kill -n");
-        }
-
         if self.list_signals {
-            return error::unimp("kill -l");
-        } else {
-            if self.args.len() != 1 {
-                writeln!(context.stderr(), "{}: invalid usage", context.command_name)?;
-                return Ok(builtins::ExitCode::InvalidUsage);
-            }
-
-            let exit_code = builtins::ExitCode::Success;
+            return self.display_signals(&context);
+        }
 
-            let pid_or_job_spec = &self.args[0];
-            if pid_or_job_spec.starts_with('%') {
-                // It's a job spec.
-                if let Some(job) = context.shell.jobs.resolve_job_spec(pid_or_job_spec) {
-                    job.kill()?;
+        let signal = match self.signal_name.as_ref().or(self.signal_number.as_ref()) {
+            Some(sigspec) => {
+                if let Some(signal) = parse_sendable_signal(sigspec) {
+                    signal
                 } else {
                     writeln!(
                         context.stderr(),
-                        "{}: {}: no such job",
-                        context.command_name,
-                        pid_or_job_spec
+                        "{}: {sigspec}: invalid signal specification",
+                        context.command_name
                     )?;
                     return Ok(builtins::ExitCode::Custom(1));
                 }
+            }
+            None => default_signal()?,
+        };
+
+        if self.args.is_empty() {
+            writeln!(context.stderr(), "{}: usage: {USAGE}", context.command_name)?;
+            return Ok(builtins::ExitCode::InvalidUsage);
+        }
+
+        let mut exit_code = builtins::ExitCode::Success;
+
+        for pid_or_job_spec in &self.args {
+            let result = if pid_or_job_spec.starts_with('%') {
+                // It's a job spec.
+                match context.shell.jobs.resolve_job_spec_to_id(pid_or_job_spec) {
+                    Ok(job_id) => context
+                        .shell
+                        .jobs
+                        .get_mut(job_id)
+                        .map_or(Err(error::Error::FailedToSendSignal), |job| {
+                            job.kill(signal)
+                        }),
+                    Err(e) => {
+                        writeln!(context.stderr(), "{}: {e}", context.command_name)?;
+                        exit_code = builtins::ExitCode::Custom(1);
+                        continue;
+                    }
+                }
+            } else if let Ok(pid) = pid_or_job_spec.parse::<sys::process::ProcessId>() {
+                // It's a pid; jobs that run within the shell can only be reached through
+                // their synthetic process IDs.
+                match context
+                    .shell
+                    .jobs
+                    .jobs
+                    .iter_mut()
+                    .find(|j| j.get_synthetic_pid() == Some(pid))
+                {
+                    Some(job) => job.kill(signal),
+                    None => sys::signal::kill_process(pid, signal),
+                }
             } else {
-                let pid = pid_or_job_spec.parse::<i32>()?;
+                writeln!(
+                    context.stderr(),
+                    "{}: {pid_or_job_spec}: arguments must be process or job IDs",
+                    context.command_name
+                )?;
+                exit_code = builtins::ExitCode::Custom(1);
+                continue;
+            };
 
-                // It's a pid.
-                nix::sys::signal::kill(nix::unistd::Pid::from_raw(pid), nix::sys::signal::SIGKILL)
-                    .map_err(|_errno| error::Error::FailedToSendSignal)?;
+            if let Err(e) = result {
+                writeln!(
+                    context.stderr(),
+                    "{}: ({pid_or_job_spec}) - {}",
+                    context.command_name,
+                    describe_error(&e)
+                )?;
+                exit_code = builtins::ExitCode::Custom(1);
             }
+        }
+
+        Ok(exit_code)
+    }
+}
 
-            Ok(exit_code)
+impl KillCommand {
+    fn display_signals(
+        &self,
+        context: &commands::ExecutionContext<'_>,
+    ) -> Result<builtins::ExitCode, error::Error> {
+        if self.args.is_empty() {
+            display_all_signals(context)?;
+            return Ok(builtins::ExitCode::Success);
         }
+
+        let mut exit_code = builtins::ExitCode::Success;
+
+        for arg in &self.args {
+            // Numbers are translated to names, and names to numbers. Numbers beyond the
+            // signal range are treated as exit statuses of processes killed by signals.
+            let output = if let Ok(number) = arg.parse::<i32>() {
+                let number = if number > 128 { number - 128 } else { number };
+                traps::parse_signal(number.to_string().as_str())
+                    .ok()
+                    .map(|signal| {
+                        let name = signal.to_string();
+                        name.strip_prefix("SIG").unwrap_or(name.as_str()).to_owned()
+                    })
+            } else {
+                parse_sendable_signal(arg)
+                    .and_then(signal_number)
+                    .map(|number| number.to_string())
+            };
+
+            if let Some(output) = output {
+                writeln!(context.stdout(), "{output}")?;
+            } else {
+                writeln!(
+                    context.stderr(),
+                    "{}: {arg}: invalid signal specification",
+                    context.command_name
+                )?;
+                exit_code = builtins::ExitCode::Custom(1);
+            }
+        }
+
+        Ok(exit_code)
+    }
+}
+
+fn display_all_signals(context: &commands::ExecutionContext<'_>) -> Result<(), error::Error> {
+    #[cfg(unix)]
+    {
+        let mut line = String::new();
+        for (i, signal) in nix::sys::signal::Signal::iterator().enumerate() {
+            if i % 5 > 0 {
+                line.push('\t');
+            }
+
+            line.push_str(std::format!("{:2}) {signal}", signal as i32).as_str());
+
+            if i % 5 == 4 {
+                writeln!(context.stdout(), "{line}")?;
+                line.clear();
+            }
+        }
+
+        if !line.is_empty() {
+            writeln!(context.stdout(), "{line}")?;
+        }
+    }
+
+    #[cfg(not(unix))]
+    let _ = context;
+
+    Ok(())
+}
+
+/// Parses a signal specification, only accepting signals that can be sent to a process
+/// (or `EXIT`, which is treated as signal 0).
+fn parse_sendable_signal(sigspec: &str) -> Option<traps::TrapSignal> {
+    match traps::parse_signal(sigspec) {
+        Ok(traps::TrapSignal::Debug | traps::TrapSignal::Err | traps::TrapSignal::Return)
+        | Err(_) => None,
+        Ok(signal) => Some(signal),
+    }
+}
+
+fn signal_number(signal: traps::TrapSignal) -> Option<i32> {
+    match signal {
+        #[cfg(unix)]
+        traps::TrapSignal::Signal(s) => Some(s as i32),
+        traps::TrapSignal::Exit => Some(0),
+        traps::TrapSignal::Debug | traps::TrapSignal::Err | traps::TrapSignal::Return => None,
+    }
+}
+
+fn default_signal() -> Result<traps::TrapSignal, error::Error> {
+    traps::parse_signal("TERM")
+}
+
+fn describe_error(e: &error::Error) -> String {
+    match e {
+        #[cfg(unix)]
+        error::Error::ErrnoError(errno) => errno.desc().to_owned(),
+        e => e.to_string(),
     }
 }
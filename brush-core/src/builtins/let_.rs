@@ -17,22 +17,42 @@ impl builtins::Command for LetCommand {
         &self,
         context: commands::ExecutionContext<'_>,
     ) -> Result<crate::builtins::ExitCode, crate::error::Error> {
-        let mut exit_code = builtins::ExitCode::InvalidUsage;
-
         if self.exprs.is_empty() {
-            writeln!(context.stderr(), "missing expression")?;
-            return Ok(exit_code);
+            writeln!(
+                context.stderr(),
+                "{}: expression expected",
+                context.command_name
+            )?;
+            return Ok(builtins::ExitCode::Custom(1));
         }
 
+        let mut exit_code = builtins::ExitCode::Success;
+
         for expr in &self.exprs {
-            let parsed = brush_parser::arithmetic::parse(expr.as_str())?;
-            let evaluated = parsed.eval(context.shell).await?;
+            let Ok(parsed) = brush_parser::arithmetic::parse(expr.as_str()) else {
+                writeln!(
+                    context.stderr(),
+                    "{}: {expr}: syntax error",
+                    context.command_name
+                )?;
+                return Ok(builtins::ExitCode::Custom(1));
+            };
+
+            // Side effects of any expressions evaluated before a failing one are kept.
+            let evaluated = match parsed.eval(context.shell).await {
+                Ok(evaluated) => evaluated,
+                Err(e) => {
+                    writeln!(context.stderr(), "{}: {expr}: {e}", context.command_name)?;
+                    return Ok(builtins::ExitCode::Custom(1));
+                }
+            };
 
-            if evaluated == 0 {
-                exit_code = builtins::ExitCode::Custom(1);
+            // Only the value of the last expression determines the exit code.
+            exit_code = if evaluated == 0 {
+                builtins::ExitCode::Custom(1)
             } else {
-                exit_code = builtins::ExitCode::Custom(0);
-            }
+                builtins::ExitCode::Success
+            };
         }
 
         Ok(exit_code)
@@ -0,0 +1,157 @@
+use std::io::Read;
+
+use crate::{error, openfiles};
+
+/// Describes why reading a line of input stopped.
+pub(crate) enum ReadTermination {
+    /// The delimiter was encountered.
+    Delimiter,
+    /// The end of input was reached.
+    EndOfInput,
+    /// Ctrl+C was pressed at a terminal.
+    CtrlC,
+    /// The character limit was reached.
+    Limit,
+    /// The timeout elapsed before the line was complete.
+    TimedOut,
+}
+
+/// Options for reading a line of input.
+pub(crate) struct ReadLineOptions {
+    /// The character terminating the line; if `None`, only the end of input or a
+    /// limit ends the line.
+    pub delimiter: Option<char>,
+    /// Optionally, the maximum number of characters to read.
+    pub char_limit: Option<usize>,
+    /// Whether a backslash escapes the character following it. Escaped characters
+    /// are returned with their backslash intact, while escaped newlines are removed.
+    pub backslash_escapes: bool,
+    /// Optionally, the maximum amount of time to wait for the line to be completed.
+    pub timeout: Option<std::time::Duration>,
+}
+
+/// Reads a line of input from the given file, one byte at a time so as not to
+/// consume any input past the end of the line. The delimiter is not included in
+/// the returned line.
+///
+/// # Arguments
+///
+/// * `input_file` - The file to read from.
+/// * `options` - Options controlling how the line is read.
+pub(crate) fn read_line(
+    input_file: &mut openfiles::OpenFile,
+    options: &ReadLineOptions,
+) -> Result<(String, ReadTermination), error::Error> {
+    // Control characters are only interpreted when typed at a terminal.
+    let is_term = input_file.is_term();
+
+    let mut line = String::new();
+    let mut char_count = 0;
+    let mut pending_bytes = vec![];
+    let mut escape_pending = false;
+    let mut buffer = [0; 1]; // 1-byte buffer
+
+    let deadline = options
+        .timeout
+        .map(|timeout| std::time::Instant::now() + timeout);
+
+    let reason = loop {
+        // Check to see if we've hit a character limit.
+        if let Some(char_limit) = options.char_limit {
+            if char_count >= char_limit {
+                break ReadTermination::Limit;
+            }
+        }
+
+        // Wait for input to become available, if we're under a deadline.
+        if let Some(deadline) = deadline {
+            let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+            if !input_file.poll_for_input(remaining)? {
+                break ReadTermination::TimedOut;
+            }
+        }
+
+        // Under a deadline, bytes read ahead into a buffer would be invisible to polling.
+        let n = if deadline.is_some() {
+            input_file.read_unbuffered(&mut buffer)?
+        } else {
+            input_file.read(&mut buffer)?
+        };
+        if n == 0 {
+            break ReadTermination::EndOfInput; // EOF reached.
+        }
+
+        // Accumulate the bytes of multi-byte characters until they're complete.
+        pending_bytes.push(buffer[0]);
+        let ch = match std::str::from_utf8(&pending_bytes) {
+            Ok(s) => s.chars().next().unwrap_or_default(),
+            Err(e) if e.error_len().is_none() => continue,
+            Err(_) => char::REPLACEMENT_CHARACTER,
+        };
+        pending_bytes.clear();
+
+        if is_term {
+            // Check for Ctrl+C.
+            if ch == '\x03' {
+                break ReadTermination::CtrlC;
+            } else if ch == '\x04' {
+                // Ctrl+D is EOF.
+                break ReadTermination::EndOfInput;
+            }
+        }
+
+        // A backslash escapes the character that follows it; an escaped newline is
+        // removed entirely, continuing the line.
+        if escape_pending {
+            escape_pending = false;
+            if ch != '\n' {
+                line.push('\\');
+                line.push(ch);
+                char_count += 1;
+            }
+            continue;
+        } else if ch == '\\' && options.backslash_escapes {
+            escape_pending = true;
+            continue;
+        }
+
+        // Check for a delimiter that indicates end-of-input.
+        if let Some(delimiter) = options.delimiter {
+            if ch == delimiter {
+                break ReadTermination::Delimiter;
+            }
+        }
+
+        // Ignore other control characters typed at a terminal without including them
+        // in the input; newlines and tabs are retained, since they may not be the
+        // delimiter.
+        if is_term && ch.is_ascii_control() && ch != '\n' && ch != '\t' {
+            continue;
+        }
+
+        line.push(ch);
+        char_count += 1;
+    };
+
+    Ok((line, reason))
+}
+
+/// Iterates over the characters of the given input, yielding the byte offset of
+/// each one along with whether it was escaped by a preceding backslash. For an
+/// escaped character, the offset is that of its backslash.
+pub(crate) fn escaped_char_indices(input: &str) -> impl Iterator<Item = (usize, char, bool)> + '_ {
+    let mut chars = input.char_indices();
+    std::iter::from_fn(move || {
+        let (i, c) = chars.next()?;
+        if c == '\\' {
+            chars.next().map(|(_, c)| (i, c, true))
+        } else {
+            Some((i, c, false))
+        }
+    })
+}
+
+/// Removes the backslashes escaping characters in the given input.
+pub(crate) fn unescape(input: &str) -> String {
+    escaped_char_indices(input).map(|(_, c, _)| c).collect()
+}
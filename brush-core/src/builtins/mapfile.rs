@@ -0,0 +1,138 @@
+use clap::Parser;
+use std::io::Write;
+
+use crate::builtins::line_reader::{self, ReadTermination};
+use crate::{builtins, commands, env, error, variables};
+
+/// Read lines from standard input into an indexed array variable.
+#[derive(Parser)]
+pub(crate) struct MapfileCommand {
+    /// Delimiter to use to terminate lines, instead of a newline character.
+    #[arg(short = 'd')]
+    delimiter: Option<String>,
+
+    /// Maximum number of lines to read; if 0 or not specified, all lines are read.
+    #[arg(short = 'n', default_value_t = 0)]
+    max_count: usize,
+
+    /// Index of the array at which to start assigning lines; if specified, the
+    /// array is not cleared first.
+    #[arg(short = 'O')]
+    origin: Option<u64>,
+
+    /// Number of leading lines to discard.
+    #[arg(short = 's', default_value_t = 0)]
+    skip_count: usize,
+
+    /// Remove the trailing delimiter from each line read.
+    #[arg(short = 't')]
+    remove_delimiter: bool,
+
+    /// File descriptor to read from instead of stdin.
+    #[arg(short = 'u', name = "FD")]
+    fd_num_to_read: Option<u32>,
+
+    /// Command to evaluate each time the specified number of lines has been read.
+    #[arg(short = 'C')]
+    callback: Option<String>,
+
+    /// Number of lines to read between invocations of the callback.
+    #[arg(short = 'c')]
+    callback_quantum: Option<usize>,
+
+    /// Name of the array variable to receive the lines; defaults to MAPFILE.
+    array_variable: Option<String>,
+}
+
+#[async_trait::async_trait]
+impl builtins::Command for MapfileCommand {
+    async fn execute(
+        &self,
+        context: commands::ExecutionContext<'_>,
+    ) -> Result<crate::builtins::ExitCode, crate::error::Error> {
+        if self.callback.is_some() || self.callback_quantum.is_some() {
+            return error::unimp("mapfile -C/-c");
+        }
+
+        // Find the input stream to use.
+        let mut input_stream = if let Some(fd_num) = self.fd_num_to_read {
+            if let Some(open_file) = context.params.open_files.files.get(&fd_num) {
+                open_file.try_dup()?
+            } else {
+                writeln!(
+                    context.stderr(),
+                    "{}: {fd_num}: invalid file descriptor",
+                    context.command_name
+                )?;
+                return Ok(crate::builtins::ExitCode::Custom(1));
+            }
+        } else {
+            context.stdin()
+        };
+
+        // Only the first character is used; an empty delimiter means NUL.
+        let delimiter = self
+            .delimiter
+            .as_ref()
+            .map_or('\n', |d| d.chars().next().unwrap_or('\0'));
+
+        let options = line_reader::ReadLineOptions {
+            delimiter: Some(delimiter),
+            char_limit: None,
+            backslash_escapes: false,
+            timeout: None,
+        };
+
+        let mut lines = vec![];
+        let mut skipped = 0;
+        while self.max_count == 0 || lines.len() < self.max_count {
+            let (mut line, termination) = line_reader::read_line(&mut input_stream, &options)?;
+
+            let delimiter_found = matches!(termination, ReadTermination::Delimiter);
+            if !delimiter_found && line.is_empty() {
+                break;
+            }
+
+            if skipped < self.skip_count {
+                skipped += 1;
+            } else {
+                if delimiter_found && !self.remove_delimiter {
+                    line.push(delimiter);
+                }
+                lines.push(line);
+            }
+
+            if !delimiter_found {
+                break;
+            }
+        }
+
+        let array_variable = self.array_variable.as_deref().unwrap_or("MAPFILE");
+
+        if let Some(origin) = self.origin {
+            // Assign at successive indices, leaving any other elements in place.
+            for (index, line) in (origin..).zip(lines) {
+                context.shell.env.update_or_add_array_element(
+                    array_variable,
+                    index.to_string(),
+                    line,
+                    |_| Ok(()),
+                    env::EnvironmentLookup::Anywhere,
+                    env::EnvironmentScope::Global,
+                )?;
+            }
+        } else {
+            // Assigning an array literal replaces any existing elements.
+            let literal_elements = lines.into_iter().map(|line| (None, line)).collect();
+            context.shell.env.update_or_add(
+                array_variable,
+                variables::ShellValueLiteral::Array(variables::ArrayLiteral(literal_elements)),
+                |_| Ok(()),
+                env::EnvironmentLookup::Anywhere,
+                env::EnvironmentScope::Global,
+            )?;
+        }
+
+        Ok(crate::builtins::ExitCode::Success)
+    }
+}
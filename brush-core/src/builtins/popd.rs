@@ -1,6 +1,7 @@
 use clap::Parser;
 use std::io::Write;
 
+use crate::builtins::dirs::{self, StackOffset};
 use crate::{builtins, commands};
 
 /// Pop a path from the current directory stack.
@@ -9,9 +10,10 @@ pub(crate) struct PopdCommand {
     /// Pop the path without changing the current working directory.
     #[clap(short = 'n')]
     no_directory_change: bool,
-    //
-    // TODO: implement +N and -N
-    //
+
+    /// Offset (+N or -N) of the stack entry to remove.
+    #[arg(allow_negative_numbers = true)]
+    offset: Option<String>,
 }
 
 #[async_trait::async_trait]
@@ -20,19 +22,51 @@ impl builtins::Command for PopdCommand {
         &self,
         context: commands::ExecutionContext<'_>,
     ) -> Result<crate::builtins::ExitCode, crate::error::Error> {
-        if let Some(popped) = context.shell.directory_stack.pop() {
-            if !self.no_directory_change {
-                context.shell.set_working_dir(&popped)?;
-            }
-
-            // Display dirs.
-            let dirs_cmd = crate::builtins::dirs::DirsCommand::default();
-            dirs_cmd.execute(context).await?;
-        } else {
+        if context.shell.directory_stack.is_empty() {
             writeln!(context.stderr(), "popd: directory stack empty")?;
             return Ok(builtins::ExitCode::Custom(1));
         }
 
+        let mut stack = dirs::get_stack(context.shell);
+
+        let index = if let Some(offset) = &self.offset {
+            let Some(offset) = StackOffset::parse(offset) else {
+                writeln!(context.stderr(), "popd: {offset}: invalid argument")?;
+                return Ok(builtins::ExitCode::InvalidUsage);
+            };
+
+            let Some(index) = offset.to_index(stack.len()) else {
+                writeln!(
+                    context.stderr(),
+                    "popd: {offset}: directory stack index out of range"
+                )?;
+                return Ok(builtins::ExitCode::Custom(1));
+            };
+
+            index
+        } else {
+            0
+        };
+
+        // Removing the top of the stack means changing to the next directory; with -n,
+        // the next entry is removed instead.
+        let result = if index == 0 && !self.no_directory_change {
+            stack.remove(0);
+            dirs::set_stack(context.shell, stack, true)
+        } else {
+            stack.remove(index.max(1));
+            dirs::set_stack(context.shell, stack, false)
+        };
+
+        if let Err(e) = result {
+            writeln!(context.stderr(), "popd: {e}")?;
+            return Ok(builtins::ExitCode::Custom(1));
+        }
+
+        // Display dirs.
+        let dirs_cmd = dirs::DirsCommand::default();
+        dirs_cmd.execute(context).await?;
+
         Ok(builtins::ExitCode::Success)
     }
 }
@@ -1,7 +1,7 @@
 use clap::Parser;
 use std::io::Write;
 
-use crate::{builtins, commands, expansion};
+use crate::{builtins, commands, escape, expansion};
 
 /// Format a string.
 #[derive(Parser)]
@@ -22,55 +22,542 @@ impl builtins::Command for PrintfCommand {
         &self,
         context: commands::ExecutionContext<'_>,
     ) -> Result<crate::builtins::ExitCode, crate::error::Error> {
-        let result = self.evaluate(&context)?;
+        let Some((format, args)) = self.format_and_args.split_first() else {
+            writeln!(
+                context.stderr(),
+                "printf: usage: printf [-v var] format [arguments]"
+            )?;
+            return Ok(builtins::ExitCode::InvalidUsage);
+        };
+
+        let mut formatter = Formatter::new(args);
+        let output = formatter.format(format.as_str());
+
+        for error in &formatter.errors {
+            writeln!(context.stderr(), "{}: {error}", context.command_name)?;
+        }
 
         if let Some(variable_name) = &self.output_variable {
-            expansion::assign_to_named_parameter(context.shell, variable_name, result).await?;
+            let output = String::from_utf8_lossy(output.as_slice()).into_owned();
+            expansion::assign_to_named_parameter(context.shell, variable_name, output).await?;
         } else {
-            write!(context.stdout(), "{result}")?;
+            context.stdout().write_all(output.as_slice())?;
             context.stdout().flush()?;
         }
 
-        return Ok(builtins::ExitCode::Success);
+        if formatter.errors.is_empty() {
+            Ok(builtins::ExitCode::Success)
+        } else {
+            Ok(builtins::ExitCode::Custom(1))
+        }
     }
 }
 
-impl PrintfCommand {
-    fn evaluate(
-        &self,
-        context: &commands::ExecutionContext<'_>,
-    ) -> Result<String, crate::error::Error> {
-        // Special-case common format string: "%s".
-        match self.format_and_args.as_slice() {
-            [fmt, arg] if fmt == "%s" => Ok(arg.clone()),
-            _ => self.evaluate_via_external_command(context),
+/// Applies a format string to a sequence of arguments.
+struct Formatter<'a> {
+    args: &'a [String],
+    next_arg_index: usize,
+    errors: Vec<String>,
+}
+
+/// A parsed conversion specification (e.g., `%-08.3f`).
+#[derive(Default)]
+struct ConversionSpec {
+    left_align: bool,
+    always_show_sign: bool,
+    space_for_sign: bool,
+    alternate_form: bool,
+    zero_pad: bool,
+    width: Option<usize>,
+    precision: Option<usize>,
+}
+
+impl<'a> Formatter<'a> {
+    fn new(args: &'a [String]) -> Self {
+        Self {
+            args,
+            next_arg_index: 0,
+            errors: vec![],
         }
     }
 
-    #[allow(clippy::unwrap_in_result)]
-    fn evaluate_via_external_command(
-        &self,
-        context: &commands::ExecutionContext<'_>,
-    ) -> Result<String, crate::error::Error> {
-        // TODO: Don't call external printf command.
-        let mut cmd = std::process::Command::new("printf");
-        cmd.env_clear();
-        cmd.args(&self.format_and_args);
+    /// Formats the arguments, reusing the format string as many times as needed
+    /// to consume all of them. Problems with arguments are collected in `errors`,
+    /// but don't stop formatting; output produced before a malformed format
+    /// string was detected is still returned.
+    fn format(&mut self, format: &str) -> Vec<u8> {
+        let mut output = vec![];
+
+        loop {
+            let arg_index_before = self.next_arg_index;
+
+            match self.format_once(format, &mut output) {
+                Ok(true) => (),
+                Ok(false) => break,
+                Err(message) => {
+                    self.errors.push(message);
+                    break;
+                }
+            }
+
+            // Stop once all args are consumed, or if the format doesn't consume any.
+            if self.next_arg_index >= self.args.len() || self.next_arg_index == arg_index_before {
+                break;
+            }
+        }
+
+        output
+    }
+
+    /// Makes one pass through the format string, appending to the given output.
+    /// Returns whether formatting should continue (i.e., that `\c` was not
+    /// encountered in a `%b` argument).
+    fn format_once(&mut self, format: &str, output: &mut Vec<u8>) -> Result<bool, String> {
+        let mut chars = format.char_indices().peekable();
+        let mut literal_start = 0;
+
+        while let Some((i, c)) = chars.next() {
+            if c != '%' {
+                continue;
+            }
+
+            // Flush the literal text preceding this conversion.
+            Self::append_literal(&format[literal_start..i], output)?;
+
+            let spec_start = i;
+            let mut spec = ConversionSpec::default();
+
+            // Parse flags.
+            while let Some((_, c)) = chars.next_if(|&(_, c)| is_flag_char(c)) {
+                match c {
+                    '-' => spec.left_align = true,
+                    '+' => spec.always_show_sign = true,
+                    ' ' => spec.space_for_sign = true,
+                    '#' => spec.alternate_form = true,
+                    _ => spec.zero_pad = true,
+                }
+            }
+
+            // Parse width.
+            if chars.next_if(|&(_, c)| c == '*').is_some() {
+                let width = self.next_integer_arg();
+                spec.left_align |= width < 0;
+                spec.width = usize::try_from(width.unsigned_abs()).ok();
+            } else {
+                spec.width = Self::parse_decimal(&mut chars);
+            }
+            check_field_size(spec.width)?;
+
+            // Parse precision.
+            if chars.next_if(|&(_, c)| c == '.').is_some() {
+                if chars.next_if(|&(_, c)| c == '*').is_some() {
+                    spec.precision = usize::try_from(self.next_integer_arg()).ok();
+                } else {
+                    spec.precision = Some(Self::parse_decimal(&mut chars).unwrap_or(0));
+                }
+            }
+            check_field_size(spec.precision)?;
+
+            // Skip any length modifiers; they have no effect.
+            while chars
+                .next_if(|&(_, c)| is_length_modifier_char(c))
+                .is_some()
+            {}
+
+            let Some((conversion_index, conversion)) = chars.next() else {
+                return Err(std::format!(
+                    "`{}': missing format character",
+                    &format[spec_start..]
+                ));
+            };
+
+            literal_start = conversion_index + conversion.len_utf8();
 
-        let output = cmd.output()?;
+            match conversion {
+                '%' if literal_start - spec_start == 2 => output.push(b'%'),
+                'd' | 'i' => {
+                    let value = self.next_integer_arg();
+                    Self::append_padded(output, &spec, &format_signed(value, &spec), true);
+                }
+                'o' | 'u' | 'x' | 'X' => {
+                    // Negative values are reinterpreted as unsigned, as in C.
+                    let value = u64::from_ne_bytes(self.next_integer_arg().to_ne_bytes());
+                    let formatted = format_unsigned(value, conversion, &spec);
+                    Self::append_padded(output, &spec, &formatted, true);
+                }
+                'f' | 'F' | 'e' | 'E' | 'g' | 'G' => {
+                    let value = self.next_float_arg();
+                    let formatted = format_float(value, conversion, &spec);
+                    Self::append_padded(output, &spec, &formatted, value.is_finite());
+                }
+                'c' => {
+                    let arg = self.next_arg().unwrap_or_default();
+                    let formatted: String = arg.chars().take(1).collect();
+                    Self::append_padded(output, &spec, formatted.as_bytes(), false);
+                }
+                's' => {
+                    let arg = self.next_arg().unwrap_or_default();
+                    let formatted: String = if let Some(precision) = spec.precision {
+                        arg.chars().take(precision).collect()
+                    } else {
+                        arg.to_owned()
+                    };
+                    Self::append_padded(output, &spec, formatted.as_bytes(), false);
+                }
+                'b' => {
+                    let arg = self.next_arg().unwrap_or_default();
+                    let (mut expanded, keep_going) =
+                        escape::expand_backslash_escapes(arg, escape::EscapeMode::PrintfEscapedArg)
+                            .map_err(|e| e.to_string())?;
+                    if let Some(precision) = spec.precision {
+                        expanded.truncate(precision);
+                    }
+                    Self::append_padded(output, &spec, expanded.as_slice(), false);
 
-        let stdout = String::from_utf8(output.stdout)?;
-        let stderr = String::from_utf8(output.stderr)?;
+                    if !keep_going {
+                        return Ok(false);
+                    }
+                }
+                'q' => {
+                    let arg = self.next_arg().unwrap_or_default();
+                    let quoted = escape::quote_for_shell_input(arg);
+                    Self::append_padded(output, &spec, quoted.as_bytes(), false);
+                }
+                _ => {
+                    return Err(std::format!(
+                        "`{}': invalid format character",
+                        &format[spec_start..literal_start]
+                    ));
+                }
+            }
+        }
+
+        Self::append_literal(&format[literal_start..], output)?;
+
+        Ok(true)
+    }
+
+    fn append_literal(literal: &str, output: &mut Vec<u8>) -> Result<(), String> {
+        if !literal.is_empty() {
+            let (mut expanded, _) =
+                escape::expand_backslash_escapes(literal, escape::EscapeMode::PrintfFormat)
+                    .map_err(|e| e.to_string())?;
+            output.append(&mut expanded);
+        }
+
+        Ok(())
+    }
+
+    /// Appends the given formatted value to the output, padded to the width in
+    /// the given spec. Numeric values may be padded with zeros after any sign
+    /// or radix prefix.
+    fn append_padded(
+        output: &mut Vec<u8>,
+        spec: &ConversionSpec,
+        formatted: &[u8],
+        zero_paddable: bool,
+    ) {
+        let padding = spec.width.unwrap_or(0).saturating_sub(formatted.len());
 
-        write!(context.stderr(), "{stderr}")?;
-        context.stderr().flush()?;
+        if spec.left_align {
+            output.extend_from_slice(formatted);
+            output.resize(output.len() + padding, b' ');
+        } else if spec.zero_pad && zero_paddable {
+            let prefix_len = formatted
+                .iter()
+                .position(|b| b.is_ascii_digit())
+                .unwrap_or(formatted.len());
+            let prefix_len = match &formatted[prefix_len..] {
+                [b'0', b'x' | b'X', ..] => prefix_len + 2,
+                _ => prefix_len,
+            };
 
-        if output.status.success() {
-            Ok(stdout)
+            output.extend_from_slice(&formatted[..prefix_len]);
+            output.resize(output.len() + padding, b'0');
+            output.extend_from_slice(&formatted[prefix_len..]);
         } else {
-            Err(crate::error::Error::PrintfFailure(
-                output.status.code().unwrap(),
-            ))
+            output.resize(output.len() + padding, b' ');
+            output.extend_from_slice(formatted);
         }
     }
+
+    fn parse_decimal(chars: &mut std::iter::Peekable<std::str::CharIndices<'_>>) -> Option<usize> {
+        let mut value: Option<usize> = None;
+        while let Some((_, c)) = chars.next_if(|&(_, c)| c.is_ascii_digit()) {
+            let digit = c
+                .to_digit(10)
+                .and_then(|digit| usize::try_from(digit).ok())
+                .unwrap_or(0);
+            value = Some(value.unwrap_or(0).saturating_mul(10).saturating_add(digit));
+        }
+        value
+    }
+
+    fn next_arg(&mut self) -> Option<&'a str> {
+        let arg = self.args.get(self.next_arg_index)?;
+        self.next_arg_index += 1;
+        Some(arg.as_str())
+    }
+
+    fn next_integer_arg(&mut self) -> i64 {
+        let Some(arg) = self.next_arg() else {
+            return 0;
+        };
+
+        let (value, valid) = parse_integer_arg(arg);
+        if !valid {
+            self.errors.push(std::format!("{arg}: invalid number"));
+        }
+
+        value
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn next_float_arg(&mut self) -> f64 {
+        let Some(arg) = self.next_arg() else {
+            return 0.0;
+        };
+
+        let trimmed = arg.trim_start();
+        if let Some(c) = char_code_arg(trimmed) {
+            return f64::from(c);
+        }
+
+        if let Ok(value) = trimmed.parse() {
+            value
+        } else if let (value, true) = parse_integer_arg(trimmed) {
+            value as f64
+        } else {
+            self.errors.push(std::format!("{arg}: invalid number"));
+            0.0
+        }
+    }
+}
+
+/// Upper bound on field widths and precisions; since the formatted output is
+/// buffered in memory, larger values are rejected rather than padded.
+const MAX_FIELD_SIZE: usize = 1 << 20;
+
+fn check_field_size(size: Option<usize>) -> Result<(), String> {
+    match size {
+        Some(size) if size > MAX_FIELD_SIZE => {
+            Err(std::format!("{size}: Numerical result out of range"))
+        }
+        _ => Ok(()),
+    }
+}
+
+const fn is_flag_char(c: char) -> bool {
+    matches!(c, '-' | '+' | ' ' | '#' | '0')
+}
+
+const fn is_length_modifier_char(c: char) -> bool {
+    matches!(c, 'h' | 'l' | 'L' | 'q' | 'j' | 'z' | 't')
+}
+
+/// Parses an integer argument, in the forms accepted by C: decimal, octal with a
+/// leading `0`, or hexadecimal with a leading `0x`. As in bash, an argument with
+/// a leading quote yields the code of the character that follows. Returns the
+/// value parsed from the longest valid prefix, along with whether the whole
+/// argument was valid.
+fn parse_integer_arg(arg: &str) -> (i64, bool) {
+    let trimmed = arg.trim_start();
+    if let Some(c) = char_code_arg(trimmed) {
+        return (i64::from(c), true);
+    }
+
+    let (negative, unsigned) = if let Some(rest) = trimmed.strip_prefix('-') {
+        (true, rest)
+    } else {
+        (false, trimmed.strip_prefix('+').unwrap_or(trimmed))
+    };
+
+    let (radix, digits) = if let Some(rest) = unsigned
+        .strip_prefix("0x")
+        .or_else(|| unsigned.strip_prefix("0X"))
+    {
+        (16, rest)
+    } else if unsigned.len() > 1 && unsigned.starts_with('0') {
+        (8, &unsigned[1..])
+    } else {
+        (10, unsigned)
+    };
+
+    let mut value: i64 = 0;
+    let mut digit_count = 0;
+    for c in digits.chars() {
+        let Some(digit) = c.to_digit(radix) else {
+            break;
+        };
+
+        value = value
+            .saturating_mul(i64::from(radix))
+            .saturating_add(i64::from(digit));
+        digit_count += 1;
+    }
+
+    if negative {
+        value = -value;
+    }
+
+    let valid = digit_count == digits.chars().count() && (digit_count > 0 || radix == 8);
+    (value, valid || arg.is_empty())
+}
+
+/// Checks for an argument of the form `'c` or `"c`, returning the code of `c`.
+fn char_code_arg(arg: &str) -> Option<u32> {
+    let rest = arg.strip_prefix(['\'', '"'])?;
+    Some(rest.chars().next().map_or(0, u32::from))
+}
+
+fn format_signed(value: i64, spec: &ConversionSpec) -> Vec<u8> {
+    let sign = if value < 0 {
+        "-"
+    } else if spec.always_show_sign {
+        "+"
+    } else if spec.space_for_sign {
+        " "
+    } else {
+        ""
+    };
+
+    let digits = apply_integer_precision(value.unsigned_abs().to_string(), spec);
+    std::format!("{sign}{digits}").into_bytes()
+}
+
+fn format_unsigned(value: u64, conversion: char, spec: &ConversionSpec) -> Vec<u8> {
+    let (prefix, digits) = match conversion {
+        'o' => {
+            let digits = apply_integer_precision(std::format!("{value:o}"), spec);
+            if spec.alternate_form && !digits.starts_with('0') {
+                ("0", digits)
+            } else {
+                ("", digits)
+            }
+        }
+        'x' => {
+            let prefix = if spec.alternate_form && value != 0 {
+                "0x"
+            } else {
+                ""
+            };
+            (
+                prefix,
+                apply_integer_precision(std::format!("{value:x}"), spec),
+            )
+        }
+        'X' => {
+            let prefix = if spec.alternate_form && value != 0 {
+                "0X"
+            } else {
+                ""
+            };
+            (
+                prefix,
+                apply_integer_precision(std::format!("{value:X}"), spec),
+            )
+        }
+        _ => ("", apply_integer_precision(value.to_string(), spec)),
+    };
+
+    std::format!("{prefix}{digits}").into_bytes()
+}
+
+/// Pads the given digits with leading zeros to satisfy the spec's precision
+/// (the minimum number of digits). A zero value with zero precision yields no
+/// digits at all.
+fn apply_integer_precision(digits: String, spec: &ConversionSpec) -> String {
+    match spec.precision {
+        Some(0) if digits == "0" => String::new(),
+        Some(precision) if precision > digits.len() => {
+            std::format!("{}{digits}", "0".repeat(precision - digits.len()))
+        }
+        _ => digits,
+    }
+}
+
+fn format_float(value: f64, conversion: char, spec: &ConversionSpec) -> Vec<u8> {
+    let sign = if value.is_sign_negative() && !value.is_nan() {
+        "-"
+    } else if spec.always_show_sign {
+        "+"
+    } else if spec.space_for_sign {
+        " "
+    } else {
+        ""
+    };
+
+    let value = value.abs();
+    let precision = spec.precision.unwrap_or(6);
+
+    let body = if value.is_nan() {
+        String::from("nan")
+    } else if value.is_infinite() {
+        String::from("inf")
+    } else {
+        match conversion {
+            'f' | 'F' => std::format!("{value:.precision$}"),
+            'e' | 'E' => format_exponential(value, precision),
+            _ => format_shortest(value, precision, spec.alternate_form),
+        }
+    };
+
+    let body = if conversion.is_ascii_uppercase() {
+        body.to_ascii_uppercase()
+    } else {
+        body
+    };
+
+    std::format!("{sign}{body}").into_bytes()
+}
+
+/// Formats a (non-negative) value in C's `%e` style, e.g. `1.234500e+03`.
+fn format_exponential(value: f64, precision: usize) -> String {
+    let formatted = std::format!("{value:.precision$e}");
+    let (mantissa, exponent) = formatted
+        .split_once('e')
+        .unwrap_or((formatted.as_str(), "0"));
+    let exponent: i32 = exponent.parse().unwrap_or(0);
+
+    let exponent_sign = if exponent < 0 { '-' } else { '+' };
+    std::format!("{mantissa}e{exponent_sign}{:02}", exponent.unsigned_abs())
+}
+
+/// Formats a (non-negative) value in C's `%g` style, choosing between fixed and
+/// exponential notation based on the value's exponent, with trailing zeros
+/// removed unless the alternate form was requested.
+fn format_shortest(value: f64, precision: usize, alternate_form: bool) -> String {
+    let precision = precision.max(1);
+
+    // Find the exponent the value would have after rounding to the precision.
+    let exponent: i64 = std::format!("{value:.prec$e}", prec = precision - 1)
+        .split_once('e')
+        .and_then(|(_, exponent)| exponent.parse().ok())
+        .unwrap_or(0);
+
+    let precision_as_exponent = i64::try_from(precision).unwrap_or(i64::MAX);
+    let formatted = if (-4..precision_as_exponent).contains(&exponent) {
+        let fraction_digits = usize::try_from(precision_as_exponent - 1 - exponent).unwrap_or(0);
+        std::format!("{value:.fraction_digits$}")
+    } else {
+        format_exponential(value, precision - 1)
+    };
+
+    if alternate_form {
+        return formatted;
+    }
+
+    // Remove trailing zeros from the fractional part of the mantissa.
+    let (mantissa, exponent_suffix) = match formatted.find('e') {
+        Some(index) => formatted.split_at(index),
+        None => (formatted.as_str(), ""),
+    };
+
+    let mantissa = if mantissa.contains('.') {
+        mantissa.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        mantissa
+    };
+
+    std::format!("{mantissa}{exponent_suffix}")
 }
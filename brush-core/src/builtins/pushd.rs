@@ -1,5 +1,7 @@
 use clap::Parser;
+use std::io::Write;
 
+use crate::builtins::dirs::{self, StackOffset};
 use crate::{builtins, commands};
 
 /// Push a path onto the current directory stack.
@@ -9,11 +11,10 @@ pub(crate) struct PushdCommand {
     #[clap(short = 'n')]
     no_directory_change: bool,
 
-    /// Directory to push on the directory stack.
-    dir: String,
-    //
-    // TODO: implement +N and -N
-    //
+    /// Directory to push on the directory stack, or offset (+N or -N) of the stack
+    /// entry to rotate to the top of the stack.
+    #[arg(allow_negative_numbers = true)]
+    dir: Option<String>,
 }
 
 #[async_trait::async_trait]
@@ -22,22 +23,55 @@ impl builtins::Command for PushdCommand {
         &self,
         context: commands::ExecutionContext<'_>,
     ) -> Result<crate::builtins::ExitCode, crate::error::Error> {
-        if self.no_directory_change {
-            context
-                .shell
-                .directory_stack
-                .push(std::path::PathBuf::from(&self.dir));
+        let mut stack = dirs::get_stack(context.shell);
+
+        let result = if let Some(offset) = self.dir.as_deref().and_then(StackOffset::parse) {
+            // Rotate the stack so the selected entry is at the top.
+            let Some(index) = offset.to_index(stack.len()) else {
+                writeln!(
+                    context.stderr(),
+                    "pushd: {offset}: directory stack index out of range"
+                )?;
+                return Ok(builtins::ExitCode::Custom(1));
+            };
+
+            stack.rotate_left(index);
+            dirs::set_stack(context.shell, stack, !self.no_directory_change)
+        } else if let Some(dir) = &self.dir {
+            if self.no_directory_change {
+                context
+                    .shell
+                    .directory_stack
+                    .push(std::path::PathBuf::from(dir));
+                Ok(())
+            } else {
+                let prev_working_dir = context.shell.working_dir.clone();
+
+                let result = context.shell.set_working_dir(std::path::Path::new(dir));
+                if result.is_ok() {
+                    context.shell.directory_stack.push(prev_working_dir);
+                }
+
+                result
+            }
         } else {
-            let prev_working_dir = context.shell.working_dir.clone();
+            // Exchange the top two entries of the stack.
+            if stack.len() < 2 {
+                writeln!(context.stderr(), "pushd: no other directory")?;
+                return Ok(builtins::ExitCode::Custom(1));
+            }
 
-            let dir = std::path::Path::new(&self.dir);
-            context.shell.set_working_dir(dir)?;
+            stack.swap(0, 1);
+            dirs::set_stack(context.shell, stack, !self.no_directory_change)
+        };
 
-            context.shell.directory_stack.push(prev_working_dir);
+        if let Err(e) = result {
+            writeln!(context.stderr(), "pushd: {e}")?;
+            return Ok(builtins::ExitCode::Custom(1));
         }
 
         // Display dirs.
-        let dirs_cmd = crate::builtins::dirs::DirsCommand::default();
+        let dirs_cmd = dirs::DirsCommand::default();
         dirs_cmd.execute(context).await?;
 
         Ok(builtins::ExitCode::Success)
@@ -21,16 +21,16 @@ impl builtins::Command for PwdCommand {
         context: commands::ExecutionContext<'_>,
     ) -> Result<crate::builtins::ExitCode, crate::error::Error> {
         //
-        // TODO: implement flags
         // TODO: look for 'physical' option in execution context
         //
 
-        if self.physical || self.allow_symlinks {
-            writeln!(context.stderr(), "UNIMPLEMENTED: pwd with -P or -L")?;
-            return Ok(builtins::ExitCode::Unimplemented);
-        }
+        let cwd = if self.physical && !self.allow_symlinks {
+            context.shell.working_dir.canonicalize()?
+        } else {
+            context.shell.working_dir.clone()
+        };
 
-        let cwd = context.shell.working_dir.to_string_lossy().into_owned();
+        let cwd = cwd.to_string_lossy().into_owned();
 
         writeln!(context.stdout(), "{cwd}")?;
 
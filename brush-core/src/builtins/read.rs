@@ -1,9 +1,8 @@
 use clap::Parser;
-use itertools::Itertools;
-use std::collections::VecDeque;
-use std::io::{Read, Write};
+use std::io::Write;
 
-use crate::{builtins, commands, env, error, openfiles, sys, variables};
+use crate::builtins::line_reader::{self, escaped_char_indices, unescape, ReadTermination};
+use crate::{arithmetic, builtins, commands, env, error, openfiles, sys, variables};
 
 /// Parse standard input.
 #[derive(Parser)]
@@ -21,7 +20,9 @@ pub(crate) struct ReadCommand {
     #[clap(short = 'e')]
     use_readline: bool,
 
-    /// Provide text to use as initial input for readline.
+    /// Provide text to use as initial input for readline. Until readline-style
+    /// editing (-e) is supported, the text is used as the start of the line
+    /// that read input is appended to.
     #[clap(short = 'i')]
     initial_text: Option<String>,
 
@@ -34,7 +35,8 @@ pub(crate) struct ReadCommand {
     #[clap(short = 'N')]
     return_after_n_chars_no_delimiter: Option<usize>,
 
-    /// Prompt to display before reading.
+    /// Prompt to display on stderr before reading, if input is coming
+    /// from a terminal.
     #[clap(short = 'p')]
     prompt: Option<String>,
 
@@ -46,14 +48,14 @@ pub(crate) struct ReadCommand {
     #[clap(short = 's')]
     silent: bool,
 
-    /// Specify timeout in seconds; fail if the timeout elapses before
-    /// input is completed.
+    /// Specify timeout in seconds (fractional values are allowed); fail if
+    /// the timeout elapses before input is completed.
     #[clap(short = 't')]
-    timeout_in_seconds: Option<usize>,
+    timeout_in_seconds: Option<f64>,
 
     /// File descriptor to read from instead of stdin.
     #[clap(short = 'u', name = "FD")]
-    fd_num_to_read: Option<u8>,
+    fd_num_to_read: Option<u32>,
 
     /// Optionally, names of variables to receive read input.
     variable_names: Vec<String>,
@@ -68,192 +70,278 @@ impl builtins::Command for ReadCommand {
         if self.use_readline {
             return error::unimp("read -e");
         }
-        if self.initial_text.is_some() {
-            return error::unimp("read -i");
-        }
-        if self.raw_mode {
-            tracing::debug!("read -r is not implemented");
-        }
-        if self.timeout_in_seconds.is_some() {
-            return error::unimp("read -t");
-        }
 
         // Find the input stream to use.
-        #[allow(clippy::cast_lossless)]
         let input_stream = if let Some(fd_num) = self.fd_num_to_read {
-            let fd_num = fd_num as u32;
-            context
-                .fd(fd_num)
-                .ok_or_else(|| error::Error::BadFileDescriptor(fd_num))?
+            if let Some(open_file) = context.params.open_files.files.get(&fd_num) {
+                open_file.try_dup()?
+            } else {
+                writeln!(
+                    context.stderr(),
+                    "{}: {fd_num}: invalid file descriptor",
+                    context.command_name
+                )?;
+                return Ok(crate::builtins::ExitCode::Custom(1));
+            }
         } else {
             context.stdin()
         };
 
-        let input_line = self.read_line(input_stream, context.stdout())?;
+        let timeout = if let Some(timeout_in_seconds) = self.timeout_in_seconds {
+            if let Ok(timeout) = std::time::Duration::try_from_secs_f64(timeout_in_seconds) {
+                Some(timeout)
+            } else {
+                writeln!(
+                    context.stderr(),
+                    "{}: {timeout_in_seconds}: invalid timeout specification",
+                    context.command_name
+                )?;
+                return Ok(crate::builtins::ExitCode::Custom(1));
+            }
+        } else {
+            None
+        };
+
+        // A zero timeout only checks whether input is available, without reading any.
+        if timeout.is_some_and(|timeout| timeout.is_zero()) {
+            return if input_stream.poll_for_input(std::time::Duration::ZERO)? {
+                Ok(crate::builtins::ExitCode::Success)
+            } else {
+                Ok(crate::builtins::ExitCode::Custom(1))
+            };
+        }
+
+        // As in bash, the prompt is only displayed when reading from a terminal.
+        if let Some(prompt) = &self.prompt {
+            if input_stream.is_term() {
+                let mut stderr = context.stderr();
+                write!(stderr, "{prompt}")?;
+                stderr.flush()?;
+            }
+        }
+
+        let (input_line, termination) = self.read_line(input_stream, timeout)?;
+
+        // An interrupted read discards its input without assigning anything.
+        if matches!(termination, ReadTermination::CtrlC) {
+            return Ok(crate::builtins::ExitCode::Custom(1));
+        }
+
+        // Input read with -N is never split; it's assigned exactly as read.
+        let ifs = if self.return_after_n_chars_no_delimiter.is_some() {
+            String::new()
+        } else {
+            context.shell.get_ifs().into_owned()
+        };
 
-        if let Some(input_line) = input_line {
-            let mut fields: VecDeque<_> = input_line
-                .split_ascii_whitespace()
-                .map(|field| field.to_owned())
+        // If -a was specified, then place the fields as elements into the array.
+        if let Some(array_variable) = &self.array_variable {
+            let literal_fields = split_fields(input_line.as_str(), ifs.as_str())
+                .into_iter()
+                .map(|f| (None, unescape(f)))
                 .collect();
 
-            // If -a was specified, then place the fields as elements into the array.
-            if let Some(array_variable) = &self.array_variable {
-                let literal_fields = fields.into_iter().map(|f| (None, f)).collect();
+            // Assigning an array literal replaces any existing elements.
+            assign_variable(
+                context.shell,
+                array_variable,
+                variables::ShellValueLiteral::Array(variables::ArrayLiteral(literal_fields)),
+            )
+            .await?;
+        } else if !self.variable_names.is_empty() {
+            let mut remaining = trim_ifs_whitespace_start(input_line.as_str(), ifs.as_str());
+            for (i, name) in self.variable_names.iter().enumerate() {
+                let last = i == self.variable_names.len() - 1;
+
+                let value = if last {
+                    // The last variable receives the unsplit remainder of the line.
+                    remainder_value(remaining, ifs.as_str())
+                } else {
+                    let (field, rest) = next_field(remaining, ifs.as_str());
+                    remaining = rest;
+                    field
+                };
+
+                assign_variable(
+                    context.shell,
+                    name,
+                    variables::ShellValueLiteral::Scalar(unescape(value)),
+                )
+                .await?;
+            }
+        } else {
+            // If no variable names were specified, then place the unsplit line into the
+            // REPLY variable.
+            assign_variable(
+                context.shell,
+                "REPLY",
+                variables::ShellValueLiteral::Scalar(unescape(input_line.as_str())),
+            )
+            .await?;
+        }
 
-                context.shell.env.update_or_add(
-                    array_variable,
-                    variables::ShellValueLiteral::Array(variables::ArrayLiteral(literal_fields)),
-                    |_| Ok(()),
-                    env::EnvironmentLookup::Anywhere,
-                    env::EnvironmentScope::Global,
-                )?;
-            } else if !self.variable_names.is_empty() {
-                for (i, name) in self.variable_names.iter().enumerate() {
-                    if fields.is_empty() {
-                        // Ensure the var is empty.
-                        context.shell.env.update_or_add(
-                            name,
-                            variables::ShellValueLiteral::Scalar(String::new()),
-                            |_| Ok(()),
-                            env::EnvironmentLookup::Anywhere,
-                            env::EnvironmentScope::Global,
-                        )?;
-                        continue;
-                    }
-
-                    let last = i == self.variable_names.len() - 1;
-                    if !last {
-                        let next_field = fields.pop_front().unwrap();
-                        context.shell.env.update_or_add(
-                            name,
-                            variables::ShellValueLiteral::Scalar(next_field),
-                            |_| Ok(()),
-                            env::EnvironmentLookup::Anywhere,
-                            env::EnvironmentScope::Global,
-                        )?;
-                    } else {
-                        let remaining_fields = fields.into_iter().join(" ");
-                        context.shell.env.update_or_add(
-                            name,
-                            variables::ShellValueLiteral::Scalar(remaining_fields),
-                            |_| Ok(()),
-                            env::EnvironmentLookup::Anywhere,
-                            env::EnvironmentScope::Global,
-                        )?;
-                        break;
-                    }
-                }
-            } else {
-                // If no variable names were specified, then place the fields into the
-                // REPLY variable.
-                context.shell.env.update_or_add(
-                    "REPLY",
-                    variables::ShellValueLiteral::Scalar(fields.into_iter().join(" ")),
-                    |_| Ok(()),
-                    env::EnvironmentLookup::Anywhere,
-                    env::EnvironmentScope::Global,
-                )?;
+        // Hitting end-of-input before the delimiter (or timing out) is reported as
+        // failure, even though any input read up to that point was still assigned.
+        match termination {
+            ReadTermination::EndOfInput => Ok(crate::builtins::ExitCode::Custom(1)),
+            ReadTermination::TimedOut => Ok(crate::builtins::ExitCode::Custom(142)),
+            ReadTermination::Delimiter | ReadTermination::Limit | ReadTermination::CtrlC => {
+                Ok(crate::builtins::ExitCode::Success)
             }
+        }
+    }
+}
 
-            Ok(crate::builtins::ExitCode::Success)
-        } else {
-            Ok(crate::builtins::ExitCode::Custom(1))
+/// Assigns a value read from input to the named variable; values assigned to a variable
+/// with the integer attribute are first evaluated arithmetically.
+async fn assign_variable(
+    shell: &mut crate::Shell,
+    name: &str,
+    value: variables::ShellValueLiteral,
+) -> Result<(), error::Error> {
+    let value = if shell
+        .env
+        .get(name)
+        .is_some_and(|(_, var)| var.is_treated_as_integer())
+    {
+        arithmetic::eval_integer_assignment(shell, value).await
+    } else {
+        value
+    };
+
+    shell.env.update_or_add(
+        name,
+        value,
+        |_| Ok(()),
+        env::EnvironmentLookup::Anywhere,
+        env::EnvironmentScope::Global,
+    )
+}
+
+/// Splits the given input into fields, following the rules used by `read`:
+/// runs of IFS whitespace are coalesced (and trimmed from the ends of the
+/// input), while each non-whitespace IFS character delimits a field. Characters
+/// escaped with a backslash never delimit fields.
+fn split_fields<'a>(input: &'a str, ifs: &str) -> Vec<&'a str> {
+    let mut fields = vec![];
+
+    let mut remaining = trim_ifs_whitespace_start(input, ifs);
+    while !remaining.is_empty() {
+        let (field, rest) = next_field(remaining, ifs);
+        fields.push(field);
+        remaining = rest;
+    }
+
+    fields
+}
+
+/// Extracts the next field from the given input, returning it along with
+/// the remaining input that follows its terminating delimiter (if any).
+fn next_field<'a>(input: &'a str, ifs: &str) -> (&'a str, &'a str) {
+    let Some((end, _, _)) =
+        escaped_char_indices(input).find(|(_, c, escaped)| !escaped && ifs.contains(*c))
+    else {
+        return (input, "");
+    };
+
+    let field = &input[..end];
+
+    // Consume any IFS whitespace around at most one non-whitespace IFS character.
+    let mut rest = trim_ifs_whitespace_start(&input[end..], ifs);
+    if let Some((_, c, false)) = escaped_char_indices(rest).next() {
+        if ifs.contains(c) && !is_ifs_whitespace(c) {
+            rest = trim_ifs_whitespace_start(&rest[c.len_utf8()..], ifs);
         }
     }
+
+    (field, rest)
+}
+
+/// Computes the value assigned to the last variable named to `read`, given the
+/// input remaining after all preceding fields were extracted. Trailing IFS
+/// whitespace is removed; if the remainder consists of a single field, then its
+/// terminating delimiter is removed as well.
+fn remainder_value<'a>(remaining: &'a str, ifs: &str) -> &'a str {
+    let (field, rest) = next_field(remaining, ifs);
+    if rest.is_empty() {
+        field
+    } else {
+        trim_ifs_whitespace_end(remaining, ifs)
+    }
+}
+
+fn trim_ifs_whitespace_start<'a>(input: &'a str, ifs: &str) -> &'a str {
+    escaped_char_indices(input)
+        .find(|(_, c, escaped)| *escaped || !is_ifs_whitespace_in(*c, ifs))
+        .map_or("", |(start, _, _)| &input[start..])
+}
+
+fn trim_ifs_whitespace_end<'a>(input: &'a str, ifs: &str) -> &'a str {
+    let end = escaped_char_indices(input)
+        .filter(|(_, c, escaped)| *escaped || !is_ifs_whitespace_in(*c, ifs))
+        .last()
+        .map_or(0, |(start, c, escaped)| {
+            start + usize::from(escaped) + c.len_utf8()
+        });
+
+    &input[..end]
 }
 
-enum ReadTermination {
-    Delimiter,
-    EndOfInput,
-    CtrlC,
-    Limit,
+fn is_ifs_whitespace_in(c: char, ifs: &str) -> bool {
+    ifs.contains(c) && is_ifs_whitespace(c)
+}
+
+const fn is_ifs_whitespace(c: char) -> bool {
+    matches!(c, ' ' | '\t' | '\n')
 }
 
 impl ReadCommand {
     fn read_line(
         &self,
         mut input_file: openfiles::OpenFile,
-        mut output_file: openfiles::OpenFile,
-    ) -> Result<Option<String>, error::Error> {
+        timeout: Option<std::time::Duration>,
+    ) -> Result<(String, ReadTermination), error::Error> {
         let orig_term_attr = self.setup_terminal_settings(&input_file)?;
 
         let delimiter = if self.return_after_n_chars_no_delimiter.is_some() {
             None
         } else if let Some(delimiter_str) = &self.delimiter {
-            delimiter_str.chars().next()
+            // Only the first character is used; an empty delimiter means NUL.
+            Some(delimiter_str.chars().next().unwrap_or('\0'))
         } else {
             Some('\n')
         };
 
-        let char_limit = self
-            .return_after_n_chars_no_delimiter
-            .or(self.return_after_n_chars);
-
-        if let Some(prompt) = &self.prompt {
-            write!(output_file, "{prompt}")?;
-            output_file.flush()?;
-        }
-
-        let mut line = String::new();
-        let mut buffer = [0; 1]; // 1-byte buffer
-
-        let reason = loop {
-            let n = input_file.read(&mut buffer)?;
-            if n == 0 {
-                break ReadTermination::EndOfInput; // EOF reached.
-            }
-
-            let ch = buffer[0] as char;
-
-            // Check for Ctrl+C.
-            if ch == '\x03' {
-                break ReadTermination::CtrlC;
-            } else if ch == '\x04' {
-                // Ctrl+D is EOF.
-                break ReadTermination::EndOfInput;
-            }
-
-            // Check for a delimiter that indicates end-of-input.
-            if let Some(delimiter) = delimiter {
-                if ch == delimiter {
-                    break ReadTermination::Delimiter;
-                }
-            }
-
-            // Ignore other control characters without including them in the input.
-            if ch.is_ascii_control() {
-                continue;
-            }
-
-            line.push(ch);
-
-            // Check to see if we've hit a character limit.
-            if let Some(char_limit) = char_limit {
-                if line.len() >= char_limit {
-                    break ReadTermination::Limit;
-                }
-            }
+        let options = line_reader::ReadLineOptions {
+            delimiter,
+            char_limit: self
+                .return_after_n_chars_no_delimiter
+                .or(self.return_after_n_chars),
+            backslash_escapes: !self.raw_mode,
+            timeout,
         };
 
+        let result = line_reader::read_line(&mut input_file, &options);
+
         if let Some(orig_term_attr) = &orig_term_attr {
             input_file.set_term_attr(orig_term_attr)?;
         }
 
-        match reason {
-            ReadTermination::EndOfInput => {
-                if line.is_empty() {
-                    Ok(None)
-                } else {
-                    Ok(Some(line))
-                }
-            }
-            ReadTermination::CtrlC => {
-                // Discard the input and return.
-                Ok(None)
-            }
-            ReadTermination::Delimiter | ReadTermination::Limit => Ok(Some(line)),
+        let (mut line, reason) = result?;
+
+        // Escaped characters are kept escaped in the line, so that they're exempt
+        // from field splitting; backslashes are removed on assignment. In raw mode,
+        // backslashes are literal and need escaping of their own.
+        if self.raw_mode {
+            line = line.replace('\\', "\\\\");
+        }
+
+        // Seed the line with any initial text; it doesn't count toward the character limit.
+        if let Some(initial_text) = &self.initial_text {
+            line.insert_str(0, initial_text.replace('\\', "\\\\").as_str());
         }
+
+        Ok((line, reason))
     }
 
     fn setup_terminal_settings(
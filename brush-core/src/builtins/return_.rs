@@ -1,4 +1,5 @@
 use clap::Parser;
+use std::io::Write;
 
 use crate::{builtins, commands};
 
@@ -6,6 +7,7 @@ use crate::{builtins, commands};
 #[derive(Parser)]
 pub(crate) struct ReturnCommand {
     /// The exit code to return.
+    #[arg(allow_negative_numbers = true)]
     code: Option<i32>,
 }
 
@@ -15,6 +17,15 @@ impl builtins::Command for ReturnCommand {
         &self,
         context: commands::ExecutionContext<'_>,
     ) -> Result<crate::builtins::ExitCode, crate::error::Error> {
+        if !context.shell.in_function() && !context.shell.in_sourced_script() {
+            writeln!(
+                context.stderr(),
+                "{}: can only `return' from a function or sourced script",
+                context.command_name
+            )?;
+            return Ok(builtins::ExitCode::InvalidUsage);
+        }
+
         let code_8bit: u8;
         #[allow(clippy::cast_sign_loss)]
         if let Some(code_32bit) = &self.code {
@@ -23,7 +34,6 @@ impl builtins::Command for ReturnCommand {
             code_8bit = context.shell.last_exit_status;
         }
 
-        // TODO: only allow return invocation from a function or script
         Ok(builtins::ExitCode::ReturnFromFunctionOrScript(code_8bit))
     }
 }
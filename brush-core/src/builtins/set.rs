@@ -1,6 +1,8 @@
 use std::collections::HashMap;
+use std::io::Write;
 
 use clap::Parser;
+use itertools::Itertools;
 
 use crate::{builtins, commands, error, namedoptions};
 
@@ -70,10 +72,10 @@ builtins::minus_or_plus_flag_arg!(
 
 #[derive(clap::Parser)]
 pub(crate) struct SetOption {
-    #[arg(short = 'o', name = "setopt_enable")]
-    enable: Vec<String>,
-    #[arg(long = concat!("+o"), name = "setopt_disable", hide = true)]
-    disable: Vec<String>,
+    #[arg(short = 'o', name = "setopt_enable", num_args = 0..=1)]
+    enable: Option<Vec<String>>,
+    #[arg(long = concat!("+o"), name = "setopt_disable", hide = true, num_args = 0..=1)]
+    disable: Option<Vec<String>>,
 }
 
 /// Manage set-based shell options.
@@ -128,6 +130,15 @@ pub(crate) struct SetCommand {
     set_option: SetOption,
 
     positional_args: Vec<String>,
+
+    /// Whether positional parameters were specified (possibly as an empty list, following
+    /// a `--`) and should therefore replace the current ones.
+    #[arg(skip)]
+    positional_args_specified: bool,
+
+    /// Whether the options were ended by a lone `-`, which also turns off `-x` and `-v`.
+    #[arg(skip)]
+    dash_terminated: bool,
 }
 
 #[async_trait::async_trait]
@@ -136,6 +147,47 @@ impl builtins::Command for SetCommand {
         true
     }
 
+    /// Override the default [builtins::Command::new] function to detect `--` and `-`
+    /// separators, which clap doesn't report. Even with no arguments following it, `--`
+    /// indicates that the positional parameters should be reset; `-` only does so if
+    /// arguments follow it.
+    fn new<I>(args: I) -> Result<Self, clap::Error>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let args: Vec<_> = args.into_iter().collect();
+        let separator_index = args.iter().skip(1).position(|arg| arg == "--");
+        let dash_index = args.iter().skip(1).position(|arg| arg == "-");
+        let dash_terminated = match (dash_index, separator_index) {
+            (Some(dash_index), Some(separator_index)) => dash_index < separator_index,
+            (dash_index, _) => dash_index.is_some(),
+        };
+
+        // N.B. clap doesn't support named options like '+x'; we rename them as the
+        // default implementation does.
+        let args = args.into_iter().map(|arg| {
+            if arg.starts_with('+') {
+                format!("--{arg}")
+            } else {
+                arg
+            }
+        });
+
+        let mut this = Self::try_parse_from(args)?;
+
+        // A `-` ending the options is reported by clap as the first positional argument.
+        this.dash_terminated =
+            dash_terminated && this.positional_args.first().is_some_and(|arg| arg == "-");
+        if this.dash_terminated {
+            this.positional_args.remove(0);
+        }
+
+        this.positional_args_specified = (separator_index.is_some() && !this.dash_terminated)
+            || !this.positional_args.is_empty();
+
+        Ok(this)
+    }
+
     #[allow(clippy::too_many_lines)]
     async fn execute(
         &self,
@@ -241,11 +293,19 @@ impl builtins::Command for SetCommand {
                 .shell_functions_inherit_debug_and_return_traps = value;
         }
 
+        // With no option names, -o and +o display the current option values.
+        if self.set_option.enable.as_ref().is_some_and(Vec::is_empty) {
+            Self::display_named_options(&context, false)?;
+        }
+        if self.set_option.disable.as_ref().is_some_and(Vec::is_empty) {
+            Self::display_named_options(&context, true)?;
+        }
+
         let mut named_options: HashMap<String, bool> = HashMap::new();
-        for option_name in &self.set_option.disable {
+        for option_name in self.set_option.disable.iter().flatten() {
             named_options.insert(option_name.to_owned(), false);
         }
-        for option_name in &self.set_option.enable {
+        for option_name in self.set_option.enable.iter().flatten() {
             named_options.insert(option_name.to_owned(), true);
         }
 
@@ -253,22 +313,47 @@ impl builtins::Command for SetCommand {
             if let Some(option_def) = namedoptions::SET_O_OPTIONS.get(option_name.as_str()) {
                 (option_def.setter)(&mut context.shell.options, value);
             } else {
+                writeln!(
+                    context.stderr(),
+                    "{}: {option_name}: invalid option name",
+                    context.command_name
+                )?;
                 result = builtins::ExitCode::InvalidUsage;
             }
         }
 
-        for (i, arg) in self.positional_args.iter().enumerate() {
-            if arg == "-" && i == 0 {
-                continue;
-            }
+        if self.dash_terminated {
+            context.shell.options.print_commands_and_arguments = false;
+            context.shell.options.print_shell_input_lines = false;
+        }
+
+        if self.positional_args_specified {
+            context.shell.positional_parameters = self.positional_args.clone();
+        }
 
-            if i < context.shell.positional_parameters.len() {
-                arg.clone_into(&mut context.shell.positional_parameters[i]);
+        Ok(result)
+    }
+}
+
+impl SetCommand {
+    fn display_named_options(
+        context: &commands::ExecutionContext<'_>,
+        as_commands: bool,
+    ) -> Result<(), error::Error> {
+        for (option_name, option_definition) in namedoptions::SET_O_OPTIONS
+            .iter()
+            .sorted_by_key(|(name, _)| *name)
+        {
+            let option_value = (option_definition.getter)(&context.shell.options);
+            if as_commands {
+                let option_value_str = if option_value { "-o" } else { "+o" };
+                writeln!(context.stdout(), "set {option_value_str} {option_name}")?;
             } else {
-                context.shell.positional_parameters.push(arg.to_owned());
+                let option_value_str = if option_value { "on" } else { "off" };
+                writeln!(context.stdout(), "{option_name:15}\t{option_value_str}")?;
             }
         }
 
-        Ok(result)
+        Ok(())
     }
 }
@@ -1,4 +1,5 @@
 use clap::Parser;
+use std::io::Write;
 
 use crate::{builtins, commands};
 
@@ -6,6 +7,7 @@ use crate::{builtins, commands};
 #[derive(Parser)]
 pub(crate) struct ShiftCommand {
     /// Number of positions to shift the arguments by (defaults to 1).
+    #[arg(allow_negative_numbers = true)]
     n: Option<i32>,
 }
 
@@ -18,14 +20,21 @@ impl builtins::Command for ShiftCommand {
         let n = self.n.unwrap_or(1);
 
         if n < 0 {
-            return Ok(builtins::ExitCode::InvalidUsage);
+            writeln!(
+                context.stderr(),
+                "{}: {n}: shift count out of range",
+                context.command_name
+            )?;
+            return Ok(builtins::ExitCode::Custom(1));
         }
 
         #[allow(clippy::cast_sign_loss)]
         let n = n as usize;
 
+        // Shifting by more than the number of positional parameters fails silently,
+        // leaving them untouched.
         if n > context.shell.positional_parameters.len() {
-            return Ok(builtins::ExitCode::InvalidUsage);
+            return Ok(builtins::ExitCode::Custom(1));
         }
 
         context.shell.positional_parameters.drain(0..n);
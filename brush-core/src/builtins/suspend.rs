@@ -0,0 +1,55 @@
+use clap::Parser;
+use std::io::Write;
+
+use crate::{builtins, commands, sys};
+
+/// Suspend the shell until it receives a `SIGCONT` signal.
+#[derive(Parser)]
+pub(crate) struct SuspendCommand {
+    /// Suspend the shell even if it is a login shell or job control is disabled. (Before
+    /// bash 5.2, this only overrode the refusal to suspend a login shell.)
+    #[arg(short = 'f')]
+    force: bool,
+}
+
+#[async_trait::async_trait]
+impl builtins::Command for SuspendCommand {
+    async fn execute(
+        &self,
+        context: commands::ExecutionContext<'_>,
+    ) -> Result<crate::builtins::ExitCode, crate::error::Error> {
+        if !self.force {
+            if !context.shell.options.enable_job_control {
+                writeln!(
+                    context.stderr(),
+                    "{}: cannot suspend: no job control",
+                    context.command_name
+                )?;
+                return Ok(builtins::ExitCode::Custom(1));
+            }
+
+            if context.shell.options.login_shell {
+                writeln!(
+                    context.stderr(),
+                    "{}: cannot suspend a login shell",
+                    context.command_name
+                )?;
+                return Ok(builtins::ExitCode::Custom(1));
+            }
+        }
+
+        let Some(pgid) = sys::terminal::get_process_group_id() else {
+            return Ok(builtins::ExitCode::Custom(1));
+        };
+
+        // Execution resumes here once the shell's process group is continued.
+        sys::signal::stop_process_group(pgid)?;
+
+        // Whoever continued us may have left another process group in the foreground.
+        if context.shell.options.interactive {
+            sys::terminal::move_self_to_foreground()?;
+        }
+
+        Ok(builtins::ExitCode::Success)
+    }
+}
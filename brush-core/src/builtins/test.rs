@@ -31,10 +31,14 @@ impl builtins::Command for TestCommand {
             args = &args[0..args.len() - 1];
         }
 
-        if execute_test(context.shell, args)? {
-            Ok(builtins::ExitCode::Success)
-        } else {
-            Ok(builtins::ExitCode::Custom(1))
+        // N.B. Malformed expressions are reported with a distinct exit code from false results.
+        match execute_test(context.shell, args) {
+            Ok(true) => Ok(builtins::ExitCode::Success),
+            Ok(false) => Ok(builtins::ExitCode::Custom(1)),
+            Err(e) => {
+                writeln!(context.stderr(), "{}: {e}", context.command_name)?;
+                Ok(builtins::ExitCode::InvalidUsage)
+            }
         }
     }
 }
@@ -0,0 +1,37 @@
+use clap::Parser;
+use nix::sys::resource::{getrusage, UsageWho};
+use nix::sys::time::TimeVal;
+use std::io::Write;
+
+use crate::{builtins, commands};
+
+/// Report the user and system CPU time consumed by the shell and its children.
+#[derive(Parser)]
+pub(crate) struct TimesCommand {}
+
+#[async_trait::async_trait]
+impl builtins::Command for TimesCommand {
+    async fn execute(
+        &self,
+        context: commands::ExecutionContext<'_>,
+    ) -> Result<crate::builtins::ExitCode, crate::error::Error> {
+        for who in [UsageWho::RUSAGE_SELF, UsageWho::RUSAGE_CHILDREN] {
+            let usage = getrusage(who)?;
+            writeln!(
+                context.stdout(),
+                "{} {}",
+                format_time(usage.user_time()),
+                format_time(usage.system_time())
+            )?;
+        }
+
+        Ok(builtins::ExitCode::Success)
+    }
+}
+
+/// Formats a CPU time as minutes and seconds (e.g., `1m2.345s`).
+fn format_time(time: TimeVal) -> String {
+    let seconds = time.tv_sec();
+    let millis = time.tv_usec() / 1000;
+    std::format!("{}m{}.{millis:03}s", seconds / 60, seconds % 60)
+}
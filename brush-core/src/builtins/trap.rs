@@ -1,7 +1,7 @@
 use clap::Parser;
 use std::io::Write;
 
-use crate::{builtins, commands, error, sys, traps};
+use crate::{builtins, commands, error, escape, traps};
 
 /// Manage signal traps.
 #[derive(Parser)]
@@ -25,33 +25,55 @@ impl builtins::Command for TrapCommand {
     ) -> Result<builtins::ExitCode, crate::error::Error> {
         if self.list_signals {
             Self::display_signals(&context)?;
-            Ok(builtins::ExitCode::Success)
-        } else if self.print_trap_commands || self.args.is_empty() {
+            return Ok(builtins::ExitCode::Success);
+        }
+
+        let mut result = builtins::ExitCode::Success;
+
+        if self.print_trap_commands || self.args.is_empty() {
             if !self.args.is_empty() {
-                for signal_type in &self.args {
-                    let signal_type = parse_signal(signal_type)?;
-                    Self::display_handlers_for(&context, signal_type)?;
+                for signal in &self.args {
+                    if let Some(signal_type) = Self::parse_signal_or_report(&context, signal)? {
+                        Self::display_handlers_for(&context, signal_type)?;
+                    } else {
+                        result = builtins::ExitCode::Custom(1);
+                    }
                 }
             } else {
                 Self::display_all_handlers(&context)?;
             }
-            Ok(builtins::ExitCode::Success)
-        } else if self.args.len() == 1 {
-            let signal = self.args[0].as_str();
-            let signal_type = parse_signal(signal)?;
-            Self::remove_all_handlers(&mut context, signal_type);
-            Ok(builtins::ExitCode::Success)
+
+            return Ok(result);
+        }
+
+        // A lone operand, or a first operand that's `-` or a signal number, indicates that the
+        // given signals should be reset to their original dispositions.
+        let (handler, signals) = if self.args.len() == 1 {
+            (None, self.args.as_slice())
+        } else if self.args[0] == "-" {
+            (None, &self.args[1..])
+        } else if self.args[0].chars().all(|c| c.is_ascii_digit())
+            && traps::parse_signal(self.args[0].as_str()).is_ok()
+        {
+            (None, self.args.as_slice())
         } else {
-            let handler = &self.args[0];
+            (Some(self.args[0].as_str()), &self.args[1..])
+        };
 
-            let mut signal_types = vec![];
-            for signal in &self.args[1..] {
-                signal_types.push(parse_signal(signal)?);
-            }
+        for signal in signals {
+            let Some(signal_type) = Self::parse_signal_or_report(&context, signal)? else {
+                result = builtins::ExitCode::Custom(1);
+                continue;
+            };
 
-            Self::register_handler(&mut context, signal_types, handler.as_str());
-            Ok(builtins::ExitCode::Success)
+            if let Some(handler) = handler {
+                Self::register_handler(&mut context, signal_type, handler)?;
+            } else {
+                Self::remove_all_handlers(&mut context, signal_type)?;
+            }
         }
+
+        Ok(result)
     }
 }
 
@@ -68,8 +90,11 @@ impl TrapCommand {
     }
 
     fn display_all_handlers(context: &commands::ExecutionContext<'_>) -> Result<(), error::Error> {
-        for signal in context.shell.traps.handlers.keys() {
-            Self::display_handlers_for(context, *signal)?;
+        let mut signals: Vec<_> = context.shell.traps.handlers.keys().copied().collect();
+        signals.sort_by_key(|signal| get_display_order(*signal));
+
+        for signal in signals {
+            Self::display_handlers_for(context, signal)?;
         }
         Ok(())
     }
@@ -79,51 +104,59 @@ impl TrapCommand {
         signal_type: traps::TrapSignal,
     ) -> Result<(), error::Error> {
         if let Some(handler) = context.shell.traps.handlers.get(&signal_type) {
-            writeln!(context.stdout(), "trap -- '{handler}' {signal_type}")?;
+            writeln!(
+                context.stdout(),
+                "trap -- {} {signal_type}",
+                escape::single_quote(handler.as_str())
+            )?;
         }
         Ok(())
     }
 
+    fn parse_signal_or_report(
+        context: &commands::ExecutionContext<'_>,
+        signal: &str,
+    ) -> Result<Option<traps::TrapSignal>, error::Error> {
+        if let Ok(signal_type) = traps::parse_signal(signal) {
+            Ok(Some(signal_type))
+        } else {
+            writeln!(
+                context.stderr(),
+                "{}: {signal}: invalid signal specification",
+                context.command_name
+            )?;
+            Ok(None)
+        }
+    }
+
     fn remove_all_handlers(
         context: &mut crate::commands::ExecutionContext<'_>,
         signal: traps::TrapSignal,
-    ) {
-        context.shell.traps.remove_handlers(signal);
+    ) -> Result<(), error::Error> {
+        context.shell.traps.remove_handlers(signal)
     }
 
     fn register_handler(
         context: &mut crate::commands::ExecutionContext<'_>,
-        signals: Vec<traps::TrapSignal>,
+        signal: traps::TrapSignal,
         handler: &str,
-    ) {
-        for signal in signals {
-            context
-                .shell
-                .traps
-                .register_handler(signal, handler.to_owned());
-        }
+    ) -> Result<(), error::Error> {
+        context
+            .shell
+            .traps
+            .register_handler(signal, handler.to_owned())
     }
 }
 
-fn parse_signal(signal: &str) -> Result<traps::TrapSignal, error::Error> {
-    if signal.chars().all(|c| c.is_ascii_digit()) {
-        let digits = signal
-            .parse::<i32>()
-            .map_err(|_| error::Error::InvalidSignal)?;
-
-        sys::signal::parse_numeric_signal(digits)
-    } else {
-        let mut signal_to_parse = signal.to_ascii_uppercase();
-
-        if !signal_to_parse.starts_with("SIG") {
-            signal_to_parse.insert_str(0, "SIG");
-        }
-
-        match signal_to_parse {
-            s if s == "SIGDEBUG" => Ok(traps::TrapSignal::Debug),
-            s if s == "SIGERR" => Ok(traps::TrapSignal::Err),
-            s if s == "SIGEXIT" => Ok(traps::TrapSignal::Exit),
-            s => sys::signal::parse_os_signal_name(s.as_str()),
-        }
+/// Orders signals for display: EXIT first, then system signals by number, then the
+/// remaining pseudo-signals.
+fn get_display_order(signal: traps::TrapSignal) -> i32 {
+    match signal {
+        traps::TrapSignal::Exit => 0,
+        #[cfg(unix)]
+        traps::TrapSignal::Signal(s) => s as i32,
+        traps::TrapSignal::Debug => 1000,
+        traps::TrapSignal::Err => 1001,
+        traps::TrapSignal::Return => 1002,
     }
 }
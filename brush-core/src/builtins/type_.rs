@@ -42,6 +42,7 @@ enum ResolvedType {
     Function(Arc<ast::FunctionDefinition>),
     Builtin,
     File(PathBuf),
+    HashedFile(PathBuf),
 }
 
 #[async_trait::async_trait]
@@ -65,7 +66,12 @@ impl builtins::Command for TypeCommand {
             }
 
             for resolved_type in resolved_types {
-                if self.show_path_only && !matches!(resolved_type, ResolvedType::File(_)) {
+                if self.show_path_only
+                    && !matches!(
+                        resolved_type,
+                        ResolvedType::File(_) | ResolvedType::HashedFile(_)
+                    )
+                {
                     // Do nothing.
                 } else if self.type_only {
                     match resolved_type {
@@ -81,7 +87,7 @@ impl builtins::Command for TypeCommand {
                         ResolvedType::Builtin => {
                             writeln!(context.stdout(), "builtin")?;
                         }
-                        ResolvedType::File(path) => {
+                        ResolvedType::File(path) | ResolvedType::HashedFile(path) => {
                             if self.show_path_only || self.force_path_search {
                                 writeln!(context.stdout(), "{}", path.to_string_lossy())?;
                             } else {
@@ -92,7 +98,7 @@ impl builtins::Command for TypeCommand {
                 } else {
                     match resolved_type {
                         ResolvedType::Alias(target) => {
-                            writeln!(context.stdout(), "{name} is aliased to '{target}'")?;
+                            writeln!(context.stdout(), "{name} is aliased to `{target}'")?;
                         }
                         ResolvedType::Keyword => {
                             writeln!(context.stdout(), "{name} is a shell keyword")?;
@@ -116,6 +122,17 @@ impl builtins::Command for TypeCommand {
                                 )?;
                             }
                         }
+                        ResolvedType::HashedFile(path) => {
+                            if self.show_path_only || self.force_path_search {
+                                writeln!(context.stdout(), "{}", path.to_string_lossy())?;
+                            } else {
+                                writeln!(
+                                    context.stdout(),
+                                    "{name} is hashed ({})",
+                                    path.to_string_lossy()
+                                )?;
+                            }
+                        }
                     }
                 }
 
@@ -163,6 +180,11 @@ impl TypeCommand {
             if shell.get_absolute_path(Path::new(name)).executable() {
                 types.push(ResolvedType::File(PathBuf::from(name)));
             }
+        } else if let Some(path) = shell
+            .get_cached_program_location(name)
+            .filter(|_| !self.all_locations)
+        {
+            types.push(ResolvedType::HashedFile(path));
         } else {
             for item in shell.find_executables_in_path(name) {
                 types.push(ResolvedType::File(item));
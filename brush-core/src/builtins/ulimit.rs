@@ -0,0 +1,431 @@
+use clap::Parser;
+use nix::libc::{rlim_t, RLIM_INFINITY};
+use nix::sys::resource::Resource;
+use std::io::Write;
+
+use crate::{builtins, commands, error, Shell};
+
+/// Display or modify resource limits.
+#[derive(Parser)]
+pub(crate) struct UlimitCommand {
+    /// Use the hard limit.
+    #[arg(short = 'H')]
+    hard: bool,
+
+    /// Use the soft limit.
+    #[arg(short = 'S')]
+    soft: bool,
+
+    /// Report all current limits.
+    #[arg(short = 'a')]
+    all: bool,
+
+    /// Maximum time a real-time process may run without blocking.
+    #[arg(short = 'R')]
+    real_time_non_blocking_time: bool,
+
+    /// Maximum size of core files created.
+    #[arg(short = 'c')]
+    core_file_size: bool,
+
+    /// Maximum size of a process's data segment.
+    #[arg(short = 'd')]
+    data_segment_size: bool,
+
+    /// Maximum scheduling priority.
+    #[arg(short = 'e')]
+    scheduling_priority: bool,
+
+    /// Maximum size of files written by the shell and its children.
+    #[arg(short = 'f')]
+    file_size: bool,
+
+    /// Maximum number of pending signals.
+    #[arg(short = 'i')]
+    pending_signals: bool,
+
+    /// Maximum size a process may lock into memory.
+    #[arg(short = 'l')]
+    locked_memory_size: bool,
+
+    /// Maximum resident set size.
+    #[arg(short = 'm')]
+    memory_size: bool,
+
+    /// Maximum number of open file descriptors.
+    #[arg(short = 'n')]
+    open_files: bool,
+
+    /// Pipe buffer size.
+    #[arg(short = 'p')]
+    pipe_size: bool,
+
+    /// Maximum number of bytes in POSIX message queues.
+    #[arg(short = 'q')]
+    message_queue_size: bool,
+
+    /// Maximum real-time scheduling priority.
+    #[arg(short = 'r')]
+    real_time_priority: bool,
+
+    /// Maximum stack size.
+    #[arg(short = 's')]
+    stack_size: bool,
+
+    /// Maximum amount of CPU time.
+    #[arg(short = 't')]
+    cpu_time: bool,
+
+    /// Maximum number of processes available to the user.
+    #[arg(short = 'u')]
+    user_processes: bool,
+
+    /// Maximum amount of virtual memory available to the shell.
+    #[arg(short = 'v')]
+    virtual_memory_size: bool,
+
+    /// Maximum number of file locks.
+    #[arg(short = 'x')]
+    file_locks: bool,
+
+    /// New value for the selected limits: a number, `unlimited`, `hard`, or `soft`.
+    limit: Option<String>,
+}
+
+/// Describes a limit that may be displayed or modified by `ulimit`.
+struct Limit {
+    /// The option character that selects the limit.
+    option: char,
+    /// Description of the limit, as displayed by `ulimit -a`.
+    description: &'static str,
+    /// Name of the unit that the limit is displayed and specified in, if any.
+    unit: Option<&'static str>,
+    /// The size of the unit, relative to the value stored by the operating system.
+    factor: rlim_t,
+    /// Where the limit comes from.
+    source: LimitSource,
+}
+
+enum LimitSource {
+    /// A resource limit managed with `getrlimit` and `setrlimit`.
+    Resource(Resource),
+    /// The size of a pipe buffer, which can't be modified.
+    PipeSize,
+}
+
+/// All known limits, in the order displayed by `ulimit -a`.
+const LIMITS: &[Limit] = &[
+    #[cfg(target_os = "linux")]
+    Limit {
+        option: 'R',
+        description: "real-time non-blocking time",
+        unit: Some("microseconds"),
+        factor: 1,
+        source: LimitSource::Resource(Resource::RLIMIT_RTTIME),
+    },
+    Limit {
+        option: 'c',
+        description: "core file size",
+        unit: Some("blocks"),
+        factor: 512,
+        source: LimitSource::Resource(Resource::RLIMIT_CORE),
+    },
+    Limit {
+        option: 'd',
+        description: "data seg size",
+        unit: Some("kbytes"),
+        factor: 1024,
+        source: LimitSource::Resource(Resource::RLIMIT_DATA),
+    },
+    #[cfg(target_os = "linux")]
+    Limit {
+        option: 'e',
+        description: "scheduling priority",
+        unit: None,
+        factor: 1,
+        source: LimitSource::Resource(Resource::RLIMIT_NICE),
+    },
+    Limit {
+        option: 'f',
+        description: "file size",
+        unit: Some("blocks"),
+        factor: 512,
+        source: LimitSource::Resource(Resource::RLIMIT_FSIZE),
+    },
+    #[cfg(target_os = "linux")]
+    Limit {
+        option: 'i',
+        description: "pending signals",
+        unit: None,
+        factor: 1,
+        source: LimitSource::Resource(Resource::RLIMIT_SIGPENDING),
+    },
+    #[cfg(target_os = "linux")]
+    Limit {
+        option: 'l',
+        description: "max locked memory",
+        unit: Some("kbytes"),
+        factor: 1024,
+        source: LimitSource::Resource(Resource::RLIMIT_MEMLOCK),
+    },
+    #[cfg(target_os = "linux")]
+    Limit {
+        option: 'm',
+        description: "max memory size",
+        unit: Some("kbytes"),
+        factor: 1024,
+        source: LimitSource::Resource(Resource::RLIMIT_RSS),
+    },
+    Limit {
+        option: 'n',
+        description: "open files",
+        unit: None,
+        factor: 1,
+        source: LimitSource::Resource(Resource::RLIMIT_NOFILE),
+    },
+    Limit {
+        option: 'p',
+        description: "pipe size",
+        unit: Some("512 bytes"),
+        factor: 512,
+        source: LimitSource::PipeSize,
+    },
+    #[cfg(target_os = "linux")]
+    Limit {
+        option: 'q',
+        description: "POSIX message queues",
+        unit: Some("bytes"),
+        factor: 1,
+        source: LimitSource::Resource(Resource::RLIMIT_MSGQUEUE),
+    },
+    #[cfg(target_os = "linux")]
+    Limit {
+        option: 'r',
+        description: "real-time priority",
+        unit: None,
+        factor: 1,
+        source: LimitSource::Resource(Resource::RLIMIT_RTPRIO),
+    },
+    Limit {
+        option: 's',
+        description: "stack size",
+        unit: Some("kbytes"),
+        factor: 1024,
+        source: LimitSource::Resource(Resource::RLIMIT_STACK),
+    },
+    Limit {
+        option: 't',
+        description: "cpu time",
+        unit: Some("seconds"),
+        factor: 1,
+        source: LimitSource::Resource(Resource::RLIMIT_CPU),
+    },
+    #[cfg(target_os = "linux")]
+    Limit {
+        option: 'u',
+        description: "max user processes",
+        unit: None,
+        factor: 1,
+        source: LimitSource::Resource(Resource::RLIMIT_NPROC),
+    },
+    #[cfg(target_os = "linux")]
+    Limit {
+        option: 'v',
+        description: "virtual memory",
+        unit: Some("kbytes"),
+        factor: 1024,
+        source: LimitSource::Resource(Resource::RLIMIT_AS),
+    },
+    #[cfg(target_os = "linux")]
+    Limit {
+        option: 'x',
+        description: "file locks",
+        unit: None,
+        factor: 1,
+        source: LimitSource::Resource(Resource::RLIMIT_LOCKS),
+    },
+];
+
+#[async_trait::async_trait]
+impl builtins::Command for UlimitCommand {
+    async fn execute(
+        &self,
+        mut context: commands::ExecutionContext<'_>,
+    ) -> Result<crate::builtins::ExitCode, crate::error::Error> {
+        if self.all {
+            for limit in LIMITS {
+                self.display_limit(&context, limit, true)?;
+            }
+            return Ok(builtins::ExitCode::Success);
+        }
+
+        let mut selected = vec![];
+        for option in self.selected_options() {
+            if let Some(limit) = LIMITS.iter().find(|limit| limit.option == option) {
+                selected.push(limit);
+            } else {
+                writeln!(
+                    context.stderr(),
+                    "{}: -{option}: limit not supported on this platform",
+                    context.command_name
+                )?;
+                return Ok(builtins::ExitCode::Custom(1));
+            }
+        }
+
+        let Some(new_limit) = &self.limit else {
+            let show_descriptions = selected.len() > 1;
+            for limit in selected {
+                self.display_limit(&context, limit, show_descriptions)?;
+            }
+            return Ok(builtins::ExitCode::Success);
+        };
+
+        let mut exit_code = builtins::ExitCode::Success;
+        for limit in selected {
+            if !self.set_limit(&mut context, limit, new_limit)? {
+                exit_code = builtins::ExitCode::Custom(1);
+            }
+        }
+
+        Ok(exit_code)
+    }
+}
+
+impl UlimitCommand {
+    /// Returns the option characters of the selected limits; the file size limit is
+    /// selected if no others are.
+    fn selected_options(&self) -> Vec<char> {
+        let options: Vec<_> = [
+            (self.real_time_non_blocking_time, 'R'),
+            (self.core_file_size, 'c'),
+            (self.data_segment_size, 'd'),
+            (self.scheduling_priority, 'e'),
+            (self.file_size, 'f'),
+            (self.pending_signals, 'i'),
+            (self.locked_memory_size, 'l'),
+            (self.memory_size, 'm'),
+            (self.open_files, 'n'),
+            (self.pipe_size, 'p'),
+            (self.message_queue_size, 'q'),
+            (self.real_time_priority, 'r'),
+            (self.stack_size, 's'),
+            (self.cpu_time, 't'),
+            (self.user_processes, 'u'),
+            (self.virtual_memory_size, 'v'),
+            (self.file_locks, 'x'),
+        ]
+        .into_iter()
+        .filter_map(|(selected, option)| selected.then_some(option))
+        .collect();
+
+        if options.is_empty() {
+            vec!['f']
+        } else {
+            options
+        }
+    }
+
+    fn display_limit(
+        &self,
+        context: &commands::ExecutionContext<'_>,
+        limit: &Limit,
+        show_description: bool,
+    ) -> Result<(), error::Error> {
+        let (soft, hard) = get_limits(context.shell, limit)?;
+        let value = if self.hard { hard } else { soft };
+
+        let formatted = if value == RLIM_INFINITY {
+            String::from("unlimited")
+        } else {
+            (value / limit.factor).to_string()
+        };
+
+        if show_description {
+            let unit = match limit.unit {
+                Some(unit) => std::format!("({unit}, -{}) ", limit.option),
+                None => std::format!("(-{}) ", limit.option),
+            };
+            writeln!(
+                context.stdout(),
+                "{:<20} {unit:>20}{formatted}",
+                limit.description
+            )?;
+        } else {
+            writeln!(context.stdout(), "{formatted}")?;
+        }
+
+        Ok(())
+    }
+
+    /// Updates the given limit, returning whether it was successfully updated. Unless
+    /// `-H` or `-S` was given, both the hard and soft limits are updated.
+    fn set_limit(
+        &self,
+        context: &mut commands::ExecutionContext<'_>,
+        limit: &Limit,
+        new_limit: &str,
+    ) -> Result<bool, error::Error> {
+        let (soft, hard) = get_limits(context.shell, limit)?;
+
+        let value = match new_limit {
+            "unlimited" => RLIM_INFINITY,
+            "hard" => hard,
+            "soft" => soft,
+            _ => {
+                if let Some(value) = new_limit
+                    .parse::<rlim_t>()
+                    .ok()
+                    .and_then(|value| value.checked_mul(limit.factor))
+                {
+                    value
+                } else {
+                    writeln!(
+                        context.stderr(),
+                        "{}: {new_limit}: invalid number",
+                        context.command_name
+                    )?;
+                    return Ok(false);
+                }
+            }
+        };
+
+        let (new_soft, new_hard) = match (self.soft, self.hard) {
+            (true, false) => (value, hard),
+            (false, true) => (soft, value),
+            _ => (value, value),
+        };
+
+        let result = match limit.source {
+            LimitSource::Resource(resource) => context
+                .shell
+                .resource_limits
+                .set(resource, new_soft, new_hard),
+            LimitSource::PipeSize => Err(nix::errno::Errno::EINVAL),
+        };
+
+        if let Err(errno) = result {
+            writeln!(
+                context.stderr(),
+                "{}: {}: cannot modify limit: {}",
+                context.command_name,
+                limit.description,
+                errno.desc()
+            )?;
+            return Ok(false);
+        }
+
+        Ok(true)
+    }
+}
+
+/// Returns the soft and hard values of the given limit, as seen by the given shell.
+fn get_limits(shell: &Shell, limit: &Limit) -> Result<(rlim_t, rlim_t), error::Error> {
+    match limit.source {
+        LimitSource::Resource(resource) => shell.resource_limits.get(resource),
+        LimitSource::PipeSize => {
+            let size = rlim_t::try_from(nix::libc::PIPE_BUF).unwrap_or(RLIM_INFINITY);
+            Ok((size, size))
+        }
+    }
+}
@@ -27,26 +27,39 @@ impl builtins::Command for UmaskCommand {
         context: commands::ExecutionContext<'_>,
     ) -> Result<crate::builtins::ExitCode, crate::error::Error> {
         if let Some(mode) = &self.mode {
-            if mode.starts_with(|c: char| c.is_digit(8)) {
-                let parsed = u32::from_str_radix(mode.as_str(), 8)?;
-                set_umask(parsed)?;
+            let parsed = if mode.starts_with(|c: char| c.is_ascii_digit()) {
+                parse_octal_mask(mode)
             } else {
-                return crate::error::unimp("umask setting mode from symbolic value");
+                parse_symbolic_mask(mode, get_umask()?)
+            };
+
+            match parsed {
+                Ok(umask) => set_umask(umask)?,
+                Err(message) => {
+                    writeln!(context.stderr(), "{}: {message}", context.command_name)?;
+                    return Ok(builtins::ExitCode::Custom(1));
+                }
+            }
+
+            // The new mask is only displayed if symbolic output was requested.
+            if self.symbolic_output {
+                writeln!(context.stdout(), "{}", format_symbolic_mask(get_umask()?))?;
             }
         } else {
             let umask = get_umask()?;
 
             let formatted = if self.symbolic_output {
-                let u = symbolic_mask_from_bits((!umask & 0o700) >> 6);
-                let g = symbolic_mask_from_bits((!umask & 0o070) >> 3);
-                let o = symbolic_mask_from_bits(!umask & 0o007);
-                std::format!("u={u},g={g},o={o}")
+                format_symbolic_mask(umask)
             } else {
                 std::format!("{umask:04o}")
             };
 
             if self.print_roundtrippable {
-                writeln!(context.stdout(), "umask {formatted}")?;
+                if self.symbolic_output {
+                    writeln!(context.stdout(), "umask -S {formatted}")?;
+                } else {
+                    writeln!(context.stdout(), "umask {formatted}")?;
+                }
             } else {
                 writeln!(context.stdout(), "{formatted}")?;
             }
@@ -79,6 +92,78 @@ fn set_umask(value: u32) -> Result<(), error::Error> {
     Ok(())
 }
 
+/// Parses an octal mask, as in `umask 022`.
+fn parse_octal_mask(mode: &str) -> Result<u32, String> {
+    u32::from_str_radix(mode, 8)
+        .ok()
+        .filter(|value| *value <= 0o7777)
+        .ok_or_else(|| std::format!("{mode}: octal number out of range"))
+}
+
+/// Applies a symbolic mode (e.g., `u=rwx,g-w,o=`) to the given mask, returning the
+/// resulting mask. Each comma-separated clause names the classes of users it applies to
+/// (`u`, `g`, `o`, or `a`; all of them if omitted), an operator (`+`, `-`, or `=`), and
+/// the permissions (`r`, `w`, or `x`) that are added to, removed from, or assigned to
+/// those classes. The permissions are the inverse of the mask.
+fn parse_symbolic_mask(mode: &str, umask: u32) -> Result<u32, String> {
+    let mut permissions = !umask & 0o777;
+
+    for clause in mode.split(',') {
+        let mut chars = clause.chars().peekable();
+
+        let mut who = 0;
+        while let Some(class) = chars.peek().and_then(|c| class_bits(*c)) {
+            who |= class;
+            chars.next();
+        }
+        if who == 0 {
+            who = 0o777;
+        }
+
+        let op = chars.next();
+        if !matches!(op, Some('+' | '-' | '=')) {
+            let op = op.map(String::from).unwrap_or_default();
+            return Err(std::format!("`{op}': invalid symbolic mode operator"));
+        }
+
+        let mut bits = 0;
+        for c in chars {
+            bits |= match c {
+                'r' => 0o444,
+                'w' => 0o222,
+                'x' => 0o111,
+                _ => return Err(std::format!("`{c}': invalid symbolic mode character")),
+            };
+        }
+
+        match op {
+            Some('+') => permissions |= who & bits,
+            Some('-') => permissions &= !(who & bits),
+            _ => permissions = (permissions & !who) | (who & bits),
+        }
+    }
+
+    Ok(!permissions & 0o777)
+}
+
+/// Returns the permission bits affected by the given user class character, if it is one.
+fn class_bits(c: char) -> Option<u32> {
+    match c {
+        'u' => Some(0o700),
+        'g' => Some(0o070),
+        'o' => Some(0o007),
+        'a' => Some(0o777),
+        _ => None,
+    }
+}
+
+fn format_symbolic_mask(umask: u32) -> String {
+    let u = symbolic_mask_from_bits((!umask & 0o700) >> 6);
+    let g = symbolic_mask_from_bits((!umask & 0o070) >> 3);
+    let o = symbolic_mask_from_bits(!umask & 0o007);
+    std::format!("u={u},g={g},o={o}")
+}
+
 fn symbolic_mask_from_bits(bits: u32) -> String {
     let mut result = String::new();
 
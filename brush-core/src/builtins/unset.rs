@@ -1,6 +1,11 @@
 use clap::Parser;
+use std::io::Write;
 
-use crate::{builtins, commands};
+use brush_parser::ast;
+
+use crate::arithmetic::ExpandAndEvaluate;
+use crate::variables::{ArrayLiteral, ShellValue, ShellValueLiteral, ShellValueUnsetType};
+use crate::{builtins, commands, error, Shell};
 
 /// Unset a variable.
 #[derive(Parser)]
@@ -40,41 +45,60 @@ impl builtins::Command for UnsetCommand {
         &self,
         context: commands::ExecutionContext<'_>,
     ) -> Result<crate::builtins::ExitCode, crate::error::Error> {
-        //
-        // TODO: implement nameref
-        //
-        if self.name_interpretation.name_references {
-            return crate::error::unimp("unset: name references are not yet implemented");
-        }
-
         let unspecified = self.name_interpretation.unspecified();
+        let mut exit_code = builtins::ExitCode::Success;
 
         for name in &self.names {
+            if self.name_interpretation.name_references {
+                context.shell.env.unset_unresolved(name.as_str())?;
+                continue;
+            }
+
             if unspecified || self.name_interpretation.shell_variables {
                 let parameter =
                     brush_parser::word::parse_parameter(name, &context.shell.parser_options())?;
 
-                let result = match parameter {
+                let (name, result) = match parameter {
                     brush_parser::word::Parameter::Positional(_) => continue,
                     brush_parser::word::Parameter::Special(_) => continue,
                     brush_parser::word::Parameter::Named(name) => {
-                        context.shell.env.unset(name.as_str())?
+                        let result = context.shell.env.unset(name.as_str());
+                        (name, result)
                     }
                     brush_parser::word::Parameter::NamedWithIndex { name, index } => {
-                        // TODO: Evaluate index?
-                        context
-                            .shell
-                            .env
-                            .unset_index(name.as_str(), index.as_str())?
+                        let result =
+                            unset_array_element(context.shell, name.as_str(), index.as_str()).await;
+                        (name, result)
+                    }
+                    brush_parser::word::Parameter::NamedWithAllIndices { name, concatenate } => {
+                        let result =
+                            unset_all_array_elements(context.shell, name.as_str(), concatenate);
+                        (name, result)
                     }
-                    brush_parser::word::Parameter::NamedWithAllIndices {
-                        name: _,
-                        concatenate: _,
-                    } => continue,
                 };
 
-                if result {
-                    continue;
+                match result {
+                    Ok(true) => continue,
+                    Ok(false) => (),
+                    Err(error::Error::ReadonlyVariable) => {
+                        writeln!(
+                            context.stderr(),
+                            "{}: {name}: cannot unset: readonly variable",
+                            context.command_name
+                        )?;
+                        exit_code = builtins::ExitCode::Custom(1);
+                        continue;
+                    }
+                    Err(error::Error::NotArray) => {
+                        writeln!(
+                            context.stderr(),
+                            "{}: {name}: not an array variable",
+                            context.command_name
+                        )?;
+                        exit_code = builtins::ExitCode::Custom(1);
+                        continue;
+                    }
+                    Err(e) => return Err(e),
                 }
             }
 
@@ -86,6 +110,69 @@ impl builtins::Command for UnsetCommand {
             }
         }
 
-        Ok(builtins::ExitCode::Success)
+        Ok(exit_code)
+    }
+}
+
+/// Unsets the element at the given index of the named array variable. Indices of indexed
+/// arrays are evaluated as arithmetic expressions; for variables that aren't arrays,
+/// index 0 refers to the variable itself.
+async fn unset_array_element(
+    shell: &mut Shell,
+    name: &str,
+    index: &str,
+) -> Result<bool, error::Error> {
+    let Some((_, var)) = shell.env.get(name) else {
+        return Ok(false);
+    };
+
+    let is_indexed_array = match var.value() {
+        ShellValue::AssociativeArray(_)
+        | ShellValue::Unset(ShellValueUnsetType::AssociativeArray) => {
+            return shell.env.unset_index(name, index);
+        }
+        ShellValue::IndexedArray(_) | ShellValue::Unset(ShellValueUnsetType::IndexedArray) => true,
+        _ => false,
+    };
+
+    let index = ast::UnexpandedArithmeticExpr {
+        value: index.to_owned(),
+    }
+    .eval(shell, false)
+    .await?;
+
+    if is_indexed_array {
+        shell.env.unset_index(name, index.to_string().as_str())
+    } else if index == 0 {
+        shell.env.unset(name)
+    } else {
+        Ok(false)
+    }
+}
+
+/// Unsets all elements of the named indexed array variable, leaving it as an empty array.
+/// For associative arrays, `@` and `*` are ordinary keys, so only the element with that key
+/// is unset; variables that aren't arrays are left alone.
+fn unset_all_array_elements(
+    shell: &mut Shell,
+    name: &str,
+    concatenate: bool,
+) -> Result<bool, error::Error> {
+    let Some((_, var)) = shell.env.get_mut(name) else {
+        return Ok(false);
+    };
+
+    match var.value() {
+        ShellValue::IndexedArray(_) => {
+            var.assign(ShellValueLiteral::Array(ArrayLiteral(vec![])), false)?;
+            Ok(true)
+        }
+        ShellValue::Unset(ShellValueUnsetType::IndexedArray) => Ok(true),
+        ShellValue::AssociativeArray(_)
+        | ShellValue::Unset(ShellValueUnsetType::AssociativeArray) => {
+            var.unset_index(if concatenate { "*" } else { "@" })?;
+            Ok(true)
+        }
+        _ => Err(error::Error::NotArray),
     }
 }
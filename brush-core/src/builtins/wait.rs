@@ -1,6 +1,7 @@
 use clap::Parser;
+use std::io::Write;
 
-use crate::{builtins, commands, error};
+use crate::{builtins, commands, error, sys};
 
 /// Wait for jobs to terminate.
 #[derive(Parser)]
@@ -28,21 +29,125 @@ impl builtins::Command for WaitCommand {
         &self,
         context: commands::ExecutionContext<'_>,
     ) -> Result<builtins::ExitCode, crate::error::Error> {
-        if self.wait_for_terminate {
-            return error::unimp("wait -f");
-        }
-        if self.wait_for_first_or_next {
-            return error::unimp("wait -n");
-        }
         if self.variable_to_receive_id.is_some() {
             return error::unimp("wait -p");
         }
-        if !self.job_specs.is_empty() {
-            return error::unimp("wait with job specs");
-        }
 
-        context.shell.jobs.wait_all().await?;
+        let mut exit_code = builtins::ExitCode::Success;
+
+        // Figure out which jobs we're waiting on.
+        let targets = if self.job_specs.is_empty() {
+            if !self.wait_for_first_or_next {
+                context.shell.jobs.wait_all().await?;
+                return Ok(builtins::ExitCode::Success);
+            }
+
+            context
+                .shell
+                .jobs
+                .jobs
+                .iter()
+                .map(|j| WaitTarget::Job(j.id))
+                .collect()
+        } else {
+            let mut targets = vec![];
+            for job_spec in &self.job_specs {
+                let resolved = if let Some(spec) = job_spec.strip_prefix('%') {
+                    let jobs = &mut context.shell.jobs;
+                    match jobs.resolve_job_spec_to_id(job_spec) {
+                        Ok(id) => Ok(WaitTarget::Job(id)),
+                        // The job may have already completed without being waited on.
+                        Err(e) => spec
+                            .parse()
+                            .ok()
+                            .and_then(|id| jobs.take_completed_job_status(id))
+                            .map(WaitTarget::Completed)
+                            .ok_or_else(|| e.to_string()),
+                    }
+                } else if let Ok(pid) = job_spec.parse::<sys::process::ProcessId>() {
+                    let jobs = &mut context.shell.jobs;
+                    jobs.jobs
+                        .iter()
+                        .find(|j| j.is_identified_by_pid(pid))
+                        .map(|j| WaitTarget::Job(j.id))
+                        .or_else(|| {
+                            jobs.take_completed_job_status_by_pgid(pid)
+                                .map(WaitTarget::Completed)
+                        })
+                        .ok_or_else(|| std::format!("pid {pid} is not a child of this shell"))
+                } else {
+                    writeln!(
+                        context.stderr(),
+                        "{}: `{job_spec}': not a pid or valid job spec",
+                        context.command_name
+                    )?;
+                    targets.push(WaitTarget::Unresolved(1));
+                    continue;
+                };
+
+                match resolved {
+                    Ok(target) => targets.push(target),
+                    Err(message) => {
+                        writeln!(context.stderr(), "{}: {message}", context.command_name)?;
+                        targets.push(WaitTarget::Unresolved(127));
+                    }
+                }
+            }
+            targets
+        };
 
-        Ok(builtins::ExitCode::Success)
+        if self.wait_for_first_or_next {
+            // A job that already completed is the first to change status.
+            if let Some(status) = targets.iter().find_map(|t| match t {
+                WaitTarget::Completed(status) => Some(*status),
+                WaitTarget::Job(_) | WaitTarget::Unresolved(_) => None,
+            }) {
+                return Ok(builtins::ExitCode::Custom(status));
+            }
+
+            let ids: Vec<_> = targets
+                .iter()
+                .filter_map(|t| match t {
+                    WaitTarget::Job(id) => Some(*id),
+                    WaitTarget::Completed(_) | WaitTarget::Unresolved(_) => None,
+                })
+                .collect();
+
+            exit_code = match context
+                .shell
+                .jobs
+                .wait_for_first(&ids, self.wait_for_terminate)
+                .await?
+            {
+                Some((_, result)) => builtins::ExitCode::Custom(result.exit_code),
+                None => builtins::ExitCode::Custom(127),
+            };
+        } else {
+            // The exit status is that of the last job spec, even if it couldn't be resolved.
+            for target in targets {
+                let status = match target {
+                    WaitTarget::Job(id) => context
+                        .shell
+                        .jobs
+                        .wait_for_first(&[id], self.wait_for_terminate)
+                        .await?
+                        .map_or(127, |(_, result)| result.exit_code),
+                    WaitTarget::Completed(status) | WaitTarget::Unresolved(status) => status,
+                };
+                exit_code = builtins::ExitCode::Custom(status);
+            }
+        }
+
+        Ok(exit_code)
     }
 }
+
+/// A job to be waited on.
+enum WaitTarget {
+    /// A job that's still managed by the shell, identified by its ID.
+    Job(usize),
+    /// A job that already completed with the given exit status.
+    Completed(u8),
+    /// A job spec that couldn't be resolved, yielding the given exit status.
+    Unresolved(u8),
+}
@@ -11,7 +11,7 @@ use crate::{
     builtins, error,
     interp::{self, Execute, ProcessGroupPolicy},
     openfiles::{self, OpenFile, OpenFiles},
-    processes, sys, trace_categories, ExecutionParameters, ExecutionResult, Shell,
+    processes, sys, trace_categories, traps, ExecutionParameters, ExecutionResult, Shell,
 };
 
 /// Represents the result of spawning a command.
@@ -112,19 +112,22 @@ pub struct ExecutionContext<'a> {
 }
 
 impl ExecutionContext<'_> {
-    /// Returns the standard input file; usable with `write!` et al.
+    /// Returns the standard input file; usable with `write!` et al. If standard input
+    /// has been closed, a null file is returned.
     pub fn stdin(&self) -> openfiles::OpenFile {
-        self.fd(0).unwrap()
+        self.fd(0).unwrap_or(openfiles::OpenFile::Null)
     }
 
-    /// Returns the standard output file; usable with `write!` et al.
+    /// Returns the standard output file; usable with `write!` et al. If standard output
+    /// has been closed, a null file is returned.
     pub fn stdout(&self) -> openfiles::OpenFile {
-        self.fd(1).unwrap()
+        self.fd(1).unwrap_or(openfiles::OpenFile::Null)
     }
 
-    /// Returns the standard error file; usable with `write!` et al.
+    /// Returns the standard error file; usable with `write!` et al. If standard error
+    /// has been closed, a null file is returned.
     pub fn stderr(&self) -> openfiles::OpenFile {
-        self.fd(2).unwrap()
+        self.fd(2).unwrap_or(openfiles::OpenFile::Null)
     }
 
     /// Returns the file descriptor with the given number.
@@ -200,22 +203,31 @@ pub(crate) fn compose_std_command<S: AsRef<OsStr>>(
     // Use the shell's current working dir.
     cmd.current_dir(shell.working_dir.as_path());
 
+    // Apply any resource limits modified in the shell.
+    shell.resource_limits.apply_to_command(&mut cmd);
+
     // Start with a clear environment.
     cmd.env_clear();
 
-    // Add in exported variables.
+    // Add in exported variables, along with `$_`, which holds the path of the command.
     if !empty_env {
         for (name, var) in shell.env.iter() {
             if var.is_exported() {
-                let value_as_str = var.value().to_cow_string();
-                cmd.env(name, value_as_str.as_ref());
+                let value = var.resolve_value(shell);
+                cmd.env(name, value.to_cow_string().as_ref());
             }
         }
+
+        cmd.env("_", command_name);
     }
 
-    // Redirect stdin, if applicable.
+    // Redirect stdin, if applicable. Closed standard streams are given to the child as
+    // null files.
     match open_files.files.remove(&0) {
-        Some(OpenFile::Stdin) | None => (),
+        Some(OpenFile::Stdin) => (),
+        None => {
+            cmd.stdin(Stdio::null());
+        }
         Some(stdin_file) => {
             let as_stdio: Stdio = stdin_file.into();
             cmd.stdin(as_stdio);
@@ -224,7 +236,10 @@ pub(crate) fn compose_std_command<S: AsRef<OsStr>>(
 
     // Redirect stdout, if applicable.
     match open_files.files.remove(&1) {
-        Some(OpenFile::Stdout) | None => (),
+        Some(OpenFile::Stdout) => (),
+        None => {
+            cmd.stdout(Stdio::null());
+        }
         Some(stdout_file) => {
             let as_stdio: Stdio = stdout_file.into();
             cmd.stdout(as_stdio);
@@ -233,7 +248,10 @@ pub(crate) fn compose_std_command<S: AsRef<OsStr>>(
 
     // Redirect stderr, if applicable.
     match open_files.files.remove(&2) {
-        Some(OpenFile::Stderr) | None => {}
+        Some(OpenFile::Stderr) => {}
+        None => {
+            cmd.stderr(Stdio::null());
+        }
         Some(stderr_file) => {
             let as_stdio: Stdio = stderr_file.into();
             cmd.stderr(as_stdio);
@@ -333,11 +351,25 @@ pub(crate) fn execute_external_command(
     // Figure out if we should be setting up a new process group.
     let new_pg = context.should_cmd_lead_own_process_group();
 
+    // Resolve the command's location, consulting the shell's cache of program locations.
+    // If we can't find it, we leave the name as-is and let spawning report the failure.
+    let program = if context.command_name.contains(std::path::MAIN_SEPARATOR) {
+        context.command_name.clone()
+    } else {
+        context
+            .shell
+            .find_first_executable_in_path_using_cache(context.command_name.as_str())
+            .map_or_else(
+                || context.command_name.clone(),
+                |path| path.to_string_lossy().to_string(),
+            )
+    };
+
     // Compose the std::process::Command that encapsulates what we want to launch.
     #[allow(unused_mut)]
     let mut cmd = compose_std_command(
         context.shell,
-        context.command_name.as_str(),
+        program.as_str(),
         context.command_name.as_str(),
         cmd_args.as_slice(),
         context.params.open_files,
@@ -463,17 +495,24 @@ pub(crate) async fn invoke_shell_function(
 ) -> Result<CommandSpawnResult, error::Error> {
     let ast::FunctionBody(body, redirects) = &function_definition.body;
 
-    // Apply any redirects specified at function definition-time.
+    // Temporarily replace positional parameters.
+    let prior_positional_params = std::mem::take(&mut context.shell.positional_parameters);
+    context.shell.positional_parameters = args.iter().map(|a| a.to_string()).collect();
+
+    // Apply any redirects specified at function definition-time. They're expanded on each
+    // invocation, after the function's positional parameters are in place.
     if let Some(redirects) = redirects {
         for redirect in &redirects.0 {
-            interp::setup_redirect(&mut context.params.open_files, context.shell, redirect).await?;
+            if let Err(e) =
+                interp::setup_redirect(&mut context.params.open_files, context.shell, redirect)
+                    .await
+            {
+                context.shell.positional_parameters = prior_positional_params;
+                return Err(e);
+            }
         }
     }
 
-    // Temporarily replace positional parameters.
-    let prior_positional_params = std::mem::take(&mut context.shell.positional_parameters);
-    context.shell.positional_parameters = args.iter().map(|a| a.to_string()).collect();
-
     // Pass through open files.
     let params = context.params.clone();
 
@@ -483,8 +522,39 @@ pub(crate) async fn invoke_shell_function(
         .shell
         .enter_function(context.command_name.as_str(), &function_definition)?;
 
+    // RETURN traps aren't inherited by functions; set aside any that's registered.
+    let prior_return_trap = context
+        .shell
+        .traps
+        .handlers
+        .remove(&traps::TrapSignal::Return);
+
     // Invoke the function.
-    let result = body.execute(context.shell, &params).await;
+    let mut result = body.execute(context.shell, &params).await;
+
+    // Run any RETURN trap registered by the function itself; otherwise, restore the one we
+    // set aside.
+    let return_trap = context
+        .shell
+        .traps
+        .handlers
+        .get(&traps::TrapSignal::Return)
+        .cloned();
+    if let Some(handler) = return_trap {
+        if result.is_ok() {
+            result = context
+                .shell
+                .run_trap_handler(handler, &params)
+                .await
+                .and(result);
+        }
+    } else if let Some(prior_return_trap) = prior_return_trap {
+        context
+            .shell
+            .traps
+            .handlers
+            .insert(traps::TrapSignal::Return, prior_return_trap);
+    }
 
     // Clean up parameters so any owned files are closed.
     drop(params);
@@ -495,5 +565,10 @@ pub(crate) async fn invoke_shell_function(
     // Restore positional parameters.
     context.shell.positional_parameters = prior_positional_params;
 
-    Ok(CommandSpawnResult::ImmediateExit(result?.exit_code))
+    let result = result?;
+    if result.exit_shell {
+        Ok(CommandSpawnResult::ExitShell(result.exit_code))
+    } else {
+        Ok(CommandSpawnResult::ImmediateExit(result.exit_code))
+    }
 }
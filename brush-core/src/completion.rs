@@ -265,6 +265,18 @@ impl Spec {
                 CompleteAction::Command => {
                     let mut command_completions = get_command_completions(shell, context);
                     candidates.append(&mut command_completions);
+
+                    // Aliases, builtins, functions, and keywords are commands too.
+                    candidates.extend(shell.aliases.keys().cloned());
+                    for (name, registration) in &shell.builtins {
+                        if !registration.disabled {
+                            candidates.insert(name.to_owned());
+                        }
+                    }
+                    for (name, _) in shell.funcs.iter() {
+                        candidates.insert(name.to_owned());
+                    }
+                    candidates.extend(shell.get_keywords());
                 }
                 CompleteAction::Directory => {
                     let mut file_completions = get_file_completions(shell, context, true);
@@ -371,10 +383,13 @@ impl Spec {
         }
 
         if let Some(glob_pattern) = &self.glob_pattern {
-            let pattern = patterns::Pattern::from(glob_pattern.as_str());
+            let pattern = patterns::Pattern::from(glob_pattern.as_str())
+                .set_case_insensitive(shell.options.case_insensitive_pathname_expansion);
             let expansions = pattern.expand(
                 shell.working_dir.as_path(),
                 shell.parser_options().enable_extended_globbing,
+                shell.options.enable_star_star_glob,
+                shell.options.glob_matches_dotfiles,
                 Some(&patterns::Pattern::accept_all_expand_filter),
             )?;
 
@@ -388,6 +403,13 @@ impl Spec {
                 candidates.insert(word);
             }
         }
+
+        // Make sure the token we have (if non-empty) is a prefix. N.B. Candidates
+        // provided by completion functions or commands are taken as-is.
+        if !context.token_to_complete.is_empty() {
+            candidates.retain(|candidate| candidate.starts_with(context.token_to_complete));
+        }
+
         if let Some(function_name) = &self.function_name {
             let call_result = self
                 .call_completion_function(shell, function_name.as_str(), context)
@@ -404,15 +426,21 @@ impl Spec {
             tracing::debug!("UNIMPLEMENTED: complete -C({command})");
         }
 
-        // Make sure the token we have (if non-empty) is a prefix.
-        if !context.token_to_complete.is_empty() {
-            candidates.retain(|candidate| candidate.starts_with(context.token_to_complete));
-        }
-
         // Apply filter pattern, if present.
         if let Some(filter_pattern) = &self.filter_pattern {
             if !filter_pattern.is_empty() {
-                tracing::debug!("UNIMPLEMENTED: complete -X (filter pattern): '{filter_pattern}'");
+                let pattern = patterns::Pattern::from(filter_pattern.as_str());
+                let enable_extended_globbing = shell.parser_options().enable_extended_globbing;
+
+                let mut filtered = IndexSet::new();
+                for candidate in candidates {
+                    let matches = pattern.exactly_matches(&candidate, enable_extended_globbing)?;
+                    if matches != self.filter_pattern_excludes {
+                        filtered.insert(candidate);
+                    }
+                }
+
+                candidates = filtered;
             }
         }
 
@@ -452,17 +480,17 @@ impl Spec {
                 tracing::debug!("UNIMPLEMENTED: complete -o bashdefault");
             }
             if options.default {
-                // TODO: if we have no completions, then fall back to default file name completion
-                tracing::debug!("UNIMPLEMENTED: complete -o default");
+                let mut file_completions = get_file_completions(shell, context, false);
+                candidates.append(&mut file_completions);
             }
             if options.dir_names {
-                // TODO: if we have no completions, then fall back to performing dir name completion
-                tracing::debug!("UNIMPLEMENTED: complete -o dirnames");
+                let mut dir_completions = get_file_completions(shell, context, true);
+                candidates.append(&mut dir_completions);
             }
         }
         if options.plus_dirs {
-            // Also add dir name completion.
-            tracing::debug!("UNIMPLEMENTED: complete -o plusdirs");
+            let mut dir_completions = get_file_completions(shell, context, true);
+            candidates.append(&mut dir_completions);
         }
 
         // Sort, unless blocked by options.
@@ -620,6 +648,14 @@ impl Config {
         }
     }
 
+    /// Removes all completion specs, including those for special scenarios.
+    pub fn clear(&mut self) {
+        self.commands.clear();
+        self.default = None;
+        self.empty_line = None;
+        self.initial_word = None;
+    }
+
     /// Returns an iterator over the completion specs.
     pub fn iter(&self) -> impl Iterator<Item = (&String, &Spec)> {
         self.commands.iter()
@@ -908,6 +944,8 @@ fn get_file_completions(shell: &Shell, context: &Context, must_be_dir: bool) ->
         .expand(
             shell.working_dir.as_path(),
             shell.options.extended_globbing,
+            shell.options.enable_star_star_glob,
+            shell.options.glob_matches_dotfiles,
             Some(&path_filter),
         )
         .unwrap_or_default()
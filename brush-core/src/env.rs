@@ -1,5 +1,5 @@
 use std::borrow::Cow;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use crate::error;
 use crate::variables::{self, ShellValue, ShellValueUnsetType, ShellVariable};
@@ -134,24 +134,20 @@ impl ShellEnvironment {
     }
 
     /// Tries to retrieve an immutable reference to the variable with the given name
-    /// in the environment.
+    /// in the environment. Name references are followed to the variable they refer to.
     ///
     /// # Arguments
     ///
     /// * `name` - The name of the variable to retrieve.
     pub fn get<S: AsRef<str>>(&self, name: S) -> Option<(EnvironmentScope, &ShellVariable)> {
-        // Look through scopes, from the top of the stack on down.
-        for (scope_type, map) in self.scopes.iter().rev() {
-            if let Some(var) = map.get(name.as_ref()) {
-                return Some((*scope_type, var));
-            }
-        }
-
-        None
+        let (name, lookup_policy) = self
+            .resolve(name.as_ref(), EnvironmentLookup::Anywhere)
+            .ok()?;
+        self.lookup(name.as_ref(), lookup_policy)
     }
 
     /// Tries to retrieve a mutable reference to the variable with the given name
-    /// in the environment.
+    /// in the environment. Name references are followed to the variable they refer to.
     ///
     /// # Arguments
     ///
@@ -160,18 +156,16 @@ impl ShellEnvironment {
         &mut self,
         name: S,
     ) -> Option<(EnvironmentScope, &mut ShellVariable)> {
-        // Look through scopes, from the top of the stack on down.
-        for (scope_type, map) in self.scopes.iter_mut().rev() {
-            if let Some(var) = map.get_mut(name.as_ref()) {
-                return Some((*scope_type, var));
-            }
-        }
-
-        None
+        let (name, lookup_policy) = self
+            .resolve(name.as_ref(), EnvironmentLookup::Anywhere)
+            .ok()?;
+        self.lookup_mut(name.as_ref(), lookup_policy)
     }
 
     /// Tries to retrieve the string value of the variable with the given name in the
-    /// environment.
+    /// environment. Dynamic variables can't be computed without a shell, so this must not
+    /// be used for variables that may be dynamic; use [`crate::Shell::get_env_str`]
+    /// instead.
     ///
     /// # Arguments
     ///
@@ -203,8 +197,21 @@ impl ShellEnvironment {
     ///
     /// # Arguments
     ///
-    /// * `name` - The name of the variable to unset.
+    /// * `name` - The name of the variable to unset. If it refers to a name reference,
+    ///   the referenced variable is unset instead.
     pub fn unset(&mut self, name: &str) -> Result<bool, error::Error> {
+        let name = self.resolve_nameref(name, EnvironmentLookup::Anywhere)?;
+        self.unset_unresolved(name.as_ref())
+    }
+
+    /// Tries to unset the variable with the given name in the environment, returning
+    /// whether or not such a variable existed. Unlike `unset`, name references are
+    /// not followed; a name reference is itself unset.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the variable to unset.
+    pub fn unset_unresolved(&mut self, name: &str) -> Result<bool, error::Error> {
         let mut local_count = 0;
         for (scope_type, map) in self.scopes.iter_mut().rev() {
             if matches!(scope_type, EnvironmentScope::Local) {
@@ -252,7 +259,8 @@ impl ShellEnvironment {
     }
 
     /// Tries to retrieve an immutable reference to a variable from the environment,
-    /// using the given name and lookup policy.
+    /// using the given name and lookup policy. Name references are followed to the
+    /// variable they refer to.
     ///
     /// # Arguments
     ///
@@ -263,6 +271,115 @@ impl ShellEnvironment {
         name: N,
         lookup_policy: EnvironmentLookup,
     ) -> Option<&ShellVariable> {
+        let (name, lookup_policy) = self.resolve(name.as_ref(), lookup_policy).ok()?;
+        self.lookup(name.as_ref(), lookup_policy)
+            .map(|(_, var)| var)
+    }
+
+    /// Tries to retrieve a mutable reference to a variable from the environment,
+    /// using the given name and lookup policy. Name references are followed to the
+    /// variable they refer to.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the variable to retrieve.
+    /// * `lookup_policy` - The policy to use when looking up the variable.
+    pub fn get_mut_using_policy<N: AsRef<str>>(
+        &mut self,
+        name: N,
+        lookup_policy: EnvironmentLookup,
+    ) -> Option<&mut ShellVariable> {
+        let (name, lookup_policy) = self.resolve(name.as_ref(), lookup_policy).ok()?;
+        self.lookup_mut(name.as_ref(), lookup_policy)
+            .map(|(_, var)| var)
+    }
+
+    /// Tries to retrieve an immutable reference to a variable from the environment,
+    /// using the given name and lookup policy. Name references are *not* followed.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the variable to retrieve.
+    /// * `lookup_policy` - The policy to use when looking up the variable.
+    pub fn get_unresolved_using_policy<N: AsRef<str>>(
+        &self,
+        name: N,
+        lookup_policy: EnvironmentLookup,
+    ) -> Option<&ShellVariable> {
+        self.lookup(name.as_ref(), lookup_policy)
+            .map(|(_, var)| var)
+    }
+
+    /// Tries to retrieve a mutable reference to a variable from the environment,
+    /// using the given name and lookup policy. Name references are *not* followed.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the variable to retrieve.
+    /// * `lookup_policy` - The policy to use when looking up the variable.
+    pub fn get_mut_unresolved_using_policy<N: AsRef<str>>(
+        &mut self,
+        name: N,
+        lookup_policy: EnvironmentLookup,
+    ) -> Option<&mut ShellVariable> {
+        self.lookup_mut(name.as_ref(), lookup_policy)
+            .map(|(_, var)| var)
+    }
+
+    /// Resolves the given name by following any chain of name references (i.e., variables
+    /// with the nameref attribute) to the name of the variable ultimately referred to.
+    /// Names that don't identify a name reference are returned as-is.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name to resolve.
+    /// * `lookup_policy` - The policy to use when looking up the name.
+    pub fn resolve_nameref<'a>(
+        &self,
+        name: &'a str,
+        lookup_policy: EnvironmentLookup,
+    ) -> Result<Cow<'a, str>, error::Error> {
+        self.resolve(name, lookup_policy).map(|(name, _)| name)
+    }
+
+    fn resolve<'a>(
+        &self,
+        name: &'a str,
+        lookup_policy: EnvironmentLookup,
+    ) -> Result<(Cow<'a, str>, EnvironmentLookup), error::Error> {
+        let mut resolved = Cow::Borrowed(name);
+        let mut lookup_policy = lookup_policy;
+        let mut visited = HashSet::new();
+
+        while let Some(target) = self.nameref_target(resolved.as_ref(), lookup_policy) {
+            visited.insert(resolved.to_string());
+            if visited.contains(target) {
+                return Err(error::Error::CircularNameReference(name.to_owned()));
+            }
+
+            // The referenced variable may live in any scope.
+            resolved = Cow::Owned(target.to_owned());
+            lookup_policy = EnvironmentLookup::Anywhere;
+        }
+
+        Ok((resolved, lookup_policy))
+    }
+
+    fn nameref_target(&self, name: &str, lookup_policy: EnvironmentLookup) -> Option<&str> {
+        match self.lookup(name, lookup_policy) {
+            Some((_, var)) if var.is_treated_as_nameref() => match var.value() {
+                ShellValue::String(target) if !target.is_empty() => Some(target.as_str()),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn lookup(
+        &self,
+        name: &str,
+        lookup_policy: EnvironmentLookup,
+    ) -> Option<(EnvironmentScope, &ShellVariable)> {
         let mut local_count = 0;
         for (scope_type, var_map) in self.scopes.iter().rev() {
             if matches!(scope_type, EnvironmentScope::Local) {
@@ -288,8 +405,8 @@ impl ShellEnvironment {
                 }
             }
 
-            if let Some(var) = var_map.get(name.as_ref()) {
-                return Some(var);
+            if let Some(var) = var_map.get(name) {
+                return Some((*scope_type, var));
             }
 
             if matches!(scope_type, EnvironmentScope::Local)
@@ -302,18 +419,11 @@ impl ShellEnvironment {
         None
     }
 
-    /// Tries to retrieve a mutable reference to a variable from the environment,
-    /// using the given name and lookup policy.
-    ///
-    /// # Arguments
-    ///
-    /// * `name` - The name of the variable to retrieve.
-    /// * `lookup_policy` - The policy to use when looking up the variable.
-    pub fn get_mut_using_policy<N: AsRef<str>>(
+    fn lookup_mut(
         &mut self,
-        name: N,
+        name: &str,
         lookup_policy: EnvironmentLookup,
-    ) -> Option<&mut ShellVariable> {
+    ) -> Option<(EnvironmentScope, &mut ShellVariable)> {
         let mut local_count = 0;
         for (scope_type, var_map) in self.scopes.iter_mut().rev() {
             if matches!(scope_type, EnvironmentScope::Local) {
@@ -339,8 +449,8 @@ impl ShellEnvironment {
                 }
             }
 
-            if let Some(var) = var_map.get_mut(name.as_ref()) {
-                return Some(var);
+            if let Some(var) = var_map.get_mut(name) {
+                return Some((*scope_type, var));
             }
 
             if matches!(scope_type, EnvironmentScope::Local)
@@ -371,8 +481,10 @@ impl ShellEnvironment {
         scope_if_creating: EnvironmentScope,
     ) -> Result<(), error::Error> {
         let name = name.into();
+        let (name, lookup_policy) = self.resolve(name.as_str(), lookup_policy)?;
+        let name = name.into_owned();
 
-        if let Some(var) = self.get_mut_using_policy(&name, lookup_policy) {
+        if let Some((_, var)) = self.lookup_mut(name.as_str(), lookup_policy) {
             var.assign(value, false)?;
             updater(var)
         } else {
@@ -404,8 +516,10 @@ impl ShellEnvironment {
         scope_if_creating: EnvironmentScope,
     ) -> Result<(), error::Error> {
         let name = name.into();
+        let (name, lookup_policy) = self.resolve(name.as_str(), lookup_policy)?;
+        let name = name.into_owned();
 
-        if let Some(var) = self.get_mut_using_policy(&name, lookup_policy) {
+        if let Some((_, var)) = self.lookup_mut(name.as_str(), lookup_policy) {
             var.assign_at_index(index, value, false)?;
             updater(var)
         } else {
@@ -35,10 +35,30 @@ pub enum Error {
     #[error("cannot assign in this way")]
     CannotAssignToSpecialParameter,
 
-    /// Checked expansion error.
-    #[error("expansion error: {0}")]
+    /// Checked expansion error (e.g., from `${var:?message}`).
+    #[error("{0}")]
     CheckedExpansionError(String),
 
+    /// History expansion referred to an event that isn't in the history list.
+    #[error("{0}: event not found")]
+    HistoryEventNotFound(String),
+
+    /// History expansion used an invalid word designator.
+    #[error("{0}: bad word specifier")]
+    BadHistoryWordSpecifier(String),
+
+    /// History expansion used an unknown modifier.
+    #[error("{0}: unrecognized history modifier")]
+    UnrecognizedHistoryModifier(String),
+
+    /// A history substitution modifier found no text to replace.
+    #[error("{0}: substitution failed")]
+    HistorySubstitutionFailed(String),
+
+    /// `TIMEFORMAT` contained an unknown format specifier.
+    #[error("TIMEFORMAT: `{0}': invalid format character")]
+    InvalidTimeFormatCharacter(String),
+
     /// A reference was made to an unknown shell function.
     #[error("function not found: {0}")]
     FunctionNotFound(String),
@@ -71,6 +91,10 @@ pub enum Error {
     #[error("invalid redirection")]
     InvalidRedirection,
 
+    /// The target of a redirection didn't expand to exactly one word.
+    #[error("{0}: ambiguous redirect")]
+    AmbiguousRedirect(String),
+
     /// An error occurred while redirecting input or output with the given file.
     #[error("failed to redirect to {0}: {1}")]
     RedirectionFailure(String, std::io::Error),
@@ -119,6 +143,26 @@ pub enum Error {
     #[error("bad substitution")]
     BadSubstitution,
 
+    /// An unset parameter was referenced while `set -u` was in effect.
+    #[error("{0}: unbound variable")]
+    UnboundVariable(String),
+
+    /// A substring expansion's length identified an end before its offset.
+    #[error("{0}: substring expression < 0")]
+    SubstringExpressionNegative(String),
+
+    /// A name reference refers to itself, directly or through other name references.
+    #[error("{0}: circular name reference")]
+    CircularNameReference(String),
+
+    /// A non-integer operand was provided where an integer was expected.
+    #[error("{0}: integer expression expected")]
+    IntegerExpressionExpected(String),
+
+    /// A pattern failed to match any paths while failglob was in effect.
+    #[error("no match: {0}")]
+    NoGlobMatches(String),
+
     /// Invalid arguments were provided to the command.
     #[error("invalid arguments")]
     InvalidArguments,
@@ -182,6 +226,18 @@ pub enum Error {
     Interrupted,
 }
 
+impl Error {
+    /// Returns whether the error should cause a non-interactive shell to exit (e.g., a
+    /// failed `${var:?message}` expansion, or a reference to an unset variable with
+    /// `set -u`).
+    pub(crate) fn is_fatal_to_non_interactive_shell(&self) -> bool {
+        matches!(
+            self,
+            Error::CheckedExpansionError(_) | Error::UnboundVariable(_)
+        )
+    }
+}
+
 /// Convenience function for returning an error for unimplemented functionality.
 ///
 /// # Arguments
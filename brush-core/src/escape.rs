@@ -1,11 +1,11 @@
 use itertools::Itertools;
 
-use crate::error;
-
 #[derive(Clone, Copy)]
 pub(crate) enum EscapeMode {
     EchoBuiltin,
     AnsiCQuotes,
+    PrintfFormat,
+    PrintfEscapedArg,
 }
 
 #[allow(clippy::too_many_lines)]
@@ -27,18 +27,31 @@ pub(crate) fn expand_backslash_escapes(
             Some('b') => result.push(b'\x08'),
             Some('c') => {
                 match mode {
-                    EscapeMode::EchoBuiltin => {
+                    EscapeMode::EchoBuiltin | EscapeMode::PrintfEscapedArg => {
                         // Stop all additional output!
                         return Ok((result, false));
                     }
-                    EscapeMode::AnsiCQuotes => {
-                        if let Some(_next_next) = it.next() {
-                            return error::unimp("control character in ANSI C quotes");
-                        } else {
+                    EscapeMode::PrintfFormat => {
+                        result.push(b'\\');
+                        result.push(b'c');
+                    }
+                    EscapeMode::AnsiCQuotes => match it.next() {
+                        // \cX yields the control character corresponding to X.
+                        Some('?') => result.push(b'\x7f'),
+                        Some(next_next) if next_next.is_ascii() => {
+                            let value = u32::from(next_next.to_ascii_uppercase()) & 0x1f;
+                            result.push(value.to_le_bytes()[0]);
+                        }
+                        Some(next_next) => {
                             result.push(b'\\');
                             result.push(b'c');
+                            result.append(next_next.to_string().into_bytes().as_mut());
                         }
-                    }
+                        None => {
+                            result.push(b'\\');
+                            result.push(b'c');
+                        }
+                    },
                 }
             }
             Some('e' | 'E') => result.push(b'\x1b'),
@@ -48,9 +61,35 @@ pub(crate) fn expand_backslash_escapes(
             Some('t') => result.push(b'\t'),
             Some('v') => result.push(b'\x0b'),
             Some('\\') => result.push(b'\\'),
-            Some('\'') if matches!(mode, EscapeMode::AnsiCQuotes) => result.push(b'\''),
-            Some('\"') if matches!(mode, EscapeMode::AnsiCQuotes) => result.push(b'\"'),
-            Some('?') if matches!(mode, EscapeMode::AnsiCQuotes) => result.push(b'?'),
+            Some('\'') if matches!(mode, EscapeMode::AnsiCQuotes | EscapeMode::PrintfFormat) => {
+                result.push(b'\'');
+            }
+            Some('\"') if matches!(mode, EscapeMode::AnsiCQuotes | EscapeMode::PrintfFormat) => {
+                result.push(b'\"');
+            }
+            Some('?') if matches!(mode, EscapeMode::AnsiCQuotes | EscapeMode::PrintfFormat) => {
+                result.push(b'?');
+            }
+            Some(first @ '0'..='7')
+                if matches!(mode, EscapeMode::AnsiCQuotes | EscapeMode::PrintfFormat)
+                    || (matches!(mode, EscapeMode::PrintfEscapedArg) && first != '0') =>
+            {
+                // Consume up to 2 more valid octal chars, for a total of 3.
+                let mut taken_so_far = 1;
+                let mut octal_chars = String::from(first);
+                octal_chars.extend(it.take_while_ref(|c| {
+                    if taken_so_far < 3 && matches!(*c, '0'..='7') {
+                        taken_so_far += 1;
+                        true
+                    } else {
+                        false
+                    }
+                }));
+
+                // Values too large for a byte wrap around.
+                let value = u16::from_str_radix(octal_chars.as_str(), 8)?;
+                result.push(value.to_le_bytes()[0]);
+            }
             Some('0') => {
                 // Consume 0-3 valid octal chars
                 let mut taken_so_far = 0;
@@ -72,7 +111,7 @@ pub(crate) fn expand_backslash_escapes(
                 let value = u8::from_str_radix(octal_chars.as_str(), 8)?;
                 result.push(value);
             }
-            Some('x') => {
+            Some(escape_char @ 'x') => {
                 // Consume 1-2 valid hex chars
                 let mut taken_so_far = 0;
                 let hex_chars: String = it
@@ -88,13 +127,13 @@ pub(crate) fn expand_backslash_escapes(
 
                 if hex_chars.is_empty() {
                     result.push(b'\\');
-                    result.append(c.to_string().into_bytes().as_mut());
+                    result.append(escape_char.to_string().into_bytes().as_mut());
                 } else {
                     let value = u8::from_str_radix(hex_chars.as_str(), 16)?;
                     result.push(value);
                 }
             }
-            Some('u') => {
+            Some(escape_char @ 'u') => {
                 // Consume 1-4 hex digits
                 let mut taken_so_far = 0;
                 let hex_chars: String = it
@@ -110,7 +149,7 @@ pub(crate) fn expand_backslash_escapes(
 
                 if hex_chars.is_empty() {
                     result.push(b'\\');
-                    result.append(c.to_string().into_bytes().as_mut());
+                    result.append(escape_char.to_string().into_bytes().as_mut());
                 } else {
                     let value = u16::from_str_radix(hex_chars.as_str(), 16)?;
 
@@ -118,11 +157,11 @@ pub(crate) fn expand_backslash_escapes(
                         result.append(decoded.to_string().into_bytes().as_mut());
                     } else {
                         result.push(b'\\');
-                        result.append(c.to_string().into_bytes().as_mut());
+                        result.append(escape_char.to_string().into_bytes().as_mut());
                     }
                 }
             }
-            Some('U') => {
+            Some(escape_char @ 'U') => {
                 // Consume 1-8 hex digits
                 let mut taken_so_far = 0;
                 let hex_chars: String = it
@@ -138,7 +177,7 @@ pub(crate) fn expand_backslash_escapes(
 
                 if hex_chars.is_empty() {
                     result.push(b'\\');
-                    result.append(c.to_string().into_bytes().as_mut());
+                    result.append(escape_char.to_string().into_bytes().as_mut());
                 } else {
                     let value = u32::from_str_radix(hex_chars.as_str(), 16)?;
 
@@ -146,7 +185,7 @@ pub(crate) fn expand_backslash_escapes(
                         result.append(decoded.to_string().into_bytes().as_mut());
                     } else {
                         result.push(b'\\');
-                        result.append(c.to_string().into_bytes().as_mut());
+                        result.append(escape_char.to_string().into_bytes().as_mut());
                     }
                 }
             }
@@ -165,6 +204,158 @@ pub(crate) fn expand_backslash_escapes(
     Ok((result, true))
 }
 
+/// Quotes the given string so that it may be reused as input to the shell, in
+/// the same way as bash's `printf %q`: strings with non-printable characters
+/// are rendered as ANSI-C quoted strings, while others have any characters
+/// special to the shell escaped with backslashes.
+pub(crate) fn quote_for_shell_input(s: &str) -> String {
+    if s.is_empty() {
+        return String::from("''");
+    }
+
+    if s.chars().any(char::is_control) {
+        return ansi_c_quote(s);
+    }
+
+    let mut result = String::new();
+    let mut prev = None;
+    for c in s.chars() {
+        if c == ',' || is_shell_meta(c, prev) {
+            result.push('\\');
+        }
+        result.push(c);
+
+        prev = Some(c);
+    }
+
+    result
+}
+
+/// Quotes the given string so that it may be reused as input to the shell, in the same
+/// way as bash's `${var@Q}`: strings with non-printable characters are rendered as ANSI-C
+/// quoted strings, while all others are single-quoted.
+pub(crate) fn quote_for_reuse(s: &str) -> String {
+    if s.chars().any(char::is_control) {
+        ansi_c_quote(s)
+    } else {
+        single_quote(s)
+    }
+}
+
+/// Quotes the given string for display in `set -x` style trace output, in the same
+/// way as bash: strings containing characters special to the shell are single-quoted,
+/// strings with other non-printable characters are ANSI-C quoted, and all others are
+/// left as-is.
+pub(crate) fn quote_for_trace(s: &str) -> String {
+    if s.is_empty() {
+        String::from("''")
+    } else if contains_shell_metas(s) {
+        single_quote(s)
+    } else if s.chars().any(char::is_control) {
+        ansi_c_quote(s)
+    } else {
+        s.to_owned()
+    }
+}
+
+/// Quotes the given string as a value in `declare -p` output, in the same way as bash:
+/// strings with non-printable characters are ANSI-C quoted, while all others are
+/// double-quoted.
+pub(crate) fn quote_for_declare(s: &str) -> String {
+    if s.chars().any(char::is_control) {
+        return ansi_c_quote(s);
+    }
+
+    let mut result = String::from("\"");
+
+    for c in s.chars() {
+        if matches!(c, '"' | '$' | '`' | '\\') {
+            result.push('\\');
+        }
+        result.push(c);
+    }
+
+    result.push('"');
+    result
+}
+
+/// Renders the given string as a single-quoted string, escaping any embedded single
+/// quotes.
+pub(crate) fn single_quote(s: &str) -> String {
+    let mut result = String::from("'");
+
+    for c in s.chars() {
+        if c == '\'' {
+            result.push_str("'\\''");
+        } else {
+            result.push(c);
+        }
+    }
+
+    result.push('\'');
+    result
+}
+
+/// Checks whether the given string contains any characters with special meaning to the
+/// shell.
+pub(crate) fn contains_shell_metas(s: &str) -> bool {
+    let mut prev = None;
+    for c in s.chars() {
+        if is_shell_meta(c, prev) {
+            return true;
+        }
+
+        prev = Some(c);
+    }
+
+    false
+}
+
+/// Checks whether the given character, preceded by the given character (if any), has
+/// special meaning to the shell.
+fn is_shell_meta(c: char, prev: Option<char>) -> bool {
+    match c {
+        ' ' | '\t' | '\n' | '\'' | '"' | '\\' | '|' | '&' | ';' | '(' | ')' | '<' | '>' | '!'
+        | '{' | '}' | '*' | '[' | '?' | ']' | '^' | '$' | '`' => true,
+        '~' => matches!(prev, None | Some('=' | ':')),
+        '#' => prev.is_none(),
+        _ => false,
+    }
+}
+
+/// Renders the given string as an ANSI-C quoted string (i.e., `$'...'`).
+pub(crate) fn ansi_c_quote(s: &str) -> String {
+    let mut result = String::from("$'");
+
+    for c in s.chars() {
+        match c {
+            '\x07' => result.push_str("\\a"),
+            '\x08' => result.push_str("\\b"),
+            '\x1b' => result.push_str("\\E"),
+            '\x0c' => result.push_str("\\f"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            '\x0b' => result.push_str("\\v"),
+            '\\' => result.push_str("\\\\"),
+            '\'' => result.push_str("\\'"),
+            c if c.is_control() => {
+                let mut buf = [0; 4];
+                for b in c.encode_utf8(&mut buf).as_bytes() {
+                    result.push('\\');
+                    for shift in [6, 3, 0] {
+                        result.push(char::from(b'0' + ((b >> shift) & 0o7)));
+                    }
+                }
+            }
+            c => result.push(c),
+        }
+    }
+
+    result.push('\'');
+    result
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -209,4 +400,44 @@ mod tests {
         assert_echo_expands_to(r"\u2620", "☠");
         assert_echo_expands_to(r"\U0001f602", "😂");
     }
+
+    #[test]
+    fn test_quote_for_shell_input() {
+        assert_eq!(quote_for_shell_input(""), "''");
+        assert_eq!(quote_for_shell_input("abc"), "abc");
+        assert_eq!(quote_for_shell_input("a b"), r"a\ b");
+        assert_eq!(quote_for_shell_input("it's"), r"it\'s");
+        assert_eq!(quote_for_shell_input("x*y"), r"x\*y");
+        assert_eq!(quote_for_shell_input("~a"), r"\~a");
+        assert_eq!(quote_for_shell_input("a~"), "a~");
+        assert_eq!(quote_for_shell_input("#a#"), r"\#a#");
+        assert_eq!(quote_for_shell_input("tab\there"), r"$'tab\there'");
+        assert_eq!(quote_for_shell_input("\x01'"), r"$'\001\''");
+    }
+
+    #[test]
+    fn test_quote_for_declare() {
+        assert_eq!(quote_for_declare(""), r#""""#);
+        assert_eq!(quote_for_declare("a b"), r#""a b""#);
+        assert_eq!(
+            quote_for_declare(r#"q"uote $d `b` \s"#),
+            r#""q\"uote \$d \`b\` \\s""#
+        );
+        assert_eq!(quote_for_declare("new\nline"), r"$'new\nline'");
+    }
+
+    #[test]
+    fn test_quote_for_trace() {
+        assert_eq!(quote_for_trace(""), "''");
+        assert_eq!(quote_for_trace("abc"), "abc");
+        assert_eq!(quote_for_trace("a b"), "'a b'");
+        assert_eq!(quote_for_trace("it's"), r"'it'\''s'");
+        assert_eq!(quote_for_trace("'a"), r"''\''a'");
+        assert_eq!(quote_for_trace("a=b"), "a=b");
+        assert_eq!(quote_for_trace("~x"), "'~x'");
+        assert_eq!(quote_for_trace("x~"), "x~");
+        assert_eq!(quote_for_trace("#a#"), "'#a#'");
+        assert_eq!(quote_for_trace("tab\there"), "'tab\there'");
+        assert_eq!(quote_for_trace("\x01"), r"$'\001'");
+    }
 }
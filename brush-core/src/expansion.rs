@@ -6,6 +6,7 @@ use brush_parser::word::SubstringMatchKind;
 use itertools::Itertools;
 
 use crate::arithmetic::ExpandAndEvaluate;
+use crate::builtins;
 use crate::env;
 use crate::error;
 use crate::escape;
@@ -85,71 +86,67 @@ impl Expansion {
         }
     }
 
-    pub(crate) fn polymorphic_len(&self) -> usize {
-        if self.fields.len() > 1 {
-            self.fields.len()
-        } else {
-            self.fields.iter().fold(0, |acc, field| acc + field.len())
-        }
+    /// Returns the number of characters in the expansion, across all fields.
+    pub(crate) fn char_len(&self) -> usize {
+        self.fields
+            .iter()
+            .flat_map(|field| field.0.iter())
+            .map(|piece| piece.as_str().chars().count())
+            .sum()
     }
 
-    pub(crate) fn polymorphic_subslice(&self, index: usize, end: usize) -> Self {
-        let len = end - index;
+    /// Extracts the fields in the given range.
+    pub(crate) fn subslice_fields(&self, index: usize, end: usize) -> Self {
+        let end = min(end, self.fields.len());
+        let fields = self.fields.get(index..end).unwrap_or_default().to_vec();
 
-        if self.fields.len() > 1 {
-            let actual_len = min(len, self.fields.len() - index);
-            let fields = self.fields[index..(index + actual_len)].to_vec();
-
-            Expansion {
-                fields,
-                concatenate: self.concatenate,
-                undefined: self.undefined,
-            }
-        } else {
-            let mut fields = vec![];
-
-            let mut offset = index;
-            let mut left = len;
-            for field in &self.fields {
-                let mut pieces = vec![];
-
-                for piece in &field.0 {
-                    if left == 0 {
-                        break;
-                    }
-
-                    let piece_str = piece.as_str();
-                    if offset < piece_str.len() {
-                        let len_from_this_piece = min(left, piece_str.len() - offset);
-
-                        let new_piece = match piece {
-                            ExpansionPiece::Unsplittable(s) => ExpansionPiece::Unsplittable(
-                                s[offset..(offset + len_from_this_piece)].to_owned(),
-                            ),
-                            ExpansionPiece::Splittable(s) => ExpansionPiece::Splittable(
-                                s[offset..(offset + len_from_this_piece)].to_owned(),
-                            ),
-                        };
+        Expansion {
+            fields,
+            concatenate: self.concatenate,
+            undefined: self.undefined,
+        }
+    }
 
-                        pieces.push(new_piece);
+    /// Extracts the characters in the given range, across all fields.
+    pub(crate) fn subslice_chars(&self, index: usize, end: usize) -> Self {
+        let mut fields = vec![];
 
-                        left -= len_from_this_piece;
-                    }
+        let mut to_skip = index;
+        let mut left = end.saturating_sub(index);
+        for field in &self.fields {
+            let mut pieces = vec![];
 
-                    offset += piece_str.len();
+            for piece in &field.0 {
+                if left == 0 {
+                    break;
                 }
 
-                if !pieces.is_empty() {
-                    fields.push(WordField(pieces));
+                let piece_len = piece.as_str().chars().count();
+                if to_skip >= piece_len {
+                    to_skip -= piece_len;
+                    continue;
                 }
+
+                let s: String = piece.as_str().chars().skip(to_skip).take(left).collect();
+                left -= s.chars().count();
+                to_skip = 0;
+
+                pieces.push(match piece {
+                    ExpansionPiece::Unsplittable(_) => ExpansionPiece::Unsplittable(s),
+                    ExpansionPiece::Splittable(_) => ExpansionPiece::Splittable(s),
+                });
             }
 
-            Expansion {
-                fields,
-                concatenate: self.concatenate,
-                undefined: self.undefined,
+            if !pieces.is_empty() {
+                fields.push(WordField(pieces));
             }
         }
+
+        Expansion {
+            fields,
+            concatenate: self.concatenate,
+            undefined: self.undefined,
+        }
     }
 }
 
@@ -160,10 +157,6 @@ impl WordField {
     pub fn new() -> Self {
         Self(vec![])
     }
-
-    pub fn len(&self) -> usize {
-        self.0.iter().fold(0, |acc, piece| acc + piece.len())
-    }
 }
 
 impl From<WordField> for String {
@@ -237,13 +230,6 @@ impl ExpansionPiece {
         }
     }
 
-    fn len(&self) -> usize {
-        match self {
-            ExpansionPiece::Unsplittable(s) => s.len(),
-            ExpansionPiece::Splittable(s) => s.len(),
-        }
-    }
-
     fn make_unsplittable(self) -> ExpansionPiece {
         match self {
             ExpansionPiece::Unsplittable(_) => self,
@@ -252,6 +238,89 @@ impl ExpansionPiece {
     }
 }
 
+/// Splits expansion pieces into fields, following the rules for IFS: runs of IFS
+/// whitespace separate fields, while each other IFS character delimits exactly one
+/// field (and so may produce empty fields).
+struct FieldSplitter<'a> {
+    ifs: &'a str,
+    fields: Vec<WordField>,
+    current_field: WordField,
+    /// Whether a field has been started, even if it's still empty.
+    in_field: bool,
+    /// Whether the most recent delimiter was IFS whitespace that terminated a field.
+    after_whitespace_delimiter: bool,
+}
+
+impl<'a> FieldSplitter<'a> {
+    fn new(ifs: &'a str) -> Self {
+        Self {
+            ifs,
+            fields: vec![],
+            current_field: WordField::new(),
+            in_field: false,
+            after_whitespace_delimiter: false,
+        }
+    }
+
+    fn push_unsplittable(&mut self, piece: ExpansionPiece) {
+        self.current_field.0.push(piece);
+        self.in_field = true;
+        self.after_whitespace_delimiter = false;
+    }
+
+    fn push_char(&mut self, c: char) {
+        if !self.ifs.contains(c) {
+            match self.current_field.0.last_mut() {
+                Some(ExpansionPiece::Splittable(last)) => last.push(c),
+                Some(ExpansionPiece::Unsplittable(_)) | None => {
+                    self.current_field
+                        .0
+                        .push(ExpansionPiece::Splittable(c.to_string()));
+                }
+            }
+
+            self.in_field = true;
+            self.after_whitespace_delimiter = false;
+        } else if matches!(c, ' ' | '\t' | '\n') {
+            if self.in_field {
+                self.end_field();
+                self.after_whitespace_delimiter = true;
+            }
+        } else {
+            if self.in_field {
+                self.end_field();
+            } else if !self.after_whitespace_delimiter {
+                self.fields.push(WordField::new());
+            }
+
+            self.after_whitespace_delimiter = false;
+        }
+    }
+
+    /// Delimits the current field as if the first character of IFS had been seen; if
+    /// IFS is empty, then only non-empty fields are retained.
+    fn delimit_field(&mut self) {
+        if let Some(c) = self.ifs.chars().next() {
+            self.push_char(c);
+        } else if self.in_field {
+            self.end_field();
+        }
+    }
+
+    fn end_field(&mut self) {
+        self.fields.push(std::mem::take(&mut self.current_field));
+        self.in_field = false;
+    }
+
+    fn finish(mut self) -> Vec<WordField> {
+        if self.in_field {
+            self.end_field();
+        }
+
+        self.fields
+    }
+}
+
 enum ParameterState {
     Undefined,
     DefinedEmptyString,
@@ -295,6 +364,25 @@ pub(crate) async fn basic_expand_str_without_tilde(
     expander.basic_expand_to_str(s).await
 }
 
+pub(crate) async fn basic_expand_assignment_value(
+    shell: &mut Shell,
+    word: &ast::Word,
+) -> Result<String, error::Error> {
+    let mut expander = WordExpander::new(shell);
+    let pieces =
+        brush_parser::word::parse_assignment_value(&word.flatten(), &expander.parser_options)?;
+    let expanded = String::from(expander.expand_pieces(pieces).await?);
+    Ok(expanded)
+}
+
+pub(crate) async fn basic_expand_here_document(
+    shell: &mut Shell,
+    body: &str,
+) -> Result<String, error::Error> {
+    let mut expander = WordExpander::new(shell);
+    expander.basic_expand_here_document_to_str(body).await
+}
+
 pub(crate) async fn full_expand_and_split_word(
     shell: &mut Shell,
     word: &ast::Word,
@@ -342,6 +430,17 @@ impl<'a> WordExpander<'a> {
         Ok(expanded)
     }
 
+    /// Apply parameter expansion, command substitution, and arithmetic expansion to the body
+    /// of a here-document.
+    pub async fn basic_expand_here_document_to_str(
+        &mut self,
+        body: &str,
+    ) -> Result<String, error::Error> {
+        let pieces = brush_parser::word::parse_here_document_body(body, &self.parser_options)?;
+        let expanded = String::from(self.expand_pieces(pieces).await?);
+        Ok(expanded)
+    }
+
     async fn basic_expand_opt_pattern(
         &mut self,
         word: &Option<String>,
@@ -408,16 +507,17 @@ impl<'a> WordExpander<'a> {
     async fn basic_expand(&mut self, word: &str) -> Result<Expansion, error::Error> {
         tracing::debug!("Basic expanding: '{word}'");
 
-        //
-        // TODO: Brace expansion in unquoted pieces
-        // Issue #42
-        //
-
         //
         // Expand: tildes, parameters, command substitutions, arithmetic.
         //
         let pieces = brush_parser::word::parse(word, &self.parser_options)?;
+        self.expand_pieces(pieces).await
+    }
 
+    async fn expand_pieces(
+        &mut self,
+        pieces: Vec<brush_parser::word::WordPieceWithSource>,
+    ) -> Result<Expansion, error::Error> {
         let mut expansions = vec![];
         for piece in pieces {
             let piece_expansion = self.expand_word_piece(piece.piece).await?;
@@ -427,88 +527,126 @@ impl<'a> WordExpander<'a> {
         Ok(coalesce_expansions(expansions))
     }
 
-    /// Apply tilde-expansion, parameter expansion, command substitution, and arithmetic expansion;
-    /// then perform field splitting and pathname expansion.
+    /// Apply brace expansion, tilde-expansion, parameter expansion, command substitution, and
+    /// arithmetic expansion; then perform field splitting and pathname expansion.
     pub async fn full_expand_with_splitting(
         &mut self,
         word: &str,
     ) -> Result<Vec<String>, error::Error> {
-        // Perform basic expansion first.
-        let basic_expansion = self.basic_expand(word).await?;
+        let mut result = vec![];
 
-        // Then split.
-        let fields: Vec<WordField> = self.split_fields(basic_expansion);
+        // Brace expansion comes first, and may yield multiple words to be expanded.
+        for brace_expanded_word in self.brace_expand_if_needed(word)? {
+            // Perform basic expansion.
+            let basic_expansion = self.basic_expand(brace_expanded_word.as_str()).await?;
 
-        // Now expand pathnames if necessary. This also unquotes as a side effect.
-        let result = fields
-            .into_iter()
-            .flat_map(|field| {
+            // Then split.
+            let fields: Vec<WordField> = self.split_fields(basic_expansion);
+
+            // Now expand pathnames if necessary. This also unquotes as a side effect.
+            for field in fields {
                 if self.shell.options.disable_filename_globbing {
-                    vec![String::from(field)]
+                    result.push(String::from(field));
                 } else {
-                    self.expand_pathnames_in_field(field)
+                    result.extend(self.expand_pathnames_in_field(field)?);
                 }
-            })
-            .collect();
+            }
+        }
 
         Ok(result)
     }
 
+    fn brace_expand_if_needed(&self, word: &str) -> Result<Vec<String>, error::Error> {
+        if !self.shell.options.perform_brace_expansion {
+            return Ok(vec![word.to_owned()]);
+        }
+
+        match brush_parser::word::parse_brace_expansions(word, &self.parser_options)? {
+            Some(pieces) => Ok(generate_and_combine_brace_expansions(pieces)),
+            None => Ok(vec![word.to_owned()]),
+        }
+    }
+
     fn split_fields(&self, expansion: Expansion) -> Vec<WordField> {
         let ifs = self.shell.get_ifs();
+        let mut splitter = FieldSplitter::new(ifs.as_ref());
 
-        let mut fields: Vec<WordField> = vec![];
-        let mut current_field = WordField::new();
+        // Go through the fields we have so far. Separate fields (e.g., from an unquoted
+        // `$@`) are delimited as if they'd been joined by the first character of IFS.
+        for (i, existing_field) in expansion.fields.into_iter().enumerate() {
+            if i > 0 {
+                splitter.delimit_field();
+            }
 
-        // Go through the fields we have so far.
-        for existing_field in expansion.fields {
             for piece in existing_field.0 {
                 match piece {
-                    ExpansionPiece::Unsplittable(_) => current_field.0.push(piece),
+                    ExpansionPiece::Unsplittable(_) => splitter.push_unsplittable(piece),
                     ExpansionPiece::Splittable(s) => {
                         for c in s.chars() {
-                            if ifs.contains(c) {
-                                if !current_field.0.is_empty() {
-                                    fields.push(std::mem::take(&mut current_field));
-                                }
-                            } else {
-                                match current_field.0.last_mut() {
-                                    Some(ExpansionPiece::Splittable(last)) => last.push(c),
-                                    Some(ExpansionPiece::Unsplittable(_)) | None => {
-                                        current_field
-                                            .0
-                                            .push(ExpansionPiece::Splittable(c.to_string()));
-                                    }
-                                }
-                            }
+                            splitter.push_char(c);
                         }
                     }
                 }
             }
-
-            if !current_field.0.is_empty() {
-                fields.push(std::mem::take(&mut current_field));
-            }
         }
 
-        fields
+        splitter.finish()
     }
 
-    fn expand_pathnames_in_field(&self, field: WordField) -> Vec<String> {
-        let pattern = patterns::Pattern::from(field.clone());
-        let expansions = pattern
+    fn expand_pathnames_in_field(&self, field: WordField) -> Result<Vec<String>, error::Error> {
+        let case_insensitive = self.shell.options.case_insensitive_pathname_expansion;
+        let pattern = patterns::Pattern::from(field.clone()).set_case_insensitive(case_insensitive);
+
+        // Matches of any of the patterns in GLOBIGNORE are removed from the results; when
+        // it's set, hidden files are matched as if dotglob were enabled.
+        let ignore_patterns: Vec<_> = self
+            .shell
+            .env
+            .get_str("GLOBIGNORE")
+            .unwrap_or_default()
+            .split(':')
+            .filter(|p| !p.is_empty())
+            .map(|p| patterns::Pattern::from(p).set_case_insensitive(case_insensitive))
+            .collect();
+
+        let mut expansions = pattern
             .expand(
                 self.shell.working_dir.as_path(),
                 self.parser_options.enable_extended_globbing,
+                self.shell.options.enable_star_star_glob,
+                self.shell.options.glob_matches_dotfiles || !ignore_patterns.is_empty(),
                 Some(&patterns::Pattern::accept_all_expand_filter),
             )
             .unwrap_or_default();
 
-        if expansions.is_empty() {
-            vec![String::from(field)]
-        } else {
-            expansions
+        if !ignore_patterns.is_empty() && pattern.requires_expansion() {
+            expansions.retain(|expansion| {
+                !ignore_patterns.iter().any(|ignore_pattern| {
+                    ignore_pattern
+                        .exactly_matches_path(
+                            expansion,
+                            self.parser_options.enable_extended_globbing,
+                        )
+                        .unwrap_or(false)
+                })
+            });
+        }
+
+        if !expansions.is_empty() {
+            return Ok(expansions);
         }
+
+        // Patterns that don't match anything are left as-is, unless nullglob or failglob
+        // say otherwise.
+        if pattern.requires_expansion() {
+            if self.shell.options.fail_expansion_on_globs_without_match {
+                return Err(error::Error::NoGlobMatches(String::from(field)));
+            } else if self.shell.options.expand_non_matching_patterns_to_null {
+                return Ok(vec![]);
+            }
+        }
+
+        Ok(vec![String::from(field)])
     }
 
     #[async_recursion::async_recursion]
@@ -526,8 +664,12 @@ impl<'a> WordExpander<'a> {
             brush_parser::word::WordPiece::AnsiCQuotedText(s) => {
                 let (expanded, _) =
                     escape::expand_backslash_escapes(s.as_str(), escape::EscapeMode::AnsiCQuotes)?;
+
+                // An embedded NUL character terminates the string.
+                let expanded = expanded.split(|b| *b == 0).next().unwrap_or_default();
+
                 Expansion::from(ExpansionPiece::Unsplittable(
-                    String::from_utf8_lossy(expanded.as_slice()).into_owned(),
+                    String::from_utf8_lossy(expanded).into_owned(),
                 ))
             }
             brush_parser::word::WordPiece::DoubleQuotedSequence(pieces) => {
@@ -535,6 +677,10 @@ impl<'a> WordExpander<'a> {
 
                 let pieces_is_empty = pieces.is_empty();
 
+                // Concatenated fields (e.g., from "$*") are joined by the first character of
+                // IFS; if IFS is unset, a space is used.
+                let separator: String = self.shell.get_ifs().chars().take(1).collect();
+
                 for piece in pieces {
                     let Expansion {
                         fields: this_fields,
@@ -552,7 +698,7 @@ impl<'a> WordExpander<'a> {
                                     .map(|piece| piece.make_unsplittable())
                                     .collect()
                             })
-                            .intersperse(vec![ExpansionPiece::Unsplittable(String::from(" "))])
+                            .intersperse(vec![ExpansionPiece::Unsplittable(separator.clone())])
                             .flatten()
                             .collect();
 
@@ -608,6 +754,13 @@ impl<'a> WordExpander<'a> {
                 // Insantiate a subshell to run the command in.
                 let mut subshell = self.shell.clone();
 
+                // Unless `inherit_errexit` is enabled (or implied by POSIX mode), the
+                // `errexit` option is cleared in the subshell.
+                if !subshell.options.command_subst_inherits_errexit && !subshell.options.posix_mode
+                {
+                    subshell.options.exit_on_nonzero_command_exit = false;
+                }
+
                 // Set up pipe so we can read the output.
                 let (reader, writer) = sys::pipes::pipe()?;
                 subshell
@@ -619,8 +772,8 @@ impl<'a> WordExpander<'a> {
                 params.process_group_policy = ProcessGroupPolicy::SameProcessGroup;
 
                 // Run the command.
-                // TODO: inspect result?
-                let _cmd_result = subshell.run_string(s, &params).await?;
+                let cmd_result = subshell.run_string(s, &params).await?;
+                self.shell.last_command_substitution_status = Some(cmd_result.exit_code);
 
                 // Make sure the subshell and params are closed; among other things, this
                 // ensures they're not holding onto the write end of the pipe.
@@ -648,16 +801,36 @@ impl<'a> WordExpander<'a> {
     }
 
     fn expand_tilde_expression(&self, prefix: &str) -> Result<String, error::Error> {
-        if !prefix.is_empty() {
-            Ok(sys::users::get_user_home_dir(prefix).map_or_else(
-                || std::format!("~{prefix}"),
-                |p| p.to_string_lossy().to_string(),
-            ))
-        } else if let Some(home_dir) = self.shell.get_home_dir() {
-            Ok(home_dir.to_string_lossy().to_string())
-        } else {
-            Err(error::Error::TildeWithoutValidHome)
-        }
+        let expanded = match prefix {
+            "" => {
+                let Some(home_dir) = self.shell.get_home_dir() else {
+                    return Err(error::Error::TildeWithoutValidHome);
+                };
+                Some(home_dir.to_string_lossy().to_string())
+            }
+            "+" => self.shell.env.get_str("PWD").map(|s| s.into_owned()),
+            "-" => self.shell.env.get_str("OLDPWD").map(|s| s.into_owned()),
+            _ => {
+                // ~N, ~+N, and ~-N refer to entries in the directory stack (as shown by `dirs`).
+                let offset = if prefix.starts_with(|c: char| c.is_ascii_digit()) {
+                    builtins::StackOffset::parse(std::format!("+{prefix}").as_str())
+                } else {
+                    builtins::StackOffset::parse(prefix)
+                };
+
+                if let Some(offset) = offset {
+                    let stack = builtins::get_directory_stack(self.shell);
+                    offset
+                        .to_index(stack.len())
+                        .map(|index| stack[index].to_string_lossy().to_string())
+                } else {
+                    sys::users::get_user_home_dir(prefix).map(|p| p.to_string_lossy().to_string())
+                }
+            }
+        };
+
+        // Anything we can't resolve is left as-is.
+        Ok(expanded.unwrap_or_else(|| std::format!("~{prefix}")))
     }
 
     #[allow(clippy::too_many_lines)]
@@ -670,7 +843,19 @@ impl<'a> WordExpander<'a> {
             brush_parser::word::ParameterExpr::Parameter {
                 parameter,
                 indirect,
-            } => self.expand_parameter(&parameter, indirect).await,
+            } => {
+                let expanded_parameter = self.expand_parameter(&parameter, indirect).await?;
+
+                // With `set -u`, it's an error to expand an unset parameter.
+                if expanded_parameter.undefined && self.shell.options.treat_unset_variables_as_error
+                {
+                    return Err(error::Error::UnboundVariable(describe_parameter(
+                        &parameter, indirect,
+                    )));
+                }
+
+                Ok(expanded_parameter)
+            }
             brush_parser::word::ParameterExpr::UseDefaultValues {
                 parameter,
                 indirect,
@@ -686,9 +871,7 @@ impl<'a> WordExpander<'a> {
                         brush_parser::word::ParameterTestType::Unset,
                         ParameterState::DefinedEmptyString,
                     ) => Ok(expanded_parameter),
-                    _ => Ok(Expansion::from(
-                        self.basic_expand_to_str(default_value).await?,
-                    )),
+                    _ => Ok(self.basic_expand(default_value).await?),
                 }
             }
             brush_parser::word::ParameterExpr::AssignDefaultValues {
@@ -722,17 +905,31 @@ impl<'a> WordExpander<'a> {
                 error_message,
             } => {
                 let expanded_parameter = self.expand_parameter(&parameter, indirect).await?;
-                let error_message = error_message.as_ref().map_or_else(|| "", |v| v.as_str());
-
-                match (test_type, expanded_parameter.classify()) {
+                match (&test_type, expanded_parameter.classify()) {
                     (_, ParameterState::NonZeroLength)
                     | (
                         brush_parser::word::ParameterTestType::Unset,
                         ParameterState::DefinedEmptyString,
                     ) => Ok(expanded_parameter),
-                    _ => Err(error::Error::CheckedExpansionError(
-                        self.basic_expand_to_str(error_message).await?,
-                    )),
+                    _ => {
+                        let error_message = match error_message {
+                            Some(error_message) => self.basic_expand_to_str(&error_message).await?,
+                            None if matches!(
+                                test_type,
+                                brush_parser::word::ParameterTestType::UnsetOrNull
+                            ) =>
+                            {
+                                String::from("parameter null or not set")
+                            }
+                            None => String::from("parameter not set"),
+                        };
+
+                        let description = describe_parameter(&parameter, indirect);
+                        Err(error::Error::CheckedExpansionError(std::format!(
+                            "{}: {error_message}",
+                            description.trim_start_matches('$')
+                        )))
+                    }
                 }
             }
             brush_parser::word::ParameterExpr::UseAlternativeValue {
@@ -760,70 +957,92 @@ impl<'a> WordExpander<'a> {
                 indirect,
             } => {
                 let expansion = self.expand_parameter(&parameter, indirect).await?;
-                Ok(Expansion::from(expansion.polymorphic_len().to_string()))
+
+                // Lengths of arrays and of the positional parameters are element counts;
+                // otherwise, it's the length of the (scalar) value.
+                let len = if matches!(
+                    parameter,
+                    brush_parser::word::Parameter::NamedWithAllIndices { .. }
+                        | brush_parser::word::Parameter::Special(
+                            brush_parser::word::SpecialParameter::AllPositionalParameters { .. }
+                        )
+                ) {
+                    expansion.fields.len()
+                } else if self.shell.uses_utf8_locale() {
+                    expansion.char_len()
+                } else {
+                    String::from(expansion).len()
+                };
+
+                Ok(Expansion::from(len.to_string()))
             }
             brush_parser::word::ParameterExpr::RemoveSmallestSuffixPattern {
                 parameter,
                 indirect,
                 pattern,
             } => {
-                let expanded_parameter: String =
-                    self.expand_parameter(&parameter, indirect).await?.into();
+                let expanded_parameter = self.expand_parameter(&parameter, indirect).await?;
                 let expanded_pattern = self.basic_expand_opt_pattern(&pattern).await?;
-                let result = patterns::remove_smallest_matching_suffix(
-                    expanded_parameter.as_str(),
-                    &expanded_pattern,
-                    self.parser_options.enable_extended_globbing,
-                )?;
-                Ok(Expansion::from(result.to_owned()))
+
+                transform_expansion(expanded_parameter, |s| {
+                    let result = patterns::remove_smallest_matching_suffix(
+                        s.as_str(),
+                        &expanded_pattern,
+                        self.parser_options.enable_extended_globbing,
+                    )?;
+                    Ok(result.to_owned())
+                })
             }
             brush_parser::word::ParameterExpr::RemoveLargestSuffixPattern {
                 parameter,
                 indirect,
                 pattern,
             } => {
-                let expanded_parameter: String =
-                    self.expand_parameter(&parameter, indirect).await?.into();
+                let expanded_parameter = self.expand_parameter(&parameter, indirect).await?;
                 let expanded_pattern = self.basic_expand_opt_pattern(&pattern).await?;
-                let result = patterns::remove_largest_matching_suffix(
-                    expanded_parameter.as_str(),
-                    &expanded_pattern,
-                    self.parser_options.enable_extended_globbing,
-                )?;
 
-                Ok(Expansion::from(result.to_owned()))
+                transform_expansion(expanded_parameter, |s| {
+                    let result = patterns::remove_largest_matching_suffix(
+                        s.as_str(),
+                        &expanded_pattern,
+                        self.parser_options.enable_extended_globbing,
+                    )?;
+                    Ok(result.to_owned())
+                })
             }
             brush_parser::word::ParameterExpr::RemoveSmallestPrefixPattern {
                 parameter,
                 indirect,
                 pattern,
             } => {
-                let expanded_parameter: String =
-                    self.expand_parameter(&parameter, indirect).await?.into();
+                let expanded_parameter = self.expand_parameter(&parameter, indirect).await?;
                 let expanded_pattern = self.basic_expand_opt_pattern(&pattern).await?;
-                let result = patterns::remove_smallest_matching_prefix(
-                    expanded_parameter.as_str(),
-                    &expanded_pattern,
-                    self.parser_options.enable_extended_globbing,
-                )?;
 
-                Ok(Expansion::from(result.to_owned()))
+                transform_expansion(expanded_parameter, |s| {
+                    let result = patterns::remove_smallest_matching_prefix(
+                        s.as_str(),
+                        &expanded_pattern,
+                        self.parser_options.enable_extended_globbing,
+                    )?;
+                    Ok(result.to_owned())
+                })
             }
             brush_parser::word::ParameterExpr::RemoveLargestPrefixPattern {
                 parameter,
                 indirect,
                 pattern,
             } => {
-                let expanded_parameter: String =
-                    self.expand_parameter(&parameter, indirect).await?.into();
+                let expanded_parameter = self.expand_parameter(&parameter, indirect).await?;
                 let expanded_pattern = self.basic_expand_opt_pattern(&pattern).await?;
-                let result = patterns::remove_largest_matching_prefix(
-                    expanded_parameter.as_str(),
-                    &expanded_pattern,
-                    self.parser_options.enable_extended_globbing,
-                )?;
 
-                Ok(Expansion::from(result.to_owned()))
+                transform_expansion(expanded_parameter, |s| {
+                    let result = patterns::remove_largest_matching_prefix(
+                        s.as_str(),
+                        &expanded_pattern,
+                        self.parser_options.enable_extended_globbing,
+                    )?;
+                    Ok(result.to_owned())
+                })
             }
             brush_parser::word::ParameterExpr::Substring {
                 parameter,
@@ -854,32 +1073,61 @@ impl<'a> WordExpander<'a> {
                     );
                 }
 
-                let expanded_offset = offset.eval(self.shell, false).await?;
-                let expanded_offset = usize::try_from(expanded_offset)?;
+                // Array-style parameters are sliced by element; everything else by character.
+                let selects_elements = matches!(
+                    parameter,
+                    brush_parser::word::Parameter::NamedWithAllIndices { .. }
+                        | brush_parser::word::Parameter::Special(
+                            brush_parser::word::SpecialParameter::AllPositionalParameters { .. }
+                        )
+                );
 
-                let expanded_parameter_len = expanded_parameter.polymorphic_len();
-                if expanded_offset >= expanded_parameter_len {
+                let expanded_parameter_len = if selects_elements {
+                    expanded_parameter.fields.len()
+                } else {
+                    expanded_parameter.char_len()
+                };
+                let expanded_parameter_len = i64::try_from(expanded_parameter_len)?;
+
+                // A negative offset counts back from the end; offsets out of range yield
+                // nothing.
+                let mut expanded_offset = offset.eval(self.shell, false).await?;
+                if expanded_offset < 0 {
+                    expanded_offset = expanded_offset.saturating_add(expanded_parameter_len);
+                }
+                if expanded_offset < 0 || expanded_offset >= expanded_parameter_len {
                     return Ok(Expansion::from(String::new()));
                 }
 
                 let end_offset = if let Some(length) = length {
-                    let mut expanded_length = length.eval(self.shell, false).await?;
-                    if expanded_length < 0 {
-                        let param_length: i64 = i64::try_from(expanded_parameter_len)?;
-                        expanded_length += param_length;
-                    }
+                    let expanded_length = length.eval(self.shell, false).await?;
 
-                    let expanded_length = std::cmp::min(
-                        usize::try_from(expanded_length)?,
-                        expanded_parameter_len - expanded_offset,
-                    );
+                    // A negative length instead identifies an end offset counting back from
+                    // the end; it's an error for that to precede the start offset, and
+                    // elements can't be selected that way at all.
+                    let end_offset = if expanded_length < 0 && !selects_elements {
+                        expanded_parameter_len.saturating_add(expanded_length)
+                    } else {
+                        expanded_offset.saturating_add(expanded_length)
+                    };
+
+                    if end_offset < expanded_offset {
+                        return Err(error::Error::SubstringExpressionNegative(length.value));
+                    }
 
-                    expanded_offset + expanded_length
+                    end_offset.min(expanded_parameter_len)
                 } else {
                     expanded_parameter_len
                 };
 
-                Ok(expanded_parameter.polymorphic_subslice(expanded_offset, end_offset))
+                let expanded_offset = usize::try_from(expanded_offset)?;
+                let end_offset = usize::try_from(end_offset)?;
+
+                if selects_elements {
+                    Ok(expanded_parameter.subslice_fields(expanded_offset, end_offset))
+                } else {
+                    Ok(expanded_parameter.subslice_chars(expanded_offset, end_offset))
+                }
             }
             brush_parser::word::ParameterExpr::Transform {
                 parameter,
@@ -904,17 +1152,24 @@ impl<'a> WordExpander<'a> {
                     .try_resolve_parameter_to_variable(&parameter, indirect)
                     .await?
                 {
-                    let assignable_value_str = var.value().to_assignable_str(index.as_deref());
+                    let assignable_value_str = var
+                        .resolve_value(self.shell)
+                        .to_assignable_str(index.as_deref());
 
                     let mut attr_str = var.get_attribute_flags();
-                    if attr_str.is_empty() {
+                    let has_attributes = !attr_str.is_empty();
+                    if !has_attributes {
                         attr_str.push('-');
                     }
 
                     match var.value() {
+                        ShellValue::String(_) if !has_attributes => {
+                            Ok(std::format!("{name}={assignable_value_str}",).into())
+                        }
                         ShellValue::IndexedArray(_)
                         | ShellValue::AssociativeArray(_)
-                        | ShellValue::Random => {
+                        | ShellValue::String(_)
+                        | ShellValue::Dynamic { .. } => {
                             let equals_or_nothing = if assignable_value_str.is_empty() {
                                 ""
                             } else {
@@ -926,9 +1181,6 @@ impl<'a> WordExpander<'a> {
                         )
                             .into())
                         }
-                        ShellValue::String(_) => {
-                            Ok(std::format!("{name}={assignable_value_str}",).into())
-                        }
                         ShellValue::Unset(_) => {
                             Ok(std::format!("declare -{attr_str} {name}").into())
                         }
@@ -957,7 +1209,7 @@ impl<'a> WordExpander<'a> {
                 let expanded_pattern = self.basic_expand_opt_pattern(&pattern).await?;
 
                 transform_expansion(expanded_parameter, |s| {
-                    self.uppercase_first_char(s, &expanded_pattern)
+                    self.convert_case(s.as_str(), &expanded_pattern, true, uppercase_char)
                 })
             }
             brush_parser::word::ParameterExpr::UppercasePattern {
@@ -969,7 +1221,7 @@ impl<'a> WordExpander<'a> {
                 let expanded_pattern = self.basic_expand_opt_pattern(&pattern).await?;
 
                 transform_expansion(expanded_parameter, |s| {
-                    self.uppercase_pattern(s.as_str(), &expanded_pattern)
+                    self.convert_case(s.as_str(), &expanded_pattern, false, uppercase_char)
                 })
             }
             brush_parser::word::ParameterExpr::LowercaseFirstChar {
@@ -980,9 +1232,9 @@ impl<'a> WordExpander<'a> {
                 let expanded_parameter = self.expand_parameter(&parameter, indirect).await?;
                 let expanded_pattern = self.basic_expand_opt_pattern(&pattern).await?;
 
-                Ok(transform_expansion(expanded_parameter, |s| {
-                    self.lowercase_first_char(s, &expanded_pattern)
-                })?)
+                transform_expansion(expanded_parameter, |s| {
+                    self.convert_case(s.as_str(), &expanded_pattern, true, lowercase_char)
+                })
             }
             brush_parser::word::ParameterExpr::LowercasePattern {
                 parameter,
@@ -992,9 +1244,33 @@ impl<'a> WordExpander<'a> {
                 let expanded_parameter = self.expand_parameter(&parameter, indirect).await?;
                 let expanded_pattern = self.basic_expand_opt_pattern(&pattern).await?;
 
-                Ok(transform_expansion(expanded_parameter, |s| {
-                    self.lowercase_pattern(s.as_str(), &expanded_pattern)
-                })?)
+                transform_expansion(expanded_parameter, |s| {
+                    self.convert_case(s.as_str(), &expanded_pattern, false, lowercase_char)
+                })
+            }
+            brush_parser::word::ParameterExpr::ToggleCaseFirstChar {
+                parameter,
+                indirect,
+                pattern,
+            } => {
+                let expanded_parameter = self.expand_parameter(&parameter, indirect).await?;
+                let expanded_pattern = self.basic_expand_opt_pattern(&pattern).await?;
+
+                transform_expansion(expanded_parameter, |s| {
+                    self.convert_case(s.as_str(), &expanded_pattern, true, toggle_char_case)
+                })
+            }
+            brush_parser::word::ParameterExpr::ToggleCasePattern {
+                parameter,
+                indirect,
+                pattern,
+            } => {
+                let expanded_parameter = self.expand_parameter(&parameter, indirect).await?;
+                let expanded_pattern = self.basic_expand_opt_pattern(&pattern).await?;
+
+                transform_expansion(expanded_parameter, |s| {
+                    self.convert_case(s.as_str(), &expanded_pattern, false, toggle_char_case)
+                })
             }
             brush_parser::word::ParameterExpr::ReplaceSubstring {
                 parameter,
@@ -1004,17 +1280,40 @@ impl<'a> WordExpander<'a> {
                 match_kind,
             } => {
                 let expanded_parameter = self.expand_parameter(&parameter, indirect).await?;
-                let expanded_pattern = self.basic_expand_to_str(&pattern).await?;
+                let expanded_pattern = self.basic_expand_pattern(&pattern).await?;
 
                 // If no replacement was provided, then we replace with an empty string.
                 let replacement = replacement.unwrap_or(String::new());
                 let expanded_replacement = self.basic_expand_to_str(&replacement).await?;
 
-                let regex = patterns::pattern_to_regex(
-                    expanded_pattern.as_str(),
+                // An empty pattern only matches when anchored to the start or end.
+                if expanded_pattern.is_empty()
+                    && matches!(
+                        match_kind,
+                        brush_parser::word::SubstringMatchKind::FirstOccurrence
+                            | brush_parser::word::SubstringMatchKind::Anywhere
+                    )
+                {
+                    return Ok(expanded_parameter);
+                }
+
+                let enable_extended_globbing = self.parser_options.enable_extended_globbing;
+                if !expanded_pattern.has_regex_equivalent(enable_extended_globbing)? {
+                    let matcher = expanded_pattern.to_matcher(enable_extended_globbing)?;
+                    return transform_expansion(expanded_parameter, |s| {
+                        patterns::replace_matching_substrings(
+                            s.as_str(),
+                            &matcher,
+                            expanded_replacement.as_str(),
+                            &match_kind,
+                        )
+                    });
+                }
+
+                let regex = expanded_pattern.to_regex(
                     matches!(match_kind, brush_parser::word::SubstringMatchKind::Prefix),
                     matches!(match_kind, brush_parser::word::SubstringMatchKind::Suffix),
-                    self.parser_options.enable_extended_globbing,
+                    enable_extended_globbing,
                 )?;
 
                 transform_expansion(expanded_parameter, |s| {
@@ -1037,8 +1336,11 @@ impl<'a> WordExpander<'a> {
                         .shell
                         .env
                         .iter()
-                        .filter_map(|(name, _)| {
-                            if name.starts_with(prefix.as_str()) {
+                        .filter_map(|(name, var)| {
+                            // N.B. Variables that have been declared but not set are skipped.
+                            if name.starts_with(prefix.as_str())
+                                && !matches!(var.value(), ShellValue::Unset(_))
+                            {
                                 Some(name.to_owned())
                             } else {
                                 None
@@ -1061,7 +1363,7 @@ impl<'a> WordExpander<'a> {
                 concatenate,
             } => {
                 let keys = if let Some((_, var)) = self.shell.env.get(variable_name) {
-                    var.value().get_element_keys()
+                    var.resolve_value(self.shell).get_element_keys()
                 } else {
                     vec![]
                 };
@@ -1213,7 +1515,9 @@ impl<'a> WordExpander<'a> {
                     if matches!(var.value(), ShellValue::Unset(_)) {
                         Ok(Expansion::undefined())
                     } else {
-                        Ok(Expansion::from(var.value().to_cow_string().to_string()))
+                        Ok(Expansion::from(
+                            var.resolve_value(self.shell).to_cow_string().to_string(),
+                        ))
                     }
                 } else {
                     Ok(Expansion::undefined())
@@ -1238,7 +1542,10 @@ impl<'a> WordExpander<'a> {
 
                 // Index into the array.
                 if let Some((_, var)) = self.shell.env.get(name.as_str()) {
-                    if let Some(value) = var.value().get_at(index_to_use.as_str())? {
+                    if let Some(value) = var
+                        .resolve_value(self.shell)
+                        .get_at(index_to_use.as_str())?
+                    {
                         Ok(Expansion::from(value.to_string()))
                     } else {
                         Ok(Expansion::undefined())
@@ -1249,7 +1556,7 @@ impl<'a> WordExpander<'a> {
             }
             brush_parser::word::Parameter::NamedWithAllIndices { name, concatenate } => {
                 if let Some((_, var)) = self.shell.env.get(name) {
-                    let values = var.value().get_element_values();
+                    let values = var.resolve_value(self.shell).get_element_values();
 
                     Ok(Expansion {
                         fields: values
@@ -1317,14 +1624,9 @@ impl<'a> WordExpander<'a> {
             brush_parser::word::SpecialParameter::ProcessId => {
                 Ok(Expansion::from(std::process::id().to_string()))
             }
-            brush_parser::word::SpecialParameter::LastBackgroundProcessId => {
-                if let Some(job) = self.shell.jobs.current_job() {
-                    if let Some(pid) = job.get_representative_pid() {
-                        return Ok(Expansion::from(pid.to_string()));
-                    }
-                }
-                Ok(Expansion::from(String::new()))
-            }
+            brush_parser::word::SpecialParameter::LastBackgroundProcessId => Ok(Expansion::from(
+                self.shell.last_background_job.clone().unwrap_or_default(),
+            )),
             brush_parser::word::SpecialParameter::ShellName => Ok(Expansion::from(
                 self.shell
                     .shell_name
@@ -1342,106 +1644,42 @@ impl<'a> WordExpander<'a> {
         Ok(value.to_string())
     }
 
-    #[allow(clippy::unwrap_in_result)]
-    fn uppercase_first_char(
-        &mut self,
-        s: String,
+    /// Applies the given case conversion to each character of the string that matches the
+    /// given pattern, or to every character if there's no pattern. Only the first character
+    /// is considered if `first_char_only` is set.
+    fn convert_case(
+        &self,
+        s: &str,
         pattern: &Option<patterns::Pattern>,
+        first_char_only: bool,
+        convert: fn(char) -> String,
     ) -> Result<String, error::Error> {
-        if let Some(first_char) = s.chars().next() {
-            let applicable = if let Some(pattern) = pattern {
-                pattern.is_empty()
-                    || pattern.exactly_matches(
-                        first_char.to_string().as_str(),
-                        self.shell.options.extended_globbing,
-                    )?
-            } else {
-                true
-            };
-
-            if applicable {
-                let mut result = String::new();
-                result.push(first_char.to_uppercase().next().unwrap());
-                result.push_str(s.get(1..).unwrap());
-                Ok(result)
-            } else {
-                Ok(s)
+        // N.B. The pattern is matched against each character individually.
+        let matcher = match pattern {
+            Some(pattern) if !pattern.is_empty() => {
+                Some(pattern.to_matcher(self.parser_options.enable_extended_globbing)?)
             }
-        } else {
-            Ok(s)
-        }
-    }
+            _ => None,
+        };
 
-    #[allow(clippy::unwrap_in_result)]
-    fn lowercase_first_char(
-        &mut self,
-        s: String,
-        pattern: &Option<patterns::Pattern>,
-    ) -> Result<String, error::Error> {
-        if let Some(first_char) = s.chars().next() {
-            let applicable = if let Some(pattern) = pattern {
-                pattern.is_empty()
-                    || pattern.exactly_matches(
-                        first_char.to_string().as_str(),
-                        self.shell.options.extended_globbing,
-                    )?
+        let mut result = String::new();
+        for (i, c) in s.chars().enumerate() {
+            let applicable = if first_char_only && i > 0 {
+                false
+            } else if let Some(matcher) = &matcher {
+                matcher.is_match(c.to_string().as_str())?
             } else {
                 true
             };
 
             if applicable {
-                let mut result = String::new();
-                result.push(first_char.to_lowercase().next().unwrap());
-                result.push_str(s.get(1..).unwrap());
-                Ok(result)
-            } else {
-                Ok(s)
-            }
-        } else {
-            Ok(s)
-        }
-    }
-
-    fn uppercase_pattern(
-        &mut self,
-        s: &str,
-        pattern: &Option<patterns::Pattern>,
-    ) -> Result<String, error::Error> {
-        if let Some(pattern) = pattern {
-            if !pattern.is_empty() {
-                let regex =
-                    pattern.to_regex(false, false, self.parser_options.enable_extended_globbing)?;
-                let result = regex.replace_all(s.as_ref(), |caps: &fancy_regex::Captures| {
-                    caps[0].to_uppercase()
-                });
-                Ok(result.into_owned())
+                result.push_str(convert(c).as_str());
             } else {
-                Ok(s.to_uppercase())
+                result.push(c);
             }
-        } else {
-            Ok(s.to_uppercase())
         }
-    }
 
-    fn lowercase_pattern(
-        &mut self,
-        s: &str,
-        pattern: &Option<patterns::Pattern>,
-    ) -> Result<String, error::Error> {
-        if let Some(pattern) = pattern {
-            if !pattern.is_empty() {
-                let regex =
-                    pattern.to_regex(false, false, self.parser_options.enable_extended_globbing)?;
-                let result = regex.replace_all(s.as_ref(), |caps: &fancy_regex::Captures| {
-                    caps[0].to_lowercase()
-                });
-                Ok(result.into_owned())
-            } else {
-                Ok(s.to_lowercase())
-            }
-        } else {
-            Ok(s.to_lowercase())
-        }
+        Ok(result)
     }
 
     #[allow(clippy::unnecessary_wraps)]
@@ -1451,6 +1689,10 @@ impl<'a> WordExpander<'a> {
         replacement: &str,
         match_kind: &SubstringMatchKind,
     ) -> Result<String, error::Error> {
+        // N.B. The replacement is literal text; it mustn't be interpreted as referencing
+        // capture groups.
+        let replacement = fancy_regex::NoExpand(replacement);
+
         match match_kind {
             brush_parser::word::SubstringMatchKind::Prefix
             | brush_parser::word::SubstringMatchKind::Suffix
@@ -1474,7 +1716,7 @@ impl<'a> WordExpander<'a> {
                 prompt::expand_prompt(self.shell, s)
             }
             brush_parser::word::ParameterTransformOp::CapitalizeInitial => {
-                Ok(to_initial_capitals(s))
+                Ok(to_initial_capital(s))
             }
             brush_parser::word::ParameterTransformOp::ExpandEscapeSequences => {
                 let (result, _) =
@@ -1486,11 +1728,9 @@ impl<'a> WordExpander<'a> {
             } => {
                 // TODO: This isn't right for arrays.
                 // TODO: This doesn't honor 'separate_words'
-                Ok(variables::quote_str_for_assignment(s))
-            }
-            brush_parser::word::ParameterTransformOp::Quoted => {
-                Ok(variables::quote_str_for_assignment(s))
+                Ok(escape::quote_for_reuse(s))
             }
+            brush_parser::word::ParameterTransformOp::Quoted => Ok(escape::quote_for_reuse(s)),
             brush_parser::word::ParameterTransformOp::ToLowerCase => Ok(s.to_lowercase()),
             brush_parser::word::ParameterTransformOp::ToUpperCase => Ok(s.to_uppercase()),
             brush_parser::word::ParameterTransformOp::ToAssignmentLogic
@@ -1501,6 +1741,149 @@ impl<'a> WordExpander<'a> {
     }
 }
 
+fn generate_and_combine_brace_expansions(
+    pieces: Vec<brush_parser::word::BraceExpressionOrText>,
+) -> Vec<String> {
+    let mut results = vec![String::new()];
+
+    for piece in pieces {
+        let expansions = match piece {
+            brush_parser::word::BraceExpressionOrText::Expr(members) => members
+                .into_iter()
+                .flat_map(generate_brace_expression_member)
+                .collect(),
+            brush_parser::word::BraceExpressionOrText::Text(text) => vec![text],
+        };
+
+        results = results
+            .iter()
+            .flat_map(|prefix| expansions.iter().map(move |e| std::format!("{prefix}{e}")))
+            .collect();
+    }
+
+    results
+}
+
+fn generate_brace_expression_member(
+    member: brush_parser::word::BraceExpressionMember,
+) -> Vec<String> {
+    match member {
+        brush_parser::word::BraceExpressionMember::NumberSequence {
+            low,
+            high,
+            increment,
+            width,
+        } => generate_brace_sequence(low, high, increment)
+            .into_iter()
+            .map(|n| std::format!("{n:0width$}"))
+            .collect(),
+        brush_parser::word::BraceExpressionMember::CharSequence {
+            low,
+            high,
+            increment,
+        } => {
+            let low = i64::from(u32::from(low));
+            let high = i64::from(u32::from(high));
+
+            generate_brace_sequence(low, high, increment)
+                .into_iter()
+                .filter_map(|n| u32::try_from(n).ok().and_then(char::from_u32))
+                .map(|c| {
+                    // Escape any punctuation between the upper- and lowercase letters, so
+                    // it's not subject to quote removal or pattern matching.
+                    if c.is_ascii_alphanumeric() {
+                        c.to_string()
+                    } else {
+                        std::format!("\\{c}")
+                    }
+                })
+                .collect()
+        }
+        brush_parser::word::BraceExpressionMember::Text(pieces) => {
+            generate_and_combine_brace_expansions(pieces)
+        }
+    }
+}
+
+fn generate_brace_sequence(low: i64, high: i64, increment: i64) -> Vec<i64> {
+    // N.B. The sign of the increment is ignored, and an increment of 0 is treated as 1.
+    let step = increment.checked_abs().unwrap_or(i64::MAX).max(1);
+
+    let mut values = vec![low];
+    let mut current = low;
+    loop {
+        let next = if low <= high {
+            current.checked_add(step).filter(|n| *n <= high)
+        } else {
+            current.checked_sub(step).filter(|n| *n >= high)
+        };
+
+        match next {
+            Some(next) => {
+                values.push(next);
+                current = next;
+            }
+            None => break,
+        }
+    }
+
+    values
+}
+
+/// Describes the given parameter the way it's referenced in error messages.
+fn describe_parameter(parameter: &brush_parser::word::Parameter, indirect: bool) -> String {
+    let description = match parameter {
+        brush_parser::word::Parameter::Positional(p) => std::format!("${p}"),
+        brush_parser::word::Parameter::Special(special) => {
+            let c = match special {
+                brush_parser::word::SpecialParameter::AllPositionalParameters { concatenate } => {
+                    if *concatenate {
+                        '*'
+                    } else {
+                        '@'
+                    }
+                }
+                brush_parser::word::SpecialParameter::PositionalParameterCount => '#',
+                brush_parser::word::SpecialParameter::LastExitStatus => '?',
+                brush_parser::word::SpecialParameter::CurrentOptionFlags => '-',
+                brush_parser::word::SpecialParameter::ProcessId => '$',
+                brush_parser::word::SpecialParameter::LastBackgroundProcessId => '!',
+                brush_parser::word::SpecialParameter::ShellName => '0',
+            };
+            c.to_string()
+        }
+        brush_parser::word::Parameter::Named(name) => name.clone(),
+        brush_parser::word::Parameter::NamedWithIndex { name, index } => {
+            std::format!("{name}[{index}]")
+        }
+        brush_parser::word::Parameter::NamedWithAllIndices { name, concatenate } => {
+            std::format!("{name}[{}]", if *concatenate { '*' } else { '@' })
+        }
+    };
+
+    if indirect {
+        std::format!("!{description}")
+    } else {
+        description
+    }
+}
+
+fn uppercase_char(c: char) -> String {
+    c.to_uppercase().collect()
+}
+
+fn lowercase_char(c: char) -> String {
+    c.to_lowercase().collect()
+}
+
+fn toggle_char_case(c: char) -> String {
+    if c.is_uppercase() {
+        lowercase_char(c)
+    } else {
+        uppercase_char(c)
+    }
+}
+
 fn coalesce_expansions(expansions: Vec<Expansion>) -> Expansion {
     expansions
         .into_iter()
@@ -1521,23 +1904,12 @@ fn coalesce_expansions(expansions: Vec<Expansion>) -> Expansion {
         })
 }
 
-fn to_initial_capitals(s: &str) -> String {
-    let mut result = String::new();
-    let mut capitalize_next = true;
-
-    for c in s.chars() {
-        if c.is_whitespace() {
-            capitalize_next = true;
-            result.push(c);
-        } else if capitalize_next {
-            result.push_str(c.to_uppercase().to_string().as_str());
-            capitalize_next = false;
-        } else {
-            result.push(c);
-        }
+fn to_initial_capital(s: &str) -> String {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
     }
-
-    result
 }
 
 fn valid_variable_name(s: &str) -> bool {
@@ -1610,6 +1982,31 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_brace_expansion() -> Result<()> {
+        let options = crate::shell::CreateOptions::default();
+        let mut shell = crate::shell::Shell::new(&options).await?;
+
+        assert_eq!(
+            full_expand_and_split_str(&mut shell, "a{b,c}d").await?,
+            vec!["abd", "acd"]
+        );
+        assert_eq!(
+            full_expand_and_split_str(&mut shell, "{a,b}{1..2}").await?,
+            vec!["a1", "a2", "b1", "b2"]
+        );
+        assert_eq!(
+            full_expand_and_split_str(&mut shell, "{08..10}").await?,
+            vec!["08", "09", "10"]
+        );
+        assert_eq!(
+            full_expand_and_split_str(&mut shell, "{a}").await?,
+            vec!["{a}"]
+        );
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_field_splitting() -> Result<()> {
         let options = crate::shell::CreateOptions::default();
@@ -1638,10 +2035,11 @@ mod tests {
     }
 
     #[test]
-    fn test_to_initial_capitals() {
-        assert_eq!(to_initial_capitals("ab bc cd"), String::from("Ab Bc Cd"));
-        assert_eq!(to_initial_capitals(" a "), String::from(" A "));
-        assert_eq!(to_initial_capitals(""), String::new());
+    fn test_to_initial_capital() {
+        assert_eq!(to_initial_capital("ab bc cd"), String::from("Ab bc cd"));
+        assert_eq!(to_initial_capital(" a "), String::from(" a "));
+        assert_eq!(to_initial_capital("ßa"), String::from("SSa"));
+        assert_eq!(to_initial_capital(""), String::new());
     }
 
     #[test]
@@ -2,7 +2,8 @@ use brush_parser::ast;
 use std::path::Path;
 
 use crate::{
-    env, error, expansion, namedoptions, patterns,
+    arithmetic::{self, Evaluatable},
+    env, error, expansion, namedoptions,
     sys::{fs::MetadataExt, fs::PathExt, users},
     variables::{self, ArrayLiteral},
     Shell,
@@ -47,7 +48,9 @@ async fn apply_unary_predicate(
     let expanded_operand = expansion::basic_expand_word(shell, operand).await?;
 
     if shell.options.print_commands_and_arguments {
-        shell.trace_command(std::format!("[[ {op} {expanded_operand} ]]"))?;
+        shell
+            .trace_command(std::format!("[[ {op} {expanded_operand} ]]"))
+            .await?;
     }
 
     apply_unary_predicate_to_str(op, expanded_operand.as_str(), shell)
@@ -183,13 +186,20 @@ async fn apply_binary_predicate(
 ) -> Result<bool, error::Error> {
     #[allow(clippy::single_match_else)]
     match op {
-        ast::BinaryPredicate::StringMatchesRegex => {
+        // N.B. Quoted portions of the regular expression are matched literally, so a fully
+        // quoted expression is a substring check that still populates BASH_REMATCH.
+        ast::BinaryPredicate::StringMatchesRegex
+        | ast::BinaryPredicate::StringContainsSubstring => {
             if shell.options.print_commands_and_arguments {
-                shell.trace_command(std::format!("[[ {left} {op} {right} ]]"))?;
+                shell
+                    .trace_command(std::format!("[[ {left} {op} {right} ]]"))
+                    .await?;
             }
 
             let s = expansion::basic_expand_word(shell, left).await?;
-            let regex = expansion::basic_expand_regex(shell, right).await?;
+            let regex = expansion::basic_expand_regex(shell, right)
+                .await?
+                .set_case_insensitive(shell.options.case_insensitive_conditionals);
 
             let (matches, captures) = if let Some(captures) = regex.matches(s.as_str())? {
                 (true, captures)
@@ -214,31 +224,28 @@ async fn apply_binary_predicate(
 
             Ok(matches)
         }
-        ast::BinaryPredicate::StringContainsSubstring => {
-            let s = expansion::basic_expand_word(shell, left).await?;
-            let substring = expansion::basic_expand_word(shell, right).await?;
+        ast::BinaryPredicate::FilesReferToSameDeviceAndInodeNumbers
+        | ast::BinaryPredicate::LeftFileIsNewerOrExistsWhenRightDoesNot
+        | ast::BinaryPredicate::LeftFileIsOlderOrDoesNotExistWhenRightDoes => {
+            let left = expansion::basic_expand_word(shell, left).await?;
+            let right = expansion::basic_expand_word(shell, right).await?;
 
             if shell.options.print_commands_and_arguments {
-                shell.trace_command(std::format!("[[ {s} {op} {substring} ]]"))?;
+                shell
+                    .trace_command(std::format!("[[ {left} {op} {right} ]]"))
+                    .await?;
             }
 
-            Ok(s.contains(substring.as_str()))
+            apply_binary_predicate_to_strs(op, left.as_str(), right.as_str(), shell)
         }
-        ast::BinaryPredicate::FilesReferToSameDeviceAndInodeNumbers => {
-            error::unimp("extended test binary predicate FilesReferToSameDeviceAndInodeNumbers")
-        }
-        ast::BinaryPredicate::LeftFileIsNewerOrExistsWhenRightDoesNot => {
-            error::unimp("extended test binary predicate LeftFileIsNewerOrExistsWhenRightDoesNot")
-        }
-        ast::BinaryPredicate::LeftFileIsOlderOrDoesNotExistWhenRightDoes => error::unimp(
-            "extended test binary predicate LeftFileIsOlderOrDoesNotExistWhenRightDoes",
-        ),
         ast::BinaryPredicate::LeftSortsBeforeRight => {
             let left = expansion::basic_expand_word(shell, left).await?;
             let right = expansion::basic_expand_word(shell, right).await?;
 
             if shell.options.print_commands_and_arguments {
-                shell.trace_command(std::format!("[[ {left} {op} {right} ]]"))?;
+                shell
+                    .trace_command(std::format!("[[ {left} {op} {right} ]]"))
+                    .await?;
             }
 
             // TODO: According to docs, should be lexicographical order of the current locale.
@@ -249,7 +256,9 @@ async fn apply_binary_predicate(
             let right = expansion::basic_expand_word(shell, right).await?;
 
             if shell.options.print_commands_and_arguments {
-                shell.trace_command(std::format!("[[ {left} {op} {right} ]]"))?;
+                shell
+                    .trace_command(std::format!("[[ {left} {op} {right} ]]"))
+                    .await?;
             }
 
             // TODO: According to docs, should be lexicographical order of the current locale.
@@ -260,107 +269,138 @@ async fn apply_binary_predicate(
             let right = expansion::basic_expand_word(shell, right).await?;
 
             if shell.options.print_commands_and_arguments {
-                shell.trace_command(std::format!("[[ {left} {op} {right} ]]"))?;
+                shell
+                    .trace_command(std::format!("[[ {left} {op} {right} ]]"))
+                    .await?;
             }
 
-            Ok(apply_binary_arithmetic_predicate(
+            apply_extended_arithmetic_predicate(
+                shell,
                 left.as_str(),
                 right.as_str(),
                 |left, right| left == right,
-            ))
+            )
+            .await
         }
         ast::BinaryPredicate::ArithmeticNotEqualTo => {
             let left = expansion::basic_expand_word(shell, left).await?;
             let right = expansion::basic_expand_word(shell, right).await?;
 
             if shell.options.print_commands_and_arguments {
-                shell.trace_command(std::format!("[[ {left} {op} {right} ]]"))?;
+                shell
+                    .trace_command(std::format!("[[ {left} {op} {right} ]]"))
+                    .await?;
             }
 
-            Ok(apply_binary_arithmetic_predicate(
+            apply_extended_arithmetic_predicate(
+                shell,
                 left.as_str(),
                 right.as_str(),
                 |left, right| left != right,
-            ))
+            )
+            .await
         }
         ast::BinaryPredicate::ArithmeticLessThan => {
             let left = expansion::basic_expand_word(shell, left).await?;
             let right = expansion::basic_expand_word(shell, right).await?;
 
             if shell.options.print_commands_and_arguments {
-                shell.trace_command(std::format!("[[ {left} {op} {right} ]]"))?;
+                shell
+                    .trace_command(std::format!("[[ {left} {op} {right} ]]"))
+                    .await?;
             }
 
-            Ok(apply_binary_arithmetic_predicate(
+            apply_extended_arithmetic_predicate(
+                shell,
                 left.as_str(),
                 right.as_str(),
                 |left, right| left < right,
-            ))
+            )
+            .await
         }
         ast::BinaryPredicate::ArithmeticLessThanOrEqualTo => {
             let left = expansion::basic_expand_word(shell, left).await?;
             let right = expansion::basic_expand_word(shell, right).await?;
 
             if shell.options.print_commands_and_arguments {
-                shell.trace_command(std::format!("[[ {left} {op} {right} ]]"))?;
+                shell
+                    .trace_command(std::format!("[[ {left} {op} {right} ]]"))
+                    .await?;
             }
 
-            Ok(apply_binary_arithmetic_predicate(
+            apply_extended_arithmetic_predicate(
+                shell,
                 left.as_str(),
                 right.as_str(),
                 |left, right| left <= right,
-            ))
+            )
+            .await
         }
         ast::BinaryPredicate::ArithmeticGreaterThan => {
             let left = expansion::basic_expand_word(shell, left).await?;
             let right = expansion::basic_expand_word(shell, right).await?;
 
             if shell.options.print_commands_and_arguments {
-                shell.trace_command(std::format!("[[ {left} {op} {right} ]]"))?;
+                shell
+                    .trace_command(std::format!("[[ {left} {op} {right} ]]"))
+                    .await?;
             }
 
-            Ok(apply_binary_arithmetic_predicate(
+            apply_extended_arithmetic_predicate(
+                shell,
                 left.as_str(),
                 right.as_str(),
                 |left, right| left > right,
-            ))
+            )
+            .await
         }
         ast::BinaryPredicate::ArithmeticGreaterThanOrEqualTo => {
             let left = expansion::basic_expand_word(shell, left).await?;
             let right = expansion::basic_expand_word(shell, right).await?;
 
             if shell.options.print_commands_and_arguments {
-                shell.trace_command(std::format!("[[ {left} {op} {right} ]]"))?;
+                shell
+                    .trace_command(std::format!("[[ {left} {op} {right} ]]"))
+                    .await?;
             }
 
-            Ok(apply_binary_arithmetic_predicate(
+            apply_extended_arithmetic_predicate(
+                shell,
                 left.as_str(),
                 right.as_str(),
                 |left, right| left >= right,
-            ))
+            )
+            .await
         }
         // N.B. The "=", "==", and "!=" operators don't compare 2 strings; they check
         // for whether the lefthand operand (a string) is matched by the righthand
         // operand (treated as a shell pattern).
-        // TODO: implement case-insensitive matching if relevant via shopt options (nocasematch).
         ast::BinaryPredicate::StringExactlyMatchesPattern => {
             let s = expansion::basic_expand_word(shell, left).await?;
-            let pattern = expansion::basic_expand_pattern(shell, right).await?;
+            let pattern = expansion::basic_expand_pattern(shell, right)
+                .await?
+                .set_case_insensitive(shell.options.case_insensitive_conditionals);
 
             if shell.options.print_commands_and_arguments {
                 let expanded_right = expansion::basic_expand_word(shell, right).await?;
-                shell.trace_command(std::format!("[[ {s} {op} {expanded_right} ]]"))?;
+                shell
+                    .trace_command(std::format!("[[ {s} {op} {expanded_right} ]]"))
+                    .await?;
             }
 
             pattern.exactly_matches(s.as_str(), shell.options.extended_globbing)
         }
         ast::BinaryPredicate::StringDoesNotExactlyMatchPattern => {
             let s = expansion::basic_expand_word(shell, left).await?;
-            let pattern = expansion::basic_expand_pattern(shell, right).await?;
+            let pattern = expansion::basic_expand_pattern(shell, right)
+                .await?
+                .set_case_insensitive(shell.options.case_insensitive_conditionals);
 
             if shell.options.print_commands_and_arguments {
                 let expanded_right = expansion::basic_expand_word(shell, right).await?;
-                shell.trace_command(std::format!("[[ {s} {op} {expanded_right} ]]"))?;
+                shell
+                    .trace_command(std::format!("[[ {s} {op} {expanded_right} ]]"))
+                    .await?;
             }
 
             let eq = pattern.exactly_matches(s.as_str(), shell.options.extended_globbing)?;
@@ -377,14 +417,34 @@ pub(crate) fn apply_binary_predicate_to_strs(
 ) -> Result<bool, error::Error> {
     match op {
         ast::BinaryPredicate::FilesReferToSameDeviceAndInodeNumbers => {
-            error::unimp("extended test binary predicate FilesReferToSameDeviceAndInodeNumbers")
+            let left = shell.get_absolute_path(Path::new(left)).metadata();
+            let right = shell.get_absolute_path(Path::new(right)).metadata();
+
+            match (left, right) {
+                (Ok(left), Ok(right)) => Ok(left.dev() == right.dev() && left.ino() == right.ino()),
+                _ => Ok(false),
+            }
         }
         ast::BinaryPredicate::LeftFileIsNewerOrExistsWhenRightDoesNot => {
-            error::unimp("extended test binary predicate LeftFileIsNewerOrExistsWhenRightDoesNot")
+            match (
+                get_modified_time(shell, left),
+                get_modified_time(shell, right),
+            ) {
+                (Some(left), Some(right)) => Ok(left > right),
+                (Some(_), None) => Ok(true),
+                _ => Ok(false),
+            }
+        }
+        ast::BinaryPredicate::LeftFileIsOlderOrDoesNotExistWhenRightDoes => {
+            match (
+                get_modified_time(shell, left),
+                get_modified_time(shell, right),
+            ) {
+                (Some(left), Some(right)) => Ok(left < right),
+                (None, Some(_)) => Ok(true),
+                _ => Ok(false),
+            }
         }
-        ast::BinaryPredicate::LeftFileIsOlderOrDoesNotExistWhenRightDoes => error::unimp(
-            "extended test binary predicate LeftFileIsOlderOrDoesNotExistWhenRightDoes",
-        ),
         ast::BinaryPredicate::LeftSortsBeforeRight => {
             // TODO: According to docs, should be lexicographical order of the current locale.
             Ok(left < right)
@@ -393,54 +453,72 @@ pub(crate) fn apply_binary_predicate_to_strs(
             // TODO: According to docs, should be lexicographical order of the current locale.
             Ok(left > right)
         }
-        ast::BinaryPredicate::ArithmeticEqualTo => Ok(apply_binary_arithmetic_predicate(
-            left,
-            right,
-            |left, right| left == right,
-        )),
-        ast::BinaryPredicate::ArithmeticNotEqualTo => Ok(apply_binary_arithmetic_predicate(
-            left,
-            right,
-            |left, right| left != right,
-        )),
-        ast::BinaryPredicate::ArithmeticLessThan => Ok(apply_binary_arithmetic_predicate(
-            left,
-            right,
-            |left, right| left < right,
-        )),
-        ast::BinaryPredicate::ArithmeticLessThanOrEqualTo => Ok(apply_binary_arithmetic_predicate(
-            left,
-            right,
-            |left, right| left <= right,
-        )),
-        ast::BinaryPredicate::ArithmeticGreaterThan => Ok(apply_binary_arithmetic_predicate(
-            left,
-            right,
-            |left, right| left > right,
-        )),
-        ast::BinaryPredicate::ArithmeticGreaterThanOrEqualTo => Ok(
-            apply_binary_arithmetic_predicate(left, right, |left, right| left >= right),
-        ),
-        ast::BinaryPredicate::StringExactlyMatchesPattern => {
-            let pattern = patterns::Pattern::from(right);
-            pattern.exactly_matches(left, shell.options.extended_globbing)
+        ast::BinaryPredicate::ArithmeticEqualTo => {
+            apply_binary_arithmetic_predicate(left, right, |left, right| left == right)
         }
-        ast::BinaryPredicate::StringDoesNotExactlyMatchPattern => {
-            let pattern = patterns::Pattern::from(right);
-            let eq = pattern.exactly_matches(left, shell.options.extended_globbing)?;
-            Ok(!eq)
+        ast::BinaryPredicate::ArithmeticNotEqualTo => {
+            apply_binary_arithmetic_predicate(left, right, |left, right| left != right)
+        }
+        ast::BinaryPredicate::ArithmeticLessThan => {
+            apply_binary_arithmetic_predicate(left, right, |left, right| left < right)
+        }
+        ast::BinaryPredicate::ArithmeticLessThanOrEqualTo => {
+            apply_binary_arithmetic_predicate(left, right, |left, right| left <= right)
         }
+        ast::BinaryPredicate::ArithmeticGreaterThan => {
+            apply_binary_arithmetic_predicate(left, right, |left, right| left > right)
+        }
+        ast::BinaryPredicate::ArithmeticGreaterThanOrEqualTo => {
+            apply_binary_arithmetic_predicate(left, right, |left, right| left >= right)
+        }
+        // N.B. Unlike in extended tests, the test command compares strings exactly; its
+        // righthand operand is not treated as a pattern.
+        ast::BinaryPredicate::StringExactlyMatchesPattern => Ok(left == right),
+        ast::BinaryPredicate::StringDoesNotExactlyMatchPattern => Ok(left != right),
         _ => error::unimp("unsupported test binary predicate"),
     }
 }
 
-fn apply_binary_arithmetic_predicate(left: &str, right: &str, op: fn(i64, i64) -> bool) -> bool {
-    let left: Result<i64, _> = left.parse();
-    let right: Result<i64, _> = right.parse();
+fn apply_binary_arithmetic_predicate(
+    left: &str,
+    right: &str,
+    op: fn(i64, i64) -> bool,
+) -> Result<bool, error::Error> {
+    Ok(op(
+        parse_integer_operand(left)?,
+        parse_integer_operand(right)?,
+    ))
+}
 
-    if let (Ok(left), Ok(right)) = (left, right) {
-        op(left, right)
-    } else {
-        false
-    }
+async fn apply_extended_arithmetic_predicate(
+    shell: &mut Shell,
+    left: &str,
+    right: &str,
+    op: fn(i64, i64) -> bool,
+) -> Result<bool, error::Error> {
+    // Operands of arithmetic operators in extended tests are evaluated as arithmetic expressions.
+    let left = eval_arithmetic_operand(shell, left).await?;
+    let right = eval_arithmetic_operand(shell, right).await?;
+
+    Ok(op(left, right))
+}
+
+async fn eval_arithmetic_operand(shell: &mut Shell, operand: &str) -> Result<i64, error::Error> {
+    let expr = brush_parser::arithmetic::parse(operand)
+        .map_err(|_| arithmetic::EvalError::ParseError(operand.to_owned()))?;
+    Ok(expr.eval(shell).await?)
+}
+
+fn parse_integer_operand(s: &str) -> Result<i64, error::Error> {
+    s.trim()
+        .parse()
+        .map_err(|_| error::Error::IntegerExpressionExpected(s.to_owned()))
+}
+
+fn get_modified_time(shell: &Shell, path: &str) -> Option<std::time::SystemTime> {
+    shell
+        .get_absolute_path(Path::new(path))
+        .metadata()
+        .and_then(|metadata| metadata.modified())
+        .ok()
 }
@@ -31,9 +31,15 @@ impl FunctionEnv {
     ///
     /// * `name` - The name of the function to update.
     /// * `definition` - The new definition for the function.
-    pub fn update(&mut self, name: String, definition: Arc<brush_parser::ast::FunctionDefinition>) {
+    /// * `line` - The line number at which the function was defined.
+    pub fn update(
+        &mut self,
+        name: String,
+        definition: Arc<brush_parser::ast::FunctionDefinition>,
+        line: u32,
+    ) {
         self.functions
-            .insert(name, FunctionRegistration { definition });
+            .insert(name, FunctionRegistration { definition, line });
     }
 
     /// Returns an iterator over the functions registered in this environment.
@@ -47,4 +53,6 @@ impl FunctionEnv {
 pub struct FunctionRegistration {
     /// The definition of the function.
     pub definition: Arc<brush_parser::ast::FunctionDefinition>,
+    /// The line number at which the function was defined.
+    pub line: u32,
 }
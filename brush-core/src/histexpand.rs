@@ -0,0 +1,553 @@
+use crate::{error, history::History};
+
+/// Characters that end the search string of a `!string` event designator.
+const EVENT_DELIMITERS: &[char] = &[
+    ' ', '\t', '\n', ':', ';', '&', '|', '(', ')', '<', '>', '"', '\'',
+];
+
+/// The result of applying history expansion to a line of input.
+pub struct HistoryExpansion {
+    /// The expanded line.
+    pub line: String,
+    /// Whether any expansion was performed.
+    pub expanded: bool,
+    /// Whether the expanded line should only be displayed and recorded in history, rather
+    /// than executed (as requested by the `:p` modifier).
+    pub print_only: bool,
+}
+
+/// Applies csh-style history expansion (e.g., `!!`, `!$`, `^old^new^`) to the given line
+/// of input, using the given history list. The line's own entry should not yet have been
+/// added to the list.
+///
+/// # Arguments
+///
+/// * `line` - The line of input to expand.
+/// * `history` - The history list to draw from.
+pub(crate) fn expand(line: &str, history: &History) -> Result<HistoryExpansion, error::Error> {
+    let mut expander = Expander {
+        history,
+        chars: line.chars().collect(),
+        pos: 0,
+        output: String::new(),
+        expanded: false,
+        print_only: false,
+        last_search: None,
+        last_substitution: None,
+    };
+
+    expander.expand()?;
+
+    Ok(HistoryExpansion {
+        line: expander.output,
+        expanded: expander.expanded,
+        print_only: expander.print_only,
+    })
+}
+
+struct Expander<'a> {
+    history: &'a History,
+    chars: Vec<char>,
+    pos: usize,
+    output: String,
+    expanded: bool,
+    print_only: bool,
+    /// The string used by the most recent `!?string?` event designator.
+    last_search: Option<String>,
+    /// The most recent `old` and `new` strings used by a substitution modifier.
+    last_substitution: Option<(String, String)>,
+}
+
+impl<'a> Expander<'a> {
+    fn expand(&mut self) -> Result<(), error::Error> {
+        // A leading `^old^new^` is shorthand for `!!:s^old^new^`.
+        if self.peek() == Some('^') {
+            let event = self.last_entry().ok_or_else(|| not_found("^"))?;
+            let text = self.apply_substitution(event.to_owned(), false, ":s")?;
+            self.output.push_str(text.as_str());
+            self.expanded = true;
+        }
+
+        let mut in_single_quotes = false;
+        let mut in_double_quotes = false;
+
+        while let Some(c) = self.peek() {
+            match c {
+                '\'' if in_single_quotes => in_single_quotes = false,
+                _ if in_single_quotes => (),
+                '\\' => {
+                    // Escaped characters (including `!`) are passed through untouched.
+                    self.advance();
+                    self.output.push(c);
+                    if let Some(next) = self.peek() {
+                        self.advance();
+                        self.output.push(next);
+                    }
+                    continue;
+                }
+                '\'' if !in_double_quotes => in_single_quotes = true,
+                '"' => in_double_quotes = !in_double_quotes,
+                '!' if self.starts_expansion() => {
+                    self.advance();
+                    let text = self.expand_designator()?;
+                    self.output.push_str(text.as_str());
+                    self.expanded = true;
+                    continue;
+                }
+                _ => (),
+            }
+
+            self.advance();
+            self.output.push(c);
+        }
+
+        Ok(())
+    }
+
+    /// Returns whether the `!` at the current position begins a history expansion.
+    fn starts_expansion(&self) -> bool {
+        // `$!` and `${!name}` are parameter expansions.
+        if self.output.ends_with('$') || self.output.ends_with("${") {
+            return false;
+        }
+
+        match self.chars.get(self.pos + 1) {
+            None | Some(' ' | '\t' | '\n' | '=' | '(' | '"') => false,
+            Some(_) => true,
+        }
+    }
+
+    /// Expands the history designator following a `!`: an event, followed by optional
+    /// word designators and modifiers.
+    fn expand_designator(&mut self) -> Result<String, error::Error> {
+        let event = self.parse_event()?;
+        let words = self.parse_word_designator(event.as_str())?;
+        let text = match words {
+            Some(words) => words,
+            None => event,
+        };
+
+        self.apply_modifiers(text)
+    }
+
+    fn parse_event(&mut self) -> Result<String, error::Error> {
+        let history = self.history;
+        let start = self.pos;
+
+        let event = match self.peek() {
+            Some('!') => {
+                self.advance();
+                self.last_entry()
+            }
+            Some('#') => {
+                self.advance();
+                return Ok(self.output.clone());
+            }
+            Some(':' | '^' | '$' | '*' | '%') => self.last_entry(),
+            Some('?') => {
+                self.advance();
+                let search = self.take_while(|c| c != '?' && c != '\n');
+                if self.peek() == Some('?') {
+                    self.advance();
+                }
+
+                let event = history
+                    .iter()
+                    .rev()
+                    .find(|(_, command)| command.contains(search.as_str()))
+                    .map(|(_, command)| command);
+
+                self.last_search = Some(search);
+                event
+            }
+            Some(c) if c.is_ascii_digit() || (c == '-' && self.next_is_digit()) => {
+                let negative = c == '-';
+                if negative {
+                    self.advance();
+                }
+
+                let digits = self.take_while(|c| c.is_ascii_digit());
+                let n: usize = digits.parse().unwrap_or(usize::MAX);
+                let number = if negative {
+                    history.next_number().checked_sub(n)
+                } else {
+                    Some(n)
+                };
+
+                number.and_then(|number| history.get(number))
+            }
+            _ => {
+                let prefix = self.take_while(|c| !EVENT_DELIMITERS.contains(&c));
+                history
+                    .iter()
+                    .rev()
+                    .find(|(_, command)| !prefix.is_empty() && command.starts_with(prefix.as_str()))
+                    .map(|(_, command)| command)
+            }
+        };
+
+        event.map(ToOwned::to_owned).ok_or_else(|| {
+            let spec: String = self.chars[start..self.pos].iter().collect();
+            not_found(std::format!("!{spec}").as_str())
+        })
+    }
+
+    fn parse_word_designator(&mut self, event: &str) -> Result<Option<String>, error::Error> {
+        let start = self.pos;
+
+        match (self.peek(), self.peek_at(1)) {
+            (Some(':'), Some(c)) if c.is_ascii_digit() || "^$*-%".contains(c) => self.advance(),
+            (Some('^' | '$' | '*' | '%'), _) => (),
+            _ => return Ok(None),
+        }
+
+        let words = split_words(event);
+        let last = words.len().saturating_sub(1);
+        let bad_specifier = |expander: &Self| {
+            let spec: String = expander.chars[start..expander.pos].iter().collect();
+            error::Error::BadHistoryWordSpecifier(spec)
+        };
+
+        let (first, last) = match self.peek() {
+            Some('^') => {
+                self.advance();
+                (1, 1)
+            }
+            Some('$') => {
+                self.advance();
+                (last, last)
+            }
+            Some('*') => {
+                self.advance();
+                if words.len() <= 1 {
+                    return Ok(Some(String::new()));
+                }
+                (1, last)
+            }
+            Some('%') => {
+                self.advance();
+                let search = self.last_search.clone().unwrap_or_default();
+                let index = words
+                    .iter()
+                    .position(|word| word.contains(search.as_str()))
+                    .ok_or_else(|| bad_specifier(self))?;
+                (index, index)
+            }
+            _ => {
+                let first = if self.peek() == Some('-') {
+                    0
+                } else {
+                    self.parse_word_index(last)
+                };
+
+                match self.peek() {
+                    Some('*') => {
+                        self.advance();
+                        (first, last)
+                    }
+                    Some('-') => {
+                        self.advance();
+                        match self.peek() {
+                            Some(c) if c.is_ascii_digit() || c == '$' => {
+                                (first, self.parse_word_index(last))
+                            }
+                            // `x-` abbreviates `x-$`, omitting the last word.
+                            _ if last == 0 => return Err(bad_specifier(self)),
+                            _ => (first, last - 1),
+                        }
+                    }
+                    _ => (first, first),
+                }
+            }
+        };
+
+        if first > last || last >= words.len() {
+            return Err(bad_specifier(self));
+        }
+
+        Ok(Some(words[first..=last].join(" ")))
+    }
+
+    /// Parses a word index, which is either a number or `$` (the given last index).
+    fn parse_word_index(&mut self, last: usize) -> usize {
+        if self.peek() == Some('$') {
+            self.advance();
+            last
+        } else {
+            self.take_while(|c| c.is_ascii_digit())
+                .parse()
+                .unwrap_or(usize::MAX)
+        }
+    }
+
+    fn apply_modifiers(&mut self, mut text: String) -> Result<String, error::Error> {
+        while self.peek() == Some(':') {
+            let Some(modifier) = self.peek_at(1) else {
+                break;
+            };
+
+            let start = self.pos;
+            self.pos += 2;
+
+            text = match modifier {
+                'h' => text
+                    .rsplit_once('/')
+                    .map_or(text.clone(), |(head, _)| head.to_owned()),
+                't' => text
+                    .rsplit_once('/')
+                    .map_or(text.clone(), |(_, tail)| tail.to_owned()),
+                'r' => match text.rfind(['.', '/']) {
+                    Some(index) if text[index..].starts_with('.') => text[..index].to_owned(),
+                    _ => text,
+                },
+                'e' => match text.rfind(['.', '/']) {
+                    Some(index) if text[index..].starts_with('.') => text[index..].to_owned(),
+                    _ => text,
+                },
+                'p' => {
+                    self.print_only = true;
+                    text
+                }
+                's' => self.apply_substitution(text, false, ":s")?,
+                'g' | 'a' if self.peek() == Some('s') => {
+                    self.advance();
+                    let prefix = std::format!(":{modifier}s");
+                    self.apply_substitution(text, true, prefix.as_str())?
+                }
+                '&' => self.repeat_substitution(text, false, start)?,
+                'g' | 'a' if self.peek() == Some('&') => {
+                    self.advance();
+                    self.repeat_substitution(text, true, start)?
+                }
+                _ => {
+                    return Err(error::Error::UnrecognizedHistoryModifier(
+                        self.chars[start + 1..self.pos].iter().collect(),
+                    ))
+                }
+            };
+        }
+
+        Ok(text)
+    }
+
+    /// Parses the `/old/new/` portion of a substitution modifier (the delimiter may be any
+    /// character) and applies it to the given text.
+    fn apply_substitution(
+        &mut self,
+        text: String,
+        global: bool,
+        prefix: &str,
+    ) -> Result<String, error::Error> {
+        let start = self.pos;
+
+        let Some(delimiter) = self.peek() else {
+            return Err(error::Error::HistorySubstitutionFailed(prefix.to_owned()));
+        };
+        self.advance();
+
+        let old = self.take_substitution_part(delimiter, None);
+        let new = self.take_substitution_part(delimiter, Some(old.as_str()));
+
+        let spec: String = self.chars[start..self.pos].iter().collect();
+        let failed = || error::Error::HistorySubstitutionFailed(std::format!("{prefix}{spec}"));
+
+        // An empty `old` reuses the most recent substitution's.
+        let old = if old.is_empty() {
+            self.last_substitution
+                .as_ref()
+                .map(|(old, _)| old.clone())
+                .ok_or_else(failed)?
+        } else {
+            old
+        };
+
+        let result = substitute(text.as_str(), old.as_str(), new.as_str(), global);
+        self.last_substitution = Some((old, new));
+
+        result.ok_or_else(failed)
+    }
+
+    fn repeat_substitution(
+        &mut self,
+        text: String,
+        global: bool,
+        start: usize,
+    ) -> Result<String, error::Error> {
+        let failed = |expander: &Self| {
+            error::Error::HistorySubstitutionFailed(
+                expander.chars[start..expander.pos].iter().collect(),
+            )
+        };
+
+        let Some((old, new)) = &self.last_substitution else {
+            return Err(failed(self));
+        };
+
+        substitute(text.as_str(), old, new, global).ok_or_else(|| failed(self))
+    }
+
+    /// Reads one part of a substitution modifier, up to (and consuming) the delimiter or
+    /// the end of the line. A backslash escapes the delimiter; when reading the
+    /// replacement, `&` stands for the text being replaced.
+    fn take_substitution_part(&mut self, delimiter: char, old: Option<&str>) -> String {
+        let mut part = String::new();
+
+        while let Some(c) = self.peek() {
+            if c == '\n' {
+                break;
+            }
+
+            self.advance();
+
+            let escapes_next = c == '\\'
+                && self
+                    .peek()
+                    .is_some_and(|next| next == delimiter || next == '&');
+
+            match (c, old) {
+                (c, _) if c == delimiter => break,
+                _ if escapes_next => {
+                    part.extend(self.peek());
+                    self.advance();
+                }
+                ('&', Some(old)) => part.push_str(old),
+                (c, _) => part.push(c),
+            }
+        }
+
+        part
+    }
+
+    fn last_entry(&self) -> Option<&'a str> {
+        let history = self.history;
+        history.last_number().and_then(|number| history.get(number))
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    fn next_is_digit(&self) -> bool {
+        self.peek_at(1).is_some_and(|c| c.is_ascii_digit())
+    }
+
+    fn advance(&mut self) {
+        self.pos += 1;
+    }
+
+    fn take_while(&mut self, predicate: impl Fn(char) -> bool) -> String {
+        let start = self.pos;
+        while self.peek().is_some_and(&predicate) {
+            self.advance();
+        }
+
+        self.chars[start..self.pos].iter().collect()
+    }
+}
+
+/// Splits a history entry into words the way the shell would tokenize it.
+fn split_words(command: &str) -> Vec<String> {
+    match brush_parser::tokenize_str(command) {
+        Ok(tokens) => tokens.iter().map(|t| t.to_str().to_owned()).collect(),
+        Err(_) => command.split_whitespace().map(ToOwned::to_owned).collect(),
+    }
+}
+
+/// Replaces the first (or, if `global` is set, every) occurrence of `old` in `text`,
+/// returning `None` if there are none.
+fn substitute(text: &str, old: &str, new: &str, global: bool) -> Option<String> {
+    if old.is_empty() || !text.contains(old) {
+        None
+    } else if global {
+        Some(text.replace(old, new))
+    } else {
+        Some(text.replacen(old, new, 1))
+    }
+}
+
+fn not_found(spec: &str) -> error::Error {
+    error::Error::HistoryEventNotFound(spec.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    fn expand_with(line: &str, entries: &[&str]) -> Result<String> {
+        let mut history = History::default();
+        for entry in entries {
+            history.add((*entry).to_owned(), None);
+        }
+
+        Ok(expand(line, &history)?.line)
+    }
+
+    #[test]
+    fn expand_events() -> Result<()> {
+        let entries = ["echo a b c", "ls -l /tmp", "echo x"];
+
+        assert_eq!(expand_with("!!", &entries)?, "echo x");
+        assert_eq!(expand_with("!2 /", &entries)?, "ls -l /tmp /");
+        assert_eq!(expand_with("!-3", &entries)?, "echo a b c");
+        assert_eq!(expand_with("!ls", &entries)?, "ls -l /tmp");
+        assert_eq!(expand_with("!?b c?", &entries)?, "echo a b c");
+        assert!(expand_with("!nope", &entries).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn expand_word_designators() -> Result<()> {
+        let entries = ["echo a b c"];
+
+        assert_eq!(expand_with("x !$ !^ !*", &entries)?, "x c a a b c");
+        assert_eq!(
+            expand_with("!:0 !:1-2 !:2- !:-1", &entries)?,
+            "echo a b b echo a"
+        );
+        assert_eq!(expand_with("!!:2*", &entries)?, "b c");
+        assert!(expand_with("!:9", &entries).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn expand_modifiers() -> Result<()> {
+        let entries = ["cat /tmp/file.txt"];
+
+        assert_eq!(
+            expand_with("!$:h !$:t !$:r !$:e", &entries)?,
+            "/tmp file.txt /tmp/file .txt"
+        );
+        assert_eq!(
+            expand_with("!!:s/cat/less/", &entries)?,
+            "less /tmp/file.txt"
+        );
+        assert_eq!(expand_with("!!:gs/t/T/", &entries)?, "caT /Tmp/file.TxT");
+        assert_eq!(
+            expand_with("^cat^head -1^", &entries)?,
+            "head -1 /tmp/file.txt"
+        );
+        assert!(expand_with("^dog^cat", &entries).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn skip_quoted_and_literal_bangs() -> Result<()> {
+        let entries = ["echo x"];
+
+        assert_eq!(
+            expand_with(r#"echo '!!' \!! "a!" ! $! a!=b"#, &entries)?,
+            r#"echo '!!' \!! "a!" ! $! a!=b"#
+        );
+        assert_eq!(expand_with(r#"echo "!!""#, &entries)?, r#"echo "echo x""#);
+
+        Ok(())
+    }
+}
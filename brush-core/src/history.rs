@@ -0,0 +1,264 @@
+use std::collections::VecDeque;
+use std::io::Write;
+use std::path::Path;
+
+/// The number of history entries retained when `HISTSIZE` isn't set to a usable value.
+pub(crate) const DEFAULT_MAX_ENTRIES: usize = 500;
+
+/// The shell's list of previously executed commands. Each entry is identified by a
+/// history number; numbers keep increasing as the oldest entries are discarded.
+#[derive(Clone)]
+pub struct History {
+    entries: VecDeque<Entry>,
+    /// The history number of the oldest retained entry.
+    first_number: usize,
+}
+
+#[derive(Clone)]
+struct Entry {
+    command: String,
+    /// Whether the entry has been written to (or was read from) a history file.
+    saved: bool,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self {
+            entries: VecDeque::new(),
+            first_number: 1,
+        }
+    }
+}
+
+impl History {
+    /// Appends a command to the history list, discarding the oldest entries as needed to
+    /// keep the list within the given size limit (if any).
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - The command to add.
+    /// * `max_entries` - The maximum number of entries to retain, if limited.
+    pub fn add(&mut self, command: String, max_entries: Option<usize>) {
+        self.entries.push_back(Entry {
+            command,
+            saved: false,
+        });
+
+        if let Some(max_entries) = max_entries {
+            self.truncate(max_entries);
+        }
+    }
+
+    /// Discards the oldest entries until at most the given number of entries remain.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_entries` - The maximum number of entries to retain.
+    pub fn truncate(&mut self, max_entries: usize) {
+        while self.entries.len() > max_entries {
+            self.entries.pop_front();
+            self.first_number += 1;
+        }
+    }
+
+    /// Replaces the most recently added entry with the given command; if the list is
+    /// empty, the command is added.
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - The replacement command.
+    pub fn replace_last(&mut self, command: String) {
+        if let Some(last) = self.entries.back_mut() {
+            *last = Entry {
+                command,
+                saved: false,
+            };
+        } else {
+            self.add(command, None);
+        }
+    }
+
+    /// Removes the most recently added entry, if there is one.
+    pub fn remove_last(&mut self) {
+        self.entries.pop_back();
+    }
+
+    /// Removes the entry with the given history number; later entries are renumbered
+    /// to fill the gap. Returns whether an entry was removed.
+    ///
+    /// # Arguments
+    ///
+    /// * `number` - The history number of the entry.
+    pub fn remove(&mut self, number: usize) -> bool {
+        number
+            .checked_sub(self.first_number)
+            .and_then(|index| self.entries.remove(index))
+            .is_some()
+    }
+
+    /// Removes all entries matching the given command.
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - The command to remove.
+    pub fn remove_matching(&mut self, command: &str) {
+        self.entries.retain(|entry| entry.command != command);
+    }
+
+    /// Removes all entries from the list; numbering starts over at 1.
+    pub fn clear(&mut self) {
+        self.first_number = 1;
+        self.entries.clear();
+    }
+
+    /// Returns the number of entries in the list.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns whether the list is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the history number of the oldest entry in the list.
+    pub fn first_number(&self) -> usize {
+        self.first_number
+    }
+
+    /// Returns the history number that the next added entry will have.
+    pub fn next_number(&self) -> usize {
+        self.first_number + self.entries.len()
+    }
+
+    /// Returns the history number of the most recently added entry, if there is one.
+    pub fn last_number(&self) -> Option<usize> {
+        if self.entries.is_empty() {
+            None
+        } else {
+            Some(self.first_number + self.entries.len() - 1)
+        }
+    }
+
+    /// Returns the entry with the given history number, if it's still in the list.
+    ///
+    /// # Arguments
+    ///
+    /// * `number` - The history number of the entry.
+    pub fn get(&self, number: usize) -> Option<&str> {
+        let index = number.checked_sub(self.first_number)?;
+        self.entries.get(index).map(|entry| entry.command.as_str())
+    }
+
+    /// Returns an iterator over the entries in the list, paired with their history
+    /// numbers, from oldest to newest.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = (usize, &str)> {
+        self.entries
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| (self.first_number + i, entry.command.as_str()))
+    }
+
+    /// Appends the commands in the given history file to the list; they're considered
+    /// to be already saved. A missing file is treated as empty.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the history file.
+    /// * `max_lines` - The maximum number of lines to read from the end of the file, if
+    ///   limited.
+    /// * `max_entries` - The maximum number of entries to retain, if limited.
+    pub fn read_file(
+        &mut self,
+        path: &Path,
+        max_lines: Option<usize>,
+        max_entries: Option<usize>,
+    ) -> Result<(), std::io::Error> {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e),
+        };
+
+        // Skip over any timestamps recorded alongside the commands.
+        let lines: Vec<_> = contents
+            .lines()
+            .filter(|line| !is_timestamp_comment(line))
+            .collect();
+
+        let skip_count = max_lines.map_or(0, |max_lines| lines.len().saturating_sub(max_lines));
+        for line in lines.into_iter().skip(skip_count) {
+            self.entries.push_back(Entry {
+                command: line.to_owned(),
+                saved: true,
+            });
+        }
+
+        if let Some(max_entries) = max_entries {
+            self.truncate(max_entries);
+        }
+
+        Ok(())
+    }
+
+    /// Writes all commands in the list to the given history file, replacing its contents.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the history file.
+    pub fn write_file(&mut self, path: &Path) -> Result<(), std::io::Error> {
+        let mut file = std::fs::File::create(path)?;
+        for entry in &mut self.entries {
+            writeln!(file, "{}", entry.command)?;
+            entry.saved = true;
+        }
+
+        Ok(())
+    }
+
+    /// Appends the commands that haven't yet been saved to the given history file.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the history file.
+    pub fn append_file(&mut self, path: &Path) -> Result<(), std::io::Error> {
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+
+        for entry in self.entries.iter_mut().filter(|entry| !entry.saved) {
+            writeln!(file, "{}", entry.command)?;
+            entry.saved = true;
+        }
+
+        Ok(())
+    }
+}
+
+/// Truncates the given history file so that only its last lines remain.
+///
+/// # Arguments
+///
+/// * `path` - The path to the history file.
+/// * `max_lines` - The maximum number of lines to retain.
+pub(crate) fn truncate_file(path: &Path, max_lines: usize) -> Result<(), std::io::Error> {
+    let contents = std::fs::read_to_string(path)?;
+
+    let line_count = contents.lines().count();
+    if line_count <= max_lines {
+        return Ok(());
+    }
+
+    let mut file = std::fs::File::create(path)?;
+    for line in contents.lines().skip(line_count - max_lines) {
+        writeln!(file, "{line}")?;
+    }
+
+    Ok(())
+}
+
+fn is_timestamp_comment(line: &str) -> bool {
+    line.strip_prefix('#')
+        .is_some_and(|rest| !rest.is_empty() && rest.chars().all(|c| c.is_ascii_digit()))
+}
@@ -1,5 +1,6 @@
 use brush_parser::ast::{self, CommandPrefixOrSuffixItem};
 use itertools::Itertools;
+use std::borrow::Cow;
 use std::collections::VecDeque;
 use std::io::Write;
 #[cfg(target_os = "linux")]
@@ -10,6 +11,7 @@ use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use crate::arithmetic::ExpandAndEvaluate;
+use crate::builtins::line_reader::{self, ReadTermination};
 use crate::commands::{self, CommandArg, CommandSpawnResult};
 use crate::env::{EnvironmentLookup, EnvironmentScope};
 use crate::openfiles::{OpenFile, OpenFiles};
@@ -17,7 +19,10 @@ use crate::shell::Shell;
 use crate::variables::{
     ArrayLiteral, ShellValue, ShellValueLiteral, ShellValueUnsetType, ShellVariable,
 };
-use crate::{error, expansion, extendedtests, jobs, openfiles, processes, sys, traps};
+use crate::{
+    arithmetic, error, escape, expansion, extendedtests, jobs, openfiles, processes, sys, timing,
+    traps,
+};
 
 /// Encapsulates the result of executing a command.
 #[derive(Debug, Default)]
@@ -114,6 +119,25 @@ pub struct ExecutionParameters {
     pub open_files: openfiles::OpenFiles,
     /// Policy for how to manage spawned external processes.
     pub process_group_policy: ProcessGroupPolicy,
+    /// Whether the `errexit` option is suppressed in this context (e.g., while evaluating
+    /// the condition of an `if` statement).
+    pub(crate) errexit_suppressed: bool,
+    /// File descriptors changed by the redirections of the simple command being executed,
+    /// as opposed to those inherited from enclosing commands.
+    pub(crate) redirected_fds: Vec<u32>,
+}
+
+impl ExecutionParameters {
+    /// Returns parameters equivalent to these, but with the `errexit` option suppressed.
+    fn with_errexit_suppressed(&self) -> Cow<'_, ExecutionParameters> {
+        if self.errexit_suppressed {
+            Cow::Borrowed(self)
+        } else {
+            let mut params = self.clone();
+            params.errexit_suppressed = true;
+            Cow::Owned(params)
+        }
+    }
 }
 
 #[derive(Clone, Debug, Default)]
@@ -151,9 +175,13 @@ impl Execute for ast::Program {
         params: &ExecutionParameters,
     ) -> Result<ExecutionResult, error::Error> {
         let mut result = ExecutionResult::success();
+        let mut params = Cow::Borrowed(params);
+        let mut open_files_generation = shell.open_files_generation;
 
         for command in &self.complete_commands {
-            result = command.execute(shell, params).await?;
+            result = command.execute(shell, &params).await?;
+            refresh_open_files(shell, &mut params, &mut open_files_generation)?;
+
             if result.exit_shell || result.return_from_function_or_script {
                 break;
             }
@@ -172,6 +200,8 @@ impl Execute for ast::CompoundList {
         params: &ExecutionParameters,
     ) -> Result<ExecutionResult, error::Error> {
         let mut result = ExecutionResult::success();
+        let mut params = Cow::Borrowed(params);
+        let mut open_files_generation = shell.open_files_generation;
 
         for ast::CompoundListItem(ao_list, sep) in &self.0 {
             let run_async = matches!(sep, ast::SeparatorOperator::Async);
@@ -180,8 +210,13 @@ impl Execute for ast::CompoundList {
                 // TODO: Reenable launching in child process?
                 // let job = spawn_ao_list_in_child(ao_list, shell, params).await?;
 
-                let job = spawn_ao_list_in_task(ao_list, shell, params);
+                let job = if let Some(pipeline) = try_get_external_async_pipeline(ao_list, shell) {
+                    spawn_pipeline_as_job(pipeline, shell, &params).await?
+                } else {
+                    spawn_ao_list_in_task(ao_list, shell, &params)
+                };
                 let job_formatted = job.to_pid_style_string();
+                shell.last_background_job = Some(job.to_background_id_string());
 
                 if shell.options.interactive {
                     writeln!(shell.stderr(), "{job_formatted}")?;
@@ -189,11 +224,19 @@ impl Execute for ast::CompoundList {
 
                 result = ExecutionResult::success();
             } else {
-                result = ao_list.execute(shell, params).await?;
+                result = ao_list.execute(shell, &params).await?;
+                refresh_open_files(shell, &mut params, &mut open_files_generation)?;
+            }
+
+            // Run handlers for any signals trapped along the way.
+            if let Some(trap_result) = shell.run_pending_signal_traps(&params).await? {
+                if trap_result.exit_shell {
+                    result = trap_result;
+                }
             }
 
             // Check for early return.
-            if result.return_from_function_or_script {
+            if result.exit_shell || result.return_from_function_or_script {
                 break;
             }
 
@@ -208,6 +251,98 @@ impl Execute for ast::CompoundList {
     }
 }
 
+/// Picks up any permanent changes made to the shell's open files (e.g., by `exec`) since
+/// the given execution parameters were last synchronized with them.
+pub(crate) fn refresh_open_files(
+    shell: &Shell,
+    params: &mut Cow<'_, ExecutionParameters>,
+    open_files_generation: &mut usize,
+) -> Result<(), error::Error> {
+    if shell.open_files_generation != *open_files_generation {
+        params.to_mut().open_files = shell.open_files.try_clone()?;
+        *open_files_generation = shell.open_files_generation;
+    }
+
+    Ok(())
+}
+
+/// Checks if the given and/or list consists solely of a simple command that is known
+/// to invoke an external program; if so, returns the pipeline containing it. Such a
+/// pipeline can be launched directly as a background process, without needing a
+/// separate task to run it, which lets us track the process's ID.
+fn try_get_external_async_pipeline<'a>(
+    ao_list: &'a ast::AndOrList,
+    shell: &Shell,
+) -> Option<&'a ast::Pipeline> {
+    if !ao_list.additional.is_empty() || !is_external_command_pipeline(&ao_list.first, shell) {
+        return None;
+    }
+
+    Some(&ao_list.first)
+}
+
+/// Returns whether the given pipeline consists solely of a simple command that's sure to
+/// invoke an external program.
+fn is_external_command_pipeline(pipeline: &ast::Pipeline, shell: &Shell) -> bool {
+    if pipeline.bang || pipeline.timed.is_some() {
+        return false;
+    }
+
+    let [ast::Command::Simple(simple_command)] = pipeline.seq.as_slice() else {
+        return false;
+    };
+
+    // Only consider command names that won't change on expansion.
+    let Some(name) = simple_command.word_or_name.as_ref() else {
+        return false;
+    };
+    let name = name.value.as_str();
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '+' | '/'))
+    {
+        return false;
+    }
+
+    if shell.funcs.get(name).is_some() || shell.builtins.get(name).is_some_and(|b| !b.disabled) {
+        return false;
+    }
+
+    name.contains(std::path::MAIN_SEPARATOR) || shell.find_first_executable_in_path(name).is_some()
+}
+
+async fn spawn_pipeline_as_job<'a>(
+    pipeline: &ast::Pipeline,
+    shell: &'a mut Shell,
+    params: &ExecutionParameters,
+) -> Result<&'a jobs::Job, error::Error> {
+    // Keep the process out of its own process group so it doesn't take over the terminal.
+    let mut params = params.clone();
+    params.process_group_policy = ProcessGroupPolicy::SameProcessGroup;
+
+    let spawn_results = spawn_pipeline_processes(pipeline, shell, &params).await?;
+
+    let tasks = spawn_results
+        .into_iter()
+        .map(|spawn_result| match spawn_result {
+            CommandSpawnResult::SpawnedProcess(child) => jobs::JobTask::External(child),
+            CommandSpawnResult::ImmediateExit(exit_code)
+            | CommandSpawnResult::ExitShell(exit_code)
+            | CommandSpawnResult::ReturnFromFunctionOrScript(exit_code)
+            | CommandSpawnResult::BreakLoop(exit_code)
+            | CommandSpawnResult::ContinueLoop(exit_code) => jobs::JobTask::Internal(tokio::spawn(
+                async move { Ok(ExecutionResult::new(exit_code)) },
+            )),
+        })
+        .collect::<Vec<_>>();
+
+    Ok(shell.jobs.add_as_current(jobs::Job::new(
+        tasks,
+        pipeline.to_string(),
+        jobs::JobState::Running,
+    )))
+}
+
 fn spawn_ao_list_in_task<'a>(
     ao_list: &ast::AndOrList,
     shell: &'a mut Shell,
@@ -243,9 +378,18 @@ impl Execute for ast::AndOrList {
         shell: &mut Shell,
         params: &ExecutionParameters,
     ) -> Result<ExecutionResult, error::Error> {
-        let mut result = self.first.execute(shell, params).await?;
+        // Failures of any pipeline other than the last one in the list don't trigger
+        // `errexit`.
+        let mut result = if self.additional.is_empty() {
+            self.first.execute(shell, params).await?
+        } else {
+            self.first
+                .execute(shell, &params.with_errexit_suppressed())
+                .await?
+        };
+        let mut last_executed = &self.first;
 
-        for next_ao in &self.additional {
+        for (i, next_ao) in self.additional.iter().enumerate() {
             // Check for exit/return
             if result.exit_shell || result.return_from_function_or_script {
                 break;
@@ -274,13 +418,57 @@ impl Execute for ast::AndOrList {
                 continue;
             }
 
-            result = pipeline.execute(shell, params).await?;
+            result = if i + 1 == self.additional.len() {
+                pipeline.execute(shell, params).await?
+            } else {
+                pipeline
+                    .execute(shell, &params.with_errexit_suppressed())
+                    .await?
+            };
+            last_executed = pipeline;
+        }
+
+        let last_pipeline = self.additional.last().map_or(&self.first, |ao| match ao {
+            ast::AndOr::And(p) | ast::AndOr::Or(p) => p,
+        });
+
+        if std::ptr::eq(last_executed, last_pipeline)
+            && should_exit_on_failure(shell, params, last_executed, &result)
+        {
+            result.exit_shell = true;
         }
 
         Ok(result)
     }
 }
 
+/// Checks whether the given failed (or successful) result of executing the given pipeline
+/// should cause the shell to exit, per the `errexit` option.
+fn should_exit_on_failure(
+    shell: &Shell,
+    params: &ExecutionParameters,
+    pipeline: &ast::Pipeline,
+    result: &ExecutionResult,
+) -> bool {
+    if result.is_success()
+        || !shell.options.exit_on_nonzero_command_exit
+        || params.errexit_suppressed
+        || pipeline.bang
+    {
+        return false;
+    }
+
+    // Compound commands that group other commands (e.g., brace groups or loops) don't
+    // trigger an exit themselves; the commands within them would have done so.
+    match pipeline.seq.as_slice() {
+        [ast::Command::Compound(compound, _)] => matches!(
+            compound,
+            ast::CompoundCommand::Subshell(_) | ast::CompoundCommand::Arithmetic(_)
+        ),
+        _ => true,
+    }
+}
+
 #[async_trait::async_trait]
 impl Execute for ast::Pipeline {
     async fn execute(
@@ -288,9 +476,22 @@ impl Execute for ast::Pipeline {
         shell: &mut Shell,
         params: &ExecutionParameters,
     ) -> Result<ExecutionResult, error::Error> {
+        // Commands in a negated pipeline don't trigger `errexit`.
+        let params = if self.bang {
+            params.with_errexit_suppressed()
+        } else {
+            Cow::Borrowed(params)
+        };
+
+        let stopwatch = if self.timed.is_some() {
+            Some(timing::Stopwatch::start()?)
+        } else {
+            None
+        };
+
         // Spawn all the processes required for the pipeline, connecting outputs/inputs with pipes
         // as needed.
-        let spawn_results = spawn_pipeline_processes(self, shell, params).await?;
+        let spawn_results = spawn_pipeline_processes(self, shell, &params).await?;
 
         // Wait for the processes.
         let mut result = wait_for_pipeline_processes(self, spawn_results, shell).await?;
@@ -302,10 +503,48 @@ impl Execute for ast::Pipeline {
 
         shell.last_exit_status = result.exit_code;
 
+        if let (Some(timed), Some(stopwatch)) = (&self.timed, stopwatch) {
+            report_pipeline_timing(shell, &params, timed, &stopwatch.stop()?)?;
+        }
+
         Ok(result)
     }
 }
 
+/// Reports the time taken to execute a timed pipeline to standard error, in the format
+/// given by `TIMEFORMAT` (or the POSIX format, if requested).
+fn report_pipeline_timing(
+    shell: &Shell,
+    params: &ExecutionParameters,
+    timed: &ast::PipelineTimed,
+    timing: &timing::Timing,
+) -> Result<(), error::Error> {
+    let format = match timed {
+        ast::PipelineTimed::Timed => shell
+            .env
+            .get_str("TIMEFORMAT")
+            .unwrap_or(Cow::Borrowed(timing::DEFAULT_TIME_FORMAT)),
+        ast::PipelineTimed::TimedWithPosixOutput => Cow::Borrowed(timing::POSIX_TIME_FORMAT),
+    };
+
+    // An empty format suppresses the report.
+    if format.is_empty() {
+        return Ok(());
+    }
+
+    let Some(stderr) = params.open_files.stderr() else {
+        return Ok(());
+    };
+
+    let mut stderr = stderr.try_dup()?;
+    match timing.format(format.as_ref()) {
+        Ok(report) => writeln!(stderr, "{report}")?,
+        Err(e) => writeln!(stderr, "{e}")?,
+    }
+
+    Ok(())
+}
+
 async fn spawn_pipeline_processes(
     pipeline: &ast::Pipeline,
     shell: &mut Shell,
@@ -363,12 +602,18 @@ async fn wait_for_pipeline_processes(
 ) -> Result<ExecutionResult, error::Error> {
     let mut result = ExecutionResult::success();
     let mut stopped_children = vec![];
+    let mut last_failure_exit_code = None;
+    let mut pipeline_statuses = vec![];
 
     while let Some(child) = process_spawn_results.pop_front() {
         match child.wait(!stopped_children.is_empty()).await? {
             commands::CommandWaitResult::CommandCompleted(current_result) => {
                 result = current_result;
                 shell.last_exit_status = result.exit_code;
+
+                if !result.is_success() {
+                    last_failure_exit_code = Some(result.exit_code);
+                }
             }
             commands::CommandWaitResult::CommandStopped(current_result, child) => {
                 result = current_result;
@@ -377,6 +622,35 @@ async fn wait_for_pipeline_processes(
                 stopped_children.push(jobs::JobTask::External(child));
             }
         }
+
+        pipeline_statuses.push((None, result.exit_code.to_string()));
+    }
+
+    // Record the status of each command in the pipeline. A lone compound command that runs
+    // pipelines of its own leaves behind the statuses of the last of those, as in bash.
+    let runs_nested_pipelines = match pipeline.seq.as_slice() {
+        [ast::Command::Compound(compound, _)] => !matches!(
+            compound,
+            ast::CompoundCommand::Arithmetic(_) | ast::CompoundCommand::Subshell(_)
+        ),
+        _ => false,
+    };
+    if !runs_nested_pipelines {
+        shell.env.update_or_add(
+            "PIPESTATUS",
+            ShellValueLiteral::Array(ArrayLiteral(pipeline_statuses)),
+            |_| Ok(()),
+            EnvironmentLookup::Anywhere,
+            EnvironmentScope::Global,
+        )?;
+    }
+
+    // With `pipefail`, the pipeline's status is that of the last command to fail.
+    if shell.options.return_first_failure_from_pipeline {
+        if let Some(exit_code) = last_failure_exit_code {
+            result.exit_code = exit_code;
+            shell.last_exit_status = exit_code;
+        }
     }
 
     if shell.options.interactive {
@@ -457,6 +731,12 @@ impl ExecuteInPipeline for ast::Command {
                     };
                 Ok(CommandSpawnResult::ImmediateExit(result))
             }
+            ast::Command::Coprocess(coproc) => {
+                let result = coproc
+                    .execute(pipeline_context.shell, &pipeline_context.params)
+                    .await?;
+                Ok(CommandSpawnResult::ImmediateExit(result.exit_code))
+            }
         }
     }
 }
@@ -478,11 +758,25 @@ impl Execute for ast::CompoundCommand {
                 g.execute(shell, params).await
             }
             ast::CompoundCommand::Subshell(ast::SubshellCommand(s)) => {
-                // Clone off a new subshell, and run the body of the subshell there.
+                // Clone off a new subshell, and run the body of the subshell there. The
+                // subshell doesn't inherit the EXIT trap, but runs any it registers itself.
                 let mut subshell = shell.clone();
-                s.execute(&mut subshell, params).await
+                subshell.traps.handlers.remove(&traps::TrapSignal::Exit);
+
+                // Errors end the subshell, but don't propagate to this shell.
+                if let Err(e) = s.execute(&mut subshell, params).await {
+                    tracing::error!("error: {:#}", e);
+                    subshell.last_exit_status = 1;
+                }
+
+                subshell.run_exit_trap().await?;
+
+                // N.B. Exiting or returning from the subshell doesn't affect this shell; only
+                // its exit status is reflected.
+                Ok(ExecutionResult::new(subshell.last_exit_status))
             }
             ast::CompoundCommand::ForClause(f) => f.execute(shell, params).await,
+            ast::CompoundCommand::SelectClause(s) => s.execute(shell, params).await,
             ast::CompoundCommand::CaseClause(c) => c.execute(shell, params).await,
             ast::CompoundCommand::IfClause(i) => i.execute(shell, params).await,
             ast::CompoundCommand::WhileClause(w) => {
@@ -516,11 +810,13 @@ impl Execute for ast::ForClauseCommand {
 
             for value in expanded_values {
                 if shell.options.print_commands_and_arguments {
-                    shell.trace_command(std::format!(
-                        "for {} in {}",
-                        self.variable_name,
-                        unexpanded_values.iter().join(" ")
-                    ))?;
+                    shell
+                        .trace_command(std::format!(
+                            "for {} in {}",
+                            self.variable_name,
+                            unexpanded_values.iter().join(" ")
+                        ))
+                        .await?;
                 }
 
                 // Update the variable.
@@ -560,6 +856,202 @@ impl Execute for ast::ForClauseCommand {
     }
 }
 
+#[async_trait::async_trait]
+impl Execute for ast::SelectClauseCommand {
+    async fn execute(
+        &self,
+        shell: &mut Shell,
+        params: &ExecutionParameters,
+    ) -> Result<ExecutionResult, error::Error> {
+        // Expand all values, with splitting enabled; without any, the positional parameters
+        // are offered.
+        let values = if let Some(unexpanded_values) = &self.values {
+            let mut expanded_values = vec![];
+            for value in unexpanded_values {
+                let mut expanded = expansion::full_expand_and_split_word(shell, value).await?;
+                expanded_values.append(&mut expanded);
+            }
+            expanded_values
+        } else {
+            shell.positional_parameters.clone()
+        };
+
+        let mut result = ExecutionResult::success();
+
+        let mut show_menu = true;
+        while !values.is_empty() {
+            // Reaching the end of input ends the loop.
+            let Some(selection) = read_selection(shell, params, values.as_slice(), show_menu)?
+            else {
+                result = ExecutionResult::new(1);
+                break;
+            };
+            show_menu = false;
+
+            // Update the variable.
+            shell.env.update_or_add(
+                &self.variable_name,
+                ShellValueLiteral::Scalar(selection),
+                |_| Ok(()),
+                EnvironmentLookup::Anywhere,
+                EnvironmentScope::Global,
+            )?;
+
+            result = self.body.0.execute(shell, params).await?;
+            if result.return_from_function_or_script {
+                break;
+            }
+
+            if let Some(continue_count) = &result.continue_loop {
+                if *continue_count > 0 {
+                    return error::unimp("continue with count > 0");
+                }
+
+                result.continue_loop = None;
+            }
+            if let Some(break_count) = &result.break_loop {
+                if *break_count == 0 {
+                    result.break_loop = None;
+                } else {
+                    result.break_loop = Some(*break_count - 1);
+                }
+                break;
+            }
+        }
+
+        shell.last_exit_status = result.exit_code;
+        Ok(result)
+    }
+}
+
+/// Prompts for and reads a selection for a select loop, storing the line read in `REPLY`.
+/// Returns the selected value, which is empty if the input doesn't identify one of the
+/// values, or `None` if the end of input was reached.
+fn read_selection(
+    shell: &mut Shell,
+    params: &ExecutionParameters,
+    values: &[String],
+    mut show_menu: bool,
+) -> Result<Option<String>, error::Error> {
+    let (Some(stdin), Some(stderr)) = (params.open_files.stdin(), params.open_files.stderr())
+    else {
+        return Ok(None);
+    };
+    let mut stdin = stdin.try_dup()?;
+    let mut stderr = stderr.try_dup()?;
+
+    let columns = shell
+        .env
+        .get_str("COLUMNS")
+        .and_then(|columns| columns.parse().ok())
+        .unwrap_or(80);
+
+    let options = line_reader::ReadLineOptions {
+        delimiter: Some('\n'),
+        char_limit: None,
+        backslash_escapes: true,
+        timeout: None,
+    };
+
+    loop {
+        if show_menu {
+            write_select_menu(&mut stderr, values, columns)?;
+        }
+
+        let prompt = shell.env.get_str("PS3").unwrap_or(Cow::Borrowed("#? "));
+        write!(stderr, "{prompt}")?;
+        stderr.flush()?;
+
+        let (line, termination) = line_reader::read_line(&mut stdin, &options)?;
+        if !matches!(termination, ReadTermination::Delimiter) {
+            // As in bash, the prompt's line is ended on standard output.
+            if let Some(stdout) = params.open_files.stdout() {
+                writeln!(stdout.try_dup()?)?;
+            }
+            return Ok(None);
+        }
+
+        let reply = line_reader::unescape(line.as_str());
+        shell.env.update_or_add(
+            "REPLY",
+            ShellValueLiteral::Scalar(reply.clone()),
+            |_| Ok(()),
+            EnvironmentLookup::Anywhere,
+            EnvironmentScope::Global,
+        )?;
+
+        // An empty line redisplays the menu.
+        if reply.is_empty() {
+            show_menu = true;
+            continue;
+        }
+
+        let selection = reply
+            .trim()
+            .parse::<usize>()
+            .ok()
+            .and_then(|n| n.checked_sub(1))
+            .and_then(|i| values.get(i))
+            .cloned()
+            .unwrap_or_default();
+
+        return Ok(Some(selection));
+    }
+}
+
+/// Writes the numbered menu of values for a select loop, laid out in as many columns as
+/// fit in the given width, filling each column before moving on to the next.
+fn write_select_menu(
+    writer: &mut impl Write,
+    values: &[String],
+    columns: usize,
+) -> Result<(), error::Error> {
+    const TAB_SIZE: usize = 8;
+
+    let index_len = values.len().to_string().len();
+    let max_value_len = values.iter().map(|v| v.chars().count()).max().unwrap_or(0);
+    let column_width = max_value_len + index_len + 4;
+
+    let rows = values.len().div_ceil((columns / column_width).max(1));
+    let rows = if rows == 1 { values.len() } else { rows };
+    let first_index_len = rows.to_string().len();
+
+    for row in 0..rows {
+        let mut line = String::new();
+        let mut index = row;
+        let mut pos = 0;
+
+        loop {
+            let width = if pos == 0 { first_index_len } else { index_len };
+            let value = &values[index];
+            line.push_str(std::format!("{:>width$}) {value}", index + 1).as_str());
+
+            index += rows;
+            if index >= values.len() {
+                break;
+            }
+
+            // Pad out to the next column, preferring tabs.
+            let mut from = pos + width + 2 + value.chars().count();
+            let to = pos + column_width;
+            while from < to {
+                if to / TAB_SIZE > from / TAB_SIZE {
+                    line.push('\t');
+                    from += TAB_SIZE - from % TAB_SIZE;
+                } else {
+                    line.push(' ');
+                    from += 1;
+                }
+            }
+            pos = to;
+        }
+
+        writeln!(writer, "{line}")?;
+    }
+
+    Ok(())
+}
+
 #[async_trait::async_trait]
 impl Execute for ast::CaseClauseCommand {
     async fn execute(
@@ -570,34 +1062,63 @@ impl Execute for ast::CaseClauseCommand {
         // N.B. One would think it makes sense to trace the expanded value being switched
         // on, but that's not it.
         if shell.options.print_commands_and_arguments {
-            shell.trace_command(std::format!("case {} in", &self.value))?;
+            shell
+                .trace_command(std::format!("case {} in", &self.value))
+                .await?;
         }
 
         let expanded_value = expansion::basic_expand_word(shell, &self.value).await?;
 
+        let mut result = ExecutionResult::success();
+        let mut force_execute_next_case = false;
+
         for case in &self.cases {
-            let mut matches = false;
-
-            for pattern in &case.patterns {
-                let expanded_pattern = expansion::basic_expand_pattern(shell, pattern).await?;
-                if expanded_pattern
-                    .exactly_matches(expanded_value.as_str(), shell.options.extended_globbing)?
-                {
-                    matches = true;
-                    break;
+            if force_execute_next_case {
+                force_execute_next_case = false;
+            } else {
+                let mut matches = false;
+
+                for pattern in &case.patterns {
+                    let expanded_pattern = expansion::basic_expand_pattern(shell, pattern)
+                        .await?
+                        .set_case_insensitive(shell.options.case_insensitive_conditionals);
+                    if expanded_pattern
+                        .exactly_matches(expanded_value.as_str(), shell.options.extended_globbing)?
+                    {
+                        matches = true;
+                        break;
+                    }
+                }
+
+                if !matches {
+                    continue;
                 }
             }
 
-            if matches {
-                if let Some(case_cmd) = &case.cmd {
-                    return case_cmd.execute(shell, params).await;
-                } else {
-                    break;
+            if let Some(case_cmd) = &case.cmd {
+                result = case_cmd.execute(shell, params).await?;
+            } else {
+                result = ExecutionResult::success();
+            }
+
+            // Don't fall through past anything that interrupts control flow.
+            if result.exit_shell
+                || result.return_from_function_or_script
+                || result.break_loop.is_some()
+                || result.continue_loop.is_some()
+            {
+                return Ok(result);
+            }
+
+            match case.post_action {
+                ast::CaseItemPostAction::ExitCase => break,
+                ast::CaseItemPostAction::UnconditionallyExecuteNextCaseItem => {
+                    force_execute_next_case = true;
                 }
+                ast::CaseItemPostAction::ContinueEvaluatingCases => (),
             }
         }
 
-        let result = ExecutionResult::success();
         shell.last_exit_status = result.exit_code;
 
         Ok(result)
@@ -611,7 +1132,10 @@ impl Execute for ast::IfClauseCommand {
         shell: &mut Shell,
         params: &ExecutionParameters,
     ) -> Result<ExecutionResult, error::Error> {
-        let condition = self.condition.execute(shell, params).await?;
+        let condition = self
+            .condition
+            .execute(shell, &params.with_errexit_suppressed())
+            .await?;
 
         if condition.is_success() {
             return self.then.execute(shell, params).await;
@@ -621,7 +1145,9 @@ impl Execute for ast::IfClauseCommand {
             for else_clause in elses {
                 match &else_clause.condition {
                     Some(else_condition) => {
-                        let else_condition_result = else_condition.execute(shell, params).await?;
+                        let else_condition_result = else_condition
+                            .execute(shell, &params.with_errexit_suppressed())
+                            .await?;
                         if else_condition_result.is_success() {
                             return else_clause.body.execute(shell, params).await;
                         }
@@ -657,7 +1183,9 @@ impl Execute for (WhileOrUntil, &ast::WhileOrUntilClauseCommand) {
         let mut result = ExecutionResult::success();
 
         loop {
-            let condition_result = test_condition.execute(shell, params).await?;
+            let condition_result = test_condition
+                .execute(shell, &params.with_errexit_suppressed())
+                .await?;
 
             if condition_result.is_success() != is_while {
                 break;
@@ -737,6 +1265,22 @@ impl Execute for ast::ArithmeticForClauseCommand {
                 break;
             }
 
+            if let Some(continue_count) = &result.continue_loop {
+                if *continue_count > 0 {
+                    return error::unimp("continue with count > 0");
+                }
+
+                result.continue_loop = None;
+            }
+            if let Some(break_count) = &result.break_loop {
+                if *break_count == 0 {
+                    result.break_loop = None;
+                } else {
+                    result.break_loop = Some(*break_count - 1);
+                }
+                break;
+            }
+
             if let Some(updater) = &self.updater {
                 updater.eval(shell, true).await?;
             }
@@ -747,6 +1291,97 @@ impl Execute for ast::ArithmeticForClauseCommand {
     }
 }
 
+#[async_trait::async_trait]
+impl Execute for ast::CoprocessCommand {
+    async fn execute(
+        &self,
+        shell: &mut Shell,
+        params: &ExecutionParameters,
+    ) -> Result<ExecutionResult, error::Error> {
+        let name = self.name.as_deref().unwrap_or("COPROC");
+
+        // Connect the coprocess's standard input and output to pipes.
+        let (coproc_stdin_reader, coproc_stdin_writer) = sys::pipes::pipe()?;
+        let (coproc_stdout_reader, coproc_stdout_writer) = sys::pipes::pipe()?;
+
+        let mut coproc_params = params.clone();
+        coproc_params.process_group_policy = ProcessGroupPolicy::SameProcessGroup;
+        coproc_params
+            .open_files
+            .files
+            .insert(0, OpenFile::PipeReader(coproc_stdin_reader));
+        coproc_params
+            .open_files
+            .files
+            .insert(1, OpenFile::PipeWriter(coproc_stdout_writer));
+
+        let pipeline = ast::Pipeline {
+            timed: None,
+            bang: false,
+            seq: vec![self.body.as_ref().clone()],
+        };
+
+        // N.B. A coprocess that doesn't run an external command runs within the shell; the
+        // job's synthetic process ID stands in for the subshell's process ID.
+        let job = if is_external_command_pipeline(&pipeline, shell) {
+            spawn_pipeline_as_job(&pipeline, shell, &coproc_params).await?
+        } else {
+            let mut subshell = shell.clone();
+            subshell.options.interactive = false;
+
+            let join_handle =
+                tokio::spawn(async move { pipeline.execute(&mut subshell, &coproc_params).await });
+
+            shell.jobs.add_as_current(jobs::Job::new(
+                [jobs::JobTask::Internal(join_handle)],
+                self.to_string(),
+                jobs::JobState::Running,
+            ))
+        };
+        let pid = job.get_background_pid();
+        shell.last_background_job = Some(job.to_background_id_string());
+
+        // Make the shell's ends of the pipes available as file descriptors, and record them in
+        // the coprocess's array variable.
+        let mut open_files = shell.open_files.try_clone()?;
+        let read_fd = find_available_fd(&open_files)?;
+        open_files
+            .files
+            .insert(read_fd, OpenFile::PipeReader(coproc_stdout_reader));
+        let write_fd = find_available_fd(&open_files)?;
+        open_files
+            .files
+            .insert(write_fd, OpenFile::PipeWriter(coproc_stdin_writer));
+        shell.replace_open_files(open_files);
+
+        shell.env.update_or_add(
+            name,
+            ShellValueLiteral::Array(ArrayLiteral(vec![
+                (None, read_fd.to_string()),
+                (None, write_fd.to_string()),
+            ])),
+            |_| Ok(()),
+            EnvironmentLookup::Anywhere,
+            EnvironmentScope::Global,
+        )?;
+
+        if let Some(pid) = pid {
+            shell.env.update_or_add(
+                std::format!("{name}_PID").as_str(),
+                ShellValueLiteral::Scalar(pid.to_string()),
+                |_| Ok(()),
+                EnvironmentLookup::Anywhere,
+                EnvironmentScope::Global,
+            )?;
+        }
+
+        let result = ExecutionResult::success();
+        shell.last_exit_status = result.exit_code;
+
+        Ok(result)
+    }
+}
+
 #[async_trait::async_trait]
 impl Execute for ast::FunctionDefinition {
     async fn execute(
@@ -754,9 +1389,10 @@ impl Execute for ast::FunctionDefinition {
         shell: &mut Shell,
         _params: &ExecutionParameters,
     ) -> Result<ExecutionResult, error::Error> {
+        let line = shell.current_line_number;
         shell
             .funcs
-            .update(self.fname.clone(), Arc::new(self.clone()));
+            .update(self.fname.clone(), Arc::new(self.clone()), line);
 
         let result = ExecutionResult::success();
         shell.last_exit_status = result.exit_code;
@@ -791,6 +1427,11 @@ impl ExecuteInPipeline for ast::SimpleCommand {
         // Set up pipelining.
         setup_pipeline_redirection(&mut params.open_files, context)?;
 
+        // Keep track of the descriptors changed by this command's own redirections.
+        params.redirected_fds.clear();
+        #[cfg(unix)]
+        let mut inherited_files = None;
+
         for item in prefix_items
             .0
             .iter()
@@ -799,6 +1440,11 @@ impl ExecuteInPipeline for ast::SimpleCommand {
         {
             match item {
                 CommandPrefixOrSuffixItem::IoRedirect(redirect) => {
+                    #[cfg(unix)]
+                    if inherited_files.is_none() {
+                        inherited_files = Some(open_file_identities(&params.open_files));
+                    }
+
                     if setup_redirect(&mut params.open_files, context.shell, redirect)
                         .await?
                         .is_none()
@@ -851,35 +1497,18 @@ impl ExecuteInPipeline for ast::SimpleCommand {
                     }
                 }
                 CommandPrefixOrSuffixItem::Word(arg) => {
-                    let mut next_args =
+                    let next_args =
                         expansion::full_expand_and_split_word(context.shell, arg).await?;
 
                     if args.is_empty() {
                         if let Some(cmd_name) = next_args.first() {
-                            if let Some(alias_value) = context.shell.aliases.get(cmd_name.as_str())
-                            {
-                                //
-                                // TODO: This is a total hack; aliases are supposed to be handled
-                                // much earlier in the process.
-                                //
-                                let mut alias_pieces: Vec<_> = alias_value
-                                    .split_ascii_whitespace()
-                                    .map(|i| i.to_owned())
-                                    .collect();
-
-                                next_args.remove(0);
-                                alias_pieces.append(&mut next_args);
-
-                                next_args = alias_pieces;
-                            }
-
                             // Check if we're going to be invoking a special declaration builtin.
                             // That will change how we parse and process
                             // args.
                             if context
                                 .shell
                                 .builtins
-                                .get(next_args[0].as_str())
+                                .get(cmd_name.as_str())
                                 .is_some_and(|r| r.declaration_builtin)
                             {
                                 invoking_declaration_builtin = true;
@@ -893,6 +1522,18 @@ impl ExecuteInPipeline for ast::SimpleCommand {
             }
         }
 
+        #[cfg(unix)]
+        if let Some(inherited_files) = inherited_files {
+            let current_files = open_file_identities(&params.open_files);
+            params.redirected_fds = inherited_files
+                .keys()
+                .chain(current_files.keys())
+                .filter(|fd| inherited_files.get(*fd) != current_files.get(*fd))
+                .unique()
+                .copied()
+                .collect();
+        }
+
         // If we have a command, then execute it.
         if let Some(CommandArg::String(cmd_name)) = args.first().cloned() {
             // Push a new ephemeral environment scope for the duration of the command. We'll
@@ -914,7 +1555,12 @@ impl ExecuteInPipeline for ast::SimpleCommand {
             if context.shell.options.print_commands_and_arguments {
                 context
                     .shell
-                    .trace_command(args.iter().map(|arg| arg.to_string()).join(" "))?;
+                    .trace_command(
+                        args.iter()
+                            .map(|arg| escape::quote_for_trace(arg.to_string().as_str()))
+                            .join(" "),
+                    )
+                    .await?;
             }
 
             // TODO: This is adding more complexity here; should be factored out into an appropriate
@@ -932,6 +1578,7 @@ impl ExecuteInPipeline for ast::SimpleCommand {
                     let handler_params = ExecutionParameters {
                         open_files: params.open_files.clone(),
                         process_group_policy: ProcessGroupPolicy::SameProcessGroup,
+                        ..Default::default()
                     };
 
                     let full_cmd = args.iter().map(|arg| arg.to_string()).join(" ");
@@ -945,18 +1592,17 @@ impl ExecuteInPipeline for ast::SimpleCommand {
                         EnvironmentScope::Global,
                     )?;
 
-                    context.shell.traps.handler_depth += 1;
-
                     // TODO: Discard result?
                     let _ = context
                         .shell
-                        .run_string(debug_trap_handler, &handler_params)
+                        .run_trap_handler(debug_trap_handler, &handler_params)
                         .await?;
-
-                    context.shell.traps.handler_depth -= 1;
                 }
             }
 
+            // Once the command has run, `$_` is updated to hold its last argument.
+            let last_arg = args.last().map(|arg| arg.to_string()).unwrap_or_default();
+
             let cmd_context = commands::ExecutionContext {
                 shell: context.shell,
                 command_name: cmd_name,
@@ -976,25 +1622,62 @@ impl ExecuteInPipeline for ast::SimpleCommand {
             // TODO: jobs: do we need to move self back to foreground on error here?
             context.shell.env.pop_scope(EnvironmentScope::Command)?;
 
+            set_last_argument(context.shell, last_arg)?;
+
             execution_result
         } else {
-            // No command to run; assignments must be applied to this shell.
+            // No command to run; assignments must be applied to this shell. The status is
+            // that of the last command substitution performed while expanding them, if any.
+            context.shell.last_command_substitution_status = None;
             for assignment in assignments {
-                apply_assignment(
+                let result = apply_assignment(
                     assignment,
                     context.shell,
                     false,
                     None,
                     EnvironmentScope::Global,
                 )
-                .await?;
+                .await;
+
+                if let Err(error::Error::ReadonlyVariable) = result {
+                    let (ast::AssignmentName::VariableName(name)
+                    | ast::AssignmentName::ArrayElementName(name, _)) = &assignment.name;
+                    writeln!(context.shell.stderr(), "{name}: readonly variable")?;
+                    return Ok(CommandSpawnResult::ImmediateExit(1));
+                }
+
+                result?;
             }
 
-            Ok(CommandSpawnResult::ImmediateExit(0))
+            set_last_argument(context.shell, String::new())?;
+
+            let exit_code = context
+                .shell
+                .last_command_substitution_status
+                .take()
+                .unwrap_or(0);
+            Ok(CommandSpawnResult::ImmediateExit(exit_code))
         }
     }
 }
 
+/// Updates `$_` to hold the last argument of the most recently run simple command. As in
+/// bash, a readonly `$_` is left as-is without failing the command.
+fn set_last_argument(shell: &mut Shell, value: String) -> Result<(), error::Error> {
+    let result = shell.env.update_or_add(
+        "_",
+        ShellValueLiteral::Scalar(value),
+        |_| Ok(()),
+        EnvironmentLookup::Anywhere,
+        EnvironmentScope::Global,
+    );
+
+    match result {
+        Err(error::Error::ReadonlyVariable) => Ok(()),
+        result => result,
+    }
+}
+
 async fn expand_assignment(
     shell: &mut Shell,
     assignment: &ast::Assignment,
@@ -1033,7 +1716,7 @@ async fn expand_assignment_value(
 ) -> Result<ast::AssignmentValue, error::Error> {
     let expanded = match value {
         ast::AssignmentValue::Scalar(s) => {
-            let expanded_word = expansion::basic_expand_word(shell, s).await?;
+            let expanded_word = expansion::basic_expand_assignment_value(shell, s).await?;
             ast::AssignmentValue::Scalar(ast::Word {
                 value: expanded_word,
             })
@@ -1088,10 +1771,16 @@ async fn apply_assignment(
         }
     };
 
+    // Assignments to a name reference update the variable it refers to.
+    let variable_name = shell
+        .env
+        .resolve_nameref(variable_name, EnvironmentLookup::Anywhere)?
+        .into_owned();
+
     // Expand the values.
     let new_value = match &assignment.value {
         ast::AssignmentValue::Scalar(unexpanded_value) => {
-            let value = expansion::basic_expand_word(shell, unexpanded_value).await?;
+            let value = expansion::basic_expand_assignment_value(shell, unexpanded_value).await?;
             ShellValueLiteral::Scalar(value)
         }
         ast::AssignmentValue::Array(unexpanded_values) => {
@@ -1121,21 +1810,40 @@ async fn apply_assignment(
 
     if shell.options.print_commands_and_arguments {
         let op = if assignment.append { "+=" } else { "=" };
-        shell.trace_command(std::format!("{}{}{}", assignment.name, op, new_value))?;
+        let traced_value = match &new_value {
+            ShellValueLiteral::Scalar(s) => escape::quote_for_trace(s),
+            ShellValueLiteral::Array(_) => new_value.to_string(),
+        };
+        shell
+            .trace_command(std::format!("{}{op}{traced_value}", assignment.name))
+            .await?;
     }
 
+    // Values assigned to variables with the integer attribute are evaluated arithmetically.
+    let new_value = if shell
+        .env
+        .get(variable_name.as_str())
+        .is_some_and(|(_, var)| var.is_treated_as_integer())
+    {
+        arithmetic::eval_integer_assignment(shell, new_value).await
+    } else {
+        new_value
+    };
+
     // See if we need to eval an array index.
     if let Some(idx) = &array_index {
-        let will_be_indexed_array = if let Some((_, existing_value)) =
-            shell.env.get(variable_name.as_str())
-        {
-            matches!(
-                existing_value.value(),
-                ShellValue::IndexedArray(_) | ShellValue::Unset(ShellValueUnsetType::IndexedArray)
-            )
-        } else {
-            true
-        };
+        let will_be_indexed_array =
+            if let Some((_, existing_value)) = shell.env.get(variable_name.as_str()) {
+                // N.B. Assigning to an element of a string variable converts it to an indexed array.
+                matches!(
+                    existing_value.value(),
+                    ShellValue::IndexedArray(_)
+                        | ShellValue::Unset(ShellValueUnsetType::IndexedArray)
+                        | ShellValue::String(_)
+                )
+            } else {
+                true
+            };
 
         if will_be_indexed_array {
             array_index = Some(
@@ -1147,6 +1855,15 @@ async fn apply_assignment(
         }
     }
 
+    shell.on_variable_assigned(variable_name.as_str());
+
+    // Scalar assignments to dynamic variables are handled by the variables themselves.
+    if let (None, ShellValueLiteral::Scalar(s)) = (&array_index, &new_value) {
+        if shell.try_assign_dynamic_variable(variable_name.as_str(), s.as_str()) {
+            return Ok(());
+        }
+    }
+
     // See if we can find an existing value associated with the variable.
     if let Some((existing_value_scope, existing_value)) = shell.env.get_mut(variable_name.as_str())
     {
@@ -1227,6 +1944,17 @@ fn setup_pipeline_redirection(
     Ok(())
 }
 
+/// Identifies the underlying file of each of the given open files, allowing changes made to
+/// them by redirections to be detected.
+#[cfg(unix)]
+fn open_file_identities(open_files: &OpenFiles) -> std::collections::HashMap<u32, Option<i32>> {
+    open_files
+        .files
+        .iter()
+        .map(|(fd, file)| (*fd, file.as_raw_fd().ok()))
+        .collect()
+}
+
 #[allow(clippy::too_many_lines)]
 pub(crate) async fn setup_redirect<'a>(
     open_files: &'a mut OpenFiles,
@@ -1237,113 +1965,118 @@ pub(crate) async fn setup_redirect<'a>(
         ast::IoRedirect::OutputAndError(f, append) => {
             let mut expanded_fields = expansion::full_expand_and_split_word(shell, f).await?;
             if expanded_fields.len() != 1 {
-                return Err(error::Error::InvalidRedirection);
+                return Err(error::Error::AmbiguousRedirect(f.value.clone()));
             }
 
-            let expanded_file_path: PathBuf =
-                shell.get_absolute_path(Path::new(expanded_fields.remove(0).as_str()));
-
-            let opened_file = std::fs::File::options()
-                .create(true)
-                .write(true)
-                .truncate(!*append)
-                .append(*append)
-                .open(expanded_file_path.as_path())
-                .map_err(|err| {
-                    error::Error::RedirectionFailure(
-                        expanded_file_path.to_string_lossy().to_string(),
-                        err,
-                    )
-                })?;
-
-            let stdout_file = OpenFile::File(opened_file);
-            let stderr_file = stdout_file.try_dup()?;
-
-            open_files.files.insert(1, stdout_file);
-            open_files.files.insert(2, stderr_file);
-
-            Ok(Some(1))
+            let expanded_file_path = expanded_fields.remove(0);
+            setup_output_and_error_redirect(open_files, shell, expanded_file_path.as_str(), *append)
         }
-        ast::IoRedirect::File(specified_fd_num, kind, target) => {
-            let fd_num;
+        ast::IoRedirect::File(specified_fd, kind, target) => {
             let target_file;
             match target {
                 ast::IoFileRedirectTarget::Filename(f) => {
                     let mut options = std::fs::File::options();
 
-                    let default_fd_if_unspecified = get_default_fd_for_redirect_kind(kind);
-                    match kind {
+                    let is_duplication = match kind {
                         ast::IoFileRedirectKind::Read => {
                             options.read(true);
+                            false
                         }
                         ast::IoFileRedirectKind::Write => {
                             // TODO: honor noclobber options
                             options.create(true);
                             options.write(true);
                             options.truncate(true);
+                            false
                         }
                         ast::IoFileRedirectKind::Append => {
                             options.create(true);
                             options.append(true);
+                            false
                         }
                         ast::IoFileRedirectKind::ReadAndWrite => {
                             options.create(true);
                             options.read(true);
                             options.write(true);
+                            false
                         }
                         ast::IoFileRedirectKind::Clobber => {
                             options.create(true);
                             options.write(true);
                             options.truncate(true);
+                            false
                         }
-                        ast::IoFileRedirectKind::DuplicateInput => {
-                            options.read(true);
-                        }
-                        ast::IoFileRedirectKind::DuplicateOutput => {
-                            options.create(true);
-                            options.write(true);
-                        }
-                    }
-
-                    fd_num = specified_fd_num.unwrap_or(default_fd_if_unspecified);
+                        ast::IoFileRedirectKind::DuplicateInput
+                        | ast::IoFileRedirectKind::DuplicateOutput => true,
+                    };
 
                     let mut expanded_fields =
                         expansion::full_expand_and_split_word(shell, f).await?;
 
                     if expanded_fields.len() != 1 {
-                        return Err(error::Error::InvalidRedirection);
+                        return Err(error::Error::AmbiguousRedirect(f.value.clone()));
                     }
 
-                    let expanded_file_path: PathBuf =
-                        shell.get_absolute_path(Path::new(expanded_fields.remove(0).as_str()));
-
-                    let opened_file =
-                        options.open(expanded_file_path.as_path()).map_err(|err| {
-                            error::Error::RedirectionFailure(
-                                expanded_file_path.to_string_lossy().to_string(),
-                                err,
-                            )
-                        })?;
-                    target_file = OpenFile::File(opened_file);
-                }
-                ast::IoFileRedirectTarget::Fd(fd) => {
-                    let default_fd_if_unspecified = match kind {
-                        ast::IoFileRedirectKind::DuplicateInput => 0,
-                        ast::IoFileRedirectKind::DuplicateOutput => 1,
-                        _ => {
-                            return error::unimp("unexpected redirect kind");
+                    let expanded_file_path = expanded_fields.remove(0);
+
+                    if is_duplication {
+                        match parse_duplication_target(expanded_file_path.as_str()) {
+                            DuplicationTarget::Close => {
+                                return close_redirected_fd(
+                                    open_files,
+                                    shell,
+                                    specified_fd.as_ref(),
+                                    get_default_fd_for_redirect_kind(kind),
+                                );
+                            }
+                            DuplicationTarget::Duplicate(source_fd) => {
+                                target_file = duplicate_open_fd(open_files, source_fd)?;
+                            }
+                            DuplicationTarget::Move(source_fd) => {
+                                target_file = duplicate_open_fd(open_files, source_fd)?;
+                                open_files.files.remove(&source_fd);
+                            }
+                            DuplicationTarget::File(path) => {
+                                // `>&word` is a synonym for `&>word`, so long as no descriptor
+                                // was specified.
+                                if matches!(kind, ast::IoFileRedirectKind::DuplicateOutput)
+                                    && specified_fd.is_none()
+                                {
+                                    return setup_output_and_error_redirect(
+                                        open_files, shell, path, false,
+                                    );
+                                }
+
+                                return Err(error::Error::InvalidRedirection);
+                            }
                         }
-                    };
-
-                    fd_num = specified_fd_num.unwrap_or(default_fd_if_unspecified);
-
-                    if let Some(f) = open_files.files.get(fd) {
-                        target_file = f.try_dup()?;
+                    } else if let Some(special_file) =
+                        open_special_redirect_target(open_files, expanded_file_path.as_str())?
+                    {
+                        target_file = special_file;
                     } else {
-                        tracing::error!("{}: Bad file descriptor", fd);
-                        return Ok(None);
+                        let expanded_file_path: PathBuf =
+                            shell.get_absolute_path(Path::new(expanded_file_path.as_str()));
+
+                        let opened_file =
+                            options.open(expanded_file_path.as_path()).map_err(|err| {
+                                error::Error::RedirectionFailure(
+                                    expanded_file_path.to_string_lossy().to_string(),
+                                    err,
+                                )
+                            })?;
+                        target_file = OpenFile::File(opened_file);
                     }
                 }
+                ast::IoFileRedirectTarget::Fd(fd) => match kind {
+                    ast::IoFileRedirectKind::DuplicateInput
+                    | ast::IoFileRedirectKind::DuplicateOutput => {
+                        target_file = duplicate_open_fd(open_files, *fd)?;
+                    }
+                    _ => {
+                        return error::unimp("unexpected redirect kind");
+                    }
+                },
                 ast::IoFileRedirectTarget::ProcessSubstitution(substitution_kind, subshell_cmd) => {
                     match kind {
                         ast::IoFileRedirectKind::Read
@@ -1360,43 +2093,303 @@ pub(crate) async fn setup_redirect<'a>(
 
                             target_file = substitution_file.try_dup()?;
                             open_files.files.insert(substitution_fd, substitution_file);
-
-                            fd_num = specified_fd_num
-                                .unwrap_or_else(|| get_default_fd_for_redirect_kind(kind));
                         }
                         _ => return error::unimp("invalid process substitution"),
                     }
                 }
             }
 
-            open_files.files.insert(fd_num, target_file);
-            Ok(Some(fd_num))
+            install_redirected_fd(
+                open_files,
+                shell,
+                specified_fd.as_ref(),
+                get_default_fd_for_redirect_kind(kind),
+                target_file,
+            )
         }
-        ast::IoRedirect::HereDocument(fd_num, io_here) => {
-            // If not specified, default to stdin (fd 0).
-            let fd_num = fd_num.unwrap_or(0);
-
-            // TODO: figure out if we need to expand?
-            let io_here_doc = io_here.doc.flatten();
+        ast::IoRedirect::HereDocument(fd, io_here) => {
+            // The body is only expanded if no part of the delimiter was quoted.
+            let here_end = io_here.here_end.flatten();
+            let io_here_doc = if here_end.contains(['\'', '"', '\\']) {
+                io_here.doc.flatten()
+            } else {
+                expansion::basic_expand_here_document(shell, io_here.doc.flatten().as_str()).await?
+            };
 
             let f = setup_open_file_with_contents(io_here_doc.as_str())?;
 
-            open_files.files.insert(fd_num, f);
-            Ok(Some(fd_num))
-        }
-        ast::IoRedirect::HereString(fd_num, word) => {
             // If not specified, default to stdin (fd 0).
-            let fd_num = fd_num.unwrap_or(0);
-
+            install_redirected_fd(open_files, shell, fd.as_ref(), 0, f)
+        }
+        ast::IoRedirect::HereString(fd, word) => {
             let mut expanded_word = expansion::basic_expand_word(shell, word).await?;
             expanded_word.push('\n');
 
             let f = setup_open_file_with_contents(expanded_word.as_str())?;
 
-            open_files.files.insert(fd_num, f);
-            Ok(Some(fd_num))
+            // If not specified, default to stdin (fd 0).
+            install_redirected_fd(open_files, shell, fd.as_ref(), 0, f)
+        }
+    }
+}
+
+/// Redirects both standard output and standard error to the given file.
+fn setup_output_and_error_redirect(
+    open_files: &mut OpenFiles,
+    shell: &Shell,
+    path: &str,
+    append: bool,
+) -> Result<Option<u32>, error::Error> {
+    let stdout_file = if let Some(special_file) = open_special_redirect_target(open_files, path)? {
+        special_file
+    } else {
+        let expanded_file_path: PathBuf = shell.get_absolute_path(Path::new(path));
+
+        let opened_file = std::fs::File::options()
+            .create(true)
+            .write(true)
+            .truncate(!append)
+            .append(append)
+            .open(expanded_file_path.as_path())
+            .map_err(|err| {
+                error::Error::RedirectionFailure(
+                    expanded_file_path.to_string_lossy().to_string(),
+                    err,
+                )
+            })?;
+
+        OpenFile::File(opened_file)
+    };
+    let stderr_file = stdout_file.try_dup()?;
+
+    open_files.files.insert(1, stdout_file);
+    open_files.files.insert(2, stderr_file);
+
+    Ok(Some(1))
+}
+
+/// The expanded target of a `<&` or `>&` redirection.
+enum DuplicationTarget<'a> {
+    /// `-`: the redirected descriptor is closed.
+    Close,
+    /// `N`: descriptor `N` is duplicated.
+    Duplicate(u32),
+    /// `N-`: descriptor `N` is duplicated and then closed.
+    Move(u32),
+    /// Anything else names a file.
+    File(&'a str),
+}
+
+fn parse_duplication_target(target: &str) -> DuplicationTarget<'_> {
+    if target == "-" {
+        return DuplicationTarget::Close;
+    }
+
+    let (digits, is_move) = match target.strip_suffix('-') {
+        Some(digits) => (digits, true),
+        None => (target, false),
+    };
+
+    if !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit()) {
+        if let Ok(fd) = digits.parse() {
+            return if is_move {
+                DuplicationTarget::Move(fd)
+            } else {
+                DuplicationTarget::Duplicate(fd)
+            };
         }
     }
+
+    DuplicationTarget::File(target)
+}
+
+fn duplicate_open_fd(open_files: &OpenFiles, fd: u32) -> Result<OpenFile, error::Error> {
+    match open_files.files.get(&fd) {
+        Some(f) => f.try_dup(),
+        None => Err(error::Error::BadFileDescriptor(fd)),
+    }
+}
+
+/// Installs the given file as the descriptor targeted by a redirection, returning its number.
+/// A `{varname}` redirection allocates a new descriptor and stores its number in the variable;
+/// such descriptors outlive the command they were opened for, so they're also made available
+/// to the shell.
+fn install_redirected_fd(
+    open_files: &mut OpenFiles,
+    shell: &mut Shell,
+    specified_fd: Option<&ast::IoFd>,
+    default_fd: u32,
+    file: OpenFile,
+) -> Result<Option<u32>, error::Error> {
+    match specified_fd {
+        None => {
+            open_files.files.insert(default_fd, file);
+            Ok(Some(default_fd))
+        }
+        Some(ast::IoFd::Number(fd)) => {
+            open_files.files.insert(*fd, file);
+            Ok(Some(*fd))
+        }
+        Some(ast::IoFd::Variable(name)) => {
+            let fd = allocate_named_fd(open_files, &shell.open_files);
+
+            shell.env.update_or_add(
+                name,
+                ShellValueLiteral::Scalar(fd.to_string()),
+                |_| Ok(()),
+                EnvironmentLookup::Anywhere,
+                EnvironmentScope::Global,
+            )?;
+
+            let mut shell_open_files = shell.open_files.try_clone()?;
+            shell_open_files.files.insert(fd, file.try_dup()?);
+            shell.replace_open_files(shell_open_files);
+
+            open_files.files.insert(fd, file);
+            Ok(Some(fd))
+        }
+    }
+}
+
+/// Closes the descriptor targeted by a `<&-` or `>&-` redirection. For `{varname}`
+/// redirections, the descriptor is the one numbered by the variable's value, and it's closed
+/// in the shell as well.
+fn close_redirected_fd(
+    open_files: &mut OpenFiles,
+    shell: &mut Shell,
+    specified_fd: Option<&ast::IoFd>,
+    default_fd: u32,
+) -> Result<Option<u32>, error::Error> {
+    let fd = match specified_fd {
+        None => default_fd,
+        Some(ast::IoFd::Number(fd)) => *fd,
+        Some(ast::IoFd::Variable(name)) => {
+            let fd = shell
+                .get_env_str(name)
+                .and_then(|value| value.parse().ok())
+                .ok_or(error::Error::InvalidRedirection)?;
+
+            if shell.open_files.files.contains_key(&fd) {
+                let mut shell_open_files = shell.open_files.try_clone()?;
+                shell_open_files.files.remove(&fd);
+                shell.replace_open_files(shell_open_files);
+            }
+
+            fd
+        }
+    };
+
+    open_files.files.remove(&fd);
+    Ok(Some(fd))
+}
+
+/// Returns the lowest descriptor number, starting at 10, that's open in neither of the given
+/// sets of files; this is how bash allocates descriptors for `{varname}` redirections.
+fn allocate_named_fd(open_files: &OpenFiles, shell_open_files: &OpenFiles) -> u32 {
+    let mut fd = 10;
+    while open_files.files.contains_key(&fd) || shell_open_files.files.contains_key(&fd) {
+        fd += 1;
+    }
+
+    fd
+}
+
+/// Opens a path with special meaning as a redirection target, the way bash does regardless
+/// of whether the platform provides it: `/dev/stdin`, `/dev/stdout`, `/dev/stderr`, and
+/// `/dev/fd/N` duplicate the shell's open file descriptors, while `/dev/tcp/HOST/PORT` and
+/// `/dev/udp/HOST/PORT` open network connections. Returns `None` if the path isn't special.
+fn open_special_redirect_target(
+    open_files: &OpenFiles,
+    path: &str,
+) -> Result<Option<OpenFile>, error::Error> {
+    let fd = match path {
+        "/dev/stdin" => 0,
+        "/dev/stdout" => 1,
+        "/dev/stderr" => 2,
+        _ => {
+            if let Some(fd) = path.strip_prefix("/dev/fd/") {
+                let Ok(fd) = fd.parse() else {
+                    return Ok(None);
+                };
+                fd
+            } else if let Some(target) = path.strip_prefix("/dev/tcp/") {
+                return open_network_redirect_target(path, target, false);
+            } else if let Some(target) = path.strip_prefix("/dev/udp/") {
+                return open_network_redirect_target(path, target, true);
+            } else {
+                return Ok(None);
+            }
+        }
+    };
+
+    match open_files.files.get(&fd) {
+        Some(file) => Ok(Some(file.try_dup()?)),
+        None => Err(error::Error::BadFileDescriptor(fd)),
+    }
+}
+
+/// Opens a network connection to the `HOST/PORT` target of a `/dev/tcp` or `/dev/udp`
+/// redirection.
+fn open_network_redirect_target(
+    path: &str,
+    target: &str,
+    udp: bool,
+) -> Result<Option<OpenFile>, error::Error> {
+    let Some((host, port)) = target.split_once('/') else {
+        return Ok(None);
+    };
+
+    let port = port.parse().map_err(|_| {
+        error::Error::RedirectionFailure(
+            path.to_owned(),
+            std::io::Error::new(std::io::ErrorKind::InvalidInput, "invalid port"),
+        )
+    })?;
+
+    let file = connect_socket(host, port, udp)
+        .map_err(|err| error::Error::RedirectionFailure(path.to_owned(), err))?;
+
+    Ok(Some(file))
+}
+
+#[cfg(unix)]
+fn connect_socket(host: &str, port: u16, udp: bool) -> Result<OpenFile, std::io::Error> {
+    use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs};
+
+    let fd: std::os::fd::OwnedFd = if udp {
+        let mut result = Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "no addresses found for host",
+        ));
+
+        for addr in (host, port).to_socket_addrs()? {
+            let local_addr: SocketAddr = if addr.is_ipv4() {
+                (Ipv4Addr::UNSPECIFIED, 0).into()
+            } else {
+                (Ipv6Addr::UNSPECIFIED, 0).into()
+            };
+
+            result = std::net::UdpSocket::bind(local_addr)
+                .and_then(|socket| socket.connect(addr).map(|()| socket));
+            if result.is_ok() {
+                break;
+            }
+        }
+
+        result?.into()
+    } else {
+        std::net::TcpStream::connect((host, port))?.into()
+    };
+
+    Ok(OpenFile::File(std::fs::File::from(fd)))
+}
+
+#[cfg(not(unix))]
+fn connect_socket(_host: &str, _port: u16, _udp: bool) -> Result<OpenFile, std::io::Error> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "network redirections are not supported on this platform",
+    ))
 }
 
 fn get_default_fd_for_redirect_kind(kind: &ast::IoFileRedirectKind) -> u32 {
@@ -1417,7 +2410,6 @@ fn setup_process_substitution(
     kind: &ast::ProcessSubstitutionKind,
     subshell_cmd: &ast::SubshellCommand,
 ) -> Result<(u32, OpenFile), error::Error> {
-    // TODO: Don't execute synchronously!
     // Execute in a subshell.
     let mut subshell = shell.clone();
 
@@ -1444,6 +2436,7 @@ fn setup_process_substitution(
     let exec_params = ExecutionParameters {
         open_files: subshell.open_files.clone(),
         process_group_policy: ProcessGroupPolicy::SameProcessGroup,
+        ..Default::default()
     };
 
     // Asynchronously spawn off the subshell; we intentionally don't block on its
@@ -1454,6 +2447,12 @@ fn setup_process_substitution(
         let _ = subshell_cmd.0.execute(&mut subshell, &exec_params).await;
     });
 
+    Ok((find_available_fd(open_files)?, target_file))
+}
+
+/// Finds a file descriptor number not in use by the given open files, for use by the shell
+/// itself.
+fn find_available_fd(open_files: &OpenFiles) -> Result<u32, error::Error> {
     // Starting at 63 (a.k.a. 64-1)--and decrementing--look for an
     // available fd.
     let mut candidate_fd_num = 63;
@@ -1464,7 +2463,7 @@ fn setup_process_substitution(
         }
     }
 
-    Ok((candidate_fd_num, target_file))
+    Ok(candidate_fd_num)
 }
 
 #[allow(unused_variables)]
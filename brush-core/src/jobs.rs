@@ -1,5 +1,7 @@
+use std::borrow::Cow;
 use std::collections::VecDeque;
 use std::fmt::Display;
+use std::sync::atomic::{AtomicI32, Ordering};
 
 use futures::FutureExt;
 
@@ -7,16 +9,39 @@ use crate::error;
 use crate::processes;
 use crate::sys;
 use crate::trace_categories;
+use crate::traps;
 use crate::ExecutionResult;
 
 pub(crate) type JobJoinHandle = tokio::task::JoinHandle<Result<ExecutionResult, error::Error>>;
 pub(crate) type JobResult = (Job, Result<ExecutionResult, error::Error>);
 
+/// The maximum number of completed jobs whose exit statuses are retained for `wait`.
+const MAX_COMPLETED_JOBS: usize = 256;
+
+/// The next synthetic process ID to hand out to a job that has no processes of its own.
+/// These start above the largest process ID that Linux (`PID_MAX_LIMIT`), macOS, or the
+/// BSDs will assign, so they can never name a real process.
+static NEXT_SYNTHETIC_PID: AtomicI32 = AtomicI32::new(1 << 22);
+
 /// Manages the jobs that are currently managed by the shell.
 #[derive(Default)]
 pub struct JobManager {
     /// The jobs that are currently managed by the shell.
     pub jobs: Vec<Job>,
+
+    /// Jobs that completed before they were waited on, retained so that a later `wait` can
+    /// still report their exit statuses.
+    completed_jobs: VecDeque<CompletedJob>,
+}
+
+/// Records the exit status of a job that completed before it was waited on.
+struct CompletedJob {
+    /// The shell-internal ID of the job.
+    id: usize,
+    /// If available, the process group ID of the job's processes.
+    pgid: Option<sys::process::ProcessId>,
+    /// The exit code of the job.
+    exit_code: u8,
 }
 
 /// Represents a task that is part of a job.
@@ -80,14 +105,18 @@ impl JobManager {
     /// * `job` - The job to add.
     pub fn add_as_current(&mut self, mut job: Job) -> &Job {
         for j in &mut self.jobs {
-            if matches!(j.annotation, JobAnnotation::Current) {
-                j.annotation = JobAnnotation::Previous;
-                break;
+            match j.annotation {
+                JobAnnotation::Current => j.annotation = JobAnnotation::Previous,
+                JobAnnotation::Previous => j.annotation = JobAnnotation::None,
+                JobAnnotation::None => (),
             }
         }
 
-        let id = self.jobs.len() + 1;
+        let id = self.jobs.iter().map(|j| j.id).max().unwrap_or(0) + 1;
         job.id = id;
+
+        // Job IDs are reused, so forget any earlier job that completed with this one's ID.
+        self.completed_jobs.retain(|j| j.id != id);
         job.annotation = JobAnnotation::Current;
         self.jobs.push(job);
         self.jobs.last().unwrap()
@@ -127,41 +156,191 @@ impl JobManager {
     ///
     /// * `job_spec` - The job specification to resolve.
     pub fn resolve_job_spec(&mut self, job_spec: &str) -> Option<&mut Job> {
-        if !job_spec.starts_with('%') {
-            return None;
-        }
+        let id = self.resolve_job_spec_to_id(job_spec).ok()?;
+        self.get_mut(id)
+    }
 
-        match &job_spec[1..] {
-            "%" | "+" => self.current_job_mut(),
-            "-" => self.prev_job_mut(),
-            s if s.chars().all(char::is_numeric) => {
-                let id = s.parse::<usize>().ok()?;
-                self.jobs.iter_mut().find(|j| j.id == id)
+    /// Tries to resolve the given job specification to the ID of a job.
+    ///
+    /// # Arguments
+    ///
+    /// * `job_spec` - The job specification to resolve.
+    pub fn resolve_job_spec_to_id(&self, job_spec: &str) -> Result<usize, JobSpecError> {
+        let Some(spec) = job_spec.strip_prefix('%') else {
+            return Err(JobSpecError::NoSuchJob(job_spec.to_owned()));
+        };
+
+        let matches: Vec<_> = match spec {
+            "" | "%" | "+" => self.current_job().into_iter().collect(),
+            "-" => self.prev_job().into_iter().collect(),
+            s if s.chars().all(|c| c.is_ascii_digit()) => {
+                let id = s
+                    .parse::<usize>()
+                    .map_err(|_| JobSpecError::NoSuchJob(job_spec.to_owned()))?;
+                self.jobs.iter().filter(|j| j.id == id).collect()
             }
-            _ => {
-                tracing::warn!("UNIMPLEMENTED: job spec naming command: '{job_spec}'");
-                None
+            s => {
+                if let Some(substring) = s.strip_prefix('?') {
+                    self.jobs
+                        .iter()
+                        .filter(|j| j.command_line.contains(substring))
+                        .collect()
+                } else {
+                    self.jobs
+                        .iter()
+                        .filter(|j| j.command_line.starts_with(s))
+                        .collect()
+                }
             }
+        };
+
+        match matches.as_slice() {
+            [job] => Ok(job.id),
+            [] => Err(JobSpecError::NoSuchJob(job_spec.to_owned())),
+            _ => Err(JobSpecError::Ambiguous(job_spec.to_owned())),
+        }
+    }
+
+    /// Tries to resolve the given job specification to the ID of a job; if no specification
+    /// is provided, resolves to the current job.
+    ///
+    /// # Arguments
+    ///
+    /// * `job_spec` - The optional job specification to resolve.
+    pub fn resolve_job_spec_or_current(
+        &self,
+        job_spec: Option<&str>,
+    ) -> Result<usize, JobSpecError> {
+        if let Some(job_spec) = job_spec {
+            self.resolve_job_spec_to_id(job_spec)
+        } else {
+            self.current_job()
+                .map(|j| j.id)
+                .ok_or_else(|| JobSpecError::NoSuchJob(String::from("current")))
         }
     }
 
-    /// Waits for all managed jobs to complete.
+    /// Returns a mutable reference to the job with the given ID, if there is one.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the job to retrieve.
+    pub fn get_mut(&mut self, id: usize) -> Option<&mut Job> {
+        self.jobs.iter_mut().find(|j| j.id == id)
+    }
+
+    /// Removes the job with the given ID from the job manager, returning it
+    /// if it was found.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the job to remove.
+    pub fn remove(&mut self, id: usize) -> Option<Job> {
+        let index = self.jobs.iter().position(|j| j.id == id)?;
+        let job = self.jobs.remove(index);
+        self.update_annotations();
+        Some(job)
+    }
+
+    /// Waits for all managed jobs to complete. The exit statuses of jobs that had already
+    /// completed are forgotten.
     pub async fn wait_all(&mut self) -> Result<Vec<Job>, error::Error> {
         for job in &mut self.jobs {
             job.wait().await?;
         }
 
+        self.completed_jobs.clear();
+
         Ok(self.sweep_completed_jobs())
     }
 
+    /// Waits for the first of the given jobs to complete or stop, returning its ID
+    /// along with its result; returns `None` if none of the given jobs exist. Jobs
+    /// that complete are removed from the job manager.
+    ///
+    /// # Arguments
+    ///
+    /// * `ids` - The IDs of the jobs to wait for.
+    /// * `wait_for_termination` - Whether to keep waiting on jobs that stop, until they
+    ///   complete.
+    pub async fn wait_for_first(
+        &mut self,
+        ids: &[usize],
+        wait_for_termination: bool,
+    ) -> Result<Option<(usize, ExecutionResult)>, error::Error> {
+        loop {
+            let waits: Vec<_> = self
+                .jobs
+                .iter_mut()
+                .filter(|j| ids.contains(&j.id))
+                .map(|j| Box::pin(async move { (j.id, j.wait().await) }))
+                .collect();
+
+            if waits.is_empty() {
+                return Ok(None);
+            }
+
+            let ((id, result), _, _) = futures::future::select_all(waits).await;
+            let result = result?;
+
+            let stopped = self
+                .get_mut(id)
+                .is_some_and(|j| matches!(j.state, JobState::Stopped));
+
+            if !stopped {
+                self.remove(id);
+            } else if wait_for_termination {
+                continue;
+            }
+
+            return Ok(Some((id, result)));
+        }
+    }
+
+    /// Retrieves and forgets the exit status of a job that completed before it was
+    /// waited on, identified by its ID.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the job.
+    pub fn take_completed_job_status(&mut self, id: usize) -> Option<u8> {
+        let index = self.completed_jobs.iter().position(|j| j.id == id)?;
+        self.completed_jobs.remove(index).map(|j| j.exit_code)
+    }
+
+    /// Retrieves and forgets the exit status of a job that completed before it was
+    /// waited on, identified by its process group ID.
+    ///
+    /// # Arguments
+    ///
+    /// * `pgid` - The process group ID of the job.
+    pub fn take_completed_job_status_by_pgid(
+        &mut self,
+        pgid: sys::process::ProcessId,
+    ) -> Option<u8> {
+        let index = self
+            .completed_jobs
+            .iter()
+            .position(|j| j.pgid == Some(pgid))?;
+        self.completed_jobs.remove(index).map(|j| j.exit_code)
+    }
+
     /// Polls all managed jobs for completion.
     pub fn poll(&mut self) -> Result<Vec<JobResult>, error::Error> {
         let mut results = vec![];
 
         let mut i = 0;
         while i != self.jobs.len() {
+            // N.B. The job's processes are forgotten as they're reaped, so look up its process
+            // group ID before polling.
+            let pgid = self.jobs[i]
+                .get_process_group_id()
+                .or(self.jobs[i].synthetic_pid);
             if let Some(result) = self.jobs[i].poll_done()? {
                 let job = self.jobs.remove(i);
+                if let Ok(result) = &result {
+                    self.record_completed_job(job.id, pgid, result.exit_code);
+                }
                 results.push((job, result));
             } else if matches!(self.jobs[i].state, JobState::Done) {
                 // TODO: This is a workaround to remove jobs that are done but for which we don't
@@ -172,9 +351,28 @@ impl JobManager {
             }
         }
 
+        self.update_annotations();
+
         Ok(results)
     }
 
+    fn record_completed_job(
+        &mut self,
+        id: usize,
+        pgid: Option<sys::process::ProcessId>,
+        exit_code: u8,
+    ) {
+        if self.completed_jobs.len() == MAX_COMPLETED_JOBS {
+            self.completed_jobs.pop_front();
+        }
+
+        self.completed_jobs.push_back(CompletedJob {
+            id,
+            pgid,
+            exit_code,
+        });
+    }
+
     fn sweep_completed_jobs(&mut self) -> Vec<Job> {
         let mut completed_jobs = vec![];
 
@@ -187,8 +385,48 @@ impl JobManager {
             }
         }
 
+        self.update_annotations();
+
         completed_jobs
     }
+
+    /// Ensures that the current and previous jobs are designated after jobs have been
+    /// removed, promoting the most recently started remaining jobs as needed.
+    fn update_annotations(&mut self) {
+        if self.current_job().is_none() {
+            if let Some(job) = self.prev_job_mut() {
+                job.annotation = JobAnnotation::Current;
+            } else if let Some(job) = self.jobs.last_mut() {
+                job.annotation = JobAnnotation::Current;
+            }
+        }
+
+        if self.prev_job().is_none() {
+            if let Some(job) = self.jobs.iter_mut().rev().find(|j| !j.is_current()) {
+                job.annotation = JobAnnotation::Previous;
+            }
+        }
+    }
+}
+
+/// Represents an error encountered resolving a job specification.
+pub enum JobSpecError {
+    /// No job matched the specification.
+    NoSuchJob(String),
+    /// More than one job matched the specification.
+    Ambiguous(String),
+}
+
+impl Display for JobSpecError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JobSpecError::NoSuchJob(job_spec) => write!(f, "{job_spec}: no such job"),
+            JobSpecError::Ambiguous(job_spec) => {
+                let job_spec = job_spec.strip_prefix('%').unwrap_or(job_spec);
+                write!(f, "{job_spec}: ambiguous job spec")
+            }
+        }
+    }
 }
 
 /// Represents the current execution state of a job.
@@ -244,6 +482,11 @@ pub struct Job {
     /// If available, the process group ID of the job's processes.
     pgid: Option<sys::process::ProcessId>,
 
+    /// For a job that runs entirely within the shell process (e.g., a backgrounded compound
+    /// command), a process ID that the shell made up to stand in for the subshell that bash
+    /// would have forked. It only identifies the job to `$!`, `wait`, and `kill`.
+    synthetic_pid: Option<sys::process::ProcessId>,
+
     /// The annotation of the job (e.g., current, previous).
     annotation: JobAnnotation,
 
@@ -261,11 +504,11 @@ impl Display for Job {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "[{}]{:3}{}\t{}",
+            "[{}]{:3}{:24}{}",
             self.id,
             self.annotation.to_string(),
-            self.state,
-            self.command_line
+            self.state.to_string(),
+            self.get_display_command_line()
         )
     }
 }
@@ -282,10 +525,19 @@ impl Job {
     where
         I: IntoIterator<Item = JobTask>,
     {
+        let tasks: VecDeque<_> = tasks.into_iter().collect();
+
+        let synthetic_pid = if tasks.iter().any(|t| matches!(t, JobTask::External(_))) {
+            None
+        } else {
+            Some(NEXT_SYNTHETIC_PID.fetch_add(1, Ordering::Relaxed))
+        };
+
         Self {
             id: 0,
-            tasks: tasks.into_iter().collect(),
+            tasks,
             pgid: None,
+            synthetic_pid,
             annotation: JobAnnotation::None,
             command_line,
             state,
@@ -295,11 +547,42 @@ impl Job {
     /// Returns a pid-style string for the job.
     pub fn to_pid_style_string(&self) -> String {
         let display_pid = self
-            .get_representative_pid()
+            .get_background_pid()
             .map_or_else(|| String::from("<pid unknown>"), |pid| pid.to_string());
         std::format!("[{}]{}\t{}", self.id, self.annotation, display_pid)
     }
 
+    /// Returns the identifier that `$!` reports for the job: the process ID of its
+    /// representative process, or its synthetic process ID if it has no processes.
+    pub fn to_background_id_string(&self) -> String {
+        self.get_background_pid()
+            .map_or_else(|| std::format!("%{}", self.id), |pid| pid.to_string())
+    }
+
+    /// Returns a string for the job that includes its process group ID.
+    pub fn to_long_string(&self) -> String {
+        let display_pid = self
+            .get_process_group_id()
+            .or(self.synthetic_pid)
+            .map_or_else(String::new, |pid| std::format!("{pid} "));
+        std::format!(
+            "[{}]{:2}{display_pid}{:24}{}",
+            self.id,
+            self.annotation.to_string(),
+            self.state.to_string(),
+            self.get_display_command_line()
+        )
+    }
+
+    /// Returns the command line of the job, as it should be displayed in job listings.
+    pub fn get_display_command_line(&self) -> Cow<'_, str> {
+        if matches!(self.state, JobState::Running) {
+            std::format!("{} &", self.command_line).into()
+        } else {
+            self.command_line.as_str().into()
+        }
+    }
+
     /// Returns the annotation of the job.
     pub fn get_annotation(&self) -> JobAnnotation {
         self.annotation.clone()
@@ -352,7 +635,7 @@ impl Job {
         Ok(result)
     }
 
-    /// Waits for the job to complete.
+    /// Waits for the job to complete or stop.
     pub async fn wait(&mut self) -> Result<ExecutionResult, error::Error> {
         let mut result = ExecutionResult::success();
 
@@ -365,11 +648,13 @@ impl Job {
                 JobTaskWaitResult::Stopped => {
                     self.state = JobState::Stopped;
                     result = ExecutionResult::stopped();
-                    break;
+                    return Ok(result);
                 }
             }
         }
 
+        self.state = JobState::Done;
+
         Ok(result)
     }
 
@@ -407,10 +692,40 @@ impl Job {
         Ok(())
     }
 
-    /// Kills the job.
-    pub fn kill(&mut self) -> Result<(), error::Error> {
-        if let Some(pid) = self.get_process_group_id() {
-            sys::signal::kill_process(pid)
+    /// Sends the given signal to the job's processes.
+    ///
+    /// A job without processes can't receive signals. Any signal but 0 instead cancels its
+    /// tasks at their next suspension point, and the job completes with the exit status of
+    /// a process killed by that signal.
+    ///
+    /// # Arguments
+    ///
+    /// * `signal` - The signal to send.
+    pub fn kill(&mut self, signal: traps::TrapSignal) -> Result<(), error::Error> {
+        if let Some(pgid) = self.get_process_group_id() {
+            sys::signal::kill_process_group(pgid, signal)
+        } else if self.synthetic_pid.is_some() {
+            let exit_code = match signal {
+                traps::TrapSignal::Exit => return Ok(()),
+                #[cfg(unix)]
+                traps::TrapSignal::Signal(s) => {
+                    u8::try_from(128 + s as i32).map_err(|_| error::Error::FailedToSendSignal)?
+                }
+                traps::TrapSignal::Debug | traps::TrapSignal::Err | traps::TrapSignal::Return => {
+                    return Err(error::Error::FailedToSendSignal)
+                }
+            };
+
+            for task in &mut self.tasks {
+                if let JobTask::Internal(handle) = task {
+                    handle.abort();
+                    *task = JobTask::Internal(tokio::spawn(async move {
+                        Ok(ExecutionResult::new(exit_code))
+                    }));
+                }
+            }
+
+            Ok(())
         } else {
             Err(error::Error::FailedToSendSignal)
         }
@@ -435,4 +750,25 @@ impl Job {
         // TODO: Don't assume that the first PID is the PGID.
         self.pgid.or_else(|| self.get_representative_pid())
     }
+
+    /// Returns the synthetic process ID of a job that has no processes of its own.
+    pub fn get_synthetic_pid(&self) -> Option<sys::process::ProcessId> {
+        self.synthetic_pid
+    }
+
+    /// Returns the process ID that identifies the job when it runs in the background:
+    /// that of its representative process, or its synthetic process ID.
+    pub fn get_background_pid(&self) -> Option<sys::process::ProcessId> {
+        self.get_representative_pid().or(self.synthetic_pid)
+    }
+
+    /// Returns whether the given process ID identifies the job, either as its process
+    /// group ID or as its synthetic process ID.
+    ///
+    /// # Arguments
+    ///
+    /// * `pid` - The process ID to check.
+    pub fn is_identified_by_pid(&self, pid: sys::process::ProcessId) -> bool {
+        self.get_process_group_id() == Some(pid) || self.synthetic_pid == Some(pid)
+    }
 }
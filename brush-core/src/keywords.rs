@@ -18,12 +18,14 @@ fn get_keywords(sh_mode_only: bool) -> HashSet<String> {
     keywords.insert(String::from("if"));
     keywords.insert(String::from("in"));
     keywords.insert(String::from("then"));
+    keywords.insert(String::from("time"));
     keywords.insert(String::from("until"));
     keywords.insert(String::from("while"));
 
     if !sh_mode_only {
         keywords.insert(String::from("[["));
         keywords.insert(String::from("]]"));
+        keywords.insert(String::from("coproc"));
         keywords.insert(String::from("function"));
         keywords.insert(String::from("select"));
     }
@@ -14,12 +14,15 @@ mod escape;
 mod expansion;
 mod extendedtests;
 mod functions;
+mod histexpand;
+mod history;
 mod interp;
 mod jobs;
 mod keywords;
 mod namedoptions;
 mod openfiles;
 mod options;
+mod pathcache;
 mod patterns;
 mod processes;
 mod prompt;
@@ -28,13 +31,15 @@ mod shell;
 mod sys;
 mod terminal;
 mod tests;
+mod timing;
 mod trace_categories;
 mod traps;
 mod variables;
 
 pub use commands::ExecutionContext;
 pub use error::Error;
+pub use histexpand::HistoryExpansion;
 pub use interp::{ExecutionParameters, ExecutionResult};
 pub use shell::{CreateOptions, Shell};
 pub use terminal::TerminalControl;
-pub use variables::{ShellValue, ShellVariable};
+pub use variables::{DynamicValueGetter, DynamicValueSetter, ShellValue, ShellVariable};
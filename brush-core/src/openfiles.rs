@@ -94,6 +94,43 @@ impl OpenFile {
         }
     }
 
+    /// Waits until input is available to be read from the open file, or until the
+    /// given timeout elapses. Returns whether input became available.
+    pub(crate) fn poll_for_input(
+        &self,
+        timeout: std::time::Duration,
+    ) -> Result<bool, error::Error> {
+        match self {
+            OpenFile::Stdin => sys::fs::poll_for_input(std::io::stdin(), timeout),
+            OpenFile::Stdout => Err(error::Error::OpenFileNotReadable("stdout")),
+            OpenFile::Stderr => Err(error::Error::OpenFileNotReadable("stderr")),
+            OpenFile::Null => Ok(true),
+            OpenFile::File(f) => sys::fs::poll_for_input(f, timeout),
+            OpenFile::PipeReader(r) => sys::fs::poll_for_input(r, timeout),
+            OpenFile::PipeWriter(_) => Err(error::Error::OpenFileNotReadable("pipe writer")),
+        }
+    }
+
+    /// Reads from the open file without any buffering, so that no more input is consumed
+    /// than is returned, and input that has arrived remains visible to
+    /// [`OpenFile::poll_for_input`]. Only the original standard input is otherwise read
+    /// through a buffer.
+    ///
+    /// # Arguments
+    ///
+    /// * `buf` - The buffer to read into.
+    pub(crate) fn read_unbuffered(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            #[cfg(unix)]
+            OpenFile::Stdin => {
+                // Read through a duplicate of the descriptor, bypassing std's buffer.
+                let mut file = std::fs::File::from(std::io::stdin().as_fd().try_clone_to_owned()?);
+                std::io::Read::read(&mut file, buf)
+            }
+            _ => std::io::Read::read(self, buf),
+        }
+    }
+
     pub(crate) fn get_term_attr(
         &self,
     ) -> Result<Option<sys::terminal::TerminalSettings>, error::Error> {
@@ -139,9 +176,14 @@ impl From<std::fs::File> for OpenFile {
 impl From<OpenFile> for Stdio {
     fn from(open_file: OpenFile) -> Self {
         match open_file {
-            OpenFile::Stdin => Stdio::inherit(),
-            OpenFile::Stdout => Stdio::inherit(),
-            OpenFile::Stderr => Stdio::inherit(),
+            // N.B. The original standard streams may have been duplicated onto other
+            // descriptors (e.g., by `2>&1`), so they can't simply be inherited.
+            #[cfg(unix)]
+            std_file @ (OpenFile::Stdin | OpenFile::Stdout | OpenFile::Stderr) => std_file
+                .into_owned_fd()
+                .map_or_else(|_| Stdio::inherit(), Stdio::from),
+            #[cfg(not(unix))]
+            OpenFile::Stdin | OpenFile::Stdout | OpenFile::Stderr => Stdio::inherit(),
             OpenFile::Null => Stdio::null(),
             OpenFile::File(f) => f.into(),
             OpenFile::PipeReader(f) => f.into(),
@@ -192,6 +192,7 @@ impl RuntimeOptions {
             do_not_execute_commands: create_options.do_not_execute_commands,
             enable_command_history: create_options.interactive,
             enable_job_control: create_options.interactive,
+            login_shell: create_options.login,
             read_commands_from_stdin: create_options.read_commands_from_stdin,
             sh_mode: create_options.sh_mode,
             posix_mode: create_options.posix,
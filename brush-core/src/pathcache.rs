@@ -0,0 +1,97 @@
+use std::{collections::HashMap, path::PathBuf};
+
+/// A cache of resolved locations of external programs, keyed by command name.
+#[derive(Clone, Default)]
+pub struct PathCache {
+    entries: HashMap<String, PathCacheEntry>,
+    /// The value of `PATH` that the cached entries were resolved against.
+    search_path: Option<String>,
+}
+
+/// Encapsulates a cached program location.
+#[derive(Clone)]
+pub struct PathCacheEntry {
+    /// The resolved path to the program.
+    pub path: PathBuf,
+    /// Number of times the entry has been used to resolve a command.
+    pub hits: usize,
+}
+
+impl PathCache {
+    /// Discards all cached entries if they were resolved against a different value of
+    /// `PATH` than the one provided.
+    ///
+    /// # Arguments
+    ///
+    /// * `search_path` - The current value of `PATH`.
+    pub fn sync_with_search_path(&mut self, search_path: &str) {
+        if !self.is_valid_for_search_path(search_path) {
+            self.entries.clear();
+            self.search_path = Some(search_path.to_owned());
+        }
+    }
+
+    /// Returns whether the cached entries were resolved against the given value of `PATH`.
+    ///
+    /// # Arguments
+    ///
+    /// * `search_path` - The current value of `PATH`.
+    pub fn is_valid_for_search_path(&self, search_path: &str) -> bool {
+        self.search_path.as_deref() == Some(search_path)
+    }
+
+    /// Looks up the cached location of a program, counting the lookup as a hit.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the command to look up.
+    pub fn get_and_record_hit(&mut self, name: &str) -> Option<PathBuf> {
+        self.entries.get_mut(name).map(|entry| {
+            entry.hits += 1;
+            entry.path.clone()
+        })
+    }
+
+    /// Looks up the cached location of a program.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the command to look up.
+    pub fn get(&self, name: &str) -> Option<&PathCacheEntry> {
+        self.entries.get(name)
+    }
+
+    /// Caches the location of a program.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the command.
+    /// * `path` - The location of the program.
+    pub fn set(&mut self, name: String, path: PathBuf) {
+        self.entries.insert(name, PathCacheEntry { path, hits: 0 });
+    }
+
+    /// Removes the cached location of a program, returning whether it was present.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the command to forget.
+    pub fn remove(&mut self, name: &str) -> bool {
+        self.entries.remove(name).is_some()
+    }
+
+    /// Removes all cached locations.
+    pub fn reset(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Returns whether the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns an iterator over the cached entries.
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &PathCacheEntry)> {
+        self.entries.iter()
+    }
+}
@@ -1,4 +1,5 @@
 use crate::{error, regex};
+use itertools::Itertools;
 use std::{
     collections::VecDeque,
     path::{Path, PathBuf},
@@ -28,11 +29,15 @@ type PatternWord = Vec<PatternPiece>;
 #[derive(Clone, Debug)]
 pub struct Pattern {
     pieces: PatternWord,
+    case_insensitive: bool,
 }
 
 impl From<PatternWord> for Pattern {
     fn from(pieces: PatternWord) -> Self {
-        Self { pieces }
+        Self {
+            pieces,
+            case_insensitive: false,
+        }
     }
 }
 
@@ -40,6 +45,7 @@ impl From<&PatternWord> for Pattern {
     fn from(value: &PatternWord) -> Self {
         Self {
             pieces: value.clone(),
+            case_insensitive: false,
         }
     }
 }
@@ -48,6 +54,7 @@ impl From<&str> for Pattern {
     fn from(value: &str) -> Self {
         Self {
             pieces: vec![PatternPiece::Pattern(value.to_owned())],
+            case_insensitive: false,
         }
     }
 }
@@ -56,16 +63,69 @@ impl From<String> for Pattern {
     fn from(value: String) -> Self {
         Self {
             pieces: vec![PatternPiece::Pattern(value)],
+            case_insensitive: false,
+        }
+    }
+}
+
+/// A pattern prepared for matching against entire strings.
+pub(crate) enum PatternMatcher {
+    /// The pattern is matched with an equivalent regular expression.
+    Regex(fancy_regex::Regex),
+    /// The pattern has no regular expression equivalent (i.e., it contains a negated extended
+    /// glob), so its parsed elements are matched directly.
+    Elements {
+        elements: Vec<brush_parser::pattern::PatternElement>,
+        case_insensitive: bool,
+    },
+}
+
+impl PatternMatcher {
+    /// Checks if the pattern matches the entirety of the given string.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The string to check for a match.
+    pub(crate) fn is_match(&self, value: &str) -> Result<bool, error::Error> {
+        match self {
+            Self::Regex(regex) => Ok(regex.is_match(value)?),
+            Self::Elements {
+                elements,
+                case_insensitive,
+            } => Ok(brush_parser::pattern::pattern_elements_match(
+                elements,
+                value,
+                *case_insensitive,
+            )),
         }
     }
 }
 
 impl Pattern {
+    /// Returns a version of the pattern that matches without regard to case, if requested.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - Whether or not the pattern should match case-insensitively.
+    #[must_use]
+    pub(crate) fn set_case_insensitive(mut self, value: bool) -> Self {
+        self.case_insensitive = value;
+        self
+    }
+
     /// Returns whether or not the pattern is empty.
     pub fn is_empty(&self) -> bool {
         self.pieces.iter().all(|p| p.as_str().is_empty())
     }
 
+    /// Returns whether or not the pattern contains unquoted characters that may require
+    /// it to be expanded.
+    pub(crate) fn requires_expansion(&self) -> bool {
+        self.pieces.iter().any(|piece| {
+            matches!(piece, PatternPiece::Pattern(_)) && requires_expansion(piece.as_str())
+        })
+    }
+
     /// Placeholder function that always returns true.
     pub(crate) fn accept_all_expand_filter(_path: &Path) -> bool {
         true
@@ -77,6 +137,10 @@ impl Pattern {
     ///
     /// * `working_dir` - The current working directory, used for relative paths.
     /// * `enable_extended_globbing` - Whether or not to enable extended globbing (extglob).
+    /// * `enable_globstar` - Whether or not a `**` path component should match any number of
+    ///   directories (globstar).
+    /// * `match_dotfiles` - Whether or not wildcards should match hidden files and directories,
+    ///   i.e., those whose names begin with `.` (dotglob).
     /// * `path_filter` - Optionally provides a function that filters paths after expansion.
     #[allow(clippy::too_many_lines)]
     #[allow(clippy::unwrap_in_result)]
@@ -84,6 +148,8 @@ impl Pattern {
         &self,
         working_dir: &Path,
         enable_extended_globbing: bool,
+        enable_globstar: bool,
+        match_dotfiles: bool,
         path_filter: Option<&PF>,
     ) -> Result<Vec<String>, error::Error>
     where
@@ -96,9 +162,7 @@ impl Pattern {
 
         // Similarly, if we're *confident* the pattern doesn't require expansion, then we
         // know there's a single expansion (before filtering).
-        } else if !self.pieces.iter().any(|piece| {
-            matches!(piece, PatternPiece::Pattern(_)) && requires_expansion(piece.as_str())
-        }) {
+        } else if !self.requires_expansion() {
             let concatenated: String = self.pieces.iter().map(|piece| piece.as_str()).collect();
 
             if let Some(filter) = path_filter {
@@ -112,29 +176,7 @@ impl Pattern {
 
         tracing::debug!("expanding pattern: {self:?}");
 
-        let mut components: Vec<PatternWord> = vec![];
-        for piece in &self.pieces {
-            let mut split_result = piece
-                .as_str()
-                .split(std::path::MAIN_SEPARATOR)
-                .map(|s| match piece {
-                    PatternPiece::Pattern(_) => PatternPiece::Pattern(s.to_owned()),
-                    PatternPiece::Literal(_) => PatternPiece::Literal(s.to_owned()),
-                })
-                .collect::<VecDeque<_>>();
-
-            if let Some(first_piece) = split_result.pop_front() {
-                if let Some(last_component) = components.last_mut() {
-                    last_component.push(first_piece);
-                } else {
-                    components.push(vec![first_piece]);
-                }
-            }
-
-            while let Some(piece) = split_result.pop_front() {
-                components.push(vec![piece]);
-            }
-        }
+        let components = self.split_into_path_components();
 
         // Check if the path appears to be absolute.
         let is_absolute = if let Some(first_component) = components.first() {
@@ -161,7 +203,31 @@ impl Pattern {
             vec![working_dir.to_path_buf()]
         };
 
-        for component in components {
+        let component_count = components.len();
+        for (i, component) in components.into_iter().enumerate() {
+            if enable_globstar && is_globstar_component(&component) {
+                // A trailing `**` matches all files and directories beneath the current paths;
+                // otherwise, it matches zero or more directories.
+                let is_last = i + 1 == component_count;
+
+                let current_paths = std::mem::take(&mut paths_so_far);
+                for current_path in current_paths {
+                    if is_last {
+                        paths_so_far.push(current_path.join(""));
+                    } else {
+                        paths_so_far.push(current_path.clone());
+                    }
+
+                    collect_descendants(
+                        current_path.as_path(),
+                        !is_last,
+                        match_dotfiles,
+                        &mut paths_so_far,
+                    );
+                }
+                continue;
+            }
+
             if !component.iter().any(|piece| {
                 matches!(piece, PatternPiece::Pattern(_)) && requires_expansion(piece.as_str())
             }) {
@@ -177,13 +243,22 @@ impl Pattern {
 
             let current_paths = std::mem::take(&mut paths_so_far);
             for current_path in current_paths {
-                let subpattern = Pattern::from(&component);
-                let regex = subpattern.to_regex(true, true, enable_extended_globbing)?;
+                let subpattern =
+                    Pattern::from(&component).set_case_insensitive(self.case_insensitive);
+                let matcher = subpattern.to_matcher(enable_extended_globbing)?;
+
+                // Hidden entries are only matched by a pattern that explicitly begins with a
+                // dot, unless dotglob is enabled. N.B. `read_dir` never yields `.` or `..`.
+                let matches_hidden = match_dotfiles || has_leading_dot(&component);
 
                 let matches_regex = |dir_entry: &std::fs::DirEntry| {
-                    regex
-                        .is_match(dir_entry.file_name().to_string_lossy().as_ref())
-                        .unwrap_or(false)
+                    let file_name = dir_entry.file_name();
+                    let file_name = file_name.to_string_lossy();
+                    if !matches_hidden && file_name.starts_with('.') {
+                        return false;
+                    }
+
+                    matcher.is_match(file_name.as_ref()).unwrap_or(false)
                 };
 
                 let mut matching_paths_in_dir: Vec<_> = current_path
@@ -217,8 +292,14 @@ impl Pattern {
                     path_ref = path_ref.strip_prefix(prefix_to_remove).unwrap();
                 }
 
+                // The working directory itself isn't a match.
+                if path_ref.is_empty() {
+                    return None;
+                }
+
                 Some(path_ref.to_string())
             })
+            .sorted()
             .collect();
 
         tracing::debug!("  => results: {results:?}");
@@ -226,6 +307,36 @@ impl Pattern {
         Ok(results)
     }
 
+    /// Splits the pattern into the patterns for each of the components of the paths it
+    /// matches.
+    fn split_into_path_components(&self) -> Vec<PatternWord> {
+        let mut components: Vec<PatternWord> = vec![];
+        for piece in &self.pieces {
+            let mut split_result = piece
+                .as_str()
+                .split(std::path::MAIN_SEPARATOR)
+                .map(|s| match piece {
+                    PatternPiece::Pattern(_) => PatternPiece::Pattern(s.to_owned()),
+                    PatternPiece::Literal(_) => PatternPiece::Literal(s.to_owned()),
+                })
+                .collect::<VecDeque<_>>();
+
+            if let Some(first_piece) = split_result.pop_front() {
+                if let Some(last_component) = components.last_mut() {
+                    last_component.push(first_piece);
+                } else {
+                    components.push(vec![first_piece]);
+                }
+            }
+
+            while let Some(piece) = split_result.pop_front() {
+                components.push(vec![piece]);
+            }
+        }
+
+        components
+    }
+
     /// Converts the pattern to a regular expression string.
     ///
     /// # Arguments
@@ -243,6 +354,10 @@ impl Pattern {
     ) -> Result<String, error::Error> {
         let mut regex_str = String::new();
 
+        if self.case_insensitive {
+            regex_str.push_str("(?i)");
+        }
+
         if strict_prefix_match {
             regex_str.push('^');
         }
@@ -292,6 +407,68 @@ impl Pattern {
         Ok(re)
     }
 
+    /// Returns whether or not the pattern can be converted to a regular expression; patterns
+    /// with negated extended globs can't be, and must be matched with a `PatternMatcher`.
+    ///
+    /// # Arguments
+    ///
+    /// * `enable_extended_globbing` - Whether or not to enable extended globbing (extglob).
+    pub(crate) fn has_regex_equivalent(
+        &self,
+        enable_extended_globbing: bool,
+    ) -> Result<bool, error::Error> {
+        let elements = self.to_elements(enable_extended_globbing)?;
+        Ok(brush_parser::pattern::pattern_elements_to_regex_str(&elements).is_some())
+    }
+
+    /// Prepares the pattern for matching against entire strings.
+    ///
+    /// # Arguments
+    ///
+    /// * `enable_extended_globbing` - Whether or not to enable extended globbing (extglob).
+    pub(crate) fn to_matcher(
+        &self,
+        enable_extended_globbing: bool,
+    ) -> Result<PatternMatcher, error::Error> {
+        let elements = self.to_elements(enable_extended_globbing)?;
+        if brush_parser::pattern::pattern_elements_to_regex_str(&elements).is_some() {
+            Ok(PatternMatcher::Regex(self.to_regex(
+                true,
+                true,
+                enable_extended_globbing,
+            )?))
+        } else {
+            Ok(PatternMatcher::Elements {
+                elements,
+                case_insensitive: self.case_insensitive,
+            })
+        }
+    }
+
+    /// Parses the pattern into its elements; literal pieces only ever match themselves.
+    fn to_elements(
+        &self,
+        enable_extended_globbing: bool,
+    ) -> Result<Vec<brush_parser::pattern::PatternElement>, error::Error> {
+        let mut elements = vec![];
+        for piece in &self.pieces {
+            match piece {
+                PatternPiece::Pattern(s) => elements.extend(brush_parser::pattern::parse_pattern(
+                    s,
+                    enable_extended_globbing,
+                )?),
+                PatternPiece::Literal(s) => {
+                    elements.extend(
+                        s.chars()
+                            .map(brush_parser::pattern::PatternElement::Literal),
+                    );
+                }
+            }
+        }
+
+        Ok(elements)
+    }
+
     /// Checks if the pattern exactly matches the given string.
     ///
     /// # Arguments
@@ -303,8 +480,78 @@ impl Pattern {
         value: &str,
         enable_extended_globbing: bool,
     ) -> Result<bool, error::Error> {
-        let re = self.to_regex(true, true, enable_extended_globbing)?;
-        Ok(re.is_match(value)?)
+        self.to_matcher(enable_extended_globbing)?.is_match(value)
+    }
+
+    /// Checks if the pattern exactly matches the given path. Unlike with `exactly_matches`,
+    /// path separators are never matched by wildcards; they must appear in the pattern.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to check for a match.
+    /// * `enable_extended_globbing` - Whether or not to enable extended globbing (extglob).
+    pub(crate) fn exactly_matches_path(
+        &self,
+        path: &str,
+        enable_extended_globbing: bool,
+    ) -> Result<bool, error::Error> {
+        let components = self.split_into_path_components();
+        let path_components: Vec<_> = path.split(std::path::MAIN_SEPARATOR).collect();
+        if components.len() != path_components.len() {
+            return Ok(false);
+        }
+
+        for (component, path_component) in components.into_iter().zip(path_components) {
+            let component = Pattern::from(component).set_case_insensitive(self.case_insensitive);
+            if !component.exactly_matches(path_component, enable_extended_globbing)? {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+}
+
+fn is_globstar_component(component: &PatternWord) -> bool {
+    matches!(component.as_slice(), [PatternPiece::Pattern(s)] if s == "**")
+}
+
+fn collect_descendants(
+    dir: &Path,
+    dirs_only: bool,
+    match_dotfiles: bool,
+    results: &mut Vec<PathBuf>,
+) {
+    let mut entries: Vec<_> = dir.read_dir().map_or_else(
+        |_| vec![],
+        |dir| dir.filter_map(|result| result.ok()).collect(),
+    );
+    entries.sort_by_key(std::fs::DirEntry::file_name);
+
+    for entry in entries {
+        if !match_dotfiles && entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+
+        let path = entry.path();
+        if !dirs_only || path.is_dir() {
+            results.push(path.clone());
+        }
+
+        // N.B. We don't descend through symlinks, which keeps us from looping forever.
+        if entry.file_type().is_ok_and(|file_type| file_type.is_dir()) {
+            collect_descendants(path.as_path(), dirs_only, match_dotfiles, results);
+        }
+    }
+}
+
+/// Checks whether the given path component pattern begins with an explicit dot, which
+/// allows it to match hidden files and directories.
+fn has_leading_dot(component: &PatternWord) -> bool {
+    match component.iter().find(|piece| !piece.as_str().is_empty()) {
+        Some(PatternPiece::Literal(s)) => s.starts_with('.'),
+        Some(PatternPiece::Pattern(s)) => s.starts_with('.') || s.starts_with("\\."),
+        None => false,
     }
 }
 
@@ -324,45 +571,12 @@ fn escape_for_regex(s: &str) -> String {
     escaped
 }
 
-/// Converts a shell pattern to a regular expression.
-///
-/// # Arguments
-///
-/// * `pattern` - The shell pattern to convert.
-/// * `strict_prefix_match` - Whether or not the pattern should strictly match the beginning of the
-///   string.
-/// * `strict_suffix_match` - Whether or not the pattern should strictly match the end of the
-///   string.
-/// * `enable_extended_globbing` - Whether or not to enable extended globbing (extglob).
-pub(crate) fn pattern_to_regex(
-    pattern: &str,
-    strict_prefix_match: bool,
-    strict_suffix_match: bool,
-    enable_extended_globbing: bool,
-) -> Result<fancy_regex::Regex, error::Error> {
-    let regex_str = pattern_to_regex_str(
-        pattern,
-        strict_prefix_match,
-        strict_suffix_match,
-        enable_extended_globbing,
-    )?;
-
-    let re = regex::compile_regex(regex_str)?;
-
-    Ok(re)
-}
-
 fn pattern_to_regex_str(
     pattern: &str,
     strict_prefix_match: bool,
     strict_suffix_match: bool,
     enable_extended_globbing: bool,
 ) -> Result<String, error::Error> {
-    // TODO: pattern matching with **
-    if pattern.contains("**") {
-        return error::unimp("pattern matching with '**' pattern");
-    }
-
     let mut regex_str =
         brush_parser::pattern::pattern_to_regex_str(pattern, enable_extended_globbing)?;
 
@@ -390,10 +604,9 @@ pub(crate) fn remove_largest_matching_prefix<'a>(
     enable_extended_globbing: bool,
 ) -> Result<&'a str, error::Error> {
     if let Some(pattern) = pattern {
-        for i in (0..s.len()).rev() {
-            let prefix = &s[0..=i];
-            if pattern.exactly_matches(prefix, enable_extended_globbing)? {
-                return Ok(&s[i + 1..]);
+        for i in char_boundaries(s).rev() {
+            if pattern.exactly_matches(&s[..i], enable_extended_globbing)? {
+                return Ok(&s[i..]);
             }
         }
     }
@@ -413,10 +626,9 @@ pub(crate) fn remove_smallest_matching_prefix<'a>(
     enable_extended_globbing: bool,
 ) -> Result<&'a str, error::Error> {
     if let Some(pattern) = pattern {
-        for i in 0..s.len() {
-            let prefix = &s[0..=i];
-            if pattern.exactly_matches(prefix, enable_extended_globbing)? {
-                return Ok(&s[i + 1..]);
+        for i in char_boundaries(s) {
+            if pattern.exactly_matches(&s[..i], enable_extended_globbing)? {
+                return Ok(&s[i..]);
             }
         }
     }
@@ -436,9 +648,8 @@ pub(crate) fn remove_largest_matching_suffix<'a>(
     enable_extended_globbing: bool,
 ) -> Result<&'a str, error::Error> {
     if let Some(pattern) = pattern {
-        for i in 0..s.len() {
-            let suffix = &s[i..];
-            if pattern.exactly_matches(suffix, enable_extended_globbing)? {
+        for i in char_boundaries(s) {
+            if pattern.exactly_matches(&s[i..], enable_extended_globbing)? {
                 return Ok(&s[..i]);
             }
         }
@@ -459,9 +670,8 @@ pub(crate) fn remove_smallest_matching_suffix<'a>(
     enable_extended_globbing: bool,
 ) -> Result<&'a str, error::Error> {
     if let Some(pattern) = pattern {
-        for i in (0..s.len()).rev() {
-            let suffix = &s[i..];
-            if pattern.exactly_matches(suffix, enable_extended_globbing)? {
+        for i in char_boundaries(s).rev() {
+            if pattern.exactly_matches(&s[i..], enable_extended_globbing)? {
                 return Ok(&s[..i]);
             }
         }
@@ -469,6 +679,87 @@ pub(crate) fn remove_smallest_matching_suffix<'a>(
     Ok(s)
 }
 
+/// Replaces substrings that match the given pattern, choosing the longest match at the
+/// leftmost position where there is one. An empty match is replaced and then the character
+/// following it is kept, so that scanning makes progress.
+///
+/// # Arguments
+///
+/// * `s` - The string to replace matches in.
+/// * `matcher` - The pattern to match.
+/// * `replacement` - The text to substitute for each match.
+/// * `match_kind` - Which matches should be replaced.
+pub(crate) fn replace_matching_substrings(
+    s: &str,
+    matcher: &PatternMatcher,
+    replacement: &str,
+    match_kind: &brush_parser::word::SubstringMatchKind,
+) -> Result<String, error::Error> {
+    let longest_match_end = |start: usize| -> Result<Option<usize>, error::Error> {
+        for end in char_boundaries(s).rev().take_while(|end| *end >= start) {
+            if matcher.is_match(&s[start..end])? {
+                return Ok(Some(end));
+            }
+        }
+        Ok(None)
+    };
+
+    match match_kind {
+        brush_parser::word::SubstringMatchKind::Prefix => {
+            if let Some(end) = longest_match_end(0)? {
+                return Ok(std::format!("{replacement}{}", &s[end..]));
+            }
+        }
+        brush_parser::word::SubstringMatchKind::Suffix => {
+            for start in char_boundaries(s) {
+                if matcher.is_match(&s[start..])? {
+                    return Ok(std::format!("{}{replacement}", &s[..start]));
+                }
+            }
+        }
+        brush_parser::word::SubstringMatchKind::FirstOccurrence
+        | brush_parser::word::SubstringMatchKind::Anywhere => {
+            let replace_all =
+                matches!(match_kind, brush_parser::word::SubstringMatchKind::Anywhere);
+
+            let mut result = String::new();
+            let mut start = 0;
+            while let Some(c) = s[start..].chars().next() {
+                let Some(end) = longest_match_end(start)? else {
+                    result.push(c);
+                    start += c.len_utf8();
+                    continue;
+                };
+
+                result.push_str(replacement);
+                if end > start {
+                    start = end;
+                } else {
+                    result.push(c);
+                    start += c.len_utf8();
+                }
+
+                if !replace_all {
+                    break;
+                }
+            }
+
+            result.push_str(&s[start..]);
+            return Ok(result);
+        }
+    }
+
+    Ok(s.to_owned())
+}
+
+/// Returns the byte offsets of all character boundaries in the given string, including
+/// its start and end; prefixes and suffixes of the string may be split at any of them.
+fn char_boundaries(s: &str) -> impl DoubleEndedIterator<Item = usize> + '_ {
+    s.char_indices()
+        .map(|(i, _)| i)
+        .chain(std::iter::once(s.len()))
+}
+
 #[cfg(test)]
 #[allow(clippy::panic_in_result_fn)]
 mod tests {
@@ -653,4 +944,36 @@ mod tests {
         );
         Ok(())
     }
+
+    #[test]
+    fn test_remove_matching_affixes_with_empty_matches() -> Result<()> {
+        let star = Some(Pattern::from("*"));
+        assert_eq!(remove_smallest_matching_prefix("foo", &star, true)?, "foo");
+        assert_eq!(remove_largest_matching_prefix("foo", &star, true)?, "");
+        assert_eq!(remove_smallest_matching_suffix("foo", &star, true)?, "foo");
+        assert_eq!(remove_largest_matching_suffix("foo", &star, true)?, "");
+        Ok(())
+    }
+
+    #[test]
+    fn test_remove_matching_affixes_with_multibyte_chars() -> Result<()> {
+        let path = "/tmp/dïr/fïlé.txt";
+        assert_eq!(
+            remove_largest_matching_prefix(path, &Some(Pattern::from("*/")), true)?,
+            "fïlé.txt"
+        );
+        assert_eq!(
+            remove_smallest_matching_suffix(path, &Some(Pattern::from("/*")), true)?,
+            "/tmp/dïr"
+        );
+        assert_eq!(
+            remove_smallest_matching_prefix("ééa", &Some(Pattern::from("?")), true)?,
+            "éa"
+        );
+        assert_eq!(
+            remove_largest_matching_suffix("aéé", &Some(Pattern::from("é*")), true)?,
+            "a"
+        );
+        Ok(())
+    }
 }
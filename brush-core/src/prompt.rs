@@ -3,7 +3,7 @@ use crate::{
     shell::Shell,
     sys::{self, users},
 };
-use std::path::Path;
+use std::{fmt::Write, path::Path};
 
 const VERSION_MAJOR: &str = env!("CARGO_PKG_VERSION_MAJOR");
 const VERSION_MINOR: &str = env!("CARGO_PKG_VERSION_MINOR");
@@ -42,18 +42,19 @@ pub(crate) fn format_prompt_piece(
         brush_parser::prompt::PromptPiece::Backslash => "\\".to_owned(),
         brush_parser::prompt::PromptPiece::BellCharacter => "\x07".to_owned(),
         brush_parser::prompt::PromptPiece::CarriageReturn => "\r".to_owned(),
+        // N.B. The command number is approximated by the number of commands read so far.
         brush_parser::prompt::PromptPiece::CurrentCommandNumber => {
-            return error::unimp("prompt: current command number")
+            (shell.current_line_number + 1).to_string()
         }
         brush_parser::prompt::PromptPiece::CurrentHistoryNumber => {
-            return error::unimp("prompt: current history number")
+            shell.history.next_number().to_string()
         }
         brush_parser::prompt::PromptPiece::CurrentUser => users::get_current_username()?,
         brush_parser::prompt::PromptPiece::CurrentWorkingDirectory {
             tilde_replaced,
             basename,
         } => format_current_working_directory(shell, *tilde_replaced, *basename),
-        brush_parser::prompt::PromptPiece::Date(_) => return error::unimp("prompt: date"),
+        brush_parser::prompt::PromptPiece::Date(format) => format_date(format)?,
         brush_parser::prompt::PromptPiece::DollarOrPound => {
             if users::is_root() {
                 "#".to_owned()
@@ -78,9 +79,7 @@ pub(crate) fn format_prompt_piece(
             hn
         }
         brush_parser::prompt::PromptPiece::Newline => "\n".to_owned(),
-        brush_parser::prompt::PromptPiece::NumberOfManagedJobs => {
-            return error::unimp("prompt: number of managed jobs")
-        }
+        brush_parser::prompt::PromptPiece::NumberOfManagedJobs => shell.jobs.jobs.len().to_string(),
         brush_parser::prompt::PromptPiece::ShellBaseName => {
             if let Some(shell_name) = &shell.shell_name {
                 Path::new(shell_name)
@@ -99,14 +98,45 @@ pub(crate) fn format_prompt_piece(
         }
         brush_parser::prompt::PromptPiece::StartNonPrintingSequence => String::new(),
         brush_parser::prompt::PromptPiece::TerminalDeviceBaseName => {
-            return error::unimp("prompt: terminal device base name")
+            sys::terminal::get_terminal_device_name()
+                .and_then(|path| {
+                    path.file_name()
+                        .map(|name| name.to_string_lossy().to_string())
+                })
+                .unwrap_or_else(|| "tty".to_owned())
         }
-        brush_parser::prompt::PromptPiece::Time(_) => return error::unimp("prompt: time"),
+        brush_parser::prompt::PromptPiece::Time(format) => format_time(format)?,
     };
 
     Ok(formatted)
 }
 
+fn format_date(format: &brush_parser::prompt::PromptDateFormat) -> Result<String, error::Error> {
+    match format {
+        brush_parser::prompt::PromptDateFormat::WeekdayMonthDate => format_now("%a %b %d"),
+        // An empty format yields the locale's time representation.
+        brush_parser::prompt::PromptDateFormat::Custom(f) if f.is_empty() => format_now("%X"),
+        brush_parser::prompt::PromptDateFormat::Custom(f) => format_now(f),
+    }
+}
+
+fn format_time(format: &brush_parser::prompt::PromptTimeFormat) -> Result<String, error::Error> {
+    let format_str = match format {
+        brush_parser::prompt::PromptTimeFormat::TwelveHourAM => "%I:%M %p",
+        brush_parser::prompt::PromptTimeFormat::TwelveHourHHMMSS => "%I:%M:%S",
+        brush_parser::prompt::PromptTimeFormat::TwentyFourHourHHMM => "%H:%M",
+        brush_parser::prompt::PromptTimeFormat::TwentyFourHourHHMMSS => "%H:%M:%S",
+    };
+
+    format_now(format_str)
+}
+
+fn format_now(format_str: &str) -> Result<String, error::Error> {
+    let mut formatted = String::new();
+    write!(formatted, "{}", chrono::Local::now().format(format_str))?;
+    Ok(formatted)
+}
+
 fn format_current_working_directory(shell: &Shell, tilde_replaced: bool, basename: bool) -> String {
     let mut working_dir_str = shell.working_dir.to_string_lossy().to_string();
 
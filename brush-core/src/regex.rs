@@ -26,26 +26,42 @@ type RegexWord = Vec<RegexPiece>;
 #[derive(Clone, Debug)]
 pub struct Regex {
     pieces: RegexWord,
+    case_insensitive: bool,
 }
 
 impl From<RegexWord> for Regex {
     fn from(pieces: RegexWord) -> Self {
-        Self { pieces }
+        Self {
+            pieces,
+            case_insensitive: false,
+        }
     }
 }
 
 impl Regex {
+    /// Returns a version of the regular expression that matches without regard to case,
+    /// if requested.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - Whether or not the expression should match case-insensitively.
+    #[must_use]
+    pub(crate) fn set_case_insensitive(mut self, value: bool) -> Self {
+        self.case_insensitive = value;
+        self
+    }
+
     /// Computes if the regular expression matches the given string.
     ///
     /// # Arguments
     ///
     /// * `value` - The string to check for a match.
     pub fn matches(&self, value: &str) -> Result<Option<Vec<Option<String>>>, error::Error> {
-        let regex_pattern: String = self
-            .pieces
-            .iter()
-            .map(|piece| piece.to_regex_str())
-            .collect();
+        let mut regex_pattern = String::new();
+        if self.case_insensitive {
+            regex_pattern.push_str("(?i)");
+        }
+        regex_pattern.extend(self.pieces.iter().map(|piece| piece.to_regex_str()));
 
         // TODO: Evaluate how compatible the `fancy_regex` crate is with POSIX EREs.
         let re = compile_regex(regex_pattern)?;
@@ -11,10 +11,10 @@ use crate::interp::{self, Execute, ExecutionParameters, ExecutionResult};
 use crate::options::RuntimeOptions;
 use crate::sys::fs::PathExt;
 use crate::trace_categories;
-use crate::variables::{self, ShellValue, ShellVariable};
+use crate::variables::{self, DynamicValueGetter, DynamicValueSetter, ShellValue, ShellVariable};
 use crate::{
-    builtins, commands, completion, env, error, expansion, functions, jobs, keywords, openfiles,
-    patterns, prompt, sys::users, traps,
+    builtins, commands, completion, env, error, expansion, functions, histexpand, history, jobs,
+    keywords, openfiles, pathcache, patterns, prompt, sys, sys::users, traps,
 };
 
 /// Represents an instance of a shell.
@@ -25,6 +25,9 @@ pub struct Shell {
     pub traps: traps::TrapHandlerConfig,
     /// Manages files opened and accessible via redirection operators.
     pub open_files: openfiles::OpenFiles,
+    /// Incremented each time the shell's open files are permanently changed (e.g., by
+    /// `exec`), allowing in-flight execution to pick up the change.
+    pub(crate) open_files_generation: usize,
     /// The current working directory.
     pub working_dir: PathBuf,
     /// The shell environment, containing shell variables.
@@ -37,12 +40,28 @@ pub struct Shell {
     pub jobs: jobs::JobManager,
     /// Shell aliases.
     pub aliases: HashMap<String, String>,
+    /// Cache of resolved locations of external programs.
+    pub program_location_cache: pathcache::PathCache,
+    /// Resource limits modified in this shell, which apply to the processes it spawns.
+    pub(crate) resource_limits: sys::resource::ResourceLimits,
 
     //
     // Additional state
     /// The status of the last completed command.
     pub last_exit_status: u8,
 
+    /// The status of the last command substitution performed, if any; used as the status
+    /// of a command consisting only of assignments.
+    pub(crate) last_command_substitution_status: Option<u8>,
+
+    /// The value of `$!`: the process ID of the most recently started background job, or
+    /// its synthetic process ID if the job runs within the shell's own process.
+    pub(crate) last_background_job: Option<String>,
+
+    /// Number of variable values currently being recursively evaluated as arithmetic
+    /// expressions.
+    pub(crate) arithmetic_recursion_depth: usize,
+
     /// Clone depth from the original ancestor shell.
     pub depth: usize,
 
@@ -55,11 +74,11 @@ pub struct Shell {
     /// Detailed display string for the shell
     pub shell_product_display_str: Option<String>,
 
-    /// Script call stack.
-    pub script_call_stack: VecDeque<String>,
+    /// Call stack of active shell function invocations and scripts, innermost first.
+    pub call_stack: VecDeque<CallFrame>,
 
-    /// Function call stack.
-    pub function_call_stack: VecDeque<FunctionCall>,
+    /// Number of scripts currently being sourced (e.g., via `source` or `.`).
+    pub(crate) sourced_script_depth: usize,
 
     /// Directory stack used by pushd et al.
     pub directory_stack: Vec<PathBuf>,
@@ -70,8 +89,25 @@ pub struct Shell {
     /// Completion configuration.
     pub completion_config: completion::Config,
 
+    /// Commands previously entered into the shell.
+    pub history: history::History,
+
     /// Shell built-in commands.
     pub builtins: HashMap<String, builtins::Registration>,
+
+    /// Progress of `getopts` through a group of options (e.g., `-abc`): the value
+    /// of `OPTIND` it last set, along with the index of the next character to
+    /// process in the argument it refers to.
+    pub(crate) getopts_char_position: Option<(usize, usize)>,
+
+    /// Generator for the values of `$RANDOM`.
+    pub(crate) random_generator: variables::RandomGenerator,
+
+    /// The time from which `$SECONDS` counts.
+    pub(crate) seconds_start_time: std::time::Instant,
+
+    /// The value of `$SECONDS` at its start time.
+    pub(crate) seconds_start_value: i64,
 }
 
 impl Clone for Shell {
@@ -79,22 +115,34 @@ impl Clone for Shell {
         Self {
             traps: self.traps.clone(),
             open_files: self.open_files.clone(),
+            open_files_generation: self.open_files_generation,
             working_dir: self.working_dir.clone(),
             env: self.env.clone(),
             funcs: self.funcs.clone(),
             options: self.options.clone(),
             jobs: jobs::JobManager::new(),
             aliases: self.aliases.clone(),
+            program_location_cache: self.program_location_cache.clone(),
+            resource_limits: self.resource_limits.clone(),
             last_exit_status: self.last_exit_status,
+            last_command_substitution_status: None,
+            last_background_job: self.last_background_job.clone(),
+            arithmetic_recursion_depth: 0,
             positional_parameters: self.positional_parameters.clone(),
             shell_name: self.shell_name.clone(),
             shell_product_display_str: self.shell_product_display_str.clone(),
-            function_call_stack: self.function_call_stack.clone(),
-            script_call_stack: self.script_call_stack.clone(),
+            sourced_script_depth: self.sourced_script_depth,
+            call_stack: self.call_stack.clone(),
             directory_stack: self.directory_stack.clone(),
             current_line_number: self.current_line_number,
             completion_config: self.completion_config.clone(),
+            history: self.history.clone(),
             builtins: self.builtins.clone(),
+            getopts_char_position: self.getopts_char_position,
+            // N.B. Like bash, subshells get their own random sequences.
+            random_generator: variables::RandomGenerator::default(),
+            seconds_start_time: self.seconds_start_time,
+            seconds_start_value: self.seconds_start_value,
             depth: self.depth + 1,
         }
     }
@@ -147,13 +195,38 @@ pub struct CreateOptions {
     pub verbose: bool,
 }
 
-/// Represents an active shell function call.
+/// Represents an active invocation of a shell function or script.
 #[derive(Clone, Debug)]
-pub struct FunctionCall {
-    /// The name of the function invoked.
-    function_name: String,
-    /// The definition of the invoked function.
-    function_definition: Arc<brush_parser::ast::FunctionDefinition>,
+pub struct CallFrame {
+    /// What was invoked.
+    pub kind: CallFrameKind,
+    /// The source file containing the code run by this frame.
+    pub source: String,
+    /// The line number from which the frame was invoked.
+    pub call_line: u32,
+}
+
+/// Describes what was invoked to create a call frame.
+#[derive(Clone, Debug)]
+pub enum CallFrameKind {
+    /// The named shell function.
+    Function(String),
+    /// A script sourced via `source` or `.`.
+    SourcedScript,
+    /// A script run by the shell.
+    Script,
+}
+
+impl CallFrame {
+    /// Returns the name of the frame: the function name for a function invocation,
+    /// `source` for a sourced script, or `main` otherwise.
+    pub fn name(&self) -> &str {
+        match &self.kind {
+            CallFrameKind::Function(name) => name.as_str(),
+            CallFrameKind::SourcedScript => "source",
+            CallFrameKind::Script => "main",
+        }
+    }
 }
 
 lazy_static::lazy_static! {
@@ -173,22 +246,33 @@ impl Shell {
         let mut shell = Shell {
             traps: traps::TrapHandlerConfig::default(),
             open_files: openfiles::OpenFiles::default(),
+            open_files_generation: 0,
             working_dir: std::env::current_dir()?,
             env: Self::initialize_vars(options)?,
             funcs: functions::FunctionEnv::default(),
             options: RuntimeOptions::defaults_from(options),
             jobs: jobs::JobManager::new(),
             aliases: HashMap::default(),
+            program_location_cache: pathcache::PathCache::default(),
+            resource_limits: sys::resource::ResourceLimits::default(),
             last_exit_status: 0,
+            last_command_substitution_status: None,
+            last_background_job: None,
+            arithmetic_recursion_depth: 0,
             positional_parameters: vec![],
             shell_name: options.shell_name.clone(),
             shell_product_display_str: options.shell_product_display_str.clone(),
-            function_call_stack: VecDeque::new(),
-            script_call_stack: VecDeque::new(),
+            sourced_script_depth: 0,
+            call_stack: VecDeque::new(),
             directory_stack: vec![],
             current_line_number: 0,
             completion_config: completion::Config::default(),
+            history: history::History::default(),
             builtins: builtins::get_default_builtins(options),
+            getopts_char_position: None,
+            random_generator: variables::RandomGenerator::default(),
+            seconds_start_time: std::time::Instant::now(),
+            seconds_start_value: 0,
             depth: 0,
         };
 
@@ -199,6 +283,13 @@ impl Shell {
         // Load profiles/configuration.
         shell.load_config(options).await?;
 
+        // Load history, if it's to be kept.
+        if shell.options.enable_command_history {
+            if let Err(e) = shell.load_history() {
+                tracing::debug!("couldn't load history: {e}");
+            }
+        }
+
         Ok(shell)
     }
 
@@ -223,12 +314,107 @@ impl Shell {
             env.set_global("EUID", euid_var)?;
         }
 
-        let mut random_var = ShellVariable::new(ShellValue::Random);
-        random_var.hide_from_enumeration();
-        random_var.treat_as_integer();
-        env.set_global("RANDOM", random_var)?;
+        #[cfg(unix)]
+        {
+            let mut ppid_var = ShellVariable::new(ShellValue::String(
+                std::os::unix::process::parent_id().to_string(),
+            ));
+            ppid_var.set_readonly();
+            env.set_global("PPID", ppid_var)?;
+        }
+
+        // Set up the variables whose values are computed each time they're read.
+        let dynamic_vars: [(&str, DynamicValueGetter, DynamicValueSetter); 5] = [
+            // N.B. Subshells run within the shell's own process rather than in forked
+            // children, so they have no process ID of their own. Unlike in bash, this is
+            // always the shell's process ID (i.e., `$$`), even in a subshell.
+            (
+                "BASHPID",
+                |_| ShellValue::String(std::process::id().to_string()),
+                |_, _| (),
+            ),
+            (
+                "LINENO",
+                |shell| ShellValue::String(shell.current_line_number.to_string()),
+                |shell, value| {
+                    if let Ok(line_number) = value.parse() {
+                        shell.current_line_number = line_number;
+                    }
+                },
+            ),
+            (
+                "RANDOM",
+                |shell| ShellValue::String(shell.random_generator.next_value().to_string()),
+                |shell, value| {
+                    // N.B. Like bash, only the low 32 bits of the seed are used.
+                    if let Ok(seed) = value.parse::<i64>() {
+                        let seed = u32::try_from(seed & 0xffff_ffff).unwrap_or_default();
+                        shell.random_generator.seed(seed);
+                    }
+                },
+            ),
+            (
+                "SECONDS",
+                |shell| {
+                    let elapsed = shell.seconds_start_time.elapsed().as_secs();
+                    let elapsed = i64::try_from(elapsed).unwrap_or(i64::MAX);
+                    ShellValue::String(
+                        shell
+                            .seconds_start_value
+                            .saturating_add(elapsed)
+                            .to_string(),
+                    )
+                },
+                |shell, value| {
+                    if let Ok(seconds) = value.parse() {
+                        shell.seconds_start_time = std::time::Instant::now();
+                        shell.seconds_start_value = seconds;
+                    }
+                },
+            ),
+            // N.B. The thread-local generator is cryptographically secure; unlike `RANDOM`,
+            // it can't be seeded, so assignments are ignored.
+            (
+                "SRANDOM",
+                |_| ShellValue::String(rand::random::<u32>().to_string()),
+                |_, _| (),
+            ),
+        ];
+
+        for (name, getter, setter) in dynamic_vars {
+            let mut var = ShellVariable::new(ShellValue::Dynamic { getter, setter });
+            var.hide_from_enumeration();
+            var.treat_as_integer();
+            env.set_global(name, var)?;
+        }
+
+        // N.B. Assignments to these are ignored.
+        let epoch_vars: [(&str, DynamicValueGetter); 2] = [
+            ("EPOCHSECONDS", |_| {
+                ShellValue::String(get_time_since_epoch().as_secs().to_string())
+            }),
+            ("EPOCHREALTIME", |shell| {
+                let now = get_time_since_epoch();
+                ShellValue::String(std::format!(
+                    "{}{}{:06}",
+                    now.as_secs(),
+                    shell.numeric_locale_decimal_point(),
+                    now.subsec_micros()
+                ))
+            }),
+        ];
+
+        for (name, getter) in epoch_vars {
+            let mut var = ShellVariable::new(ShellValue::Dynamic {
+                getter,
+                setter: |_, _| (),
+            });
+            var.hide_from_enumeration();
+            env.set_global(name, var)?;
+        }
 
         env.set_global("IFS", ShellVariable::new(" \t\n".into()))?;
+        env.set_global("OPTIND", ShellVariable::new("1".into()))?;
         env.set_global(
             "COMP_WORDBREAKS",
             ShellVariable::new(" \t\n\"\'><=;|&(:".into()),
@@ -261,6 +447,13 @@ impl Shell {
             )?;
         }
 
+        // `$_` starts out as the name the shell was invoked with; afterwards, it's updated with
+        // the last argument of each command run.
+        env.set_global(
+            "_",
+            ShellVariable::new(options.shell_name.as_deref().unwrap_or_default().into()),
+        )?;
+
         if !options.sh_mode {
             if let Some(shell_name) = &options.shell_name {
                 env.set_global("BASH", ShellVariable::new(shell_name.into()))?;
@@ -380,6 +573,27 @@ impl Shell {
         path: &Path,
         args: &[S],
         params: &ExecutionParameters,
+    ) -> Result<ExecutionResult, error::Error> {
+        self.sourced_script_depth += 1;
+        let result = self.execute_file(path, args, params).await;
+        self.sourced_script_depth -= 1;
+
+        result
+    }
+
+    /// Executes the given file as a shell script in the current shell, returning the
+    /// execution result.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the file to execute.
+    /// * `args` - The arguments to pass to the script as positional parameters.
+    /// * `params` - Execution parameters.
+    async fn execute_file<S: AsRef<str>>(
+        &mut self,
+        path: &Path,
+        args: &[S],
+        params: &ExecutionParameters,
     ) -> Result<ExecutionResult, error::Error> {
         tracing::debug!("sourcing: {}", path.display());
         let opened_file: openfiles::OpenFile = self
@@ -421,35 +635,38 @@ impl Shell {
             brush_parser::Parser::new(&mut reader, &self.parser_options(), source_info);
 
         tracing::debug!(target: trace_categories::PARSE, "Parsing sourced file: {}", source_info.source);
-        let parse_result = parser.parse(false);
 
-        let mut other_positional_parameters = args.iter().map(|s| s.as_ref().to_owned()).collect();
-        let mut other_shell_name = Some(source_info.source.clone());
+        // If arguments were provided, they replace the positional parameters for the
+        // duration of the script; otherwise, the script sees (and may update) the current
+        // positional parameters.
+        let mut other_positional_parameters: Option<Vec<String>> = if args.is_empty() {
+            None
+        } else {
+            Some(args.iter().map(|s| s.as_ref().to_owned()).collect())
+        };
 
         // TODO: Find a cleaner way to change args.
-        std::mem::swap(&mut self.shell_name, &mut other_shell_name);
-        std::mem::swap(
-            &mut self.positional_parameters,
-            &mut other_positional_parameters,
-        );
+        if let Some(other_positional_parameters) = &mut other_positional_parameters {
+            std::mem::swap(&mut self.positional_parameters, other_positional_parameters);
+        }
 
-        self.script_call_stack
-            .push_front(source_info.source.clone());
-        self.update_bash_source_var()?;
+        let kind = if self.in_sourced_script() {
+            CallFrameKind::SourcedScript
+        } else {
+            CallFrameKind::Script
+        };
+        self.push_call_frame(kind, source_info.source.as_str())?;
 
         let result = self
-            .run_parsed_result(parse_result, source_info, params)
+            .run_parsed_commands(&mut parser, source_info, params)
             .await;
 
-        self.script_call_stack.pop_front();
-        self.update_bash_source_var()?;
+        self.pop_call_frame()?;
 
         // Restore.
-        std::mem::swap(&mut self.shell_name, &mut other_shell_name);
-        std::mem::swap(
-            &mut self.positional_parameters,
-            &mut other_positional_parameters,
-        );
+        if let Some(other_positional_parameters) = &mut other_positional_parameters {
+            std::mem::swap(&mut self.positional_parameters, other_positional_parameters);
+        }
 
         result
     }
@@ -514,14 +731,86 @@ impl Shell {
         // each string we run could be multiple lines.
         self.current_line_number += 1;
 
-        let parse_result = self.parse_string(command);
         let source_info = brush_parser::SourceInfo {
             source: String::from("main"),
         };
-        self.run_parsed_result(parse_result, &source_info, params)
+
+        // Each command is parsed only once the ones before it have run, so that aliases
+        // (and the options enabling them) defined earlier in the string apply to it.
+        let mut reader = std::io::BufReader::new(command.as_bytes());
+        let mut parser =
+            brush_parser::Parser::new(&mut reader, &self.parser_options(), &source_info);
+
+        self.run_parsed_commands(&mut parser, &source_info, params)
             .await
     }
 
+    /// Runs the given trap handler. The last exit status is preserved across the handler,
+    /// unless the handler exits the shell.
+    ///
+    /// # Arguments
+    ///
+    /// * `handler` - The trap handler command to run.
+    /// * `params` - Execution parameters.
+    pub(crate) async fn run_trap_handler(
+        &mut self,
+        handler: String,
+        params: &ExecutionParameters,
+    ) -> Result<ExecutionResult, error::Error> {
+        let last_exit_status = self.last_exit_status;
+
+        self.traps.handler_depth += 1;
+        let result = self.run_string(handler, params).await;
+        self.traps.handler_depth -= 1;
+
+        let result = result?;
+        if !result.exit_shell {
+            self.last_exit_status = last_exit_status;
+        }
+
+        Ok(result)
+    }
+
+    /// Runs the handlers registered for any trapped signals received since the last check.
+    /// Returns the result of the last handler run, if any were run.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - Execution parameters.
+    pub(crate) async fn run_pending_signal_traps(
+        &mut self,
+        params: &ExecutionParameters,
+    ) -> Result<Option<ExecutionResult>, error::Error> {
+        let mut result = None;
+
+        for signal in sys::signal::take_pending_trapped_signals() {
+            let handler = self.traps.handlers.get(&signal).cloned();
+            if let Some(handler) = handler.filter(|handler| !handler.is_empty()) {
+                let handler_result = self.run_trap_handler(handler, params).await?;
+                let exit_shell = handler_result.exit_shell;
+                result = Some(handler_result);
+
+                if exit_shell {
+                    break;
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Runs the handler registered for the EXIT trap, if there is one. The handler is
+    /// unregistered before it is run, ensuring it runs at most once. Expected to be
+    /// called when the shell is about to exit.
+    pub async fn run_exit_trap(&mut self) -> Result<(), error::Error> {
+        if let Some(handler) = self.traps.handlers.remove(&traps::TrapSignal::Exit) {
+            let params = self.default_exec_params();
+            self.run_trap_handler(handler, &params).await?;
+        }
+
+        Ok(())
+    }
+
     /// Parses the given string as a shell program, returning the resulting Abstract Syntax Tree
     /// for the program.
     ///
@@ -570,6 +859,16 @@ impl Shell {
         }
     }
 
+    /// Permanently replaces the shell's open files with the given ones.
+    ///
+    /// # Arguments
+    ///
+    /// * `open_files` - The new set of open files.
+    pub(crate) fn replace_open_files(&mut self, open_files: openfiles::OpenFiles) {
+        self.open_files = open_files;
+        self.open_files_generation += 1;
+    }
+
     /// Executes the given script file, returning the resulting exit status.
     ///
     /// # Arguments
@@ -581,11 +880,61 @@ impl Shell {
         script_path: &Path,
         args: &[S],
     ) -> Result<ExecutionResult, error::Error> {
-        self.source(script_path, args, &self.default_exec_params())
+        // The script is now what the shell is running; it's what $0 should refer to.
+        self.shell_name = Some(script_path.to_string_lossy().to_string());
+
+        self.execute_file(script_path, args, &self.default_exec_params())
             .await
     }
 
-    async fn run_parsed_result(
+    /// Parses and runs the input of the given parser one complete command at a time, so that
+    /// aliases defined by a command apply to the commands that follow it.
+    ///
+    /// # Arguments
+    ///
+    /// * `parser` - The parser to read commands from.
+    /// * `source_info` - Information about the source of the commands.
+    /// * `params` - Execution parameters.
+    async fn run_parsed_commands<R: std::io::BufRead>(
+        &mut self,
+        parser: &mut brush_parser::Parser<R>,
+        source_info: &brush_parser::SourceInfo,
+        params: &ExecutionParameters,
+    ) -> Result<ExecutionResult, error::Error> {
+        let no_aliases = HashMap::new();
+        let mut commands = parser.commands();
+        let mut result = ExecutionResult::success();
+        let mut params = Cow::Borrowed(params);
+        let mut open_files_generation = self.open_files_generation;
+
+        loop {
+            let aliases = if self.options.expand_aliases {
+                &self.aliases
+            } else {
+                &no_aliases
+            };
+
+            let parse_result = match commands.parse_next(aliases) {
+                Ok(Some(program)) => Ok(program),
+                Ok(None) => break,
+                Err(e) => Err(e),
+            };
+            let parse_failed = parse_result.is_err();
+
+            result = self
+                .run_parsed_result(parse_result, source_info, &params)
+                .await?;
+            interp::refresh_open_files(self, &mut params, &mut open_files_generation)?;
+
+            if parse_failed || result.exit_shell || result.return_from_function_or_script {
+                break;
+            }
+        }
+
+        Ok(result)
+    }
+
+    pub(crate) async fn run_parsed_result(
         &mut self,
         parse_result: Result<brush_parser::ast::Program, brush_parser::ParseError>,
         source_info: &brush_parser::SourceInfo,
@@ -603,7 +952,11 @@ impl Shell {
                 Err(e) => {
                     tracing::error!("error: {:#}", e);
                     self.last_exit_status = 1;
-                    ExecutionResult::new(1)
+
+                    let mut result = ExecutionResult::new(1);
+                    result.exit_shell =
+                        !self.options.interactive && e.is_fatal_to_non_interactive_shell();
+                    result
                 }
             },
             Err(brush_parser::ParseError::ParsingNearToken(token_near_error)) => {
@@ -619,12 +972,23 @@ impl Shell {
                 self.last_exit_status = 2;
                 ExecutionResult::new(2)
             }
+            #[allow(deprecated)]
             Err(brush_parser::ParseError::ParsingAtEndOfInput) => {
                 tracing::error!("{}syntax error at end of input", error_prefix);
 
                 self.last_exit_status = 2;
                 ExecutionResult::new(2)
             }
+            Err(brush_parser::ParseError::UnexpectedEndOfInput { inner }) => {
+                if let Some(inner) = inner {
+                    tracing::error!("{}{}", error_prefix, inner);
+                }
+
+                tracing::error!("{}syntax error at end of input", error_prefix);
+
+                self.last_exit_status = 2;
+                ExecutionResult::new(2)
+            }
             Err(brush_parser::ParseError::Tokenizing { inner, position }) => {
                 let mut error_message = error_prefix.clone();
                 error_message.push_str(inner.to_string().as_str());
@@ -674,8 +1038,26 @@ impl Shell {
         // Retrieve the spec.
         let ps1 = self.parameter_or_default("PS1", self.default_prompt());
 
-        // Expand it.
-        let formatted_prompt = prompt::expand_prompt(self, ps1.as_ref())?;
+        self.expand_prompt_string(ps1.as_str()).await
+    }
+
+    /// Compose's the shell's alternate-side prompt, applying all appropriate expansions.
+    #[allow(clippy::unused_async)]
+    pub async fn compose_alt_side_prompt(&mut self) -> Result<String, error::Error> {
+        Ok(String::new())
+    }
+
+    /// Composes the shell's continuation prompt (displayed when more input is needed
+    /// to complete a command), applying all appropriate expansions.
+    pub async fn compose_continuation_prompt(&mut self) -> Result<String, error::Error> {
+        let ps2 = self.parameter_or_default("PS2", "> ");
+
+        self.expand_prompt_string(ps2.as_str()).await
+    }
+
+    async fn expand_prompt_string(&mut self, spec: &str) -> Result<String, error::Error> {
+        // Expand prompt escape sequences.
+        let formatted_prompt = prompt::expand_prompt(self, spec)?;
 
         // NOTE: We're having difficulty with xterm escape sequences going through rustyline;
         // so we strip them here.
@@ -689,28 +1071,14 @@ impl Shell {
         Ok(formatted_prompt)
     }
 
-    /// Compose's the shell's alternate-side prompt, applying all appropriate expansions.
-    #[allow(clippy::unused_async)]
-    pub async fn compose_alt_side_prompt(&mut self) -> Result<String, error::Error> {
-        Ok(String::new())
-    }
-
-    /// Retrieve's the shell's continuation prompt.
-    pub fn continuation_prompt(&self) -> Result<String, error::Error> {
-        // TODO: Evaluate expansion?
-        Ok(self.parameter_or_default("PS2", "> "))
-    }
-
     /// Returns the exit status of the last command executed in this shell.
     pub fn last_result(&self) -> u8 {
         self.last_exit_status
     }
 
     fn parameter_or_default(&self, name: &str, default: &str) -> String {
-        self.env.get(name).map_or_else(
-            || default.to_owned(),
-            |(_, s)| s.value().to_cow_string().to_string(),
-        )
+        self.get_env_str(name)
+            .map_or_else(|| default.to_owned(), Cow::into_owned)
     }
 
     /// Returns a string representing the current `set`-style option flags set in the shell.
@@ -756,7 +1124,14 @@ impl Shell {
 
     /// Returns whether or not the shell is actively executing in a shell function.
     pub(crate) fn in_function(&self) -> bool {
-        !self.function_call_stack.is_empty()
+        self.call_stack
+            .iter()
+            .any(|frame| matches!(frame.kind, CallFrameKind::Function(_)))
+    }
+
+    /// Returns whether or not the shell is actively executing a sourced script.
+    pub(crate) fn in_sourced_script(&self) -> bool {
+        self.sourced_script_depth > 0
     }
 
     /// Updates the shell's internal tracking state to reflect that a new shell
@@ -771,77 +1146,196 @@ impl Shell {
         name: &str,
         function_def: &Arc<brush_parser::ast::FunctionDefinition>,
     ) -> Result<(), error::Error> {
-        self.function_call_stack.push_front(FunctionCall {
-            function_name: name.to_owned(),
-            function_definition: function_def.clone(),
-        });
         self.env.push_scope(env::EnvironmentScope::Local);
-        self.update_funcname_var()?;
-        Ok(())
+        self.push_call_frame(
+            CallFrameKind::Function(name.to_owned()),
+            function_def.source.as_str(),
+        )
     }
 
     /// Updates the shell's internal tracking state to reflect that the shell
     /// has exited the top-most function on its call stack.
     pub(crate) fn leave_function(&mut self) -> Result<(), error::Error> {
         self.env.pop_scope(env::EnvironmentScope::Local)?;
-        self.function_call_stack.pop_front();
-        self.update_funcname_var()?;
-        Ok(())
+        self.pop_call_frame()
     }
 
-    fn update_funcname_var(&mut self) -> Result<(), error::Error> {
-        //
-        // Fill out FUNCNAME[*]
-        //
-        let funcname_values = self
-            .function_call_stack
-            .iter()
-            .map(|s| (None, s.function_name.clone()))
-            .collect::<Vec<_>>();
-
-        self.env.update_or_add(
-            "FUNCNAME",
-            variables::ShellValueLiteral::Array(variables::ArrayLiteral(funcname_values)),
-            |_| Ok(()),
-            EnvironmentLookup::Anywhere,
-            EnvironmentScope::Global,
-        )?;
+    fn push_call_frame(&mut self, kind: CallFrameKind, source: &str) -> Result<(), error::Error> {
+        self.call_stack.push_front(CallFrame {
+            kind,
+            source: source.to_owned(),
+            call_line: self.current_line_number,
+        });
+        self.update_call_stack_vars()
+    }
 
-        self.update_bash_source_var()
+    fn pop_call_frame(&mut self) -> Result<(), error::Error> {
+        self.call_stack.pop_front();
+        self.update_call_stack_vars()
     }
 
-    fn update_bash_source_var(&mut self) -> Result<(), error::Error> {
+    fn update_call_stack_vars(&mut self) -> Result<(), error::Error> {
         //
-        // Fill out BASH_SOURCE[*]
+        // Fill out FUNCNAME[*]; it's only populated while a function is running.
         //
-        let source_values = if self.function_call_stack.is_empty() {
-            self.script_call_stack
-                .front()
-                .map_or_else(Vec::new, |s| vec![(None, s.to_owned())])
-        } else {
-            self.function_call_stack
+        let funcname_values = if self.in_function() {
+            self.call_stack
                 .iter()
-                .map(|s| (None, s.function_definition.source.clone()))
+                .map(|frame| (None, frame.name().to_owned()))
                 .collect::<Vec<_>>()
+        } else {
+            vec![]
         };
 
-        self.env.update_or_add(
-            "BASH_SOURCE",
-            variables::ShellValueLiteral::Array(variables::ArrayLiteral(source_values)),
-            |_| Ok(()),
-            EnvironmentLookup::Anywhere,
-            EnvironmentScope::Global,
-        )?;
+        //
+        // Fill out BASH_SOURCE[*] and BASH_LINENO[*]
+        //
+        let source_values = self
+            .call_stack
+            .iter()
+            .map(|frame| (None, frame.source.clone()))
+            .collect::<Vec<_>>();
+
+        let lineno_values = self
+            .call_stack
+            .iter()
+            .map(|frame| (None, frame.call_line.to_string()))
+            .collect::<Vec<_>>();
+
+        for (name, values) in [
+            ("FUNCNAME", funcname_values),
+            ("BASH_SOURCE", source_values),
+            ("BASH_LINENO", lineno_values),
+        ] {
+            self.env.update_or_add(
+                name,
+                variables::ShellValueLiteral::Array(variables::ArrayLiteral(values)),
+                |_| Ok(()),
+                EnvironmentLookup::Anywhere,
+                EnvironmentScope::Global,
+            )?;
+        }
 
         Ok(())
     }
 
     /// Returns the path to the history file used by the shell, if one is set.
     pub fn get_history_file_path(&self) -> Option<PathBuf> {
-        self.env.get("HISTFILE").map(|(_, var)| {
-            let histfile_str: String = var.value().to_cow_string().to_string();
-            PathBuf::from(histfile_str)
-        })
+        self.env
+            .get_str("HISTFILE")
+            .filter(|histfile| !histfile.is_empty())
+            .map(|histfile| PathBuf::from(histfile.as_ref()))
+    }
+
+    /// Records the given command in the shell's history list, if command history is
+    /// enabled. Entries are filtered as directed by `HISTCONTROL`, and the list is kept
+    /// within the size given by `HISTSIZE`.
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - The command to record.
+    pub fn add_to_history(&mut self, command: &str) {
+        if !self.options.enable_command_history {
+            return;
+        }
+
+        let command = command.trim_end_matches('\n');
+        if command.trim().is_empty() {
+            return;
+        }
+
+        let history_control = self.env.get_str("HISTCONTROL").unwrap_or_default();
+        let controls: Vec<_> = history_control.split(':').collect();
+        let has_control = |names: &[&str]| controls.iter().any(|c| names.contains(c));
+
+        if (has_control(&["ignorespace", "ignoreboth"]) && command.starts_with(' '))
+            || (has_control(&["ignoredups", "ignoreboth"]) && self.is_last_history_entry(command))
+        {
+            return;
+        }
+
+        if has_control(&["erasedups"]) {
+            self.history.remove_matching(command);
+        }
+
+        let max_entries = self.get_history_limit("HISTSIZE");
+        self.history.add(command.to_owned(), max_entries);
+    }
+
+    /// Applies history expansion (e.g., `!!` or `^old^new^`) to the given line of input,
+    /// if enabled by the `histexpand` option.
+    ///
+    /// # Arguments
+    ///
+    /// * `line` - The line of input to expand.
+    pub fn expand_history(&self, line: &str) -> Result<histexpand::HistoryExpansion, error::Error> {
+        if !self.options.enable_bang_style_history_substitution {
+            return Ok(histexpand::HistoryExpansion {
+                line: line.to_owned(),
+                expanded: false,
+                print_only: false,
+            });
+        }
+
+        histexpand::expand(line, &self.history)
+    }
+
+    fn is_last_history_entry(&self, command: &str) -> bool {
+        self.history
+            .last_number()
+            .and_then(|number| self.history.get(number))
+            .is_some_and(|last| last == command)
+    }
+
+    /// Returns the limit imposed by the given history size variable (i.e., `HISTSIZE` or
+    /// `HISTFILESIZE`): a negative value means no limit, while an unset or non-numeric
+    /// value selects the default limit.
+    ///
+    /// # Arguments
+    ///
+    /// * `var_name` - The name of the variable.
+    pub(crate) fn get_history_limit(&self, var_name: &str) -> Option<usize> {
+        match self
+            .env
+            .get_str(var_name)
+            .map(|size| size.trim().parse::<i64>())
+        {
+            Some(Ok(size)) if size < 0 => None,
+            Some(Ok(size)) => Some(usize::try_from(size).unwrap_or(usize::MAX)),
+            Some(Err(_)) | None => Some(history::DEFAULT_MAX_ENTRIES),
+        }
+    }
+
+    /// Loads the history list from the shell's history file, reading at most
+    /// `HISTFILESIZE` of its most recent lines.
+    pub fn load_history(&mut self) -> Result<(), error::Error> {
+        if let Some(history_file_path) = self.get_history_file_path() {
+            let max_lines = self.get_history_limit("HISTFILESIZE");
+            let max_entries = self.get_history_limit("HISTSIZE");
+            self.history
+                .read_file(&history_file_path, max_lines, max_entries)?;
+        }
+
+        Ok(())
+    }
+
+    /// Saves the history list to the shell's history file, then truncates the file to
+    /// `HISTFILESIZE` lines. With `histappend` enabled, the commands added during this
+    /// session are appended to the file; otherwise, the file is overwritten.
+    pub fn save_history(&mut self) -> Result<(), error::Error> {
+        if let Some(history_file_path) = self.get_history_file_path() {
+            if self.options.append_to_history_file {
+                self.history.append_file(&history_file_path)?;
+            } else {
+                self.history.write_file(&history_file_path)?;
+            }
+
+            if let Some(max_lines) = self.get_history_limit("HISTFILESIZE") {
+                history::truncate_file(&history_file_path, max_lines)?;
+            }
+        }
+
+        Ok(())
     }
 
     /// Returns the number of the line being executed in the currently executing program.
@@ -849,12 +1343,101 @@ impl Shell {
         self.current_line_number
     }
 
+    /// Returns whether the shell's character-handling locale (as selected by the `LC_ALL`,
+    /// `LC_CTYPE`, and `LANG` variables) uses UTF-8 encoding; if not, strings are treated
+    /// as sequences of bytes.
+    pub(crate) fn uses_utf8_locale(&self) -> bool {
+        let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+            .into_iter()
+            .filter_map(|name| self.env.get_str(name))
+            .find(|value| !value.is_empty());
+
+        locale.is_some_and(|locale| {
+            let locale = locale.to_ascii_lowercase();
+            locale.contains("utf-8") || locale.contains("utf8")
+        })
+    }
+
+    /// Returns the decimal point used by the shell's numeric formatting locale (as selected
+    /// by the `LC_ALL`, `LC_NUMERIC`, and `LANG` variables).
+    ///
+    /// This is a known approximation: the system's locale definitions aren't consulted,
+    /// so the decimal point is guessed from the locale's language alone. Unlike bash, a
+    /// comma is used even if the locale isn't installed, and regional variants that differ
+    /// from their language's usual convention (e.g., `de_CH`) aren't recognized.
+    pub(crate) fn numeric_locale_decimal_point(&self) -> char {
+        // Languages whose locales conventionally use a decimal comma.
+        const DECIMAL_COMMA_LANGUAGES: &[&str] = &[
+            "bg", "ca", "cs", "da", "de", "el", "es", "et", "fi", "fr", "hr", "hu", "id", "it",
+            "lt", "lv", "nb", "nl", "nn", "pl", "pt", "ro", "ru", "sk", "sl", "sr", "sv", "tr",
+            "uk", "vi",
+        ];
+
+        let locale = ["LC_ALL", "LC_NUMERIC", "LANG"]
+            .into_iter()
+            .filter_map(|name| self.env.get_str(name))
+            .find(|value| !value.is_empty());
+
+        let language = locale.as_deref().map_or("", |locale| {
+            locale
+                .split(|c| matches!(c, '_' | '.' | '@'))
+                .next()
+                .unwrap_or_default()
+        });
+
+        if DECIMAL_COMMA_LANGUAGES.contains(&language) {
+            ','
+        } else {
+            '.'
+        }
+    }
+
+    /// Tries to retrieve the string value of the variable with the given name, computing
+    /// it if the variable is dynamic.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the variable to retrieve.
+    pub fn get_env_str<S: AsRef<str>>(&self, name: S) -> Option<Cow<'_, str>> {
+        let (_, var) = self.env.get(name)?;
+        match var.resolve_value(self) {
+            Cow::Borrowed(value) => Some(value.to_cow_string()),
+            Cow::Owned(value) => Some(Cow::Owned(value.to_cow_string().into_owned())),
+        }
+    }
+
     /// Returns the current value of the IFS variable, or the default value if it is not set.
     pub(crate) fn get_ifs(&self) -> Cow<'_, str> {
-        self.env.get("IFS").map_or_else(
-            || Cow::Borrowed(" \t\n"),
-            |(_, v)| v.value().to_cow_string(),
-        )
+        self.get_env_str("IFS").unwrap_or(Cow::Borrowed(" \t\n"))
+    }
+
+    /// Resets any shell state derived from the given variable when a script assigns or
+    /// declares it (e.g., assigning to `OPTIND` restarts option parsing by `getopts`).
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the variable being assigned.
+    pub(crate) fn on_variable_assigned(&mut self, name: &str) {
+        if name == "OPTIND" {
+            self.getopts_char_position = None;
+        }
+    }
+
+    /// Hands a scalar value being assigned to the named variable to its setter, if it's a
+    /// writable dynamic variable. Returns whether it was; if not, the caller is responsible
+    /// for making the assignment.
+    pub(crate) fn try_assign_dynamic_variable(&mut self, name: &str, value: &str) -> bool {
+        let setter = self.env.get(name).and_then(|(_, var)| match var.value() {
+            ShellValue::Dynamic { setter, .. } if !var.is_readonly() => Some(*setter),
+            _ => None,
+        });
+
+        if let Some(setter) = setter {
+            setter(self, value);
+            true
+        } else {
+            false
+        }
     }
 
     /// Generates command completions for the shell.
@@ -890,6 +1473,8 @@ impl Shell {
             if let Ok(entries) = patterns::Pattern::from(pattern).expand(
                 &self.working_dir,
                 self.options.extended_globbing,
+                false,
+                false,
                 Some(&is_executable),
             ) {
                 for entry in entries {
@@ -901,6 +1486,70 @@ impl Shell {
         executables
     }
 
+    /// Finds the first executable in the shell's current default PATH with the given name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the executable to find.
+    pub fn find_first_executable_in_path(&self, name: &str) -> Option<PathBuf> {
+        for dir_str in self.env.get_str("PATH").unwrap_or_default().split(':') {
+            let candidate = self.get_absolute_path(Path::new(dir_str)).join(name);
+            if candidate.is_file() && candidate.executable() {
+                return Some(candidate);
+            }
+        }
+
+        None
+    }
+
+    /// Returns the cached location of the given program, if one was cached while the
+    /// current value of PATH was in effect.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the program to look up.
+    pub fn get_cached_program_location(&self, name: &str) -> Option<PathBuf> {
+        let search_path = self.env.get_str("PATH").unwrap_or_default();
+        if !self
+            .program_location_cache
+            .is_valid_for_search_path(search_path.as_ref())
+        {
+            return None;
+        }
+
+        self.program_location_cache
+            .get(name)
+            .map(|entry| entry.path.clone())
+    }
+
+    /// Finds the first executable in the shell's current default PATH with the given name,
+    /// consulting and updating the shell's cache of program locations.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the executable to find.
+    pub fn find_first_executable_in_path_using_cache(&mut self, name: &str) -> Option<PathBuf> {
+        let search_path = self.env.get_str("PATH").unwrap_or_default().into_owned();
+        self.program_location_cache
+            .sync_with_search_path(search_path.as_str());
+
+        if let Some(path) = self.program_location_cache.get_and_record_hit(name) {
+            if path.executable() {
+                return Some(path);
+            }
+
+            // The cached location is stale; forget it and search again.
+            self.program_location_cache.remove(name);
+        }
+
+        let path = self.find_first_executable_in_path(name)?;
+        self.program_location_cache
+            .set(name.to_owned(), path.clone());
+        self.program_location_cache.get_and_record_hit(name);
+
+        Some(path)
+    }
+
     /// Gets the absolute form of the given path.
     ///
     /// # Arguments
@@ -945,18 +1594,20 @@ impl Shell {
         Ok(std::fs::File::open(path_to_open)?.into())
     }
 
-    /// Sets the shell's current working directory to the given path.
+    /// Sets the shell's current working directory to the given path. The path is
+    /// interpreted logically: `..` components remove the preceding component rather than
+    /// referring to the physical parent of a symlinked directory.
     ///
     /// # Arguments
     ///
     /// * `target_dir` - The path to set as the working directory.
     pub fn set_working_dir(&mut self, target_dir: &Path) -> Result<(), error::Error> {
-        let abs_path = self.get_absolute_path(target_dir);
+        let cleaned_path = normalize_path(self.get_absolute_path(target_dir).as_path());
 
-        match std::fs::metadata(&abs_path) {
+        match std::fs::metadata(&cleaned_path) {
             Ok(m) => {
                 if !m.is_dir() {
-                    return Err(error::Error::NotADirectory(abs_path));
+                    return Err(error::Error::NotADirectory(cleaned_path));
                 }
             }
             Err(e) => {
@@ -964,23 +1615,23 @@ impl Shell {
             }
         }
 
-        // TODO: Don't canonicalize, just normalize.
-        let cleaned_path = abs_path.canonicalize()?;
-
         let pwd = cleaned_path.to_string_lossy().to_string();
+        let oldpwd = self.working_dir.to_string_lossy().to_string();
 
-        // TODO: handle updating PWD
         self.working_dir = cleaned_path;
-        self.env.update_or_add(
-            "PWD",
-            variables::ShellValueLiteral::Scalar(pwd),
-            |var| {
-                var.export();
-                Ok(())
-            },
-            EnvironmentLookup::Anywhere,
-            EnvironmentScope::Global,
-        )?;
+
+        for (name, value) in [("PWD", pwd), ("OLDPWD", oldpwd)] {
+            self.env.update_or_add(
+                name,
+                variables::ShellValueLiteral::Scalar(value),
+                |var| {
+                    var.export();
+                    Ok(())
+                },
+                EnvironmentLookup::Anywhere,
+                EnvironmentScope::Global,
+            )?;
+        }
 
         Ok(())
     }
@@ -993,7 +1644,10 @@ impl Shell {
     pub(crate) fn tilde_shorten(&self, s: String) -> String {
         if let Some(home_dir) = self.get_home_dir() {
             if let Some(stripped) = s.strip_prefix(home_dir.to_string_lossy().as_ref()) {
-                return format!("~{stripped}");
+                // Only replace whole path components (e.g., not /home/user2 for /home/user).
+                if stripped.is_empty() || stripped.starts_with('/') {
+                    return format!("~{stripped}");
+                }
             }
         }
         s
@@ -1014,36 +1668,64 @@ impl Shell {
     }
 
     /// Returns a value that can be used to write to the shell's currently configured
-    /// standard output stream using `write!` at al.
+    /// standard output stream using `write!` at al. If the stream has been closed, a null
+    /// file is returned.
     pub fn stdout(&self) -> openfiles::OpenFile {
-        self.open_files.files.get(&1).unwrap().try_dup().unwrap()
+        self.open_files
+            .files
+            .get(&1)
+            .map_or(openfiles::OpenFile::Null, |f| f.try_dup().unwrap())
     }
 
     /// Returns a value that can be used to write to the shell's currently configured
-    /// standard error stream using `write!` et al.
+    /// standard error stream using `write!` et al. If the stream has been closed, a null
+    /// file is returned.
     pub fn stderr(&self) -> openfiles::OpenFile {
-        self.open_files.files.get(&2).unwrap().try_dup().unwrap()
+        self.open_files
+            .files
+            .get(&2)
+            .map_or(openfiles::OpenFile::Null, |f| f.try_dup().unwrap())
     }
 
-    /// Outputs `set -x` style trace output for a command.
+    /// Outputs `set -x` style trace output for a command, prefixed by the expansion of
+    /// `PS4`.
     ///
     /// # Arguments
     ///
     /// * `command` - The command to trace.
-    pub(crate) fn trace_command<S: AsRef<str>>(&self, command: S) -> Result<(), std::io::Error> {
-        // TODO: get prefix from PS4
-        const DEFAULT_PREFIX: &str = "+ ";
-
-        let mut prefix = DEFAULT_PREFIX.to_owned();
+    pub(crate) async fn trace_command<S: AsRef<str>>(
+        &mut self,
+        command: S,
+    ) -> Result<(), error::Error> {
+        let mut prefix = self.compose_trace_prefix().await?;
 
-        let additional_depth = self.script_call_stack.len() + self.depth;
+        // The first character of the prefix is replicated to indicate levels of
+        // indirection (e.g., command substitutions or sourced scripts).
+        let additional_depth = self.sourced_script_depth + self.depth;
         if let Some(c) = prefix.chars().next() {
             for _ in 0..additional_depth {
                 prefix.insert(0, c);
             }
         }
 
-        writeln!(self.stderr(), "{prefix}{}", command.as_ref())
+        writeln!(self.stderr(), "{prefix}{}", command.as_ref())?;
+
+        Ok(())
+    }
+
+    async fn compose_trace_prefix(&mut self) -> Result<String, error::Error> {
+        // Retrieve the spec.
+        let ps4 = self.parameter_or_default("PS4", "+ ");
+
+        // Expand it; commands run as part of the expansion aren't themselves traced.
+        let xtrace = std::mem::take(&mut self.options.print_commands_and_arguments);
+        let result = match prompt::expand_prompt(self, ps4.as_str()) {
+            Ok(formatted) => expansion::basic_expand_str(self, &formatted).await,
+            Err(e) => Err(e),
+        };
+        self.options.print_commands_and_arguments = xtrace;
+
+        result
     }
 
     /// Returns the keywords that are reserved by the shell.
@@ -1106,3 +1788,29 @@ fn parse_string_impl(
     tracing::debug!(target: trace_categories::PARSE, "Parsing string as program...");
     parser.parse(true)
 }
+
+/// Returns the time elapsed since the Unix epoch, or zero if the system clock is set
+/// before it.
+fn get_time_since_epoch() -> std::time::Duration {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+}
+
+/// Lexically normalizes the given path, removing `.` components and resolving `..`
+/// components against the components that precede them.
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            std::path::Component::CurDir => (),
+            std::path::Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+
+    normalized
+}
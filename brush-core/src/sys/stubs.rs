@@ -8,6 +8,7 @@ pub(crate) mod fs;
 pub(crate) mod network;
 pub(crate) mod pipes;
 pub(crate) mod process;
+pub(crate) mod resource;
 pub(crate) mod signal;
 pub(crate) mod terminal;
 pub(crate) mod users;
@@ -42,6 +42,14 @@ impl crate::sys::fs::PathExt for std::path::Path {
 }
 
 pub(crate) trait StubMetadataExt {
+    fn dev(&self) -> u64 {
+        0
+    }
+
+    fn ino(&self) -> u64 {
+        0
+    }
+
     fn gid(&self) -> u32 {
         0
     }
@@ -52,3 +60,11 @@ pub(crate) trait StubMetadataExt {
 }
 
 impl StubMetadataExt for std::fs::Metadata {}
+
+pub(crate) fn poll_for_input<Fd>(
+    _fd: Fd,
+    _timeout: std::time::Duration,
+) -> Result<bool, crate::error::Error> {
+    // TODO: implement; for now, report input as always being available.
+    Ok(true)
+}
@@ -12,10 +12,24 @@ pub(crate) fn continue_process(_pid: sys::process::ProcessId) -> Result<(), erro
     error::unimp("continue process")
 }
 
-pub(crate) fn kill_process(_pid: sys::process::ProcessId) -> Result<(), error::Error> {
+pub(crate) fn stop_process_group(_pgid: sys::process::ProcessId) -> Result<(), error::Error> {
+    error::unimp("stop process group")
+}
+
+pub(crate) fn kill_process(
+    _pid: sys::process::ProcessId,
+    _signal: traps::TrapSignal,
+) -> Result<(), error::Error> {
     error::unimp("kill process")
 }
 
+pub(crate) fn kill_process_group(
+    _pgid: sys::process::ProcessId,
+    _signal: traps::TrapSignal,
+) -> Result<(), error::Error> {
+    error::unimp("kill process group")
+}
+
 pub(crate) fn lead_new_process_group() -> Result<(), error::Error> {
     Ok(())
 }
@@ -49,6 +63,22 @@ pub(crate) fn mask_sigttou() -> Result<(), error::Error> {
     Ok(())
 }
 
+pub(crate) fn install_trap_handler(_signal: traps::TrapSignal) -> Result<(), error::Error> {
+    Ok(())
+}
+
+pub(crate) fn ignore_trapped_signal(_signal: traps::TrapSignal) -> Result<(), error::Error> {
+    Ok(())
+}
+
+pub(crate) fn reset_trapped_signal(_signal: traps::TrapSignal) -> Result<(), error::Error> {
+    Ok(())
+}
+
+pub(crate) fn take_pending_trapped_signals() -> Vec<traps::TrapSignal> {
+    vec![]
+}
+
 pub(crate) fn poll_for_stopped_children() -> Result<bool, error::Error> {
     Ok(false)
 }
@@ -32,6 +32,10 @@ pub(crate) fn is_stdin_a_terminal() -> Result<bool, error::Error> {
     Ok(false)
 }
 
+pub(crate) fn get_terminal_device_name() -> Option<std::path::PathBuf> {
+    None
+}
+
 pub(crate) fn get_parent_process_id() -> Option<sys::process::ProcessId> {
     None
 }
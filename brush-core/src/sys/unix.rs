@@ -2,6 +2,7 @@ pub(crate) use crate::sys::os_pipe as pipes;
 pub(crate) mod fs;
 pub(crate) mod network;
 pub(crate) use crate::sys::tokio_process as process;
+pub(crate) mod resource;
 pub(crate) mod signal;
 pub(crate) mod terminal;
 pub(crate) mod users;
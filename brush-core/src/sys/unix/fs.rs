@@ -56,3 +56,34 @@ fn try_get_file_type(path: &Path) -> Option<std::fs::FileType> {
 fn try_get_file_mode(path: &Path) -> Option<u32> {
     path.metadata().map(|metadata| metadata.mode()).ok()
 }
+
+/// Waits until the given file descriptor has input available to read, or until the
+/// given timeout elapses. Returns whether input became available.
+pub(crate) fn poll_for_input<Fd: std::os::fd::AsFd>(
+    fd: Fd,
+    timeout: std::time::Duration,
+) -> Result<bool, crate::error::Error> {
+    let deadline = std::time::Instant::now() + timeout;
+
+    // poll() only accepts a limited timeout, so we may need to wait in several rounds.
+    loop {
+        let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+        let round_timeout_ms = u16::try_from(remaining.as_millis()).unwrap_or(u16::MAX);
+
+        let mut poll_fds = [nix::poll::PollFd::new(
+            fd.as_fd(),
+            nix::poll::PollFlags::POLLIN,
+        )];
+        match nix::poll::poll(&mut poll_fds, round_timeout_ms) {
+            Ok(0) => (),
+            Ok(_) => return Ok(true),
+            // A signal interrupted the wait; keep waiting for whatever time remains.
+            Err(nix::errno::Errno::EINTR) => continue,
+            Err(e) => return Err(e.into()),
+        }
+
+        if remaining.as_millis() <= u128::from(round_timeout_ms) {
+            return Ok(false);
+        }
+    }
+}
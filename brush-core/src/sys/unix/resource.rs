@@ -0,0 +1,155 @@
+use crate::error;
+use nix::libc::rlim_t;
+use nix::sys::resource::{getrlimit, getrusage, setrlimit, Resource, UsageWho};
+use nix::sys::time::TimeVal;
+use std::collections::HashMap;
+use std::os::unix::process::CommandExt;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Returns the user and system CPU time consumed by the shell and the children it has
+/// waited for, in that order.
+pub(crate) fn get_self_and_children_cpu_times() -> Result<(Duration, Duration), error::Error> {
+    let mut user_time = Duration::ZERO;
+    let mut system_time = Duration::ZERO;
+
+    for who in [UsageWho::RUSAGE_SELF, UsageWho::RUSAGE_CHILDREN] {
+        let usage = getrusage(who)?;
+        user_time += to_duration(usage.user_time());
+        system_time += to_duration(usage.system_time());
+    }
+
+    Ok((user_time, system_time))
+}
+
+fn to_duration(time: TimeVal) -> Duration {
+    let seconds = u64::try_from(time.tv_sec()).unwrap_or_default();
+    let micros = u64::try_from(time.tv_usec()).unwrap_or_default();
+    Duration::from_secs(seconds) + Duration::from_micros(micros)
+}
+
+lazy_static::lazy_static! {
+    /// The limits of the shell's process as they were before any shell first modified them.
+    static ref ORIGINAL_LIMITS: Mutex<HashMap<Resource, (rlim_t, rlim_t)>> =
+        Mutex::new(HashMap::new());
+}
+
+/// Resource limits modified within a shell (e.g., by `ulimit`). Limits are changed on the
+/// shell's own process, so they constrain the shell itself as well as the processes it
+/// spawns. Subshells run within the same process as their parent, so each shell also
+/// tracks the limits it set: a subshell puts back the limits it changed when it goes
+/// away, and the processes a shell spawns get that shell's limits.
+///
+/// N.B. An unprivileged process can't raise a hard limit it has lowered, so a hard limit
+/// lowered in a subshell stays in force for the whole process, even though the parent
+/// shell still reports its own value. A subshell running in the background changes the
+/// limits of its parent for as long as it runs.
+#[derive(Debug, Default)]
+pub(crate) struct ResourceLimits {
+    /// The limits modified by this shell, or by the shell it was cloned from.
+    modified: HashMap<Resource, (rlim_t, rlim_t)>,
+    /// For a clone of another shell, the limits that shell had modified at the time.
+    inherited: Option<HashMap<Resource, (rlim_t, rlim_t)>>,
+}
+
+impl Clone for ResourceLimits {
+    fn clone(&self) -> Self {
+        Self {
+            modified: self.modified.clone(),
+            inherited: Some(self.modified.clone()),
+        }
+    }
+}
+
+impl Drop for ResourceLimits {
+    fn drop(&mut self) {
+        let Some(inherited) = &self.inherited else {
+            return;
+        };
+
+        for (resource, limits) in &self.modified {
+            let restored = match inherited.get(resource) {
+                Some(inherited_limits) if inherited_limits == limits => continue,
+                Some(inherited_limits) => Some(*inherited_limits),
+                None => get_original_limits(*resource),
+            };
+
+            // N.B. This fails if the hard limit was lowered; there's nothing more to do.
+            if let Some((soft, hard)) = restored {
+                let _ = setrlimit(*resource, soft, hard);
+            }
+        }
+    }
+}
+
+impl ResourceLimits {
+    /// Returns the soft and hard values of the given limit.
+    pub(crate) fn get(&self, resource: Resource) -> Result<(rlim_t, rlim_t), error::Error> {
+        if let Some(limits) = self
+            .modified
+            .get(&resource)
+            .copied()
+            .or_else(|| get_original_limits(resource))
+        {
+            Ok(limits)
+        } else {
+            Ok(getrlimit(resource)?)
+        }
+    }
+
+    /// Updates the soft and hard values of the given limit, for the shell's process and
+    /// the processes it spawns.
+    pub(crate) fn set(
+        &mut self,
+        resource: Resource,
+        soft: rlim_t,
+        hard: rlim_t,
+    ) -> Result<(), nix::errno::Errno> {
+        let original = getrlimit(resource)?;
+        setrlimit(resource, soft, hard)?;
+
+        if let Ok(mut original_limits) = ORIGINAL_LIMITS.lock() {
+            original_limits.entry(resource).or_insert(original);
+        }
+
+        self.modified.insert(resource, (soft, hard));
+        Ok(())
+    }
+
+    /// Arranges for the shell's limits to be applied to the process started by the given
+    /// command, in case another shell in this process has since changed them.
+    pub(crate) fn apply_to_command(&self, cmd: &mut std::process::Command) {
+        let mut limits = ORIGINAL_LIMITS
+            .lock()
+            .map(|original_limits| original_limits.clone())
+            .unwrap_or_default();
+        limits.extend(self.modified.clone());
+
+        if limits.is_empty() {
+            return;
+        }
+
+        let limits: Vec<_> = limits
+            .into_iter()
+            .map(|(resource, (soft, hard))| (resource, soft, hard))
+            .collect();
+
+        // SAFETY: setrlimit is async-signal-safe, and the closure doesn't allocate. Limits
+        // that can no longer be restored (e.g., a lowered hard limit) are left as they are.
+        unsafe {
+            cmd.pre_exec(move || {
+                for (resource, soft, hard) in &limits {
+                    let _ = setrlimit(*resource, *soft, *hard);
+                }
+                Ok(())
+            });
+        }
+    }
+}
+
+fn get_original_limits(resource: Resource) -> Option<(rlim_t, rlim_t)> {
+    ORIGINAL_LIMITS
+        .lock()
+        .ok()
+        .and_then(|original_limits| original_limits.get(&resource).copied())
+}
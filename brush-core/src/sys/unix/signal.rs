@@ -1,7 +1,18 @@
+use std::collections::BTreeMap;
 use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, PoisonError};
 
 use crate::{error, sys, traps};
 
+/// Bitmask of trapped signals that have been received but not yet handled.
+static PENDING_TRAPPED_SIGNALS: AtomicU64 = AtomicU64::new(0);
+
+/// Actions that were in place for signals before a trap first replaced them; resetting a
+/// trap restores these.
+static ORIGINAL_SIGNAL_ACTIONS: Mutex<BTreeMap<i32, nix::sys::signal::SigAction>> =
+    Mutex::new(BTreeMap::new());
+
 pub(crate) fn parse_numeric_signal(signal: i32) -> Result<traps::TrapSignal, error::Error> {
     Ok(traps::TrapSignal::Signal(
         nix::sys::signal::Signal::try_from(signal).map_err(|_| error::Error::InvalidSignal)?,
@@ -21,13 +32,53 @@ pub(crate) fn continue_process(pid: sys::process::ProcessId) -> Result<(), error
     Ok(())
 }
 
-pub(crate) fn kill_process(pid: sys::process::ProcessId) -> Result<(), error::Error> {
-    nix::sys::signal::kill(nix::unistd::Pid::from_raw(pid), nix::sys::signal::SIGKILL)
+/// Stops all processes in the given process group.
+pub(crate) fn stop_process_group(pgid: sys::process::ProcessId) -> Result<(), error::Error> {
+    nix::sys::signal::killpg(nix::unistd::Pid::from_raw(pgid), nix::sys::signal::SIGSTOP)
         .map_err(|_errno| error::Error::FailedToSendSignal)?;
+    Ok(())
+}
 
+pub(crate) fn kill_process(
+    pid: sys::process::ProcessId,
+    signal: traps::TrapSignal,
+) -> Result<(), error::Error> {
+    nix::sys::signal::kill(nix::unistd::Pid::from_raw(pid), to_sendable_signal(signal)?)?;
     Ok(())
 }
 
+/// Sends the given signal to the process group led by the given process. If the process
+/// doesn't lead its own process group (e.g., because it was launched while job control
+/// was disabled), then only the process itself is signaled.
+pub(crate) fn kill_process_group(
+    pgid: sys::process::ProcessId,
+    signal: traps::TrapSignal,
+) -> Result<(), error::Error> {
+    let pid = nix::unistd::Pid::from_raw(pgid);
+    let target = if nix::unistd::getpgid(Some(pid)).is_ok_and(|actual| actual == pid) {
+        nix::unistd::Pid::from_raw(-pgid)
+    } else {
+        pid
+    };
+
+    nix::sys::signal::kill(target, to_sendable_signal(signal)?)?;
+    Ok(())
+}
+
+/// Converts the given signal to a form that can be sent to a process; `EXIT` corresponds
+/// to signal 0, which only checks whether the target may be signaled.
+fn to_sendable_signal(
+    signal: traps::TrapSignal,
+) -> Result<Option<nix::sys::signal::Signal>, error::Error> {
+    match signal {
+        traps::TrapSignal::Signal(s) => Ok(Some(s)),
+        traps::TrapSignal::Exit => Ok(None),
+        traps::TrapSignal::Debug | traps::TrapSignal::Err | traps::TrapSignal::Return => {
+            Err(error::Error::InvalidSignal)
+        }
+    }
+}
+
 pub(crate) fn lead_new_process_group() -> Result<(), error::Error> {
     nix::unistd::setpgid(nix::unistd::Pid::from_raw(0), nix::unistd::Pid::from_raw(0))?;
     Ok(())
@@ -58,6 +109,96 @@ pub(crate) fn mask_sigttou() -> Result<(), error::Error> {
     Ok(())
 }
 
+pub(crate) fn install_trap_handler(signal: traps::TrapSignal) -> Result<(), error::Error> {
+    set_trapped_signal_handler(
+        signal,
+        nix::sys::signal::SigHandler::Handler(record_trapped_signal),
+    )
+}
+
+pub(crate) fn ignore_trapped_signal(signal: traps::TrapSignal) -> Result<(), error::Error> {
+    set_trapped_signal_handler(signal, nix::sys::signal::SigHandler::SigIgn)
+}
+
+pub(crate) fn reset_trapped_signal(signal: traps::TrapSignal) -> Result<(), error::Error> {
+    let traps::TrapSignal::Signal(signal) = signal else {
+        return Ok(());
+    };
+
+    let original = ORIGINAL_SIGNAL_ACTIONS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .remove(&(signal as i32));
+
+    // If no trap ever replaced the signal's action, there's nothing to restore.
+    if let Some(original) = original {
+        unsafe { nix::sys::signal::sigaction(signal, &original) }?;
+    }
+
+    Ok(())
+}
+
+fn set_trapped_signal_handler(
+    signal: traps::TrapSignal,
+    handler: nix::sys::signal::SigHandler,
+) -> Result<(), error::Error> {
+    let traps::TrapSignal::Signal(signal) = signal else {
+        // Nothing to do for pseudo-signals.
+        return Ok(());
+    };
+
+    // Leave alone the signals that can't be caught, as well as those the shell relies on
+    // handling itself for job control; traps for them are recorded but never run.
+    if matches!(
+        signal,
+        nix::sys::signal::SIGKILL
+            | nix::sys::signal::SIGSTOP
+            | nix::sys::signal::SIGCHLD
+            | nix::sys::signal::SIGTSTP
+            | nix::sys::signal::SIGTTOU
+    ) {
+        return Ok(());
+    }
+
+    let action = nix::sys::signal::SigAction::new(
+        handler,
+        nix::sys::signal::SaFlags::SA_RESTART,
+        nix::sys::signal::SigSet::empty(),
+    );
+    let previous = unsafe { nix::sys::signal::sigaction(signal, &action) }?;
+    ORIGINAL_SIGNAL_ACTIONS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .entry(signal as i32)
+        .or_insert(previous);
+
+    Ok(())
+}
+
+extern "C" fn record_trapped_signal(signal: nix::libc::c_int) {
+    // N.B. Only async-signal-safe operations are permissible here; the handler registered
+    // for the trap will be run later, from the shell's main flow of execution.
+    if let Some(bit) = u32::try_from(signal)
+        .ok()
+        .and_then(|signal| 1_u64.checked_shl(signal))
+    {
+        PENDING_TRAPPED_SIGNALS.fetch_or(bit, Ordering::SeqCst);
+    }
+}
+
+pub(crate) fn take_pending_trapped_signals() -> Vec<traps::TrapSignal> {
+    let pending = PENDING_TRAPPED_SIGNALS.swap(0, Ordering::SeqCst);
+    if pending == 0 {
+        return vec![];
+    }
+
+    (0..64_i32)
+        .filter(|signal| pending & (1_u64 << signal) != 0)
+        .filter_map(|signal| nix::sys::signal::Signal::try_from(signal).ok())
+        .map(traps::TrapSignal::Signal)
+        .collect()
+}
+
 pub(crate) fn poll_for_stopped_children() -> Result<bool, error::Error> {
     let mut found_stopped = false;
 
@@ -47,6 +47,10 @@ pub(crate) fn is_stdin_a_terminal() -> Result<bool, error::Error> {
     Ok(result)
 }
 
+pub(crate) fn get_terminal_device_name() -> Option<std::path::PathBuf> {
+    nix::unistd::ttyname(std::io::stdin()).ok()
+}
+
 #[allow(clippy::unnecessary_wraps)]
 pub(crate) fn get_parent_process_id() -> Option<sys::process::ProcessId> {
     Some(nix::unistd::getppid().as_raw())
@@ -0,0 +1,181 @@
+use std::time::{Duration, Instant};
+
+use crate::{error, sys};
+
+/// The format used to report the timing of a pipeline when `TIMEFORMAT` is unset.
+pub(crate) const DEFAULT_TIME_FORMAT: &str = "\nreal\t%3lR\nuser\t%3lU\nsys\t%3lS";
+
+/// The format used to report the timing of a pipeline run with `time -p`.
+pub(crate) const POSIX_TIME_FORMAT: &str = "real %2R\nuser %2U\nsys %2S";
+
+/// Measures the time taken to execute a timed pipeline.
+pub(crate) struct Stopwatch {
+    start: Instant,
+    start_user_time: Duration,
+    start_system_time: Duration,
+}
+
+/// The time taken to execute a timed pipeline.
+pub(crate) struct Timing {
+    /// Elapsed wall-clock time.
+    real: Duration,
+    /// CPU time spent in user mode.
+    user: Duration,
+    /// CPU time spent in kernel mode.
+    system: Duration,
+}
+
+impl Stopwatch {
+    /// Starts measuring time.
+    pub fn start() -> Result<Self, error::Error> {
+        let (start_user_time, start_system_time) =
+            sys::resource::get_self_and_children_cpu_times()?;
+
+        Ok(Self {
+            start: Instant::now(),
+            start_user_time,
+            start_system_time,
+        })
+    }
+
+    /// Returns the time taken since the stopwatch was started.
+    pub fn stop(&self) -> Result<Timing, error::Error> {
+        let real = self.start.elapsed();
+        let (user_time, system_time) = sys::resource::get_self_and_children_cpu_times()?;
+
+        Ok(Timing {
+            real,
+            user: user_time.saturating_sub(self.start_user_time),
+            system: system_time.saturating_sub(self.start_system_time),
+        })
+    }
+}
+
+impl Timing {
+    /// Formats the timing as described by a `TIMEFORMAT` string. In it, `%R`, `%U`,
+    /// and `%S` expand to the real, user, and system times in seconds; each may be
+    /// preceded by a precision (the number of fractional digits, up to 3) and by `l`
+    /// to select a longer format that includes minutes. `%P` expands to the CPU
+    /// percentage, and `%%` to a literal `%`.
+    ///
+    /// # Arguments
+    ///
+    /// * `format` - The format string.
+    pub fn format(&self, format: &str) -> Result<String, error::Error> {
+        let mut result = String::new();
+        let mut chars = format.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            if c != '%' || chars.peek().is_none() {
+                result.push(c);
+                continue;
+            }
+
+            if chars.next_if_eq(&'%').is_some() {
+                result.push('%');
+                continue;
+            }
+
+            if chars.next_if_eq(&'P').is_some() {
+                result.push_str(self.format_cpu_percentage().as_str());
+                continue;
+            }
+
+            let precision = chars
+                .next_if(char::is_ascii_digit)
+                .and_then(|c| c.to_digit(10))
+                .map_or(3, |digits| digits.min(3));
+            let long = chars.next_if_eq(&'l').is_some();
+
+            let duration = match chars.next() {
+                Some('R') => self.real,
+                Some('U') => self.user,
+                Some('S') => self.system,
+                c => {
+                    return Err(error::Error::InvalidTimeFormatCharacter(
+                        c.map(String::from).unwrap_or_default(),
+                    ))
+                }
+            };
+
+            result.push_str(format_duration(duration, precision, long).as_str());
+        }
+
+        Ok(result)
+    }
+
+    fn format_cpu_percentage(&self) -> String {
+        let real = self.real.as_secs_f64();
+        let percentage = if real > 0.0 {
+            (self.user + self.system).as_secs_f64() * 100.0 / real
+        } else {
+            100.0
+        };
+
+        std::format!("{percentage:.2}")
+    }
+}
+
+/// Formats a duration in seconds with the given number of fractional digits; the long
+/// format separates out the minutes (e.g., `1m2.345s`).
+fn format_duration(duration: Duration, precision: u32, long: bool) -> String {
+    let seconds = duration.as_secs();
+    let fraction = if precision > 0 {
+        let digits = duration.subsec_millis() / 10u32.pow(3 - precision);
+        std::format!(".{digits:0width$}", width = precision as usize)
+    } else {
+        String::new()
+    };
+
+    if long {
+        std::format!("{}m{}{fraction}s", seconds / 60, seconds % 60)
+    } else {
+        std::format!("{seconds}{fraction}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    fn timing() -> Timing {
+        Timing {
+            real: Duration::from_millis(62_345),
+            user: Duration::from_millis(1_500),
+            system: Duration::from_millis(250),
+        }
+    }
+
+    #[test]
+    fn format_times() -> Result<()> {
+        let timing = timing();
+
+        assert_eq!(timing.format("%R %U %S")?, "62.345 1.500 0.250");
+        assert_eq!(timing.format("%0R|%1R|%2R|%9R")?, "62|62.3|62.34|62.345");
+        assert_eq!(timing.format("%lR|%1lU|%0lS")?, "1m2.345s|0m1.5s|0m0s");
+        assert_eq!(
+            timing.format(DEFAULT_TIME_FORMAT)?,
+            "\nreal\t1m2.345s\nuser\t0m1.500s\nsys\t0m0.250s"
+        );
+        assert_eq!(
+            timing.format(POSIX_TIME_FORMAT)?,
+            "real 62.34\nuser 1.50\nsys 0.25"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn format_other_specifiers() -> Result<()> {
+        let timing = timing();
+
+        assert_eq!(timing.format("%P")?, "2.81");
+        assert_eq!(timing.format("100%% of 50%")?, "100% of 50%");
+        assert!(timing.format("%x").is_err());
+        assert!(timing.format("%2P").is_err());
+        assert!(timing.format("%l").is_err());
+
+        Ok(())
+    }
+}
@@ -1,5 +1,7 @@
 use std::{collections::HashMap, fmt::Display};
 
+use crate::{error, sys};
+
 /// Type of signal that can be trapped in the shell.
 #[derive(Clone, Copy, Eq, Hash, PartialEq)]
 pub enum TrapSignal {
@@ -12,6 +14,8 @@ pub enum TrapSignal {
     Err,
     /// The `EXIT` trap.
     Exit,
+    /// The `RETURN` trap.
+    Return,
 }
 
 impl Display for TrapSignal {
@@ -22,6 +26,7 @@ impl Display for TrapSignal {
             TrapSignal::Debug => write!(f, "DEBUG"),
             TrapSignal::Err => write!(f, "ERR"),
             TrapSignal::Exit => write!(f, "EXIT"),
+            TrapSignal::Return => write!(f, "RETURN"),
         }
     }
 }
@@ -30,7 +35,12 @@ impl TrapSignal {
     /// Returns all possible values of `TrapSignal`.
     #[allow(unused_mut)]
     pub fn all_values() -> Vec<TrapSignal> {
-        let mut signals = vec![TrapSignal::Debug, TrapSignal::Err, TrapSignal::Exit];
+        let mut signals = vec![
+            TrapSignal::Debug,
+            TrapSignal::Err,
+            TrapSignal::Exit,
+            TrapSignal::Return,
+        ];
 
         #[cfg(unix)]
         for signal in nix::sys::signal::Signal::iterator() {
@@ -56,9 +66,21 @@ impl TrapHandlerConfig {
     /// # Arguments
     ///
     /// * `signal_type` - The type of signal to register a handler for.
-    /// * `command` - The command to execute when the signal is trapped.
-    pub fn register_handler(&mut self, signal_type: TrapSignal, command: String) {
+    /// * `command` - The command to execute when the signal is trapped; if empty, the signal
+    ///   is ignored.
+    pub fn register_handler(
+        &mut self,
+        signal_type: TrapSignal,
+        command: String,
+    ) -> Result<(), error::Error> {
+        if command.is_empty() {
+            sys::signal::ignore_trapped_signal(signal_type)?;
+        } else {
+            sys::signal::install_trap_handler(signal_type)?;
+        }
+
         let _ = self.handlers.insert(signal_type, command);
+        Ok(())
     }
 
     /// Removes handlers for a trap signal.
@@ -66,7 +88,44 @@ impl TrapHandlerConfig {
     /// # Arguments
     ///
     /// * `signal_type` - The type of signal to remove handlers for.
-    pub fn remove_handlers(&mut self, signal_type: TrapSignal) {
+    pub fn remove_handlers(&mut self, signal_type: TrapSignal) -> Result<(), error::Error> {
+        sys::signal::reset_trapped_signal(signal_type)?;
         self.handlers.remove(&signal_type);
+        Ok(())
+    }
+}
+
+/// Parses the given string as a signal specification, accepting either a signal number
+/// or a (case-insensitive) signal name with or without a leading `SIG`.
+///
+/// # Arguments
+///
+/// * `signal` - The signal specification to parse.
+pub(crate) fn parse_signal(signal: &str) -> Result<TrapSignal, error::Error> {
+    if signal.chars().all(|c| c.is_ascii_digit()) {
+        let digits = signal
+            .parse::<i32>()
+            .map_err(|_| error::Error::InvalidSignal)?;
+
+        // Signal 0 is synonymous with EXIT.
+        if digits == 0 {
+            Ok(TrapSignal::Exit)
+        } else {
+            sys::signal::parse_numeric_signal(digits)
+        }
+    } else {
+        let mut signal_to_parse = signal.to_ascii_uppercase();
+
+        if !signal_to_parse.starts_with("SIG") {
+            signal_to_parse.insert_str(0, "SIG");
+        }
+
+        match signal_to_parse {
+            s if s == "SIGDEBUG" => Ok(TrapSignal::Debug),
+            s if s == "SIGERR" => Ok(TrapSignal::Err),
+            s if s == "SIGEXIT" => Ok(TrapSignal::Exit),
+            s if s == "SIGRETURN" => Ok(TrapSignal::Return),
+            s => sys::signal::parse_os_signal_name(s.as_str()),
+        }
     }
 }
@@ -1,9 +1,10 @@
-use rand::Rng;
 use std::borrow::Cow;
 use std::collections::BTreeMap;
 use std::fmt::{Display, Write};
+use std::sync::atomic::{AtomicU32, Ordering};
 
-use crate::error;
+use crate::shell::Shell;
+use crate::{error, escape};
 
 /// A shell variable.
 #[derive(Clone, Debug)]
@@ -70,6 +71,18 @@ impl ShellVariable {
         &self.value
     }
 
+    /// Returns the value associated with the variable, computing it if it's dynamic.
+    ///
+    /// # Arguments
+    ///
+    /// * `shell` - The shell in which the variable is being read.
+    pub fn resolve_value(&self, shell: &Shell) -> Cow<'_, ShellValue> {
+        match &self.value {
+            ShellValue::Dynamic { getter, .. } => Cow::Owned(getter(shell)),
+            value => Cow::Borrowed(value),
+        }
+    }
+
     /// Returns whether or not the variable is exported to child processes.
     pub fn is_exported(&self) -> bool {
         self.exported
@@ -179,7 +192,7 @@ impl ShellVariable {
             }
             _ => {
                 let mut new_values = BTreeMap::new();
-                new_values.insert(0, self.value.to_cow_string().to_string());
+                new_values.insert(0, self.scalar_value_for_conversion());
                 self.value = ShellValue::IndexedArray(new_values);
                 Ok(())
             }
@@ -195,13 +208,22 @@ impl ShellVariable {
             }
             _ => {
                 let mut new_values: BTreeMap<String, String> = BTreeMap::new();
-                new_values.insert(String::from("0"), self.value.to_cow_string().to_string());
+                new_values.insert(String::from("0"), self.scalar_value_for_conversion());
                 self.value = ShellValue::AssociativeArray(new_values);
                 Ok(())
             }
         }
     }
 
+    /// Returns the scalar value that becomes element 0 when this variable is converted to
+    /// an array. The value of a dynamic variable can't be computed here, so it's dropped.
+    fn scalar_value_for_conversion(&self) -> String {
+        match &self.value {
+            ShellValue::Dynamic { .. } => String::new(),
+            value => value.to_cow_string().into_owned(),
+        }
+    }
+
     /// Assign the given value to the variable, conditionally appending to the preexisting value.
     ///
     /// # Arguments
@@ -274,7 +296,7 @@ impl ShellVariable {
                     }
                 },
                 ShellValue::Unset(_) => error::unimp("appending to unset variable"),
-                ShellValue::Random => Ok(()),
+                ShellValue::Dynamic { .. } => Ok(()),
             }
         } else {
             match (&self.value, value) {
@@ -298,7 +320,7 @@ impl ShellVariable {
                         ShellValueUnsetType::IndexedArray | ShellValueUnsetType::Untyped,
                     )
                     | ShellValue::String(_)
-                    | ShellValue::Random,
+                    | ShellValue::Dynamic { .. },
                     ShellValueLiteral::Array(literal_values),
                 ) => {
                     self.value = ShellValue::indexed_array_from_literals(literal_values)?;
@@ -315,8 +337,9 @@ impl ShellVariable {
                     Ok(())
                 }
 
-                // Drop other updates to random values.
-                (ShellValue::Random, _) => Ok(()),
+                // Drop other updates to dynamic values; scalar assignments are instead handed to
+                // their setters by the shell.
+                (ShellValue::Dynamic { .. }, _) => Ok(()),
 
                 // Assign a scalar value to a scalar or unset (and untyped) variable.
                 (ShellValue::String(_) | ShellValue::Unset(_), ShellValueLiteral::Scalar(s)) => {
@@ -414,6 +437,10 @@ impl ShellVariable {
     ///
     /// * `index` - The index at which to unset the value.
     pub fn unset_index(&mut self, index: &str) -> Result<bool, error::Error> {
+        if self.is_readonly() {
+            return Err(error::Error::ReadonlyVariable);
+        }
+
         match &mut self.value {
             ShellValue::Unset(ty) => match ty {
                 ShellValueUnsetType::Untyped => Err(error::Error::NotArray),
@@ -421,11 +448,25 @@ impl ShellVariable {
                     Ok(false)
                 }
             },
-            ShellValue::String(_) | ShellValue::Random => Err(error::Error::NotArray),
+            ShellValue::String(_) | ShellValue::Dynamic { .. } => Err(error::Error::NotArray),
             ShellValue::AssociativeArray(values) => Ok(values.remove(index).is_some()),
             ShellValue::IndexedArray(values) => {
-                let key = index.parse::<u64>().unwrap_or(0);
-                Ok(values.remove(&key).is_some())
+                let key = index.parse::<i64>().unwrap_or(0);
+
+                // Negative indices count back from the end of the array.
+                let key = if key < 0 {
+                    values
+                        .last_key_value()
+                        .and_then(|(last, _)| i64::try_from(*last).ok())
+                        .map_or(key, |last| last + 1 + key)
+                } else {
+                    key
+                };
+
+                match u64::try_from(key) {
+                    Ok(key) => Ok(values.remove(&key).is_some()),
+                    Err(_) => Ok(false),
+                }
             }
         }
     }
@@ -484,10 +525,22 @@ pub enum ShellValue {
     AssociativeArray(BTreeMap<String, String>),
     /// An indexed array.
     IndexedArray(BTreeMap<u64, String>),
-    /// A special value that yields a different random number each time its read.
-    Random,
+    /// A value that's computed each time it's read. Until resolved with
+    /// [`ShellVariable::resolve_value`], it's treated as empty.
+    Dynamic {
+        /// Function that computes the value.
+        getter: DynamicValueGetter,
+        /// Function that receives scalar values assigned to the variable.
+        setter: DynamicValueSetter,
+    },
 }
 
+/// A function that computes the current value of a dynamic variable.
+pub type DynamicValueGetter = fn(&Shell) -> ShellValue;
+
+/// A function that receives a value assigned to a dynamic variable.
+pub type DynamicValueSetter = fn(&mut Shell, &str);
+
 /// The type of an unset shell value.
 #[derive(Clone, Debug)]
 pub enum ShellValueUnsetType {
@@ -673,25 +726,28 @@ impl ShellValue {
     pub fn format(&self, style: FormatStyle) -> Result<Cow<'_, str>, error::Error> {
         match self {
             ShellValue::Unset(_) => Ok("".into()),
-            ShellValue::String(s) => {
-                // TODO: Handle embedded newlines and other special chars.
-                match style {
-                    FormatStyle::Basic => {
-                        if s.contains(' ') {
-                            Ok(format!("'{s}'").into())
-                        } else {
-                            Ok(s.into())
-                        }
+            ShellValue::String(s) => match style {
+                FormatStyle::Basic => {
+                    if s.contains(' ') {
+                        Ok(format!("'{s}'").into())
+                    } else {
+                        Ok(s.into())
                     }
-                    FormatStyle::DeclarePrint => Ok(format!("\"{s}\"").into()),
                 }
-            }
+                FormatStyle::DeclarePrint => Ok(escape::quote_for_declare(s).into()),
+            },
             ShellValue::AssociativeArray(values) => {
                 let mut result = String::new();
                 result.push('(');
 
                 for (key, value) in values {
-                    write!(result, "[{key}]=\"{value}\" ")?;
+                    let key = if escape::contains_shell_metas(key) {
+                        escape::quote_for_declare(key)
+                    } else {
+                        key.to_owned()
+                    };
+
+                    write!(result, "[{key}]={} ", escape::quote_for_declare(value))?;
                 }
 
                 result.push(')');
@@ -705,13 +761,13 @@ impl ShellValue {
                     if i > 0 {
                         result.push(' ');
                     }
-                    write!(result, "[{key}]=\"{value}\"")?;
+                    write!(result, "[{key}]={}", escape::quote_for_declare(value))?;
                 }
 
                 result.push(')');
                 Ok(result.into())
             }
-            ShellValue::Random => Ok(std::format!("\"{}\"", get_random_str()).into()),
+            ShellValue::Dynamic { .. } => Ok(Cow::Borrowed("")),
         }
     }
 
@@ -738,7 +794,7 @@ impl ShellValue {
                 let key = index.parse::<u64>().unwrap_or(0);
                 Ok(values.get(&key).map(|s| Cow::Borrowed(s.as_str())))
             }
-            ShellValue::Random => Ok(Some(Cow::Owned(get_random_str()))),
+            ShellValue::Dynamic { .. } => Ok(None),
         }
     }
 
@@ -746,7 +802,7 @@ impl ShellValue {
     pub fn get_element_keys(&self) -> Vec<String> {
         match self {
             ShellValue::Unset(_) => vec![],
-            ShellValue::String(_) | ShellValue::Random => vec!["0".to_owned()],
+            ShellValue::String(_) | ShellValue::Dynamic { .. } => vec!["0".to_owned()],
             ShellValue::AssociativeArray(array) => array.keys().map(|k| k.to_owned()).collect(),
             ShellValue::IndexedArray(array) => array.keys().map(|k| k.to_string()).collect(),
         }
@@ -755,18 +811,24 @@ impl ShellValue {
     /// Returns the values of the elements in this variable.
     pub fn get_element_values(&self) -> Vec<String> {
         match self {
-            ShellValue::Unset(_) => vec![],
+            ShellValue::Unset(_) | ShellValue::Dynamic { .. } => vec![],
             ShellValue::String(s) => vec![s.to_owned()],
             ShellValue::AssociativeArray(array) => array.values().map(|v| v.to_owned()).collect(),
             ShellValue::IndexedArray(array) => array.values().map(|v| v.to_owned()).collect(),
-            ShellValue::Random => vec![get_random_str()],
         }
     }
 
-    /// Converts this value to a string.
+    /// Converts this value to a string. Dynamic values must first be resolved with
+    /// [`ShellVariable::resolve_value`]; an unresolved dynamic value is converted to an
+    /// empty string, and is treated as a bug in debug builds.
     pub fn to_cow_string(&self) -> Cow<'_, str> {
+        debug_assert!(
+            !matches!(self, ShellValue::Dynamic { .. }),
+            "dynamic values must be resolved before conversion to a string"
+        );
+
         match self {
-            ShellValue::Unset(_) => Cow::Borrowed(""),
+            ShellValue::Unset(_) | ShellValue::Dynamic { .. } => Cow::Borrowed(""),
             ShellValue::String(s) => Cow::Borrowed(s.as_str()),
             ShellValue::AssociativeArray(values) => values
                 .get("0")
@@ -774,7 +836,6 @@ impl ShellValue {
             ShellValue::IndexedArray(values) => values
                 .get(&0)
                 .map_or_else(|| Cow::Borrowed(""), |s| Cow::Borrowed(s.as_str())),
-            ShellValue::Random => Cow::Owned(get_random_str()),
         }
     }
 
@@ -785,12 +846,12 @@ impl ShellValue {
     /// * `index` - The index at which to retrieve the value, if indexing is to be performed.
     pub fn to_assignable_str(&self, index: Option<&str>) -> String {
         match self {
-            ShellValue::Unset(_) => String::new(),
-            ShellValue::String(s) => quote_str_for_assignment(s.as_str()),
+            ShellValue::Unset(_) | ShellValue::Dynamic { .. } => String::new(),
+            ShellValue::String(s) => escape::quote_for_reuse(s.as_str()),
             ShellValue::AssociativeArray(_) | ShellValue::IndexedArray(_) => {
                 if let Some(index) = index {
                     if let Ok(Some(value)) = self.get_at(index) {
-                        quote_str_for_assignment(value.as_ref())
+                        escape::quote_for_reuse(value.as_ref())
                     } else {
                         String::new()
                     }
@@ -798,7 +859,6 @@ impl ShellValue {
                     self.format(FormatStyle::DeclarePrint).unwrap().into_owned()
                 }
             }
-            ShellValue::Random => quote_str_for_assignment(get_random_str().as_str()),
         }
     }
 }
@@ -815,30 +875,62 @@ impl From<&String> for ShellValue {
     }
 }
 
-pub(crate) fn get_random_str() -> String {
-    let mut rng = rand::thread_rng();
-    let value = rng.gen_range(0..32768);
-    value.to_string()
+/// Generates the values of `$RANDOM`. The same algorithm as bash is used, so that a given
+/// seed yields the same sequence of values.
+#[derive(Debug)]
+pub(crate) struct RandomGenerator {
+    /// The current state of the generator.
+    state: AtomicU32,
+    /// The last value generated, which won't be immediately repeated.
+    last_value: AtomicU32,
 }
 
-pub(crate) fn quote_str_for_assignment(s: &str) -> String {
-    let mut result = String::new();
-
-    let mut first = true;
-    for part in s.split('\'') {
-        if !first {
-            result.push('\\');
-            result.push('\'');
-        } else {
-            first = false;
+impl Clone for RandomGenerator {
+    fn clone(&self) -> Self {
+        Self {
+            state: AtomicU32::new(self.state.load(Ordering::Relaxed)),
+            last_value: AtomicU32::new(self.last_value.load(Ordering::Relaxed)),
         }
+    }
+}
 
-        if !part.is_empty() {
-            result.push('\'');
-            result.push_str(part);
-            result.push('\'');
+impl Default for RandomGenerator {
+    fn default() -> Self {
+        Self {
+            state: AtomicU32::new(rand::random()),
+            last_value: AtomicU32::new(0),
         }
     }
+}
 
-    result
+impl RandomGenerator {
+    /// Reseeds the generator.
+    pub fn seed(&self, seed: u32) {
+        self.state.store(seed, Ordering::Relaxed);
+        self.last_value.store(0, Ordering::Relaxed);
+    }
+
+    /// Returns the next value in the range 0-32767.
+    pub fn next_value(&self) -> u32 {
+        loop {
+            // Park-Miller "minimal standard" generator; 0 isn't a usable state.
+            let state = match self.state.load(Ordering::Relaxed) {
+                0 => 123_459_876,
+                state => state,
+            };
+            let high = i64::from(state / 127_773);
+            let low = i64::from(state % 127_773);
+            let mut next_state = 16_807 * low - 2_836 * high;
+            if next_state < 0 {
+                next_state += 0x7fff_ffff;
+            }
+            let next_state = u32::try_from(next_state).unwrap_or_default();
+            self.state.store(next_state, Ordering::Relaxed);
+
+            let value = ((next_state >> 16) ^ (next_state & 0xffff)) & 0x7fff;
+            if self.last_value.swap(value, Ordering::Relaxed) != value {
+                return value;
+            }
+        }
+    }
 }
@@ -44,6 +44,11 @@ impl InteractiveShell for BasicShell {
         let mut result = String::new();
 
         while result.is_empty() || !self.is_valid_input(result.as_str()) {
+            if !result.is_empty() && self.should_display_prompt() {
+                print!("{}", prompt.continuation_prompt);
+                let _ = std::io::stdout().flush();
+            }
+
             let mut read_buffer = String::new();
             let bytes_read = stdin
                 .read_line(&mut read_buffer)
@@ -62,10 +67,6 @@ impl InteractiveShell for BasicShell {
             Ok(ReadResult::Input(result))
         }
     }
-
-    fn update_history(&mut self) -> Result<(), ShellError> {
-        Ok(())
-    }
 }
 
 impl BasicShell {
@@ -75,14 +76,6 @@ impl BasicShell {
     }
 
     fn is_valid_input(&self, input: &str) -> bool {
-        match self.shell.parse_string(input.to_owned()) {
-            Err(brush_parser::ParseError::Tokenizing { inner, position: _ })
-                if inner.is_incomplete() =>
-            {
-                false
-            }
-            Err(brush_parser::ParseError::ParsingAtEndOfInput) => false,
-            _ => true,
-        }
+        !matches!(self.shell.parse_string(input.to_owned()), Err(e) if e.is_incomplete())
     }
 }
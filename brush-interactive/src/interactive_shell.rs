@@ -47,8 +47,11 @@ pub trait InteractiveShell {
     /// * `prompt` - The prompt to display to the user.
     fn read_line(&mut self, prompt: InteractivePrompt) -> Result<ReadResult, ShellError>;
 
-    /// Update history, if relevant.
-    fn update_history(&mut self) -> Result<(), ShellError>;
+    /// Saves the commands entered during this session to the shell's history file.
+    fn update_history(&mut self) -> Result<(), ShellError> {
+        self.shell_mut().as_mut().save_history()?;
+        Ok(())
+    }
 
     /// Runs the interactive shell loop, reading commands from standard input and writing
     /// results to standard output and standard error. Continues until the shell
@@ -85,12 +88,12 @@ pub trait InteractiveShell {
 
         if self.shell().as_ref().options.interactive {
             writeln!(self.shell().as_ref().stderr(), "exit")?;
-        }
 
-        if let Err(e) = self.update_history() {
-            // N.B. This seems like the sort of thing that's worth being noisy about,
-            // but bash doesn't do that -- and probably for a reason.
-            tracing::debug!("couldn't save history: {e}");
+            if let Err(e) = self.update_history() {
+                // N.B. This seems like the sort of thing that's worth being noisy about,
+                // but bash doesn't do that -- and probably for a reason.
+                tracing::debug!("couldn't save history: {e}");
+            }
         }
 
         Ok(())
@@ -103,16 +106,28 @@ pub trait InteractiveShell {
         // Check for any completed jobs.
         shell_mut.as_mut().check_for_completed_jobs()?;
 
-        // If there's a variable called PROMPT_COMMAND, then run it first.
-        if let Some((_, prompt_cmd)) = shell_mut.as_mut().env.get("PROMPT_COMMAND") {
-            let prompt_cmd = prompt_cmd.value().to_cow_string().to_string();
+        // If there's a variable called PROMPT_COMMAND, then run it first. It may also be an
+        // array, in which case each of its elements is run in turn.
+        let prompt_cmds = shell_mut
+            .as_mut()
+            .env
+            .get("PROMPT_COMMAND")
+            .map(|(_, prompt_cmd)| prompt_cmd.value().get_element_values())
+            .unwrap_or_default();
 
+        if !prompt_cmds.is_empty() {
             // Save (and later restore) the last exit status.
             let prev_last_result = shell_mut.as_mut().last_exit_status;
 
             let params = shell_mut.as_mut().default_exec_params();
 
-            shell_mut.as_mut().run_string(prompt_cmd, &params).await?;
+            for prompt_cmd in prompt_cmds {
+                let result = shell_mut.as_mut().run_string(prompt_cmd, &params).await?;
+                if result.exit_shell {
+                    return Ok(InteractiveExecutionResult::Executed(result));
+                }
+            }
+
             shell_mut.as_mut().last_exit_status = prev_last_result;
         }
 
@@ -120,7 +135,7 @@ pub trait InteractiveShell {
         let prompt = InteractivePrompt {
             prompt: shell_mut.as_mut().compose_prompt().await?,
             alt_side_prompt: shell_mut.as_mut().compose_alt_side_prompt().await?,
-            continuation_prompt: shell_mut.as_mut().continuation_prompt()?,
+            continuation_prompt: shell_mut.as_mut().compose_continuation_prompt().await?,
         };
 
         drop(shell_mut);
@@ -128,8 +143,31 @@ pub trait InteractiveShell {
         match self.read_line(prompt)? {
             ReadResult::Input(read_result) => {
                 let mut shell_mut = self.shell_mut();
+
+                // Apply history expansion; if it fails, the input isn't executed.
+                let expansion = match shell_mut.as_mut().expand_history(read_result.as_str()) {
+                    Ok(expansion) => expansion,
+                    Err(e) => return Ok(InteractiveExecutionResult::Failed(e)),
+                };
+
+                if expansion.expanded {
+                    write!(shell_mut.as_mut().stderr(), "{}", expansion.line)?;
+                    if !expansion.line.ends_with('\n') {
+                        writeln!(shell_mut.as_mut().stderr())?;
+                    }
+                }
+
+                shell_mut.as_mut().add_to_history(expansion.line.as_str());
+
+                if expansion.print_only {
+                    let last_exit_status = shell_mut.as_mut().last_exit_status;
+                    return Ok(InteractiveExecutionResult::Executed(
+                        brush_core::ExecutionResult::new(last_exit_status),
+                    ));
+                }
+
                 let params = shell_mut.as_mut().default_exec_params();
-                match shell_mut.as_mut().run_string(read_result, &params).await {
+                match shell_mut.as_mut().run_string(expansion.line, &params).await {
                     Ok(result) => Ok(InteractiveExecutionResult::Executed(result)),
                     Err(e) => Ok(InteractiveExecutionResult::Failed(e)),
                 }
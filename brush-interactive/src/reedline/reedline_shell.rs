@@ -1,4 +1,4 @@
-use reedline::MenuBuilder;
+use reedline::{History, MenuBuilder};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
@@ -24,7 +24,13 @@ impl ReedlineShell {
         // Set up shell first. Its initialization may influence how the
         // editor needs to operate.
         let shell = brush_core::Shell::new(&options.shell).await?;
-        let history_file_path = shell.get_history_file_path();
+
+        // Seed the editor's history with the shell's, so it can be recalled while editing.
+        // N.B. The shell itself takes care of persisting history.
+        let mut history = reedline::FileBackedHistory::default();
+        for (_, command) in shell.history.iter() {
+            let _ = history.save(reedline::HistoryItem::from_command_line(command));
+        }
 
         let shell_ref = Arc::new(Mutex::new(shell));
 
@@ -73,20 +79,13 @@ impl ReedlineShell {
             .with_menu(reedline::ReedlineMenu::EngineCompleter(completion_menu))
             .with_edit_mode(Box::new(reedline::Emacs::new(key_bindings)));
 
+        reedline = reedline.with_history(Box::new(history));
+
         // If requested, apply some additional niceties.
         if !options.disable_highlighting && !options.disable_color {
             reedline = reedline.with_highlighter(Box::new(highlighter));
         }
 
-        // If we have a history file, wire it up.
-        if let Some(history_file_path) = history_file_path {
-            if let Ok(history) =
-                reedline::FileBackedHistory::with_file(reedline::HISTORY_SIZE, history_file_path)
-            {
-                reedline = reedline.with_history(Box::new(history));
-            }
-        }
-
         Ok(ReedlineShell {
             reedline,
             shell: shell_ref,
@@ -122,12 +121,6 @@ impl InteractiveShell for ReedlineShell {
             Err(err) => Err(ShellError::IoError(err)),
         }
     }
-
-    /// Update history, if relevant.
-    fn update_history(&mut self) -> Result<(), ShellError> {
-        // N.B. With our current usage, reedline auto-updates the history file.
-        Ok(())
-    }
 }
 
 fn compose_key_bindings(completion_menu_name: &str) -> reedline::Keybindings {
@@ -11,14 +11,7 @@ impl reedline::Validator for ReedlineValidator {
         });
 
         match shell.parse_string(line.to_owned()) {
-            Err(brush_parser::ParseError::Tokenizing { inner, position: _ })
-                if inner.is_incomplete() =>
-            {
-                reedline::ValidationResult::Incomplete
-            }
-            Err(brush_parser::ParseError::ParsingAtEndOfInput) => {
-                reedline::ValidationResult::Incomplete
-            }
+            Err(e) if e.is_incomplete() => reedline::ValidationResult::Incomplete,
             _ => reedline::ValidationResult::Complete,
         }
     }
@@ -1,5 +1,5 @@
 use rustyline::validate::ValidationResult;
-use std::{borrow::Cow, path::PathBuf};
+use std::borrow::Cow;
 
 use crate::{
     completion,
@@ -14,8 +14,6 @@ type Editor = rustyline::Editor<EditorHelper, rustyline::history::FileHistory>;
 pub struct RustylineShell {
     /// The `rustyline` editor.
     editor: Editor,
-    /// Optional path to the history file used for the shell.
-    history_file_path: Option<PathBuf>,
 }
 
 impl RustylineShell {
@@ -28,24 +26,20 @@ impl RustylineShell {
         // Set up shell first. Its initialization may influence how the
         // editor needs to operate.
         let shell = brush_core::Shell::new(&options.shell).await?;
-        let history_file_path = shell.get_history_file_path();
+        let history: Vec<_> = shell
+            .history
+            .iter()
+            .map(|(_, command)| command.to_owned())
+            .collect();
 
         let mut editor = Self::new_editor(options, shell).map_err(|_err| ShellError::InputError)?;
-        if let Some(history_file_path) = &history_file_path {
-            // If the history file doesn't already exist, then make a best-effort attempt.
-            // to create it.
-            if !history_file_path.exists() {
-                let _ = std::fs::File::create(history_file_path);
-            }
 
-            // Make a best effort attempt to load the history file.
-            let _ = editor.load_history(history_file_path);
+        // Seed the editor's history with the shell's, so it can be recalled while editing.
+        for command in history {
+            let _ = editor.add_history_entry(command);
         }
 
-        Ok(RustylineShell {
-            editor,
-            history_file_path,
-        })
+        Ok(RustylineShell { editor })
     }
 
     fn new_editor(options: &crate::Options, shell: brush_core::Shell) -> rustyline::Result<Editor> {
@@ -89,23 +83,6 @@ impl InteractiveShell for RustylineShell {
             Err(_err) => Err(ShellError::InputError),
         }
     }
-
-    /// Update history, if relevant.
-    fn update_history(&mut self) -> Result<(), ShellError> {
-        if let Some(history_file_path) = &self.history_file_path {
-            if self.shell().as_ref().options.append_to_history_file {
-                self.editor
-                    .append_history(history_file_path)
-                    .map_err(|_err| ShellError::InputError)
-            } else {
-                self.editor
-                    .save_history(history_file_path)
-                    .map_err(|_err| ShellError::InputError)
-            }
-        } else {
-            Ok(())
-        }
-    }
 }
 
 //
@@ -225,12 +202,7 @@ impl rustyline::validate::Validator for EditorHelper {
         let parse_result = self.shell.parse_string(line.to_owned());
 
         let validation_result = match parse_result {
-            Err(brush_parser::ParseError::Tokenizing { inner, position: _ })
-                if inner.is_incomplete() =>
-            {
-                ValidationResult::Incomplete
-            }
-            Err(brush_parser::ParseError::ParsingAtEndOfInput) => ValidationResult::Incomplete,
+            Err(e) if e.is_incomplete() => ValidationResult::Incomplete,
             _ => ValidationResult::Valid(None),
         };
 
@@ -0,0 +1,203 @@
+use std::collections::HashMap;
+
+use crate::tokenizer::{tokenize_str, Token};
+
+/// Reserved words after which the parser still expects to see a command name.
+const COMMAND_PREFIX_RESERVED_WORDS: &[&str] = &[
+    "!", "{", "do", "elif", "else", "if", "then", "time", "until", "while",
+];
+
+/// Reserved words whose following word is *not* in command position (e.g., a loop
+/// variable name or the word being matched by a `case` statement).
+const NON_COMMAND_PREFIX_RESERVED_WORDS: &[&str] = &["case", "for", "function", "in", "select"];
+
+/// Performs alias substitution on the given sequence of tokens, returning the
+/// resulting sequence.
+///
+/// # Arguments
+///
+/// * `tokens` - The tokens to perform substitution on.
+/// * `aliases` - The alias definitions to apply.
+pub(crate) fn expand_aliases(tokens: Vec<Token>, aliases: &HashMap<String, String>) -> Vec<Token> {
+    if aliases.is_empty() {
+        return tokens;
+    }
+
+    let mut expander = AliasExpander {
+        aliases,
+        output: Vec::with_capacity(tokens.len()),
+        expect_command: true,
+        expand_next_word: false,
+        pending_redirect_words: 0,
+    };
+
+    let mut active_aliases = vec![];
+    for token in tokens {
+        expander.process(token, &mut active_aliases);
+    }
+
+    expander.output
+}
+
+struct AliasExpander<'a> {
+    aliases: &'a HashMap<String, String>,
+    output: Vec<Token>,
+    /// Whether the next word token is in a position where it would be treated
+    /// as a command name (and is therefore eligible for alias substitution).
+    expect_command: bool,
+    /// Whether the next word token follows an alias whose value ended in a blank,
+    /// making it eligible for alias substitution regardless of its position.
+    expand_next_word: bool,
+    /// Number of upcoming word tokens that are redirection targets (or here-document
+    /// bodies) and should be passed through untouched.
+    pending_redirect_words: usize,
+}
+
+impl AliasExpander<'_> {
+    fn process(&mut self, token: Token, active_aliases: &mut Vec<String>) {
+        let word = match token {
+            Token::Operator(ref op, _) => {
+                match op.as_str() {
+                    "<<" | "<<-" => self.pending_redirect_words = 2,
+                    "<" | ">" | ">|" | ">>" | "<&" | ">&" | "<>" | "<<<" | "&>" | "&>>" => {
+                        self.pending_redirect_words = 1;
+                    }
+                    _ => self.expect_command = true,
+                }
+
+                self.output.push(token);
+                return;
+            }
+            Token::Word(ref word, _) => word,
+        };
+
+        if self.pending_redirect_words > 0 {
+            self.pending_redirect_words -= 1;
+            self.output.push(token);
+            return;
+        }
+
+        let expand_next_word = std::mem::take(&mut self.expand_next_word);
+        if !self.expect_command && !expand_next_word {
+            self.output.push(token);
+            return;
+        }
+
+        if COMMAND_PREFIX_RESERVED_WORDS.contains(&word.as_str()) || is_assignment_word(word) {
+            self.output.push(token);
+            return;
+        }
+
+        if NON_COMMAND_PREFIX_RESERVED_WORDS.contains(&word.as_str()) {
+            self.expect_command = false;
+            self.output.push(token);
+            return;
+        }
+
+        // Don't recursively expand an alias that's already being expanded.
+        let aliases = self.aliases;
+        let value = match aliases.get(word) {
+            Some(value) if !active_aliases.contains(word) => value,
+            _ => {
+                self.expect_command = false;
+                self.output.push(token);
+                return;
+            }
+        };
+
+        // If the alias's value can't be tokenized on its own, then leave the word alone.
+        let Ok(replacement_tokens) = tokenize_str(value) else {
+            self.expect_command = false;
+            self.output.push(token);
+            return;
+        };
+
+        let location = token.location().clone();
+        active_aliases.push(word.clone());
+
+        for replacement_token in replacement_tokens {
+            // Attribute all substituted tokens to the location of the alias name.
+            let replacement_token = match replacement_token {
+                Token::Operator(s, _) => Token::Operator(s, location.clone()),
+                Token::Word(s, _) => Token::Word(s, location.clone()),
+            };
+
+            self.process(replacement_token, active_aliases);
+        }
+
+        active_aliases.pop();
+
+        // If the alias value ends in a blank, then the next word is also checked
+        // for alias substitution.
+        self.expand_next_word = value.ends_with([' ', '\t']);
+    }
+}
+
+fn is_assignment_word(word: &str) -> bool {
+    let Some(end) = word.find(['=', '[', '+']) else {
+        return false;
+    };
+
+    let name = &word[..end];
+    let valid_name = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+
+    valid_name && word.contains('=')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    fn expand(input: &str, aliases: &[(&str, &str)]) -> Result<Vec<String>> {
+        let aliases = aliases
+            .iter()
+            .map(|(name, value)| ((*name).to_owned(), (*value).to_owned()))
+            .collect();
+
+        let tokens = expand_aliases(tokenize_str(input)?, &aliases);
+        Ok(tokens.iter().map(|t| t.to_str().to_owned()).collect())
+    }
+
+    #[test]
+    fn expand_command_name() -> Result<()> {
+        let aliases = [("ll", "ls -l")];
+
+        assert_eq!(expand("ll /", &aliases)?, ["ls", "-l", "/"]);
+        assert_eq!(expand("echo ll", &aliases)?, ["echo", "ll"]);
+        assert_eq!(
+            expand("x=1 ll; if ll; then ll; fi", &aliases)?,
+            ["x=1", "ls", "-l", ";", "if", "ls", "-l", ";", "then", "ls", "-l", ";", "fi"]
+        );
+        assert_eq!(expand("echo >ll ll", &aliases)?, ["echo", ">", "ll", "ll"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn expand_recursive_aliases() -> Result<()> {
+        let aliases = [("ls", "ls -F"), ("a", "b"), ("b", "a x")];
+
+        assert_eq!(expand("ls", &aliases)?, ["ls", "-F"]);
+        assert_eq!(expand("a", &aliases)?, ["a", "x"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn expand_after_trailing_blank() -> Result<()> {
+        let aliases = [
+            ("sudo", "sudo "),
+            ("quiet", "sudo"),
+            ("ll", "ls -l"),
+            ("x", "echo a;"),
+        ];
+
+        assert_eq!(expand("sudo ll", &aliases)?, ["sudo", "ls", "-l"]);
+        assert_eq!(expand("quiet ll", &aliases)?, ["sudo", "ll"]);
+        assert_eq!(expand("x ll", &aliases)?, ["echo", "a", ";", "ls", "-l"]);
+
+        Ok(())
+    }
+}
@@ -35,6 +35,8 @@ peg::parser! {
             x:lvalue() _ "<<=" _ y:(@) { ast::ArithmeticExpr::BinaryAssignment(ast::BinaryOperator::ShiftLeft, x, Box::new(y)) }
             x:lvalue() _ ">>=" _ y:(@) { ast::ArithmeticExpr::BinaryAssignment(ast::BinaryOperator::ShiftRight, x, Box::new(y)) }
             x:lvalue() _ "&=" _ y:(@) { ast::ArithmeticExpr::BinaryAssignment(ast::BinaryOperator::BitwiseAnd, x, Box::new(y)) }
+            x:lvalue() _ "|=" _ y:(@) { ast::ArithmeticExpr::BinaryAssignment(ast::BinaryOperator::BitwiseOr, x, Box::new(y)) }
+            x:lvalue() _ "^=" _ y:(@) { ast::ArithmeticExpr::BinaryAssignment(ast::BinaryOperator::BitwiseXor, x, Box::new(y)) }
             --
             x:lvalue() _ "=" _ y:(@) { ast::ArithmeticExpr::Assignment(x, Box::new(y)) }
             --
@@ -101,9 +103,89 @@ peg::parser! {
         rule _() -> () = quiet!{[' ' | '\t' | '\n' | '\r']*} {}
 
         rule literal_number() -> i64 =
-            // TODO: handle explicit radix (e.g., <base>#<literal>) for bases 2 through 64
+            base:$(['1'..='9'] ['0'..='9']*) "#" digits:$(['0'..='9' | 'a'..='z' | 'A'..='Z' | '@' | '_']+) {?
+                parse_number_with_base(base, digits).ok_or("i64")
+            } /
             "0" ['x' | 'X'] s:$(['0'..='9' | 'a'..='f' | 'A'..='F']*) {? i64::from_str_radix(s, 16).or(Err("i64")) } /
-            s:$("0" ['0'..='8']*) {? i64::from_str_radix(s, 8).or(Err("i64")) } /
+            s:$("0" ['0'..='7']*) {? i64::from_str_radix(s, 8).or(Err("i64")) } /
             s:$(['1'..='9'] ['0'..='9']*) {? s.parse().or(Err("i64")) }
     }
 }
+
+/// Parses a number expressed in the given base (2 through 64), as in a `base#digits`
+/// literal. Digits beyond 9 are represented by lowercase letters, uppercase letters, `@`,
+/// and `_`, in that order; for bases up to 36, letters are case-insensitive.
+fn parse_number_with_base(base: &str, digits: &str) -> Option<i64> {
+    let base = base.parse::<u32>().ok()?;
+    if !(2..=64).contains(&base) {
+        return None;
+    }
+
+    let mut value: i64 = 0;
+    for c in digits.chars() {
+        let digit = match c {
+            '0'..='9' => u32::from(c) - u32::from('0'),
+            'a'..='z' => u32::from(c) - u32::from('a') + 10,
+            'A'..='Z' if base <= 36 => u32::from(c) - u32::from('A') + 10,
+            'A'..='Z' => u32::from(c) - u32::from('A') + 36,
+            '@' => 62,
+            '_' => 63,
+            _ => return None,
+        };
+
+        if digit >= base {
+            return None;
+        }
+
+        value = value
+            .wrapping_mul(i64::from(base))
+            .wrapping_add(i64::from(digit));
+    }
+
+    Some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    fn parse_literal(input: &str) -> Result<i64> {
+        match parse(input)? {
+            ast::ArithmeticExpr::Literal(value) => Ok(value),
+            expr => Err(anyhow::anyhow!("unexpected expression: {expr:?}")),
+        }
+    }
+
+    #[test]
+    fn parse_literals_with_base() -> Result<()> {
+        assert_eq!(parse_literal("2#1010")?, 10);
+        assert_eq!(parse_literal("16#ff")?, 255);
+        assert_eq!(parse_literal("16#FF")?, 255);
+        assert_eq!(parse_literal("36#z")?, 35);
+        assert_eq!(parse_literal("62#Z")?, 61);
+        assert_eq!(parse_literal("64#@")?, 62);
+        assert_eq!(parse_literal("64#_")?, 63);
+        assert_eq!(parse_literal("10#010")?, 10);
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_octal_and_hex_literals() -> Result<()> {
+        assert_eq!(parse_literal("017")?, 15);
+        assert_eq!(parse_literal("0")?, 0);
+        assert_eq!(parse_literal("0x1F")?, 31);
+        assert_eq!(parse_literal("0Xff")?, 255);
+
+        Ok(())
+    }
+
+    #[test]
+    fn reject_invalid_literals() {
+        assert!(parse("08").is_err());
+        assert!(parse("2#3").is_err());
+        assert!(parse("1#0").is_err());
+        assert!(parse("65#1").is_err());
+    }
+}
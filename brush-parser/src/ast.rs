@@ -17,7 +17,10 @@ pub struct Program {
 
 impl Display for Program {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for complete_command in &self.complete_commands {
+        for (i, complete_command) in self.complete_commands.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
             write!(f, "{}", complete_command)?;
         }
         Ok(())
@@ -59,6 +62,20 @@ pub struct AndOrList {
     pub additional: Vec<AndOr>,
 }
 
+impl AndOrList {
+    /// Returns the here-documents redirected to the commands in this list, in order. Any
+    /// here-documents nested within the bodies of compound commands are not included.
+    fn here_documents(&self) -> Vec<&IoHereDocument> {
+        std::iter::once(&self.first)
+            .chain(self.additional.iter().map(|and_or| match and_or {
+                AndOr::And(pipeline) | AndOr::Or(pipeline) => pipeline,
+            }))
+            .flat_map(|pipeline| pipeline.seq.iter())
+            .flat_map(Command::here_documents)
+            .collect()
+    }
+}
+
 impl Display for AndOrList {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.first)?;
@@ -97,6 +114,9 @@ impl Display for AndOr {
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
 pub struct Pipeline {
+    /// Indicates whether the pipeline's execution should be timed, and if so, how the
+    /// timing should be reported.
+    pub timed: Option<PipelineTimed>,
     /// Indicates whether the result of the overall pipeline should be the logical
     /// negation of the result of the pipeline.
     pub bang: bool,
@@ -106,12 +126,15 @@ pub struct Pipeline {
 
 impl Display for Pipeline {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(timed) = &self.timed {
+            write!(f, "{timed} ")?;
+        }
         if self.bang {
-            write!(f, "!")?;
+            write!(f, "! ")?;
         }
         for (i, command) in self.seq.iter().enumerate() {
             if i > 0 {
-                write!(f, " |")?;
+                write!(f, " | ")?;
             }
             write!(f, "{}", command)?;
         }
@@ -120,6 +143,25 @@ impl Display for Pipeline {
     }
 }
 
+/// Describes how the execution time of a timed pipeline is reported.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
+pub enum PipelineTimed {
+    /// The timing is reported in the format given by `TIMEFORMAT`.
+    Timed,
+    /// The timing is reported in the POSIX-specified format.
+    TimedWithPosixOutput,
+}
+
+impl Display for PipelineTimed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PipelineTimed::Timed => write!(f, "time"),
+            PipelineTimed::TimedWithPosixOutput => write!(f, "time -p"),
+        }
+    }
+}
+
 /// Represents a shell command.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
@@ -133,6 +175,50 @@ pub enum Command {
     Function(FunctionDefinition),
     /// A command that evaluates an extended test expression.
     ExtendedTest(ExtendedTestExpr),
+    /// A command executed asynchronously as a coprocess.
+    Coprocess(CoprocessCommand),
+}
+
+impl Command {
+    /// Returns the here-documents redirected to this command itself.
+    fn here_documents(&self) -> Vec<&IoHereDocument> {
+        let redirects: Vec<&IoRedirect> = match self {
+            Command::Simple(simple_command) => simple_command
+                .prefix
+                .iter()
+                .flat_map(|prefix| prefix.0.iter())
+                .chain(
+                    simple_command
+                        .suffix
+                        .iter()
+                        .flat_map(|suffix| suffix.0.iter()),
+                )
+                .filter_map(|item| match item {
+                    CommandPrefixOrSuffixItem::IoRedirect(redirect) => Some(redirect),
+                    _ => None,
+                })
+                .collect(),
+            Command::Compound(_, redirect_list) => redirect_list
+                .iter()
+                .flat_map(|redirect_list| redirect_list.0.iter())
+                .collect(),
+            Command::Function(function_definition) => function_definition
+                .body
+                .1
+                .iter()
+                .flat_map(|redirect_list| redirect_list.0.iter())
+                .collect(),
+            Command::ExtendedTest(_) | Command::Coprocess(_) => vec![],
+        };
+
+        redirects
+            .into_iter()
+            .filter_map(|redirect| match redirect {
+                IoRedirect::HereDocument(_, here_doc) => Some(here_doc),
+                _ => None,
+            })
+            .collect()
+    }
 }
 
 impl Display for Command {
@@ -150,10 +236,32 @@ impl Display for Command {
             Command::ExtendedTest(extended_test_expr) => {
                 write!(f, "[[ {} ]]", extended_test_expr)
             }
+            Command::Coprocess(coprocess_command) => write!(f, "{}", coprocess_command),
         }
     }
 }
 
+/// A command executed asynchronously as a coprocess, with its standard input and output
+/// connected to the shell through pipes.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
+pub struct CoprocessCommand {
+    /// Optionally, the name of the coprocess; only compound commands may be named.
+    pub name: Option<String>,
+    /// The command to execute.
+    pub body: Box<Command>,
+}
+
+impl Display for CoprocessCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "coproc ")?;
+        if let Some(name) = &self.name {
+            write!(f, "{name} ")?;
+        }
+        write!(f, "{}", self.body)
+    }
+}
+
 /// Represents a compound command, potentially made up of multiple nested commands.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
@@ -168,6 +276,8 @@ pub enum CompoundCommand {
     Subshell(SubshellCommand),
     /// A for clause, which loops over a set of values.
     ForClause(ForClauseCommand),
+    /// A select clause, which repeatedly prompts the user to select one of a set of values.
+    SelectClause(SelectClauseCommand),
     /// A case clause, which selects a command based on a value and a set of
     /// pattern-based filters.
     CaseClause(CaseClauseCommand),
@@ -191,6 +301,9 @@ impl Display for CompoundCommand {
             }
             CompoundCommand::Subshell(subshell_command) => write!(f, "{}", subshell_command),
             CompoundCommand::ForClause(for_clause_command) => write!(f, "{}", for_clause_command),
+            CompoundCommand::SelectClause(select_clause_command) => {
+                write!(f, "{}", select_clause_command)
+            }
             CompoundCommand::CaseClause(case_clause_command) => {
                 write!(f, "{}", case_clause_command)
             }
@@ -256,6 +369,45 @@ impl Display for ForClauseCommand {
 
                 write!(f, "{}", value)?;
             }
+        } else {
+            // With no values, the loop iterates over the positional parameters.
+            write!(f, "\"$@\"")?;
+        }
+
+        writeln!(f, ";")?;
+
+        write!(f, "{}", self.body)
+    }
+}
+
+/// A select clause, which repeatedly displays a menu of values and prompts the user to
+/// select one of them.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
+pub struct SelectClauseCommand {
+    /// The name of the variable that receives the selected value.
+    pub variable_name: String,
+    /// The values offered for selection.
+    pub values: Option<Vec<Word>>,
+    /// The command to run after each selection.
+    pub body: DoGroupCommand,
+}
+
+impl Display for SelectClauseCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "select {} in ", self.variable_name)?;
+
+        if let Some(values) = &self.values {
+            for (i, value) in values.iter().enumerate() {
+                if i > 0 {
+                    write!(f, " ")?;
+                }
+
+                write!(f, "{}", value)?;
+            }
+        } else {
+            // With no values, the positional parameters are offered.
+            write!(f, "\"$@\"")?;
         }
 
         writeln!(f, ";")?;
@@ -347,6 +499,17 @@ impl Display for CompoundList {
             } else {
                 write!(f, "{}", item.1)?;
             }
+
+            // Write the bodies of any here-documents, which follow the line they're used on.
+            for here_doc in item.0.here_documents() {
+                writeln!(f)?;
+                write!(
+                    f,
+                    "{}{}",
+                    here_doc.doc,
+                    tokenizer::unquote_here_tag(here_doc.here_end.value.as_str())
+                )?;
+            }
         }
 
         Ok(())
@@ -434,6 +597,8 @@ pub struct CaseItem {
     pub patterns: Vec<Word>,
     /// The commands to execute if this case branch is selected.
     pub cmd: Option<CompoundList>,
+    /// What to do after executing this case branch.
+    pub post_action: CaseItemPostAction,
 }
 
 impl Display for CaseItem {
@@ -451,7 +616,30 @@ impl Display for CaseItem {
             write!(indenter::indented(f).with_str(DISPLAY_INDENT), "{}", cmd)?;
         }
         writeln!(f)?;
-        write!(f, ";;")
+        write!(f, "{}", self.post_action)
+    }
+}
+
+/// Describes the action to take after executing the body of a case branch.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
+pub enum CaseItemPostAction {
+    /// The case clause should be exited (`;;`).
+    ExitCase,
+    /// The body of the next case branch should be executed, regardless of whether its
+    /// patterns match (`;&`).
+    UnconditionallyExecuteNextCaseItem,
+    /// The patterns of the remaining case branches should continue to be tested (`;;&`).
+    ContinueEvaluatingCases,
+}
+
+impl Display for CaseItemPostAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CaseItemPostAction::ExitCase => write!(f, ";;"),
+            CaseItemPostAction::UnconditionallyExecuteNextCaseItem => write!(f, ";&"),
+            CaseItemPostAction::ContinueEvaluatingCases => write!(f, ";;&"),
+        }
     }
 }
 
@@ -654,7 +842,7 @@ impl Display for CommandPrefixOrSuffixItem {
             CommandPrefixOrSuffixItem::Word(word) => write!(f, "{}", word),
             CommandPrefixOrSuffixItem::AssignmentWord(_assignment, word) => write!(f, "{}", word),
             CommandPrefixOrSuffixItem::ProcessSubstitution(kind, subshell_command) => {
-                write!(f, "{}({})", kind, subshell_command)
+                write!(f, "{}{}", kind, subshell_command)
             }
         }
     }
@@ -742,7 +930,7 @@ pub struct RedirectList(pub Vec<IoRedirect>);
 impl Display for RedirectList {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         for item in &self.0 {
-            write!(f, "{}", item)?;
+            write!(f, " {}", item)?;
         }
         Ok(())
     }
@@ -753,11 +941,11 @@ impl Display for RedirectList {
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
 pub enum IoRedirect {
     /// Redirection to a file.
-    File(Option<u32>, IoFileRedirectKind, IoFileRedirectTarget),
+    File(Option<IoFd>, IoFileRedirectKind, IoFileRedirectTarget),
     /// Redirection from a here-document.
-    HereDocument(Option<u32>, IoHereDocument),
+    HereDocument(Option<IoFd>, IoHereDocument),
     /// Redirection from a here-string.
-    HereString(Option<u32>, Word),
+    HereString(Option<IoFd>, Word),
     /// Redirection of both standard output and standard error (with optional append).
     OutputAndError(Word, bool),
 }
@@ -784,22 +972,21 @@ impl Display for IoRedirect {
                 IoHereDocument {
                     remove_tabs,
                     here_end,
-                    doc,
+                    ..
                 },
             ) => {
                 if let Some(fd_num) = fd_num {
                     write!(f, "{}", fd_num)?;
                 }
 
+                // N.B. The body of the document is written by the enclosing list, after the
+                // end of the line containing this redirection.
                 write!(f, "<<")?;
                 if *remove_tabs {
                     write!(f, "-")?;
                 }
 
-                writeln!(f, "{}", here_end)?;
-
-                write!(f, "{}", doc)?;
-                writeln!(f, "{}", here_end)?;
+                write!(f, "{}", here_end)?;
             }
             IoRedirect::HereString(fd_num, s) => {
                 if let Some(fd_num) = fd_num {
@@ -814,6 +1001,26 @@ impl Display for IoRedirect {
     }
 }
 
+/// File descriptor designated by an I/O redirection.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
+pub enum IoFd {
+    /// An explicit file descriptor number (e.g., `2>`).
+    Number(u32),
+    /// A variable that receives the number of a newly allocated file descriptor, or that
+    /// holds the number of the descriptor to close (e.g., `{fd}>`).
+    Variable(String),
+}
+
+impl Display for IoFd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IoFd::Number(fd) => write!(f, "{}", fd),
+            IoFd::Variable(name) => write!(f, "{{{}}}", name),
+        }
+    }
+}
+
 /// Kind of file I/O redirection.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
@@ -828,9 +1035,11 @@ pub enum IoFileRedirectKind {
     ReadAndWrite,
     /// Clobber (`>|`).
     Clobber,
-    /// Duplicate input (`<&`).
+    /// Duplicate input (`<&`). A target of `-` closes the descriptor instead, and a
+    /// target of `N-` moves descriptor `N`.
     DuplicateInput,
-    /// Duplicate output (`>&`).
+    /// Duplicate output (`>&`). A target of `-` closes the descriptor instead, and a
+    /// target of `N-` moves descriptor `N`.
     DuplicateOutput,
 }
 
@@ -9,9 +9,20 @@ pub enum ParseError {
     ParsingNearToken(Token),
 
     /// A parsing error occurred at the end of the input.
+    #[deprecated(note = "no longer produced by the parser; use `UnexpectedEndOfInput` instead")]
     #[error("parse error at end of input")]
     ParsingAtEndOfInput,
 
+    /// The input ended in the middle of a construct (e.g., an unterminated quote, command
+    /// substitution, compound command, or here-document). Unlike other errors, this
+    /// one may be resolved by providing more input.
+    #[error("unexpected end of input")]
+    UnexpectedEndOfInput {
+        /// The error reported by the tokenizer, if the unterminated construct was
+        /// detected while tokenizing.
+        inner: Option<tokenizer::TokenizerError>,
+    },
+
     /// An error occurred while tokenizing the input stream.
     #[error("failed to tokenize input")]
     Tokenizing {
@@ -22,6 +33,18 @@ pub enum ParseError {
     },
 }
 
+impl ParseError {
+    /// Returns whether the error indicates that the input ended before a complete
+    /// command could be parsed, i.e., that more input may turn it into a valid command.
+    #[allow(deprecated)]
+    pub fn is_incomplete(&self) -> bool {
+        matches!(
+            self,
+            Self::UnexpectedEndOfInput { .. } | Self::ParsingAtEndOfInput
+        )
+    }
+}
+
 /// Represents an error that occurred while parsing a word.
 #[derive(Debug, thiserror::Error)]
 pub enum WordParseError {
@@ -37,6 +60,10 @@ pub enum WordParseError {
     #[error("failed to parse prompt string")]
     Prompt(peg::error::ParseError<peg::str::LineCol>),
 
+    /// An error occurred while parsing the body of a here-document.
+    #[error("failed to parse here-document body")]
+    HereDocument(peg::error::ParseError<peg::str::LineCol>),
+
     /// An error occurred while parsing a parameter.
     #[error("failed to parse parameter '{0}'")]
     Parameter(String, peg::error::ParseError<peg::str::LineCol>),
@@ -44,6 +71,10 @@ pub enum WordParseError {
     /// An error occurred while parsing a word.
     #[error("failed to parse word '{0}'")]
     Word(String, peg::error::ParseError<peg::str::LineCol>),
+
+    /// An error occurred while parsing brace expressions in a word.
+    #[error("failed to parse brace expansions in word '{0}'")]
+    BraceExpansion(String, peg::error::ParseError<peg::str::LineCol>),
 }
 
 /// Represents an error that occurred while parsing a (non-extended) test command.
@@ -63,6 +94,6 @@ pub(crate) fn convert_peg_parse_error(
     if approx_token_index < tokens.len() {
         ParseError::ParsingNearToken(tokens[approx_token_index].clone())
     } else {
-        ParseError::ParsingAtEndOfInput
+        ParseError::UnexpectedEndOfInput { inner: None }
     }
 }
@@ -9,10 +9,11 @@ pub mod prompt;
 pub mod test_command;
 pub mod word;
 
+mod aliases;
 mod error;
 mod parser;
 mod tokenizer;
 
 pub use error::{ParseError, TestCommandParseError, WordParseError};
-pub use parser::{parse_tokens, Parser, ParserOptions, SourceInfo};
+pub use parser::{parse_tokens, CommandParser, Parser, ParserOptions, SourceInfo};
 pub use tokenizer::{tokenize_str, SourcePosition, Token, TokenLocation};
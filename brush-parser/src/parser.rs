@@ -1,3 +1,6 @@
+use std::collections::HashMap;
+
+use crate::aliases;
 use crate::ast::{self, SeparatorOperator};
 use crate::error;
 use crate::tokenizer::{Token, TokenEndReason, Tokenizer, TokenizerOptions, Tokens};
@@ -82,6 +85,9 @@ impl<R: std::io::BufRead> Parser<R> {
         loop {
             let result = match tokenizer.next_token() {
                 Ok(result) => result,
+                Err(e) if e.is_incomplete() => {
+                    return Err(error::ParseError::UnexpectedEndOfInput { inner: Some(e) });
+                }
                 Err(e) => {
                     return Err(error::ParseError::Tokenizing {
                         inner: e,
@@ -110,6 +116,96 @@ impl<R: std::io::BufRead> Parser<R> {
 
         parse_tokens(&tokens, &self.options, &self.source_info)
     }
+
+    /// Returns a parser that parses the input one complete command at a time, allowing
+    /// the caller to execute each command (and thereby update alias definitions) before
+    /// the next one is read.
+    pub fn commands(&mut self) -> CommandParser<'_, R> {
+        CommandParser {
+            tokenizer: Tokenizer::new(
+                &mut self.reader,
+                &TokenizerOptions {
+                    enable_extended_globbing: self.options.enable_extended_globbing,
+                    posix_mode: self.options.posix_mode,
+                },
+            ),
+            options: &self.options,
+            source_info: &self.source_info,
+        }
+    }
+}
+
+/// Parses shell programs one complete command at a time; obtained from [`Parser::commands`].
+pub struct CommandParser<'a, R: std::io::BufRead> {
+    tokenizer: Tokenizer<'a, R>,
+    options: &'a ParserOptions,
+    source_info: &'a SourceInfo,
+}
+
+impl<R: std::io::BufRead> CommandParser<'_, R> {
+    /// Parses the next complete command from the input, returning it as a program; returns
+    /// `None` once the input has been exhausted. Lines are read until they form a complete
+    /// command, and aliases are substituted into each line as it is read.
+    ///
+    /// # Arguments
+    ///
+    /// * `aliases` - The alias definitions to apply to the command.
+    pub fn parse_next(
+        &mut self,
+        aliases: &HashMap<String, String>,
+    ) -> Result<Option<ast::Program>, error::ParseError> {
+        let mut tokens = vec![];
+
+        loop {
+            let (line, end_of_input) = self.read_line()?;
+            tokens.extend(aliases::expand_aliases(line, aliases));
+
+            match parse_tokens(&tokens, self.options, self.source_info) {
+                // Skip over blank lines (and lines containing only comments).
+                Ok(program) if program.complete_commands.is_empty() && !end_of_input => {
+                    tokens.clear();
+                }
+                Ok(program) if program.complete_commands.is_empty() => return Ok(None),
+                Ok(program) => return Ok(Some(program)),
+                Err(e) if e.is_incomplete() && !end_of_input => (),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Reads the tokens through the end of the next unescaped newline at the top level
+    /// of the token stream, along with whether the end of input was reached.
+    fn read_line(&mut self) -> Result<(Vec<Token>, bool), error::ParseError> {
+        let mut tokens = vec![];
+
+        loop {
+            let result = match self.tokenizer.next_token() {
+                Ok(result) => result,
+                Err(e) if e.is_incomplete() => {
+                    return Err(error::ParseError::UnexpectedEndOfInput { inner: Some(e) });
+                }
+                Err(e) => {
+                    return Err(error::ParseError::Tokenizing {
+                        inner: e,
+                        position: self.tokenizer.current_location(),
+                    });
+                }
+            };
+
+            let end_of_line = matches!(&result.token, Some(Token::Operator(op, _)) if op == "\n");
+            if let Some(token) = result.token {
+                tokens.push(token);
+            }
+
+            if matches!(result.reason, TokenEndReason::EndOfInput) {
+                return Ok((tokens, true));
+            }
+
+            if end_of_line {
+                return Ok((tokens, false));
+            }
+        }
+    }
 }
 
 /// Parses a sequence of tokens into the abstract syntax tree (AST) of a shell program.
@@ -251,14 +347,24 @@ peg::parser! {
             specific_operator("||") { ast::AndOr::Or }
 
         rule pipeline() -> ast::Pipeline =
-            bang:bang()? seq:pipe_sequence() { ast::Pipeline { bang: bang.is_some(), seq } }
+            bang() timed:pipeline_timed() seq:pipe_sequence() {
+                ast::Pipeline { timed: Some(timed), bang: true, seq }
+            } /
+            timed:pipeline_timed()? bang:bang()? seq:pipe_sequence() {
+                ast::Pipeline { timed, bang: bang.is_some(), seq }
+            }
         rule bang() -> bool = specific_word("!") { true }
+        rule pipeline_timed() -> ast::PipelineTimed =
+            specific_word("time") specific_word("-p") { ast::PipelineTimed::TimedWithPosixOutput } /
+            specific_word("time") { ast::PipelineTimed::Timed }
 
         rule pipe_sequence() -> Vec<ast::Command> =
             c:command() ++ (specific_operator("|") linebreak()) { c }
 
         // N.B. We needed to move the function definition branch up to avoid conflicts with array assignment syntax.
         rule command() -> ast::Command =
+            // N.B. Coprocesses are bash extensions.
+            non_posix_extensions_enabled() c:coprocess() { ast::Command::Coprocess(c) } /
             f:function_definition() { ast::Command::Function(f) } /
             c:simple_command() { ast::Command::Simple(c) } /
             c:compound_command() r:redirect_list()? { ast::Command::Compound(c, r) } /
@@ -266,6 +372,17 @@ peg::parser! {
             non_posix_extensions_enabled() c:extended_test_command() { ast::Command::ExtendedTest(c) } /
             expected!("command")
 
+        rule coprocess() -> ast::CoprocessCommand =
+            specific_word("coproc") n:name() c:compound_command() r:redirect_list()? {
+                ast::CoprocessCommand { name: Some(n.to_owned()), body: Box::new(ast::Command::Compound(c, r)) }
+            } /
+            specific_word("coproc") c:compound_command() r:redirect_list()? {
+                ast::CoprocessCommand { name: None, body: Box::new(ast::Command::Compound(c, r)) }
+            } /
+            specific_word("coproc") c:simple_command() {
+                ast::CoprocessCommand { name: None, body: Box::new(ast::Command::Simple(c)) }
+            }
+
         // N.B. The arithmetic command is a non-sh extension.
         // N.B. The arithmetic for clause command is a non-sh extension.
         rule compound_command() -> ast::CompoundCommand =
@@ -278,6 +395,7 @@ peg::parser! {
             w:while_clause() { ast::CompoundCommand::WhileClause(w) } /
             u:until_clause() { ast::CompoundCommand::UntilClause(u) } /
             non_posix_extensions_enabled() c:arithmetic_for_clause() { ast::CompoundCommand::ArithmeticForClause(c) } /
+            non_posix_extensions_enabled() s:select_clause() { ast::CompoundCommand::SelectClause(s) } /
             expected!("compound command")
 
         rule arithmetic_command() -> ast::ArithmeticCommand =
@@ -295,7 +413,8 @@ peg::parser! {
         // TODO: evaluate arithmetic end; the semicolon is used in arithmetic for loops.
         rule arithmetic_end() -> () =
             specific_operator(")") specific_operator(")") {} /
-            specific_operator(";") {}
+            specific_operator(";") {} /
+            specific_operator(";;") {}
 
         rule subshell() -> ast::SubshellCommand =
             specific_operator("(") c:compound_list() specific_operator(")") { ast::SubshellCommand(c) }
@@ -330,17 +449,47 @@ peg::parser! {
                 ast::ForClauseCommand { variable_name: n.to_owned(), values: None, body: d }
             }
 
+        // N.B. The select loop is a non-sh extension.
+        rule select_clause() -> ast::SelectClauseCommand =
+            specific_word("select") n:name() linebreak() _in() w:wordlist()? sequential_sep() d:do_group() {
+                ast::SelectClauseCommand { variable_name: n.to_owned(), values: w, body: d }
+            } /
+            specific_word("select") n:name() sequential_sep()? d:do_group() {
+                ast::SelectClauseCommand { variable_name: n.to_owned(), values: None, body: d }
+            }
+
         // N.B. The arithmetic for loop is a non-sh extension.
         rule arithmetic_for_clause() -> ast::ArithmeticForClauseCommand =
             specific_word("for")
             specific_operator("(") specific_operator("(")
-                initializer:arithmetic_expression()? specific_operator(";")
-                condition:arithmetic_expression()? specific_operator(";")
-                updater:arithmetic_expression()?
+                exprs:arithmetic_for_expressions()
             specific_operator(")") specific_operator(")")
-            sequential_sep()
+            sequential_sep()?
             body:do_group() {
-                ast::ArithmeticForClauseCommand { initializer, condition, updater, body }
+                let (initializer, condition, updater) = exprs;
+
+                // N.B. An empty expression is the same as an omitted one.
+                let non_empty = |expr: Option<ast::UnexpandedArithmeticExpr>| {
+                    expr.filter(|expr| !expr.value.trim().is_empty())
+                };
+
+                ast::ArithmeticForClauseCommand {
+                    initializer: non_empty(initializer),
+                    condition: non_empty(condition),
+                    updater: non_empty(updater),
+                    body,
+                }
+            }
+
+        // N.B. An omitted condition may leave its separators tokenized together as ';;'.
+        rule arithmetic_for_expressions() -> (Option<ast::UnexpandedArithmeticExpr>, Option<ast::UnexpandedArithmeticExpr>, Option<ast::UnexpandedArithmeticExpr>) =
+            initializer:arithmetic_expression()? specific_operator(";")
+            condition:arithmetic_expression()? specific_operator(";")
+            updater:arithmetic_expression()? {
+                (initializer, condition, updater)
+            } /
+            initializer:arithmetic_expression()? specific_operator(";;") updater:arithmetic_expression()? {
+                (initializer, None, updater)
             }
 
         rule extended_test_command() -> ast::ExtendedTestExpr =
@@ -446,20 +595,26 @@ peg::parser! {
 
         pub(crate) rule case_item_ns() -> ast::CaseItem =
             specific_operator("(")? p:pattern() specific_operator(")") c:compound_list() {
-                ast::CaseItem { patterns: p, cmd: Some(c) }
+                ast::CaseItem { patterns: p, cmd: Some(c), post_action: ast::CaseItemPostAction::ExitCase }
             } /
             specific_operator("(")? p:pattern() specific_operator(")") linebreak() {
-                ast::CaseItem { patterns: p, cmd: None }
+                ast::CaseItem { patterns: p, cmd: None, post_action: ast::CaseItemPostAction::ExitCase }
             }
 
         pub(crate) rule case_item() -> ast::CaseItem =
-            specific_operator("(")? p:pattern() specific_operator(")") linebreak() specific_operator(";;") linebreak() {
-                ast::CaseItem { patterns: p, cmd: None }
+            specific_operator("(")? p:pattern() specific_operator(")") linebreak() post_action:case_item_post_action() linebreak() {
+                ast::CaseItem { patterns: p, cmd: None, post_action }
             } /
-            specific_operator("(")? p:pattern() specific_operator(")") c:compound_list() specific_operator(";;") linebreak() {
-                ast::CaseItem { patterns: p, cmd: Some(c) }
+            specific_operator("(")? p:pattern() specific_operator(")") c:compound_list() post_action:case_item_post_action() linebreak() {
+                ast::CaseItem { patterns: p, cmd: Some(c), post_action }
             }
 
+        // N.B. The ;& and ;;& terminators are non-sh extensions.
+        rule case_item_post_action() -> ast::CaseItemPostAction =
+            specific_operator(";;") { ast::CaseItemPostAction::ExitCase } /
+            non_posix_extensions_enabled() specific_operator(";;&") { ast::CaseItemPostAction::ContinueEvaluatingCases } /
+            non_posix_extensions_enabled() specific_operator(";&") { ast::CaseItemPostAction::UnconditionallyExecuteNextCaseItem }
+
         // TODO: validate if this should call non_reserved_word() or word()
         rule pattern() -> Vec<ast::Word> =
             (w:word() { ast::Word::from(w) }) ++ specific_operator("|")
@@ -684,6 +839,7 @@ peg::parser! {
         rule non_posix_reserved_word_token() -> &'input Token =
             specific_word("[[") /
             specific_word("]]") /
+            specific_word("coproc") /
             specific_word("function") /
             specific_word("select")
 
@@ -720,10 +876,16 @@ peg::parser! {
 
         // N.B. An I/O number must be a string of only digits, and it must be
         // followed by a '<' or '>' character (but not consume them).
-        rule io_number() -> u32 =
+        // N.B. The `{varname}` form is an extension to the POSIX standard.
+        rule io_number() -> ast::IoFd =
             [Token::Word(w, _) if w.chars().all(|c: char| c.is_ascii_digit())]
             &([Token::Operator(o, _) if o.starts_with('<') || o.starts_with('>')]) {
-                w.parse().unwrap()
+                ast::IoFd::Number(w.parse().unwrap())
+            } /
+            non_posix_extensions_enabled()
+            [Token::Word(w, _)]
+            &([Token::Operator(o, _) if o.starts_with('<') || o.starts_with('>')]) {?
+                parse_named_fd(w)
             }
 
         //
@@ -761,6 +923,9 @@ peg::parser! {
                 (None, value.to_owned())
             }
 
+        pub(crate) rule named_fd() -> &'input str =
+            "{" name:scalar_name() "}" { name }
+
         rule name() -> ast::AssignmentName =
             aen:array_element_name() {
                 let (name, index) = aen;
@@ -790,6 +955,11 @@ peg::parser! {
     }
 }
 
+fn parse_named_fd(word: &str) -> Result<ast::IoFd, &'static str> {
+    let name = assignments::named_fd(word).map_err(|_| "not named fd")?;
+    Ok(ast::IoFd::Variable(name.to_owned()))
+}
+
 fn parse_assignment_word(word: &str) -> Result<ast::Assignment, &'static str> {
     let parse_result = assignments::name_and_scalar_value(word);
     parse_result.map_err(|_| "not assignment word")
@@ -880,4 +1050,107 @@ esac\
 
         Ok(())
     }
+
+    #[test]
+    fn parse_commands_one_at_a_time() -> Result<()> {
+        let input = "x\n\n# comment\nif true\nthen x\nfi\nx )\nx\n";
+        let mut parser = Parser::new(
+            input.as_bytes(),
+            &ParserOptions::default(),
+            &SourceInfo::default(),
+        );
+        let mut commands = parser.commands();
+        let aliases = HashMap::from([("x".to_owned(), "echo".to_owned())]);
+
+        let mut next =
+            |aliases: &HashMap<String, String>| -> Result<Option<String>, error::ParseError> {
+                Ok(commands.parse_next(aliases)?.map(|p| p.to_string()))
+            };
+
+        assert_eq!(next(&HashMap::new())?.as_deref(), Some("x"));
+        assert!(next(&aliases)?.is_some_and(|p| p.starts_with("if") && p.contains("echo")));
+        assert!(next(&aliases).is_err());
+        assert_eq!(next(&aliases)?.as_deref(), Some("echo"));
+        assert!(next(&aliases)?.is_none());
+
+        Ok(())
+    }
+
+    fn parse_str(input: &str) -> Result<ast::Program, error::ParseError> {
+        let mut parser = Parser::new(
+            input.as_bytes(),
+            &ParserOptions::default(),
+            &SourceInfo::default(),
+        );
+        parser.parse(false)
+    }
+
+    #[test]
+    fn parse_timed_pipelines() -> Result<()> {
+        let program = parse_str("time -p ! false | cat")?;
+        let pipeline = &program.complete_commands[0].0[0].0.first;
+        assert!(matches!(
+            pipeline.timed,
+            Some(ast::PipelineTimed::TimedWithPosixOutput)
+        ));
+        assert!(pipeline.bang);
+        assert_eq!(pipeline.seq.len(), 2);
+
+        let program = parse_str("echo time")?;
+        assert!(program.complete_commands[0].0[0].0.first.timed.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn display_here_documents() -> Result<()> {
+        let input = "cat <<'E'\"OF\" | wc -l\nbody $x\nEOF\necho after";
+        assert_eq!(parse_str(input)?.to_string(), input);
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_named_fd_redirects() -> Result<()> {
+        let program = parse_str("echo {fd}>out")?;
+        let ast::Command::Simple(command) = &program.complete_commands[0].0[0].0.first.seq[0]
+        else {
+            panic!("expected simple command");
+        };
+
+        assert!(matches!(
+            &command.suffix.as_ref().unwrap().0[..],
+            [ast::CommandPrefixOrSuffixItem::IoRedirect(ast::IoRedirect::File(
+                Some(ast::IoFd::Variable(name)),
+                ast::IoFileRedirectKind::Write,
+                _
+            ))] if name == "fd"
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_arithmetic_for_clauses() -> Result<()> {
+        let program = parse_str("for ((i = 0;; i++)) do :; done")?;
+        let ast::Command::Compound(ast::CompoundCommand::ArithmeticForClause(for_clause), _) =
+            &program.complete_commands[0].0[0].0.first.seq[0]
+        else {
+            panic!("expected arithmetic for clause");
+        };
+
+        assert!(for_clause.initializer.is_some());
+        assert!(for_clause.condition.is_none());
+        assert!(for_clause.updater.is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_incomplete_input() {
+        assert!(parse_str("echo 'unterminated").is_err_and(|e| e.is_incomplete()));
+        assert!(parse_str("cat <<EOF\nbody\n").is_err_and(|e| e.is_incomplete()));
+        assert!(parse_str("if true; then echo hi").is_err_and(|e| e.is_incomplete()));
+        assert!(parse_str("echo hi; done").is_err_and(|e| !e.is_incomplete()));
+    }
 }
@@ -1,8 +1,11 @@
 //! Implements parsing for shell glob and extglob patterns.
 
+use std::collections::BTreeSet;
+
 use crate::error;
 
 /// Represents the kind of an extended glob.
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum ExtendedGlobKind {
     /// The `+` extended glob; matches one or more occurrences of the inner pattern.
     Plus,
@@ -16,7 +19,55 @@ pub enum ExtendedGlobKind {
     Star,
 }
 
-/// Converts a shell pattern to a regular expression string.
+/// Represents an element of a parsed shell pattern.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PatternElement {
+    /// A character that only matches itself.
+    Literal(char),
+    /// The `?` wildcard; matches any single character.
+    AnyChar,
+    /// The `*` wildcard; matches any sequence of characters, including an empty one.
+    AnyString,
+    /// A bracket expression; matches a single character that is one of its members or,
+    /// if negated, that isn't.
+    BracketExpression {
+        /// Whether or not the expression is negated.
+        negated: bool,
+        /// The members of the expression.
+        members: Vec<BracketMember>,
+    },
+    /// An extended glob, with the alternative patterns it applies to.
+    ExtendedGlob(ExtendedGlobKind, Vec<Vec<PatternElement>>),
+}
+
+/// Represents a member of a bracket expression.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BracketMember {
+    /// A single character.
+    Char(char),
+    /// An inclusive range of characters.
+    Range(char, char),
+    /// A named character class (e.g., `alpha` for `[:alpha:]`).
+    CharClass(String),
+}
+
+/// Parses a shell pattern into its elements.
+///
+/// # Arguments
+///
+/// * `pattern` - The shell pattern to parse.
+/// * `enable_extended_globbing` - Whether to enable extended globbing (extglob).
+pub fn parse_pattern(
+    pattern: &str,
+    enable_extended_globbing: bool,
+) -> Result<Vec<PatternElement>, error::WordParseError> {
+    pattern_parser::pattern(pattern, enable_extended_globbing)
+        .map_err(error::WordParseError::Pattern)
+}
+
+/// Converts a shell pattern to a regular expression string. Fails if the pattern contains a
+/// negated extended glob, which has no regular expression equivalent; such patterns may
+/// instead be matched with [`pattern_elements_match`].
 ///
 /// # Arguments
 ///
@@ -26,93 +77,292 @@ pub fn pattern_to_regex_str(
     pattern: &str,
     enable_extended_globbing: bool,
 ) -> Result<String, error::WordParseError> {
-    let regex_str = pattern_to_regex_translator::pattern(pattern, enable_extended_globbing)
+    let regex_str = pattern_parser::regex_pattern(pattern, enable_extended_globbing)
         .map_err(error::WordParseError::Pattern)?;
     Ok(regex_str)
 }
 
-peg::parser! {
-    grammar pattern_to_regex_translator(enable_extended_globbing: bool) for str {
-        pub(crate) rule pattern() -> String =
-            pieces:(pattern_piece()*) {
-                pieces.join("")
+/// Converts parsed pattern elements to an equivalent regular expression string. Returns
+/// `None` if the elements include a negated extended glob, which has no equivalent.
+///
+/// # Arguments
+///
+/// * `elements` - The pattern elements to convert.
+pub fn pattern_elements_to_regex_str(elements: &[PatternElement]) -> Option<String> {
+    let mut regex_str = String::new();
+    for element in elements {
+        append_element_regex_str(element, &mut regex_str)?;
+    }
+
+    Some(regex_str)
+}
+
+fn append_element_regex_str(element: &PatternElement, regex_str: &mut String) -> Option<()> {
+    match element {
+        PatternElement::Literal(c) => {
+            if regex_char_needs_escaping(*c) {
+                regex_str.push('\\');
+            }
+            regex_str.push(*c);
+        }
+        PatternElement::AnyChar => regex_str.push('.'),
+        PatternElement::AnyString => regex_str.push_str(".*"),
+        PatternElement::BracketExpression { negated, members } => {
+            regex_str.push('[');
+            if *negated {
+                regex_str.push('^');
             }
 
-        rule pattern_piece() -> String =
-            escape_sequence() /
-            bracket_expression() /
-            extglob_enabled() s:extended_glob_pattern() { s } /
-            wildcard() /
-            [c if regex_char_needs_escaping(c)] {
-                let mut s = '\\'.to_string();
-                s.push(c);
-                s
-            } /
-            [c] { c.to_string() }
-
-        rule escape_sequence() -> String =
-            sequence:$(['\\'] [c if regex_char_needs_escaping(c)]) { sequence.to_owned() } /
-            ['\\'] [c] { c.to_string() }
-
-        rule bracket_expression() -> String =
-            "[" invert:(("!")?) members:bracket_member()+ "]" {
-                let mut members = members;
-                if invert.is_some() {
-                    members.insert(0, String::from("^"));
+            for member in members {
+                match member {
+                    BracketMember::Char(c) => append_bracket_char(*c, regex_str),
+                    BracketMember::Range(start, end) => {
+                        append_bracket_char(*start, regex_str);
+                        regex_str.push('-');
+                        append_bracket_char(*end, regex_str);
+                    }
+                    BracketMember::CharClass(class) => {
+                        regex_str.push_str("[:");
+                        regex_str.push_str(class);
+                        regex_str.push_str(":]");
+                    }
                 }
-
-                std::format!("[{}]", members.join(""))
             }
 
-        rule bracket_member() -> String =
-            char_class_expression() /
-            char_range() /
-            char_list()
+            regex_str.push(']');
+        }
+        PatternElement::ExtendedGlob(kind, branches) => {
+            let suffix = match kind {
+                ExtendedGlobKind::Plus => "+",
+                ExtendedGlobKind::Question => "?",
+                ExtendedGlobKind::Star => "*",
+                ExtendedGlobKind::At => "",
+                ExtendedGlobKind::Exclamation => return None,
+            };
+
+            regex_str.push('(');
+            for (i, branch) in branches.iter().enumerate() {
+                if i > 0 {
+                    regex_str.push('|');
+                }
+                for element in branch {
+                    append_element_regex_str(element, regex_str)?;
+                }
+            }
+            regex_str.push(')');
+            regex_str.push_str(suffix);
+        }
+    }
 
-        rule char_class_expression() -> String =
-            e:$("[:" char_class() ":]") { e.to_owned() }
+    Some(())
+}
 
-        rule char_class() =
-            "alnum" / "alpha" / "blank" / "cntrl" / "digit" / "graph" / "lower" / "print" / "punct" / "space" / "upper"/ "xdigit"
+fn append_bracket_char(c: char, regex_str: &mut String) {
+    if matches!(c, '[' | ']' | '\\' | '^' | '-' | '&' | '~') {
+        regex_str.push('\\');
+    }
+    regex_str.push(c);
+}
 
-        rule char_range() -> String =
-            range:$([_] "-" [_]) { range.to_owned() }
+/// Checks whether parsed pattern elements match the entirety of the given value.
+///
+/// # Arguments
+///
+/// * `elements` - The pattern elements to match.
+/// * `value` - The string to check for a match.
+/// * `case_insensitive` - Whether or not characters should be matched without regard to case.
+pub fn pattern_elements_match(
+    elements: &[PatternElement],
+    value: &str,
+    case_insensitive: bool,
+) -> bool {
+    let chars: Vec<char> = value.chars().collect();
+    let matcher = ElementMatcher {
+        value: chars.as_slice(),
+        case_insensitive,
+    };
+
+    matcher.sequence_ends(elements, 0).contains(&chars.len())
+}
 
-        rule char_list() -> String =
-            chars:$([c if c != ']']+) { escape_char_class_char_list(chars) }
+/// Matches pattern elements against a string by tracking every position in the string that
+/// a prefix of the pattern can reach. Unlike backtracking one alternative at a time, this
+/// considers all ways of splitting the string among the elements (which negated extended
+/// globs depend on) while keeping the work polynomial in the length of the string.
+struct ElementMatcher<'a> {
+    value: &'a [char],
+    case_insensitive: bool,
+}
 
-        rule wildcard() -> String =
-            "?" { String::from(".") } /
-            "*" { String::from(".*") }
+impl ElementMatcher<'_> {
+    /// Returns the positions at which matching the given elements, in order, from the given
+    /// start position may end.
+    fn sequence_ends(&self, elements: &[PatternElement], start: usize) -> BTreeSet<usize> {
+        let mut positions = BTreeSet::from([start]);
+        for element in elements {
+            positions = positions
+                .into_iter()
+                .flat_map(|position| self.element_ends(element, position))
+                .collect();
+
+            if positions.is_empty() {
+                break;
+            }
+        }
 
-        rule extglob_enabled() -> () =
-            &[_] {? if enable_extended_globbing { Ok(()) } else { Err("extglob disabled") } }
+        positions
+    }
 
-        pub(crate) rule extended_glob_pattern() -> String =
-            kind:extended_glob_prefix() "(" branches:extended_glob_body() ")" {
-                let mut s = String::new();
+    /// Returns the positions at which matching the given element from the given start
+    /// position may end.
+    fn element_ends(&self, element: &PatternElement, start: usize) -> BTreeSet<usize> {
+        match element {
+            PatternElement::Literal(expected) => {
+                self.single_char_end(start, |c| self.chars_equal(c, *expected))
+            }
+            PatternElement::AnyChar => self.single_char_end(start, |_| true),
+            PatternElement::AnyString => (start..=self.value.len()).collect(),
+            PatternElement::BracketExpression { negated, members } => self
+                .single_char_end(start, |c| {
+                    members.iter().any(|member| self.member_matches(member, c)) != *negated
+                }),
+            PatternElement::ExtendedGlob(kind, branches) => match kind {
+                ExtendedGlobKind::At => self.branches_ends(branches, start),
+                ExtendedGlobKind::Question => {
+                    let mut ends = self.branches_ends(branches, start);
+                    ends.insert(start);
+                    ends
+                }
+                ExtendedGlobKind::Star => self.repeated_branches_ends(branches, [start].into()),
+                ExtendedGlobKind::Plus => {
+                    let first_ends = self.branches_ends(branches, start);
+                    self.repeated_branches_ends(branches, first_ends)
+                }
+                ExtendedGlobKind::Exclamation => {
+                    // Any substring that none of the branches match exactly is a match.
+                    let excluded = self.branches_ends(branches, start);
+                    (start..=self.value.len())
+                        .filter(|end| !excluded.contains(end))
+                        .collect()
+                }
+            },
+        }
+    }
 
-                s.push('(');
+    fn branches_ends(&self, branches: &[Vec<PatternElement>], start: usize) -> BTreeSet<usize> {
+        branches
+            .iter()
+            .flat_map(|branch| self.sequence_ends(branch, start))
+            .collect()
+    }
 
-                if matches!(kind, ExtendedGlobKind::Exclamation) {
-                    s.push_str("?!");
+    /// Returns the positions reachable from the given ones by matching any of the branches
+    /// zero or more times in succession.
+    fn repeated_branches_ends(
+        &self,
+        branches: &[Vec<PatternElement>],
+        starts: BTreeSet<usize>,
+    ) -> BTreeSet<usize> {
+        let mut reached = starts.clone();
+        let mut pending: Vec<usize> = starts.into_iter().collect();
+
+        while let Some(position) = pending.pop() {
+            for end in self.branches_ends(branches, position) {
+                if reached.insert(end) {
+                    pending.push(end);
                 }
+            }
+        }
 
-                s.push_str(&branches.join("|"));
-                s.push(')');
+        reached
+    }
 
-                match kind {
-                    ExtendedGlobKind::Plus => s.push('+'),
-                    ExtendedGlobKind::Question => s.push('?'),
-                    ExtendedGlobKind::Star => s.push('*'),
-                    ExtendedGlobKind::At | ExtendedGlobKind::Exclamation => (),
-                }
+    fn single_char_end(&self, start: usize, predicate: impl Fn(char) -> bool) -> BTreeSet<usize> {
+        match self.value.get(start) {
+            Some(c) if predicate(*c) => BTreeSet::from([start + 1]),
+            _ => BTreeSet::new(),
+        }
+    }
 
-                if matches!(kind, ExtendedGlobKind::Exclamation) {
-                    s.push_str(".*?");
-                }
+    fn chars_equal(&self, c: char, expected: char) -> bool {
+        c == expected || (self.case_insensitive && c.to_lowercase().eq(expected.to_lowercase()))
+    }
+
+    fn member_matches(&self, member: &BracketMember, c: char) -> bool {
+        let matches_exactly = |c: char| match member {
+            BracketMember::Char(member_char) => c == *member_char,
+            BracketMember::Range(start, end) => (*start..=*end).contains(&c),
+            BracketMember::CharClass(class) => char_class_contains(class, c),
+        };
+
+        matches_exactly(c)
+            || (self.case_insensitive
+                && (c.to_lowercase().any(matches_exactly) || c.to_uppercase().any(matches_exactly)))
+    }
+}
+
+fn char_class_contains(class: &str, c: char) -> bool {
+    match class {
+        "alnum" => c.is_alphanumeric(),
+        "alpha" => c.is_alphabetic(),
+        "blank" => c == ' ' || c == '\t',
+        "cntrl" => c.is_control(),
+        "digit" => c.is_ascii_digit(),
+        "graph" => !c.is_whitespace() && !c.is_control(),
+        "lower" => c.is_lowercase(),
+        "print" => !c.is_control(),
+        "punct" => c.is_ascii_punctuation(),
+        "space" => c.is_whitespace(),
+        "upper" => c.is_uppercase(),
+        "xdigit" => c.is_ascii_hexdigit(),
+        _ => false,
+    }
+}
+
+peg::parser! {
+    grammar pattern_parser(enable_extended_globbing: bool) for str {
+        pub(crate) rule pattern() -> Vec<PatternElement> =
+            pattern_element()*
+
+        pub(crate) rule regex_pattern() -> String =
+            elements:pattern() {?
+                pattern_elements_to_regex_str(&elements)
+                    .ok_or("pattern without negated extended globs")
+            }
+
+        rule pattern_element() -> PatternElement =
+            escape_sequence() /
+            bracket_expression() /
+            extglob_enabled() e:extended_glob_pattern() { e } /
+            wildcard() /
+            c:[_] { PatternElement::Literal(c) }
+
+        rule escape_sequence() -> PatternElement =
+            ['\\'] c:[_] { PatternElement::Literal(c) }
+
+        rule bracket_expression() -> PatternElement =
+            "[" negated:("!")? members:bracket_member()+ "]" {
+                PatternElement::BracketExpression { negated: negated.is_some(), members }
+            }
+
+        rule bracket_member() -> BracketMember =
+            "[:" class:$(char_class()) ":]" { BracketMember::CharClass(class.to_owned()) } /
+            start:[_] "-" end:[c if c != ']'] { BracketMember::Range(start, end) } /
+            c:[c if c != ']'] { BracketMember::Char(c) }
 
-                s
+        rule char_class() =
+            "alnum" / "alpha" / "blank" / "cntrl" / "digit" / "graph" / "lower" / "print" / "punct" / "space" / "upper"/ "xdigit"
+
+        rule wildcard() -> PatternElement =
+            "?" { PatternElement::AnyChar } /
+            "*" { PatternElement::AnyString }
+
+        rule extglob_enabled() -> () =
+            &[_] {? if enable_extended_globbing { Ok(()) } else { Err("extglob disabled") } }
+
+        pub(crate) rule extended_glob_pattern() -> PatternElement =
+            kind:extended_glob_prefix() "(" branches:extended_glob_body() ")" {
+                PatternElement::ExtendedGlob(kind, branches)
             }
 
         rule extended_glob_prefix() -> ExtendedGlobKind =
@@ -122,15 +372,15 @@ peg::parser! {
             "?" { ExtendedGlobKind::Question } /
             "*" { ExtendedGlobKind::Star }
 
-        pub(crate) rule extended_glob_body() -> Vec<String> =
+        pub(crate) rule extended_glob_body() -> Vec<Vec<PatternElement>> =
             first_branches:((b:extended_glob_branch() "|" { b })*) last_branch:extended_glob_branch() {
                 let mut branches = first_branches;
                 branches.push(last_branch);
                 branches
             }
 
-        rule extended_glob_branch() -> String =
-            pieces:(!['|' | ')'] piece:pattern_piece() { piece })* { pieces.join("") }
+        rule extended_glob_branch() -> Vec<PatternElement> =
+            (!['|' | ')'] e:pattern_element() { e })*
     }
 }
 
@@ -146,32 +396,92 @@ pub fn regex_char_needs_escaping(c: char) -> bool {
     )
 }
 
-fn escape_char_class_char_list(s: &str) -> String {
-    s.replace('[', r"\[")
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
     use anyhow::Result;
 
+    fn matches(pattern: &str, value: &str) -> Result<bool> {
+        Ok(pattern_elements_match(
+            &parse_pattern(pattern, true)?,
+            value,
+            false,
+        ))
+    }
+
     #[test]
     fn test_extended_glob() -> Result<()> {
-        assert_eq!(
-            pattern_to_regex_translator::extended_glob_pattern("@(a|b)", true)?,
-            "(a|b)"
-        );
+        assert_eq!(pattern_to_regex_str("@(a|b)", true)?, "(a|b)");
 
         assert_eq!(
-            pattern_to_regex_translator::extended_glob_body("ab|ac", true)?,
-            vec!["ab", "ac"],
+            pattern_parser::extended_glob_body("ab|ac", true)?,
+            vec![
+                vec![PatternElement::Literal('a'), PatternElement::Literal('b')],
+                vec![PatternElement::Literal('a'), PatternElement::Literal('c')],
+            ],
         );
 
+        assert_eq!(pattern_to_regex_str("*(ab|ac)", true)?, "(ab|ac)*");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_negated_extended_glob() -> Result<()> {
+        assert!(pattern_to_regex_str("!(a|b)", true).is_err());
         assert_eq!(
-            pattern_to_regex_translator::extended_glob_pattern("*(ab|ac)", true)?,
-            "(ab|ac)*"
+            parse_pattern("!(a)", true)?,
+            vec![PatternElement::ExtendedGlob(
+                ExtendedGlobKind::Exclamation,
+                vec![vec![PatternElement::Literal('a')]]
+            )]
         );
 
+        // `!(x)*`
+        assert!(matches("!(a)*", "abc")?);
+        assert!(matches("!(a)*", "a")?);
+        assert!(matches("!(a)*", "")?);
+
+        // `*!(x)`
+        assert!(matches("*!(x)", "ax")?);
+        assert!(matches("*!(x)", "")?);
+
+        // `!` nested inside `@(...)`
+        assert!(matches("@(a|!(b)c)", "ac")?);
+        assert!(matches("@(a|!(b)c)", "c")?);
+        assert!(matches("@(a|!(b)c)", "a")?);
+        assert!(!matches("@(a|!(b)c)", "bc")?);
+        assert!(!matches("@(a|!(b)c)", "bcd")?);
+
+        assert!(!matches("!(abc)", "abc")?);
+        assert!(matches("!(abc)", "abcd")?);
+        assert!(!matches("!(*.c)", "foo.c")?);
+        assert!(matches("!(*.c)", "foo.h")?);
+        assert!(matches("x!(a)y", "xaby")?);
+        assert!(!matches("x!(a)y", "xay")?);
+        assert!(matches("x!(a)y", "xy")?);
+        assert!(!matches("*(!(x))", "x")?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pattern_elements_match() -> Result<()> {
+        assert!(matches("a*c", "abbc")?);
+        assert!(!matches("a?c", "ac")?);
+        assert!(matches("[!a-c]x", "dx")?);
+        assert!(!matches("[!a-c]x", "bx")?);
+        assert!(matches("[[:digit:]]+([[:alpha:]])", "1ab")?);
+        assert!(matches("\\*", "*")?);
+        assert!(!matches("\\*", "a")?);
+        assert!(matches("+(ab|c)", "abcab")?);
+        assert!(!matches("+(ab|c)", "")?);
+        assert!(pattern_elements_match(
+            &parse_pattern("A[B-C]", true)?,
+            "ab",
+            true
+        ));
+
         Ok(())
     }
 }
@@ -75,6 +75,8 @@ pub enum PromptTimeFormat {
     TwelveHourAM,
     /// A twelve-hour time format (HHMMSS).
     TwelveHourHHMMSS,
+    /// A twenty-four-hour time format (HHMM).
+    TwentyFourHourHHMM,
     /// A twenty-four-hour time format (HHMMSS).
     TwentyFourHourHHMMSS,
 }
@@ -106,6 +108,7 @@ peg::parser! {
             "\\t" { PromptPiece::Time(PromptTimeFormat::TwentyFourHourHHMMSS ) } /
             "\\T" { PromptPiece::Time(PromptTimeFormat::TwelveHourHHMMSS ) } /
             "\\@" { PromptPiece::Time(PromptTimeFormat::TwelveHourAM ) } /
+            "\\A" { PromptPiece::Time(PromptTimeFormat::TwentyFourHourHHMM ) } /
             "\\u" { PromptPiece::CurrentUser } /
             "\\v" { PromptPiece::ShellVersion } /
             "\\V" { PromptPiece::ShellRelease } /
@@ -123,10 +126,10 @@ peg::parser! {
             s:$((!special_sequence() [c])+) { PromptPiece::Literal(s.to_owned()) }
 
         rule date_format() -> String =
-            s:$(!"}" [c]+) { s.to_owned() }
+            s:$((!"}" [c])*) { s.to_owned() }
 
         rule octal_number() -> u32 =
-            s:$(['0'..='9']*<3,3>) {? u32::from_str_radix(s, 8).or(Err("invalid octal number")) }
+            s:$(['0'..='7']*<3,3>) {? u32::from_str_radix(s, 8).or(Err("invalid octal number")) }
     }
 }
 
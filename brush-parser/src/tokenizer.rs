@@ -165,6 +165,7 @@ pub(crate) struct Tokens<'a> {
 enum QuoteMode {
     None,
     Single(SourcePosition),
+    AnsiC(SourcePosition),
     Double(SourcePosition),
 }
 
@@ -307,12 +308,29 @@ impl TokenParseState {
         self.token_so_far = s;
     }
 
+    /// Queues up the current token to be served after the body of the most recently
+    /// encountered here-document.
+    fn queue_after_last_here_tag(
+        &mut self,
+        reason: TokenEndReason,
+        cross_token_state: &mut CrossTokenParseState,
+    ) {
+        let token = self.pop(&cross_token_state.cursor);
+        if let Some(last_here_tag) = cross_token_state.current_here_tags.last_mut() {
+            last_here_tag.pending_tokens_after.push(TokenizeResult {
+                reason,
+                token: Some(token),
+            });
+        }
+    }
+
     pub fn delimit_current_token(
         &mut self,
         reason: TokenEndReason,
         cross_token_state: &mut CrossTokenParseState,
     ) -> Result<Option<TokenizeResult>, TokenizerError> {
-        if !self.started_token() {
+        // N.B. An empty here-document body is still a token.
+        if !self.started_token() && !matches!(cross_token_state.here_state, HereState::InHereDocs) {
             return Ok(Some(TokenizeResult {
                 reason,
                 token: None,
@@ -323,6 +341,13 @@ impl TokenParseState {
         match cross_token_state.here_state {
             HereState::NextTokenIsHereTag { remove_tabs } => {
                 cross_token_state.here_state = HereState::CurrentTokenIsHereTag { remove_tabs };
+
+                // If an earlier here-document on this line is still awaiting its body, then
+                // this operator belongs after that body.
+                if !cross_token_state.current_here_tags.is_empty() {
+                    self.queue_after_last_here_tag(reason, cross_token_state);
+                    return Ok(None);
+                }
             }
             HereState::CurrentTokenIsHereTag { remove_tabs } => {
                 if self.is_newline() {
@@ -333,20 +358,23 @@ impl TokenParseState {
 
                 cross_token_state.here_state = HereState::NextLineIsHereDoc;
 
-                if self.current_token().contains('\"')
-                    || self.current_token().contains('\'')
-                    || self.current_token().contains('\\')
-                {
-                    return Err(TokenizerError::Unimplemented("quoted or escaped here tag"));
-                }
-
-                // Include the \n in the here tag so it's easier to check against.
-                cross_token_state.current_here_tags.push(HereTag {
-                    tag: std::format!("\n{}\n", self.current_token()),
+                // Any quoting in the here tag is removed before looking for the delimiter;
+                // include the \n in the here tag so it's easier to check against.
+                let here_tag = HereTag {
+                    tag: std::format!("\n{}\n", unquote_here_tag(self.current_token())),
                     remove_tabs,
                     position: cross_token_state.cursor.clone(),
                     pending_tokens_after: vec![],
-                });
+                };
+
+                // As above, this tag may need to follow an earlier here-document's body.
+                if !cross_token_state.current_here_tags.is_empty() {
+                    self.queue_after_last_here_tag(reason, cross_token_state);
+                    cross_token_state.current_here_tags.push(here_tag);
+                    return Ok(None);
+                }
+
+                cross_token_state.current_here_tags.push(here_tag);
             }
             HereState::NextLineIsHereDoc => {
                 if self.is_newline() {
@@ -355,18 +383,11 @@ impl TokenParseState {
 
                 // We need to queue it up for later so we can get the here-document
                 // body to show up in the token stream right after the here tag.
-                if let Some(last_here_tag) = cross_token_state.current_here_tags.last_mut() {
-                    let token = self.pop(&cross_token_state.cursor);
-                    let result = TokenizeResult {
-                        reason,
-                        token: Some(token),
-                    };
-
-                    last_here_tag.pending_tokens_after.push(result);
-                } else {
+                if cross_token_state.current_here_tags.is_empty() {
                     return Err(TokenizerError::MissingHereTagForDocumentBody);
                 }
 
+                self.queue_after_last_here_tag(reason, cross_token_state);
                 return Ok(None);
             }
             HereState::InHereDocs => {
@@ -396,6 +417,39 @@ impl TokenParseState {
     }
 }
 
+/// Checks whether the given in-progress here-document ends with a line consisting solely
+/// of the given here tag (which includes its surrounding newlines); if so, returns the
+/// body of the here-document preceding that line.
+fn here_doc_body_if_terminated(token: &str, tag: &str) -> Option<String> {
+    if token == &tag[1..] {
+        return Some(String::new());
+    }
+
+    token.strip_suffix(tag).map(|body| std::format!("{body}\n"))
+}
+
+/// Removes quoting from a here tag, yielding the delimiter it denotes.
+pub(crate) fn unquote_here_tag(tag: &str) -> String {
+    let mut result = String::new();
+    let mut quote = None;
+    let mut chars = tag.chars();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (None, '\'' | '"') => quote = Some(c),
+            (Some(q), c) if c == q => quote = None,
+            (None | Some('"'), '\\') => {
+                if let Some(next) = chars.next() {
+                    result.push(next);
+                }
+            }
+            _ => result.push(c),
+        }
+    }
+
+    result
+}
+
 /// Break the given input shell script string into tokens, returning the tokens.
 ///
 /// # Arguments
@@ -508,7 +562,7 @@ impl<'a, R: ?Sized + std::io::BufRead> Tokenizer<'a, R> {
                 }
                 match state.quote_mode {
                     QuoteMode::None => (),
-                    QuoteMode::Single(pos) => {
+                    QuoteMode::Single(pos) | QuoteMode::AnsiC(pos) => {
                         return Err(TokenizerError::UnterminatedSingleQuote(pos));
                     }
                     QuoteMode::Double(pos) => {
@@ -516,6 +570,21 @@ impl<'a, R: ?Sized + std::io::BufRead> Tokenizer<'a, R> {
                     }
                 }
 
+                // A here-document's delimiter may appear on the last line of the input,
+                // without a trailing newline.
+                if matches!(self.cross_state.here_state, HereState::InHereDocs) {
+                    state.append_char('\n');
+                    if let Some(body) = here_doc_body_if_terminated(
+                        state.current_token(),
+                        self.cross_state.current_here_tags[0].tag.as_str(),
+                    ) {
+                        state.replace_with_here_doc(body);
+                        result = state
+                            .delimit_current_token(TokenEndReason::Other, &mut self.cross_state)?;
+                        continue;
+                    }
+                }
+
                 // Verify we're not in a here document.
                 if !matches!(self.cross_state.here_state, HereState::None) {
                     let tag_positions = self
@@ -557,15 +626,11 @@ impl<'a, R: ?Sized + std::io::BufRead> Tokenizer<'a, R> {
                     self.consume_char()?;
                     state.append_char(c);
 
-                    let without_suffix = state
-                        .current_token()
-                        .strip_suffix(self.cross_state.current_here_tags[0].tag.as_str())
-                        .map(|s| s.to_owned());
-
-                    if let Some(mut without_suffix) = without_suffix {
-                        without_suffix.push('\n');
-
-                        state.replace_with_here_doc(without_suffix);
+                    if let Some(body) = here_doc_body_if_terminated(
+                        state.current_token(),
+                        self.cross_state.current_here_tags[0].tag.as_str(),
+                    ) {
+                        state.replace_with_here_doc(body);
 
                         result = state
                             .delimit_current_token(TokenEndReason::Other, &mut self.cross_state)?;
@@ -609,7 +674,13 @@ impl<'a, R: ?Sized + std::io::BufRead> Tokenizer<'a, R> {
             // See if this is a character that changes the current escaping/quoting state.
             //
             } else if does_char_newly_affect_quoting(&state, c) {
-                if c == '\\' {
+                if c == '\\' && matches!(state.quote_mode, QuoteMode::AnsiC(_)) {
+                    // Within ANSI-C quotes, escape sequences are preserved as-is (including
+                    // escaped newlines) for later decoding.
+                    self.consume_char()?;
+                    state.in_escape = true;
+                    state.append_char(c);
+                } else if c == '\\' {
                     // Consume the backslash ourselves so we can peek past it.
                     self.consume_char()?;
 
@@ -635,7 +706,7 @@ impl<'a, R: ?Sized + std::io::BufRead> Tokenizer<'a, R> {
             //
             // Handle end of single-quote or double-quote.
             else if !state.in_escape
-                && matches!(state.quote_mode, QuoteMode::Single(_))
+                && matches!(state.quote_mode, QuoteMode::Single(_) | QuoteMode::AnsiC(_))
                 && c == '\''
             {
                 state.quote_mode = QuoteMode::None;
@@ -770,6 +841,17 @@ impl<'a, R: ?Sized + std::io::BufRead> Tokenizer<'a, R> {
                                 }
                             }
                         }
+                        Some('\'') if matches!(state.quote_mode, QuoteMode::None) => {
+                            // This is the start of an ANSI-C quoted string (e.g., `$'\n'`).
+                            let quote_pos = self.cross_state.cursor.clone();
+
+                            // Add the '$' we already consumed to the token, then consume and
+                            // add the opening quote.
+                            state.append_char('$');
+                            state.append_char(self.next_char()?.unwrap());
+
+                            state.quote_mode = QuoteMode::AnsiC(quote_pos);
+                        }
                         _ => {
                             // This is either a different character, or else the end of the string.
                             // Either way, add the '$' we already consumed to the token.
@@ -935,7 +1017,7 @@ impl<'a, R: ?Sized + std::io::BufRead> Tokenizer<'a, R> {
 
     fn is_operator(&self, s: &str) -> bool {
         // Handle non-POSIX operators.
-        if !self.options.posix_mode && matches!(s, "<<<" | "&>" | "&>>") {
+        if !self.options.posix_mode && matches!(s, "<<<" | "&>" | "&>>" | ";&" | ";;&") {
             return true;
         }
 
@@ -999,6 +1081,8 @@ fn does_char_newly_affect_quoting(state: &TokenParseState, c: char) -> bool {
         }
         // When we're in a single quote, nothing affects quoting.
         QuoteMode::Single(_) => false,
+        // When we're in an ANSI-C quote, only a backslash affects quoting.
+        QuoteMode::AnsiC(_) => c == '\\',
         // When we're not already in a quote, then we can straightforwardly look for a
         // quote mark or backslash.
         QuoteMode::None => is_quoting_char(c),
@@ -1126,6 +1210,80 @@ echo after
         Ok(())
     }
 
+    #[test]
+    fn tokenize_here_doc_with_quoted_tag() -> Result<()> {
+        let tokens = tokenize_str(
+            r#"cat <<'HE'"RE"
+$SOMETHING
+HERE
+"#,
+        )?;
+        assert_matches!(
+            &tokens[..],
+            [t1 @ Token::Word(_, _),
+             t2 @ Token::Operator(_, _),
+             t3 @ Token::Word(_, _),
+             t4 @ Token::Word(_, _),
+             t5 @ Token::Operator(_, _)] if
+                t1.to_str() == "cat" &&
+                t2.to_str() == "<<" &&
+                t3.to_str() == r#"'HE'"RE""# &&
+                t4.to_str() == "$SOMETHING\n" &&
+                t5.to_str() == "\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn tokenize_multiple_here_docs() -> Result<()> {
+        let tokens = tokenize_str(
+            r#"cat <<A <<B
+first
+A
+B
+"#,
+        )?;
+        assert_matches!(
+            &tokens[..],
+            [t1 @ Token::Word(_, _),
+             t2 @ Token::Operator(_, _),
+             t3 @ Token::Word(_, _),
+             t4 @ Token::Word(_, _),
+             t5 @ Token::Operator(_, _),
+             t6 @ Token::Word(_, _),
+             t7 @ Token::Word(_, _),
+             t8 @ Token::Operator(_, _)] if
+                t1.to_str() == "cat" &&
+                t2.to_str() == "<<" &&
+                t3.to_str() == "A" &&
+                t4.to_str() == "first\n" &&
+                t5.to_str() == "<<" &&
+                t6.to_str() == "B" &&
+                t7.to_str().is_empty() &&
+                t8.to_str() == "\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn tokenize_here_doc_terminated_at_end_of_input() -> Result<()> {
+        let tokens = tokenize_str("cat <<HERE\nSOMETHING\nHERE")?;
+        assert_matches!(
+            &tokens[..],
+            [t1 @ Token::Word(_, _),
+             t2 @ Token::Operator(_, _),
+             t3 @ Token::Word(_, _),
+             t4 @ Token::Word(_, _),
+             t5 @ Token::Operator(_, _)] if
+                t1.to_str() == "cat" &&
+                t2.to_str() == "<<" &&
+                t3.to_str() == "HERE" &&
+                t4.to_str() == "SOMETHING\n" &&
+                t5.to_str() == "\n"
+        );
+        Ok(())
+    }
+
     #[test]
     fn tokenize_unterminated_here_doc() -> Result<()> {
         let result = tokenize_str(
@@ -1350,6 +1508,46 @@ SOMETHING
         Ok(())
     }
 
+    #[test]
+    fn tokenize_ansi_c_quote() -> Result<()> {
+        assert_matches!(
+            &tokenize_str(r"x$'a b\t'y")?[..],
+            [t1 @ Token::Word(_, _)] if
+                t1.to_str() == r"x$'a b\t'y"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn tokenize_ansi_c_quote_with_escaped_quote() -> Result<()> {
+        assert_matches!(
+            &tokenize_str(r"echo $'a\'b' c")?[..],
+            [t1 @ Token::Word(_, _), t2 @ Token::Word(_, _), t3 @ Token::Word(_, _)] if
+                t1.to_str() == "echo" &&
+                t2.to_str() == r"$'a\'b'" &&
+                t3.to_str() == "c"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn tokenize_unterminated_ansi_c_quote() {
+        assert_matches!(
+            tokenize_str(r"echo $'a\'"),
+            Err(TokenizerError::UnterminatedSingleQuote(_))
+        );
+    }
+
+    #[test]
+    fn tokenize_double_quoted_ansi_c_quote() -> Result<()> {
+        assert_matches!(
+            &tokenize_str(r#""$'a'""#)?[..],
+            [t1 @ Token::Word(_, _)] if
+                t1.to_str() == r#""$'a'""#
+        );
+        Ok(())
+    }
+
     #[test]
     fn tokenize_double_quoted_command_substitution() -> Result<()> {
         assert_matches!(
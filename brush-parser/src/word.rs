@@ -8,6 +8,7 @@
 //! - Parameter expansion expressions.
 //! - Command substitution expressions.
 //! - Arithmetic expansion expressions.
+//! - Brace expansion expressions.
 
 use crate::ast;
 use crate::error;
@@ -293,6 +294,28 @@ pub enum ParameterExpr {
         /// Optionally provides a pattern to match.
         pattern: Option<String>,
     },
+    /// Toggle the case of the first character of the given parameter.
+    ToggleCaseFirstChar {
+        /// The parameter.
+        parameter: Parameter,
+        /// Whether to treat the expanded parameter as an indirect
+        /// reference, which should be subsequently dereferenced
+        /// for the expansion.
+        indirect: bool,
+        /// Optionally provides a pattern to match.
+        pattern: Option<String>,
+    },
+    /// Toggle the case of the portion of the given parameter matching the given pattern.
+    ToggleCasePattern {
+        /// The parameter.
+        parameter: Parameter,
+        /// Whether to treat the expanded parameter as an indirect
+        /// reference, which should be subsequently dereferenced
+        /// for the expansion.
+        indirect: bool,
+        /// Optionally provides a pattern to match.
+        pattern: Option<String>,
+    },
     /// Replace occurrences of the given pattern in the given parameter.
     ReplaceSubstring {
         /// The parameter.
@@ -363,6 +386,45 @@ pub enum ParameterTransformOp {
     ToUpperCase,
 }
 
+/// Represents a piece of a word, as seen by brace expansion.
+#[derive(Clone, Debug)]
+pub enum BraceExpressionOrText {
+    /// A brace expression.
+    Expr(BraceExpression),
+    /// Other text (which may include quotes and other expansions).
+    Text(String),
+}
+
+/// A brace expression, consisting of the members to be generated in order.
+pub type BraceExpression = Vec<BraceExpressionMember>;
+
+/// Represents a member of a brace expression.
+#[derive(Clone, Debug)]
+pub enum BraceExpressionMember {
+    /// An integer sequence, e.g. `1..10` or `01..10..2`.
+    NumberSequence {
+        /// The first number in the sequence.
+        low: i64,
+        /// The number at which the sequence ends.
+        high: i64,
+        /// The increment between numbers; its sign is ignored.
+        increment: i64,
+        /// The minimum width to which generated numbers are zero-padded.
+        width: usize,
+    },
+    /// A character sequence, e.g. `a..e`.
+    CharSequence {
+        /// The first character in the sequence.
+        low: char,
+        /// The character at which the sequence ends.
+        high: char,
+        /// The increment between characters; its sign is ignored.
+        increment: i64,
+    },
+    /// A text alternative, which may itself contain brace expressions.
+    Text(Vec<BraceExpressionOrText>),
+}
+
 /// Parse a word into its constituent pieces.
 ///
 /// # Arguments
@@ -391,6 +453,46 @@ fn cacheable_parse(
     Ok(pieces)
 }
 
+/// Parse the value of an assignment into its constituent pieces. Unlike other words, tilde
+/// prefixes are recognized after each unquoted `:` in the value, not just at its start.
+///
+/// # Arguments
+///
+/// * `value` - The assignment value to parse.
+/// * `options` - The parser options to use.
+pub fn parse_assignment_value(
+    value: &str,
+    options: &ParserOptions,
+) -> Result<Vec<WordPieceWithSource>, error::WordParseError> {
+    cacheable_parse_assignment_value(value.to_owned(), options.to_owned())
+}
+
+#[cached::proc_macro::cached(size = 64, result = true)]
+fn cacheable_parse_assignment_value(
+    value: String,
+    options: ParserOptions,
+) -> Result<Vec<WordPieceWithSource>, error::WordParseError> {
+    expansion_parser::assignment_value(value.as_str(), &options)
+        .map_err(|err| error::WordParseError::Word(value.to_owned(), err))
+}
+
+/// Parse the body of a here-document (whose delimiter was unquoted) into its constituent
+/// pieces. Unlike a word, the body is subject only to parameter expansion, command
+/// substitution, and arithmetic expansion; quote characters carry no special meaning.
+///
+/// # Arguments
+///
+/// * `body` - The body of the here-document to parse.
+/// * `options` - The parser options to use.
+pub fn parse_here_document_body(
+    body: &str,
+    options: &ParserOptions,
+) -> Result<Vec<WordPieceWithSource>, error::WordParseError> {
+    let pieces = expansion_parser::here_document_body(body, options)
+        .map_err(error::WordParseError::HereDocument)?;
+    Ok(pieces)
+}
+
 /// Parse the given word into a parameter expression.
 ///
 /// # Arguments
@@ -406,6 +508,31 @@ pub fn parse_parameter(
     Ok(pieces)
 }
 
+/// Parse the brace expressions in a word, if any. Returns `None` if the word
+/// contains no valid brace expressions, in which case it's left as-is by brace
+/// expansion.
+///
+/// # Arguments
+///
+/// * `word` - The word to parse.
+/// * `options` - The parser options to use.
+pub fn parse_brace_expansions(
+    word: &str,
+    options: &ParserOptions,
+) -> Result<Option<Vec<BraceExpressionOrText>>, error::WordParseError> {
+    let pieces = expansion_parser::brace_expansions(word, options)
+        .map_err(|err| error::WordParseError::BraceExpansion(word.to_owned(), err))?;
+
+    if pieces
+        .iter()
+        .any(|piece| matches!(piece, BraceExpressionOrText::Expr(_)))
+    {
+        Ok(Some(pieces))
+    } else {
+        Ok(None)
+    }
+}
+
 peg::parser! {
     grammar expansion_parser(parser_options: &ParserOptions) for str {
         pub(crate) rule unexpanded_word() -> Vec<WordPieceWithSource> = word(<![_]>)
@@ -420,6 +547,113 @@ peg::parser! {
                 all_pieces
             }
 
+        pub(crate) rule assignment_value() -> Vec<WordPieceWithSource> =
+            first:word(<[':']>) rest:assignment_value_segment()* {
+                let mut all_pieces = first;
+                for segment in rest {
+                    all_pieces.extend(segment);
+                }
+                all_pieces
+            }
+
+        // N.B. Each segment following a ':' may start with its own tilde prefix.
+        rule assignment_value_segment() -> Vec<WordPieceWithSource> =
+            start_index:position!() ":" end_index:position!() pieces:word(<[':']>) {
+                let mut all_pieces = vec![WordPieceWithSource {
+                    piece: WordPiece::Text(":".to_owned()),
+                    start_index,
+                    end_index,
+                }];
+                all_pieces.extend(pieces);
+                all_pieces
+            }
+
+        pub(crate) rule here_document_body() -> Vec<WordPieceWithSource> =
+            here_document_piece_with_source()*
+
+        rule here_document_piece_with_source() -> WordPieceWithSource =
+            start_index:position!() piece:here_document_piece() end_index:position!() {
+                WordPieceWithSource { piece, start_index, end_index }
+            }
+
+        rule here_document_piece() -> WordPiece =
+            arithmetic_expansion() /
+            command_substitution() /
+            parameter_expansion() /
+            s:$(['$' | '`']) { WordPiece::Text(s.to_owned()) } /
+            "\\\n" { WordPiece::Text(String::new()) } /
+            s:$("\\" ['$' | '`' | '\\']) { WordPiece::EscapeSequence(s.to_owned()) } /
+            s:$("\\" / [^'$' | '`' | '\\']+) { WordPiece::Text(s.to_owned()) }
+
+        pub(crate) rule brace_expansions() -> Vec<BraceExpressionOrText> =
+            brace_expansion_piece(<![_]>)*
+
+        rule brace_expansion_piece<T>(stop_condition: rule<T>) -> BraceExpressionOrText =
+            expr:brace_expr() { BraceExpressionOrText::Expr(expr) } /
+            text:$(brace_text_piece(<stop_condition()>)+) {
+                BraceExpressionOrText::Text(text.to_owned())
+            }
+
+        rule brace_expr() -> BraceExpression =
+            "{" members:brace_text_list_expr() "}" { members } /
+            "{" member:brace_sequence_expr() "}" { vec![member] }
+
+        // N.B. A list needs at least 2 members; otherwise the braces are left as-is.
+        rule brace_text_list_expr() -> Vec<BraceExpressionMember> =
+            brace_text_list_member() **<2,> ","
+
+        rule brace_text_list_member() -> BraceExpressionMember =
+            &[',' | '}'] { BraceExpressionMember::Text(vec![]) } /
+            pieces:brace_expansion_piece(<[',' | '}']>)+ {
+                BraceExpressionMember::Text(pieces)
+            }
+
+        rule brace_sequence_expr() -> BraceExpressionMember =
+            low:brace_sequence_number() ".." high:brace_sequence_number()
+                increment:brace_sequence_increment()? {?
+                let width = if is_zero_padded(low) || is_zero_padded(high) {
+                    low.len().max(high.len())
+                } else {
+                    0
+                };
+
+                Ok(BraceExpressionMember::NumberSequence {
+                    low: low.parse().or(Err("invalid sequence start"))?,
+                    high: high.parse().or(Err("invalid sequence end"))?,
+                    increment: increment.unwrap_or(1),
+                    width,
+                })
+            } /
+            low:[c if c.is_ascii_alphabetic()] ".." high:[c if c.is_ascii_alphabetic()]
+                increment:brace_sequence_increment()? {
+                BraceExpressionMember::CharSequence {
+                    low,
+                    high,
+                    increment: increment.unwrap_or(1),
+                }
+            }
+
+        rule brace_sequence_number() -> &'input str =
+            $(['-' | '+']? ['0'..='9']+)
+
+        rule brace_sequence_increment() -> i64 =
+            ".." n:brace_sequence_number() {? n.parse().or(Err("invalid increment")) }
+
+        // N.B. Quoted text and other expansions are skipped over, since braces within
+        // them aren't subject to brace expansion.
+        rule brace_text_piece<T>(stop_condition: rule<T>) =
+            !stop_condition() (
+                arithmetic_expansion() {} /
+                command_substitution() {} /
+                parameter_expansion() {} /
+                double_quoted_sequence() {} /
+                single_quoted_literal_text() {} /
+                ansi_c_quoted_text() {} /
+                "\\" [_] {} /
+                !brace_expr() "{" {} /
+                [^'{'] {}
+            )
+
         // N.B. We don't bother returning the word pieces, as all users of this rule
         // only try to extract the consumed input string and not the parse result.
         rule arithmetic_word<T>(stop_condition: rule<T>) =
@@ -478,7 +712,7 @@ peg::parser! {
             "\'" inner:$([^'\'']*) "\'" { inner }
 
         rule ansi_c_quoted_text() -> &'input str =
-            "$\'" inner:$([^'\'']*) "\'" { inner }
+            "$\'" inner:$(("\\" [_] / [^'\'' | '\\'])*) "\'" { inner }
 
         rule unquoted_literal_text<T>(stop_condition: rule<T>) -> WordPiece =
             s:$(unquoted_literal_text_piece(<stop_condition()>)+) { WordPiece::Text(s.to_owned()) }
@@ -581,10 +815,12 @@ peg::parser! {
             "!" variable_name:variable_name() "[@]" {
                 ParameterExpr::MemberKeys { variable_name: variable_name.to_owned(), concatenate: false }
             } /
-            "!" prefix:variable_name() "*" {
+            // N.B. We check for the closing brace to avoid confusion with transformations
+            // applied to indirect references (e.g., `${!ref@Q}`).
+            "!" prefix:variable_name() "*" &"}" {
                 ParameterExpr::VariableNames { prefix: prefix.to_owned(), concatenate: true }
             } /
-            "!" prefix:variable_name() "@" {
+            "!" prefix:variable_name() "@" &"}" {
                 ParameterExpr::VariableNames { prefix: prefix.to_owned(), concatenate: false }
             } /
             indirect:parameter_indirection() parameter:parameter() ":" offset:substring_offset() length:(":" l:substring_length() { l })? {
@@ -616,6 +852,12 @@ peg::parser! {
             } /
             indirect:parameter_indirection() parameter:parameter() "," pattern:parameter_expression_word()? {
                 ParameterExpr::LowercaseFirstChar { parameter, indirect, pattern }
+            } /
+            indirect:parameter_indirection() parameter:parameter() "~~" pattern:parameter_expression_word()? {
+                ParameterExpr::ToggleCasePattern { parameter, indirect, pattern }
+            } /
+            indirect:parameter_indirection() parameter:parameter() "~" pattern:parameter_expression_word()? {
+                ParameterExpr::ToggleCaseFirstChar { parameter, indirect, pattern }
             }
 
         rule parameter_indirection() -> bool =
@@ -697,7 +939,7 @@ peg::parser! {
             s:$(arithmetic_word(<[':' | '}']>)) { ast::UnexpandedArithmeticExpr { value: s.to_owned() } }
 
         rule parameter_replacement_str() -> String =
-            "/" s:$(word(<['}' | '/']>)) { s.to_owned() }
+            "/" s:$(word(<['}']>)) { s.to_owned() }
 
         rule parameter_search_pattern() -> String =
             s:$(word(<['}' | '/']>)) { s.to_owned() }
@@ -716,6 +958,11 @@ peg::parser! {
     }
 }
 
+fn is_zero_padded(number: &str) -> bool {
+    let digits = number.trim_start_matches(['-', '+']);
+    digits.len() > 1 && digits.starts_with('0')
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -767,4 +1014,84 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn parse_parameter_length() -> Result<()> {
+        let length_of = |word: &str| -> Result<Option<Parameter>> {
+            let parsed = super::parse(word, &ParserOptions::default())?;
+            Ok(match parsed.into_iter().next() {
+                Some(WordPieceWithSource {
+                    piece:
+                        WordPiece::ParameterExpansion(ParameterExpr::ParameterLength {
+                            parameter,
+                            indirect: false,
+                        }),
+                    ..
+                }) => Some(parameter),
+                _ => None,
+            })
+        };
+
+        assert_matches!(length_of("${#x}")?, Some(Parameter::Named(name)) if name == "x");
+        assert_matches!(length_of("${#1}")?, Some(Parameter::Positional(1)));
+        assert_matches!(
+            length_of("${#arr[@]}")?,
+            Some(Parameter::NamedWithAllIndices { name, concatenate: false }) if name == "arr"
+        );
+        assert_matches!(
+            length_of("${#arr[*]}")?,
+            Some(Parameter::NamedWithAllIndices { name, concatenate: true }) if name == "arr"
+        );
+        assert_matches!(
+            length_of("${#@}")?,
+            Some(Parameter::Special(
+                SpecialParameter::AllPositionalParameters { concatenate: false }
+            ))
+        );
+        assert_matches!(
+            length_of("${#*}")?,
+            Some(Parameter::Special(
+                SpecialParameter::AllPositionalParameters { concatenate: true }
+            ))
+        );
+        assert_matches!(length_of("${#}")?, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_brace_expansions() -> Result<()> {
+        let parsed = super::parse_brace_expansions("a{b,c}d", &ParserOptions::default())?;
+        assert_matches!(
+            parsed.as_deref(),
+            Some([BraceExpressionOrText::Text(pre), BraceExpressionOrText::Expr(members), BraceExpressionOrText::Text(post)])
+                if pre == "a" && members.len() == 2 && post == "d"
+        );
+
+        let parsed = super::parse_brace_expansions("{01..10..3}", &ParserOptions::default())?;
+        assert_matches!(
+            parsed.as_deref(),
+            Some([BraceExpressionOrText::Expr(members)])
+                if matches!(members[..], [BraceExpressionMember::NumberSequence { low: 1, high: 10, increment: 3, width: 2 }])
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_invalid_brace_expansions() -> Result<()> {
+        for word in [
+            "{a}",
+            "{1..}",
+            "{a,b",
+            "{}",
+            "'{a,b}'",
+            "${x:-{a,b}}",
+            "{1..a}",
+        ] {
+            assert!(super::parse_brace_expansions(word, &ParserOptions::default())?.is_none());
+        }
+
+        Ok(())
+    }
 }
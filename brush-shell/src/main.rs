@@ -173,6 +173,9 @@ async fn run_impl(
         shell.run_interactively().await?;
     }
 
+    // Give any EXIT trap a chance to run before we exit.
+    shell.shell_mut().as_mut().run_exit_trap().await?;
+
     // Make sure to return the last result observed in the shell.
     let result = shell.shell().as_ref().last_result();
 